@@ -0,0 +1,48 @@
+//! Capability markers for [`Jail`](crate::Jail).
+
+use std::fmt::Debug;
+use std::hash::Hash;
+
+pub(crate) mod sealed {
+    pub trait Sealed {
+        /// Whether the handle may modify the filesystem.
+        const WRITABLE: bool;
+    }
+    impl Sealed for super::ReadOnly {
+        const WRITABLE: bool = false;
+    }
+    impl Sealed for super::ReadWrite {
+        const WRITABLE: bool = true;
+    }
+}
+
+/// What a [`Jail`](crate::Jail) handle may do: [`ReadOnly`] or [`ReadWrite`].
+///
+/// Sealed; the two markers are the only implementations.
+pub trait Access:
+    sealed::Sealed + Debug + Clone + Copy + PartialEq + Eq + Hash + Send + Sync + 'static
+{
+}
+
+/// Marker for a jail that can validate and read, but not create, modify
+/// or delete anything.
+///
+/// Obtained with [`Jail::read_only`](crate::Jail::read_only). Writing
+/// methods simply do not exist on such a handle:
+///
+/// ```compile_fail
+/// use path_jail::Jail;
+///
+/// let jail = Jail::new("/var/uploads")?.read_only();
+/// jail.write("report.txt", b"data")?;
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ReadOnly;
+
+/// Marker for a jail with full access. The default for [`Jail`](crate::Jail).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct ReadWrite;
+
+impl Access for ReadOnly {}
+impl Access for ReadWrite {}
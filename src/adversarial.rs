@@ -0,0 +1,211 @@
+//! Adversarial relative paths for property tests (`test-util` feature).
+//!
+//! The same corpus of traversals, Unicode look-alikes, reserved names,
+//! overlong components and embedded separators drives
+//! [`adversarial_path`] (a proptest strategy) and [`AdversarialPath`]'s
+//! `Arbitrary` impls (proptest and `arbitrary`, for fuzzers), so handlers
+//! downstream can be tested against what `path_jail` is tested against.
+
+#![cfg(feature = "test-util")]
+
+use proptest::prelude::*;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+/// Components that climb, or decode or normalize into something that does.
+const TRAVERSALS: &[&str] = &[
+    "..",
+    ".",
+    "...",
+    "....",
+    "..;",
+    ".. ",
+    "%2e%2e",
+    "%2E%2E",
+    "%252e%252e",
+    "..%2f",
+    "..%5c",
+    "%2e%2e%2f%2e%2e",
+    "..%00",
+    "%c0%ae%c0%ae",
+];
+
+/// Characters that look like dots and slashes, or hide what a name says.
+const UNICODE_TRICKS: &[&str] = &[
+    "\u{2024}\u{2024}",         // one dot leaders
+    "\u{2025}",                 // two dot leader
+    "\u{FF0E}\u{FF0E}",         // fullwidth full stops
+    "\u{FE52}\u{FE52}",         // small full stops
+    "\u{2215}",                 // division slash
+    "\u{2044}",                 // fraction slash
+    "\u{FF0F}",                 // fullwidth solidus
+    "\u{29F5}",                 // reverse solidus operator
+    "\u{FF3C}",                 // fullwidth reverse solidus
+    "\u{202E}fdp.exe",          // right-to-left override
+    "a\u{200B}b",               // zero-width space
+    "\u{FEFF}name",             // byte order mark
+    "caf\u{E9}",                // NFC
+    "cafe\u{301}",              // NFD
+    "\u{130}",                  // dotted capital I
+    "\u{FF23}\u{FF2F}\u{FF2E}", // fullwidth CON
+];
+
+/// Names Windows treats specially, and stream and trailing-dot forms.
+const RESERVED: &[&str] = &[
+    "CON",
+    "con",
+    "nul.txt",
+    "AUX",
+    "PRN",
+    "COM1",
+    "LPT9",
+    "CONIN$",
+    "file.txt::$DATA",
+    "file.txt:stream",
+    "file.txt.",
+    "file.txt ",
+    "PROGRA~1",
+    ".git",
+    ".env",
+];
+
+/// Components with separators, roots or terminators inside them.
+const EMBEDDED: &[&str] = &[
+    "a/b",
+    "a\\b",
+    "a//b",
+    "/",
+    "\\",
+    "/etc/passwd",
+    "\\windows\\system32",
+    "C:",
+    "C:\\",
+    "c:foo",
+    "\\\\server\\share",
+    "\\\\?\\C:\\",
+    "\\\\.\\pipe\\x",
+    "a\0b",
+    "\0",
+    "~",
+    "$HOME",
+];
+
+fn component() -> impl Strategy<Value = OsString> {
+    let corpus = [TRAVERSALS, UNICODE_TRICKS, RESERVED, EMBEDDED].concat();
+    prop_oneof![
+        3 => proptest::sample::select(corpus).prop_map(OsString::from),
+        2 => "[a-z0-9_.-]{1,12}".prop_map(OsString::from),
+        1 => (1usize..=300, proptest::sample::select(vec!['a', '.', '\u{E9}']))
+            .prop_map(|(len, c)| OsString::from(c.to_string().repeat(len))),
+        1 => any::<String>().prop_map(OsString::from),
+        1 => non_utf8(),
+    ]
+}
+
+#[cfg(unix)]
+fn non_utf8() -> BoxedStrategy<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+
+    proptest::collection::vec(any::<u8>(), 1..16)
+        .prop_map(OsString::from_vec)
+        .boxed()
+}
+
+#[cfg(windows)]
+fn non_utf8() -> BoxedStrategy<OsString> {
+    use std::os::windows::ffi::OsStringExt;
+
+    // Unpaired surrogates among ordinary units
+    proptest::collection::vec(prop_oneof![0xD800u16..0xE000, 0x20u16..0x7F], 1..16)
+        .prop_map(|units| OsString::from_wide(&units))
+        .boxed()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn non_utf8() -> BoxedStrategy<OsString> {
+    any::<String>().prop_map(OsString::from).boxed()
+}
+
+/// A proptest strategy for relative paths built to get out of a jail.
+///
+/// Each path joins one to eight components with `/`, `\` or `//`; a
+/// component is a traversal (`..`, `%2e%2e`, ...), a Unicode look-alike
+/// (fullwidth dots, division slash, bidi overrides), a reserved Windows
+/// name, a component with a separator or root embedded in it, an ordinary
+/// name, an overlong one, or random (on Unix and Windows, also not valid
+/// Unicode). Some paths are absolute. Most should be rejected; none should
+/// ever resolve outside the jail.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::{adversarial_path, LexicalJail};
+/// use proptest::prelude::*;
+///
+/// let jail = LexicalJail::new(std::env::temp_dir().join("jail")).unwrap();
+/// proptest!(|(path in adversarial_path())| {
+///     if let Ok(joined) = jail.join(&path) {
+///         prop_assert!(joined.starts_with(jail.root()));
+///     }
+/// });
+/// ```
+pub fn adversarial_path() -> BoxedStrategy<PathBuf> {
+    let separator = proptest::sample::select(vec!["/", "\\", "//"]);
+    (
+        proptest::option::weighted(0.1, proptest::sample::select(vec!["/", "\\", "C:\\"])),
+        proptest::collection::vec((component(), separator), 1..=8),
+    )
+        .prop_map(|(root, parts)| {
+            let mut path = OsString::from(root.unwrap_or_default());
+            for (index, (component, separator)) in parts.into_iter().enumerate() {
+                if index > 0 {
+                    path.push(separator);
+                }
+                path.push(component);
+            }
+            PathBuf::from(path)
+        })
+        .boxed()
+}
+
+/// A path from the [`adversarial_path`] corpus.
+///
+/// Implements proptest's `Arbitrary` (so `any::<AdversarialPath>()`
+/// works) and the `arbitrary` crate's, for fuzz targets fed raw bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdversarialPath(pub PathBuf);
+
+impl proptest::arbitrary::Arbitrary for AdversarialPath {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        adversarial_path().prop_map(AdversarialPath).boxed()
+    }
+}
+
+impl<'a> arbitrary::Arbitrary<'a> for AdversarialPath {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        const CORPORA: &[&[&str]] = &[TRAVERSALS, UNICODE_TRICKS, RESERVED, EMBEDDED];
+        const SEPARATORS: &[&str] = &["/", "\\", "//"];
+
+        let mut path = String::new();
+        if u.ratio(1, 10)? {
+            path.push_str(u.choose(&["/", "\\", "C:\\"])?);
+        }
+        for index in 0..u.int_in_range(1..=8)? {
+            if index > 0 {
+                path.push_str(u.choose(SEPARATORS)?);
+            }
+            match u.int_in_range(0..=5)? {
+                0..=2 => {
+                    let corpus = *u.choose(CORPORA)?;
+                    path.push_str(u.choose(corpus)?);
+                }
+                3 => path.push_str(&"a".repeat(u.int_in_range(1..=300)?)),
+                _ => path.push_str(<&str>::arbitrary(u)?),
+            }
+        }
+        Ok(Self(PathBuf::from(path)))
+    }
+}
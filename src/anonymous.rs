@@ -0,0 +1,209 @@
+//! Unnamed files created with `O_TMPFILE` (Linux, `secure-open` feature).
+//!
+//! An `O_TMPFILE` file has no name until it is explicitly linked into a
+//! directory, so there is no window where a half-written file is visible and
+//! no temporary name an attacker could race for.
+
+#![cfg(all(feature = "secure-open", target_os = "linux"))]
+
+use crate::open::O_NOFOLLOW;
+use crate::{Jail, JailError, JailedPath};
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::raw::{c_char, c_int};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+
+// O_TMPFILE is __O_TMPFILE | O_DIRECTORY, and O_DIRECTORY varies by arch
+#[cfg(any(
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64"
+))]
+const O_TMPFILE: Option<i32> = Some(0o20040000);
+
+#[cfg(any(
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "riscv64",
+    target_arch = "s390x",
+    target_arch = "loongarch64"
+))]
+const O_TMPFILE: Option<i32> = Some(0o20200000);
+
+// Unknown value on other architectures: report Unsupported at runtime
+#[cfg(not(any(
+    target_arch = "arm",
+    target_arch = "aarch64",
+    target_arch = "powerpc",
+    target_arch = "powerpc64",
+    target_arch = "x86",
+    target_arch = "x86_64",
+    target_arch = "riscv64",
+    target_arch = "s390x",
+    target_arch = "loongarch64"
+)))]
+const O_TMPFILE: Option<i32> = None;
+
+const AT_FDCWD: c_int = -100;
+const AT_SYMLINK_FOLLOW: c_int = 0x400;
+
+extern "C" {
+    // Provided by the C library std already links against
+    fn linkat(
+        olddirfd: c_int,
+        oldpath: *const c_char,
+        newdirfd: c_int,
+        newpath: *const c_char,
+        flags: c_int,
+    ) -> c_int;
+}
+
+/// An unnamed file inside a [`Jail`] directory, created with `O_TMPFILE`.
+///
+/// Created by [`Jail::create_anonymous`]. Write to it like any file, then
+/// give it a name with [`link`](Self::link). If it is never linked, the
+/// kernel discards it when the handle is closed.
+#[derive(Debug)]
+pub struct AnonymousFile {
+    jail: Jail,
+    file: File,
+}
+
+impl AnonymousFile {
+    /// Link the file into the jail at `relative`.
+    ///
+    /// The parent is validated like [`Jail::rename`]; the destination must
+    /// not exist, and a symlink there is never followed. The file is held
+    /// to [`max_file_size`](crate::JailBuilder::max_file_size), and its
+    /// size is counted against the [`quota`](crate::JailBuilder::quota) for
+    /// each name it is given.
+    pub fn link<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath, JailError> {
+        let dest = self.jail.entry_path(relative.as_ref())?;
+        let size = self.file.metadata()?.len();
+        self.jail.config().check_file_size(size)?;
+        self.jail.config().resize(0, size)?;
+
+        // linkat() with AT_SYMLINK_FOLLOW on the /proc magic link works
+        // without CAP_DAC_READ_SEARCH (which AT_EMPTY_PATH would need)
+        let source = CString::new(format!("/proc/self/fd/{}", self.file.as_raw_fd()))
+            .expect("no null bytes in fd path");
+        let target = CString::new(dest.as_os_str().as_bytes())
+            .map_err(|_| JailError::InvalidPath("null bytes not allowed".into()))?;
+
+        // SAFETY: both strings are valid and NUL-terminated.
+        let ret = unsafe {
+            linkat(
+                AT_FDCWD,
+                source.as_ptr(),
+                AT_FDCWD,
+                target.as_ptr(),
+                AT_SYMLINK_FOLLOW,
+            )
+        };
+        if ret == -1 {
+            let err = io::Error::last_os_error();
+            self.jail.config().release(size);
+            return Err(err.into());
+        }
+        Ok(JailedPath::new(&self.jail, dest))
+    }
+
+    /// Returns the underlying [`File`].
+    pub fn into_inner(self) -> File {
+        self.file
+    }
+}
+
+impl std::ops::Deref for AnonymousFile {
+    type Target = File;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.file
+    }
+}
+
+impl std::ops::DerefMut for AnonymousFile {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.file
+    }
+}
+
+impl io::Read for AnonymousFile {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl io::Write for AnonymousFile {
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl io::Seek for AnonymousFile {
+    #[inline]
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Jail {
+    /// Create an unnamed file in a directory inside the jail (Linux only).
+    ///
+    /// Uses `O_TMPFILE`, so the data is never visible under a temporary name;
+    /// link it into place with [`AnonymousFile::link`] once complete. The
+    /// directory is opened with `O_NOFOLLOW`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Write;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let mut file = jail.create_anonymous("alice")?;
+    /// file.write_all(b"complete upload")?;
+    /// file.link("alice/upload.bin")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The directory would escape the jail or is a symlink
+    /// - The filesystem does not support `O_TMPFILE` (e.g. some network filesystems)
+    pub fn create_anonymous<P: AsRef<Path>>(&self, dir: P) -> Result<AnonymousFile, JailError> {
+        let Some(o_tmpfile) = O_TMPFILE else {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "O_TMPFILE is not supported on this architecture",
+            )
+            .into());
+        };
+        let dir = self.join(dir)?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .mode(0o600)
+            .custom_flags(o_tmpfile | O_NOFOLLOW)
+            .open(&dir)?;
+        Ok(AnonymousFile {
+            jail: self.clone(),
+            file,
+        })
+    }
+}
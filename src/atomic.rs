@@ -0,0 +1,300 @@
+//! Atomic whole-file writes via a temporary file and rename.
+
+use crate::{Jail, JailError, JailedPath};
+use std::collections::hash_map::RandomState;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+/// How many names to try before giving up on finding a free one.
+pub(crate) const MAX_ATTEMPTS: usize = 16;
+
+/// The longest `base` kept whole in a [`unique_name`], so the name stays
+/// within the usual 255-byte limit (`NAME_MAX`).
+const MAX_BASE_LEN: usize = 255 - ".".len() - ".0123456789abcdef.tmp".len();
+
+/// A hidden, practically unique file name derived from `base`.
+///
+/// Built from the process id, a counter, the clock, and std's randomly
+/// seeded hasher, so no external RNG is needed. A long `base` is cut
+/// short, so any name the filesystem accepts gets a temporary name too.
+pub(crate) fn unique_name(base: &OsStr) -> OsString {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u32(std::process::id());
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    if let Ok(elapsed) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(elapsed.as_nanos());
+    }
+
+    let mut name = OsString::from(".");
+    if base.len() <= MAX_BASE_LEN {
+        name.push(base);
+    } else {
+        let base = base.to_string_lossy();
+        let mut end = MAX_BASE_LEN;
+        while !base.is_char_boundary(end) {
+            end -= 1;
+        }
+        name.push(&base[..end]);
+    }
+    name.push(format!(".{:016x}.tmp", hasher.finish()));
+    name
+}
+
+/// Create a new file exclusively, never following a symlink at `path`.
+///
+/// `mode` is only used on Unix (subject to the umask).
+pub(crate) fn create_new(path: &Path, mode: u32) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    #[cfg(all(feature = "secure-open", unix))]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, crate::open::O_NOFOLLOW);
+    options.open(path)
+}
+
+/// Create a uniquely named file in `dir`, retrying on name collisions.
+pub(crate) fn create_unique(dir: &Path, base: &OsStr, mode: u32) -> io::Result<(File, PathBuf)> {
+    for _ in 0..MAX_ATTEMPTS {
+        let path = dir.join(unique_name(base));
+        match create_new(&path, mode) {
+            Ok(file) => return Ok((file, path)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "could not find a free temporary file name",
+    ))
+}
+
+/// A writer that replaces a file inside the jail atomically.
+///
+/// Created by [`Jail::atomic_writer`]. Data goes to a uniquely named
+/// temporary file next to the destination; [`commit`](Self::commit) syncs it
+/// and renames it into place, so readers see either the old contents or the
+/// complete new contents, never a partial write. Dropping the writer without
+/// committing removes the temporary file.
+#[derive(Debug)]
+pub struct AtomicWriter {
+    jail: Jail,
+    file: Option<File>,
+    temp: PathBuf,
+    dest: PathBuf,
+    /// Bytes written so far, as counted against the quota.
+    written: u64,
+}
+
+impl AtomicWriter {
+    /// Returns the underlying temporary [`File`].
+    pub fn as_file(&self) -> &File {
+        self.file.as_ref().expect("file is present until commit")
+    }
+
+    /// Flush the data to disk and move it into place.
+    ///
+    /// Returns the path of the destination.
+    pub fn commit(mut self) -> Result<JailedPath, JailError> {
+        let file = self.file.take().expect("file is present until commit");
+        file.sync_all()?;
+        drop(file);
+
+        let replaced = self.jail.quota_size(&self.dest);
+        if self.jail.config().is_append_only() {
+            // Link rather than rename, so a file created since is never replaced
+            fs::hard_link(&self.temp, &self.dest)
+                .map_err(|err| self.jail.config().refuse_existing(&self.dest, err.into()))?;
+            fs::remove_file(&self.temp)?;
+        } else {
+            fs::rename(&self.temp, &self.dest)?;
+        }
+        self.temp = PathBuf::new();
+        self.jail.config().release(replaced);
+
+        // Persist the rename itself
+        #[cfg(unix)]
+        if let Some(dir) = self.dest.parent() {
+            File::open(dir)?.sync_all()?;
+        }
+        self.jail.config().notify_created(&self.dest)?;
+
+        Ok(JailedPath::new(&self.jail, std::mem::take(&mut self.dest)))
+    }
+}
+
+impl Write for AtomicWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let requested = buf.len() as u64;
+        let config = self.jail.config();
+        config
+            .check_file_size(self.written + requested)
+            .and_then(|()| config.resize(0, requested))
+            .map_err(io::Error::other)?;
+        let result = self
+            .file
+            .as_mut()
+            .expect("file is present until commit")
+            .write(buf);
+        let written = result.as_ref().map_or(0, |&n| n as u64);
+        self.jail.config().release(requested - written);
+        self.written += written;
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file
+            .as_mut()
+            .expect("file is present until commit")
+            .flush()
+    }
+}
+
+impl Drop for AtomicWriter {
+    fn drop(&mut self) {
+        // Not committed (or the rename failed): don't leave the temp behind
+        if !self.temp.as_os_str().is_empty() && fs::remove_file(&self.temp).is_ok() {
+            self.jail.config().release(self.written);
+        }
+    }
+}
+
+impl Jail {
+    /// Atomically replace the contents of a file inside the jail.
+    ///
+    /// Writes to a temporary file in the same directory and renames it over
+    /// the destination, so concurrent readers never observe a partial write.
+    /// A symlink at the destination is replaced, not written through.
+    ///
+    /// The temporary file is created with `O_EXCL` (and `O_NOFOLLOW` when the
+    /// `secure-open` feature is enabled on Unix). On Unix it takes the
+    /// permission bits of the regular file it replaces, so a private file
+    /// stays private; a new file gets the
+    /// [`file_mode`](crate::JailBuilder::file_mode), if set.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/app")?;
+    /// jail.write_atomic("config/settings.json", br#"{"theme":"dark"}"#)?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn write_atomic<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        relative: P,
+        contents: C,
+    ) -> Result<JailedPath, JailError> {
+        let mut writer = self.atomic_writer(relative)?;
+        writer.write_all(contents.as_ref())?;
+        writer.commit()
+    }
+
+    /// Stream `reader` into a file inside the jail, failing if it yields
+    /// more than `limit` bytes. Returns the number of bytes written.
+    ///
+    /// The data goes through an [`AtomicWriter`], so the file appears with
+    /// its complete contents or not at all: a reader that runs over the
+    /// limit or fails leaves no partial file behind, and an existing file
+    /// is only replaced once everything has arrived. Going over the limit
+    /// fails with [`JailError::FileTooLarge`] as soon as byte `limit + 1`
+    /// is read, with that as the `size`, so at most `limit + 1` bytes are
+    /// read from an oversized source. The jail's
+    /// [`quota`](crate::JailBuilder::quota) and
+    /// [`max_file_size`](crate::JailBuilder::max_file_size) apply too.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let body = std::io::stdin().lock();
+    /// let size = jail.copy_from("alice/upload.bin", body, 10 * 1024 * 1024)?;
+    /// println!("stored {size} bytes");
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn copy_from<P: AsRef<Path>, R: Read>(
+        &self,
+        relative: P,
+        reader: R,
+        limit: u64,
+    ) -> Result<u64, JailError> {
+        let mut writer = self.atomic_writer(relative)?;
+        // One byte more tells a source over the limit from one that fills it
+        let copied = io::copy(&mut reader.take(limit.saturating_add(1)), &mut writer)?;
+        if copied > limit {
+            return Err(JailError::FileTooLarge {
+                size: copied,
+                max: limit,
+            });
+        }
+        writer.commit()?;
+        Ok(copied)
+    }
+
+    /// Start an atomic replacement of a file inside the jail.
+    ///
+    /// See [`AtomicWriter`] and [`write_atomic`](Self::write_atomic).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Write;
+    ///
+    /// let jail = Jail::new("/var/app")?;
+    /// let mut writer = jail.atomic_writer("data/export.csv")?;
+    /// writeln!(writer, "id,name")?;
+    /// writeln!(writer, "1,alice")?;
+    /// writer.commit()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn atomic_writer<P: AsRef<Path>>(&self, relative: P) -> Result<AtomicWriter, JailError> {
+        let dest = self.entry_path(relative.as_ref())?;
+        if fs::symlink_metadata(&dest).is_ok() {
+            self.config().check_append_only(relative.as_ref())?;
+        }
+        let (Some(dir), Some(name)) = (dest.parent(), dest.file_name()) else {
+            return Err(JailError::InvalidPath(
+                "path must name an entry inside the jail".into(),
+            ));
+        };
+        #[cfg(unix)]
+        let perm = self.config().file_mode().unwrap_or(0o666);
+        #[cfg(not(unix))]
+        let perm = 0o666;
+        let (file, temp) = create_unique(dir, name, perm)?;
+        // Keep the mode of the file being replaced, or else use the jail's
+        #[cfg(unix)]
+        let mode = fs::symlink_metadata(&dest)
+            .ok()
+            .filter(|meta| meta.is_file())
+            .map(|meta| std::os::unix::fs::PermissionsExt::mode(&meta.permissions()) & 0o777)
+            .or(self.config().file_mode());
+        #[cfg(unix)]
+        if let Some(mode) = mode {
+            if let Err(err) = crate::perms::set_mode(&file, mode) {
+                let _ = fs::remove_file(&temp);
+                return Err(err.into());
+            }
+        }
+        Ok(AtomicWriter {
+            jail: self.clone(),
+            file: Some(file),
+            temp,
+            dest,
+            written: 0,
+        })
+    }
+}
@@ -0,0 +1,195 @@
+//! Reporting rejected paths
+//! ([`JailBuilder::on_reject`](crate::JailBuilder::on_reject)).
+
+use crate::{JailError, JailErrorKind};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// What the jail was doing when it refused a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Operation {
+    /// Validating a path, on its own ([`Jail::join`](crate::Jail::join))
+    /// or as the first step of a file operation.
+    Validate,
+    /// Checking a file after opening it (or reading its metadata), such
+    /// as for [`reject_hard_links`](crate::JailBuilder::reject_hard_links).
+    Open,
+    /// Overwriting, truncating, renaming or removing, refused by
+    /// [`append_only`](crate::JailBuilder::append_only).
+    Modify,
+}
+
+/// A path the jail refused, passed to the
+/// [`on_reject`](crate::JailBuilder::on_reject) hook.
+#[derive(Debug)]
+pub struct RejectionEvent<'a> {
+    operation: Operation,
+    input: &'a Path,
+    error: &'a JailError,
+    jail_id: Option<&'a str>,
+}
+
+impl<'a> RejectionEvent<'a> {
+    pub(crate) fn new(
+        operation: Operation,
+        input: &'a Path,
+        error: &'a JailError,
+        jail_id: Option<&'a str>,
+    ) -> Self {
+        Self {
+            operation,
+            input,
+            error,
+            jail_id,
+        }
+    }
+
+    /// What the jail was doing.
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    /// The path that was validated, after percent-decoding and Unicode
+    /// normalization if the jail applies them.
+    pub fn input(&self) -> &'a Path {
+        self.input
+    }
+
+    /// Where validation stopped: the symlink, junction, magic link or
+    /// file that was refused. `None` when the input itself was the
+    /// problem, such as `..` climbing out or an absolute path.
+    pub fn path(&self) -> Option<&'a Path> {
+        self.error.path()
+    }
+
+    /// Why the path was refused; also what the caller gets back.
+    pub fn reason(&self) -> &'a JailError {
+        self.error
+    }
+
+    /// Shorthand for `reason().kind()`.
+    pub fn kind(&self) -> JailErrorKind {
+        self.error.kind()
+    }
+
+    /// The [`id`](crate::JailBuilder::id) of the jail, if it was given one.
+    pub fn jail_id(&self) -> Option<&'a str> {
+        self.jail_id
+    }
+
+    /// An owned record of this event, stamped with the current time, to
+    /// queue or ship to a log pipeline.
+    pub fn to_security_event(&self) -> SecurityEvent {
+        SecurityEvent {
+            timestamp: SystemTime::now(),
+            operation: self.operation,
+            input: self.input.to_path_buf(),
+            path: self.path().map(Path::to_path_buf),
+            kind: self.kind(),
+            reason: self.error.to_string(),
+            jail_id: self.jail_id.map(str::to_owned),
+        }
+    }
+}
+
+/// An owned record of a refused path, from
+/// [`RejectionEvent::to_security_event`].
+///
+/// With the `serde` feature it serializes to a flat record with a fixed
+/// schema, ready for a SIEM:
+///
+/// ```json
+/// {
+///   "timestamp": "2024-05-01T12:00:00.000Z",
+///   "operation": "validate",
+///   "input": "../../etc/passwd",
+///   "path": null,
+///   "kind": "escape",
+///   "reason": "path '../../etc/passwd' escapes jail root '/var/uploads'",
+///   "jail_id": "uploads"
+/// }
+/// ```
+///
+/// `timestamp` is RFC 3339 in UTC with milliseconds; `operation` and `kind`
+/// are the snake_case names of [`Operation`] and [`JailErrorKind`]
+/// variants. Paths that are not valid UTF-8 are written lossily.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::Jail;
+/// use std::sync::mpsc;
+///
+/// let (tx, rx) = mpsc::channel();
+/// let jail = Jail::builder("/var/uploads")
+///     .id("uploads")
+///     .on_reject(move |event| {
+///         let _ = tx.send(event.to_security_event());
+///     })
+///     .build()?;
+/// let _ = jail.join("../../etc/passwd");
+/// let event = rx.recv().unwrap();
+/// assert_eq!(event.jail_id.as_deref(), Some("uploads"));
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct SecurityEvent {
+    /// When the event was recorded.
+    pub timestamp: SystemTime,
+    /// What the jail was doing.
+    pub operation: Operation,
+    /// The path that was validated.
+    pub input: PathBuf,
+    /// Where validation stopped, if not at the input itself.
+    pub path: Option<PathBuf>,
+    /// The class of refusal.
+    pub kind: JailErrorKind,
+    /// The error message the caller got.
+    pub reason: String,
+    /// The [`id`](crate::JailBuilder::id) of the jail.
+    pub jail_id: Option<String>,
+}
+
+type Hook = dyn Fn(&RejectionEvent<'_>) + Send + Sync;
+
+/// The hook set with [`JailBuilder::on_reject`](crate::JailBuilder::on_reject),
+/// shared by every clone of a jail.
+#[derive(Clone)]
+pub(crate) struct RejectHook(Arc<Hook>);
+
+impl RejectHook {
+    pub(crate) fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&RejectionEvent<'_>) + Send + Sync + 'static,
+    {
+        Self(Arc::new(hook))
+    }
+
+    /// Report `event`, unless its error is an I/O error rather than a
+    /// refusal.
+    pub(crate) fn report(&self, event: &RejectionEvent<'_>) {
+        if !event.error.is_io() {
+            (self.0)(event);
+        }
+    }
+}
+
+impl fmt::Debug for RejectHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RejectHook(..)")
+    }
+}
+
+// Jails compare by options; two hooks are the same option only if they
+// are the same closure
+impl PartialEq for RejectHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for RejectHook {}
@@ -0,0 +1,212 @@
+//! Batch validation with [`Jail::join_many`] (and `Jail::par_join_many`
+//! with the `rayon` feature).
+
+use crate::jail::{Disk, Lookup, Node};
+use crate::{Access, Jail, JailError};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+impl<A: Access> Jail<A> {
+    /// Validate a batch of relative paths, as [`join`](Self::join) would
+    /// each one.
+    ///
+    /// Filesystem queries are shared across the batch: a directory that
+    /// several paths go through is checked and canonicalized once, not
+    /// once per path, which is most of the cost of validating large
+    /// manifests. Results are in input order, and one failing path does
+    /// not affect the others.
+    ///
+    /// Answers are reused for the whole call, so the batch sees the tree
+    /// as it was when each directory was first reached. For a tree that
+    /// may change while validating, this is the same race as with
+    /// [`join`](Self::join), over the length of the batch.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let manifest = ["site/index.html", "site/css/main.css", "../etc/passwd"];
+    /// for (entry, result) in manifest.iter().zip(jail.join_many(manifest)) {
+    ///     match result {
+    ///         Ok(path) => println!("{entry} -> {}", path.display()),
+    ///         Err(err) => eprintln!("{entry}: {err}"),
+    ///     }
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn join_many<I, P>(&self, paths: I) -> Vec<Result<PathBuf, JailError>>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        if self.config().trusts_tree() {
+            // Resolved lexically, like join: there are no lookups to share
+            return paths
+                .into_iter()
+                .map(|relative| self.join(relative))
+                .collect();
+        }
+        match self.config().dir_cache() {
+            Some(cache) => self.join_all(&Memo::new(&cache.over(&Disk, self.root())), paths),
+            None => self.join_all(&Memo::new(&Disk), paths),
+        }
+    }
+
+    /// Like [`join_many`](Self::join_many), but validating in parallel on
+    /// the rayon thread pool. Results are in input order.
+    ///
+    /// Each worker shares lookups across the paths it handles, so a
+    /// directory may be checked once per worker rather than once per
+    /// batch. Worth it for manifests of thousands of paths; for a few
+    /// dozen, [`join_many`](Self::join_many) is faster.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/ingest")?;
+    /// let manifest: Vec<String> = (0..10_000).map(|i| format!("batch/{i}.json")).collect();
+    /// let results = jail.par_join_many(&manifest);
+    /// assert_eq!(results.len(), manifest.len());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_join_many<I, P>(&self, paths: I) -> Vec<Result<PathBuf, JailError>>
+    where
+        I: rayon::iter::IntoParallelIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        if self.config().trusts_tree() {
+            use rayon::iter::ParallelIterator;
+
+            return paths
+                .into_par_iter()
+                .map(|relative| self.join(relative))
+                .collect();
+        }
+        match self.config().dir_cache() {
+            Some(cache) => self.par_join_all(&cache.over(&Disk, self.root()), paths),
+            None => self.par_join_all(&Disk, paths),
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    fn par_join_all<L, I, P>(&self, fs: &L, paths: I) -> Vec<Result<PathBuf, JailError>>
+    where
+        L: Lookup + Sync,
+        I: rayon::iter::IntoParallelIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        use rayon::iter::ParallelIterator;
+
+        paths
+            .into_par_iter()
+            .map_init(
+                || Memo::new(fs),
+                |fs, relative| {
+                    let path = self.config().prepare(relative.as_ref())?;
+                    self.resolve_in(fs, &path)
+                },
+            )
+            .collect()
+    }
+
+    fn join_all<L, I, P>(&self, fs: &L, paths: I) -> Vec<Result<PathBuf, JailError>>
+    where
+        L: Lookup,
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        paths
+            .into_iter()
+            .map(|relative| {
+                let path = self.config().prepare(relative.as_ref())?;
+                self.resolve_in(fs, &path)
+            })
+            .collect()
+    }
+}
+
+/// A [`Lookup`] that remembers its answers, so paths sharing directories
+/// query each of them once.
+struct Memo<'a, L> {
+    fs: &'a L,
+    exists: RefCell<HashMap<PathBuf, bool>>,
+    is_symlink: RefCell<HashMap<PathBuf, bool>>,
+    is_dir: RefCell<HashMap<PathBuf, bool>>,
+    is_junction: RefCell<HashMap<PathBuf, bool>>,
+    /// Successful results only; errors are not cloneable and are rare.
+    canonical: RefCell<HashMap<PathBuf, PathBuf>>,
+    device: RefCell<HashMap<PathBuf, Option<u64>>>,
+    node: RefCell<HashMap<PathBuf, Node>>,
+}
+
+impl<'a, L: Lookup> Memo<'a, L> {
+    fn new(fs: &'a L) -> Self {
+        Self {
+            fs,
+            exists: RefCell::default(),
+            is_symlink: RefCell::default(),
+            is_dir: RefCell::default(),
+            is_junction: RefCell::default(),
+            canonical: RefCell::default(),
+            device: RefCell::default(),
+            node: RefCell::default(),
+        }
+    }
+}
+
+fn remember<T: Clone>(
+    cache: &RefCell<HashMap<PathBuf, T>>,
+    path: &Path,
+    query: impl FnOnce() -> T,
+) -> T {
+    if let Some(value) = cache.borrow().get(path) {
+        return value.clone();
+    }
+    let value = query();
+    cache.borrow_mut().insert(path.to_path_buf(), value.clone());
+    value
+}
+
+impl<L: Lookup> Lookup for Memo<'_, L> {
+    fn exists(&self, path: &Path) -> bool {
+        remember(&self.exists, path, || self.fs.exists(path))
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        remember(&self.is_symlink, path, || self.fs.is_symlink(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        remember(&self.is_dir, path, || self.fs.is_dir(path))
+    }
+
+    fn is_junction(&self, path: &Path) -> bool {
+        remember(&self.is_junction, path, || self.fs.is_junction(path))
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if let Some(canonical) = self.canonical.borrow().get(path) {
+            return Ok(canonical.clone());
+        }
+        let canonical = self.fs.canonicalize(path)?;
+        self.canonical
+            .borrow_mut()
+            .insert(path.to_path_buf(), canonical.clone());
+        Ok(canonical)
+    }
+
+    fn device(&self, path: &Path) -> Option<u64> {
+        remember(&self.device, path, || self.fs.device(path))
+    }
+
+    fn node(&self, path: &Path) -> Node {
+        remember(&self.node, path, || self.fs.node(path))
+    }
+}
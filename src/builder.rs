@@ -0,0 +1,1610 @@
+//! Configuring a [`Jail`] before it is created.
+
+use crate::audit::{Operation, RejectHook, RejectionEvent};
+use crate::cache::DirCache;
+use crate::deny::{self, DenyRule};
+use crate::jail::{contains_unit, Disk, Lookup};
+use crate::perms::CreateHook;
+use crate::quota::Quota;
+#[cfg(feature = "metrics")]
+use crate::stats::Counters;
+#[cfg(feature = "unicode")]
+use crate::UnicodeForm;
+use crate::{Jail, JailError};
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs::DirBuilder;
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Component, Path, PathBuf, Prefix};
+
+/// Longest path the platform accepts, in the units of [`path_len`]:
+/// `PATH_MAX` minus the terminating NUL on Unix, the `\\?\` limit of
+/// 32,767 UTF-16 units on Windows.
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) const DEFAULT_MAX_PATH_LEN: Option<usize> = Some(4095);
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "android"))))]
+pub(crate) const DEFAULT_MAX_PATH_LEN: Option<usize> = Some(1023);
+#[cfg(windows)]
+pub(crate) const DEFAULT_MAX_PATH_LEN: Option<usize> = Some(32_767);
+#[cfg(not(any(unix, windows)))]
+pub(crate) const DEFAULT_MAX_PATH_LEN: Option<usize> = None;
+
+/// Options shared by every clone of a [`Jail`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Config {
+    percent_decode: bool,
+    allow_alternate_streams: bool,
+    case_insensitive: bool,
+    #[cfg(feature = "unicode")]
+    unicode: Option<UnicodeForm>,
+    /// Lowercase, without the leading dot.
+    allowed_extensions: Option<Vec<String>>,
+    deny: Vec<DenyRule>,
+    max_depth: Option<usize>,
+    max_path_len: Option<usize>,
+    deny_symlinks: bool,
+    /// Canonical directories symlinks may point into from the jail.
+    symlink_targets: Vec<PathBuf>,
+    reject_hard_links: bool,
+    require_secure_root: bool,
+    require_root_owner: bool,
+    /// Check owners above the root too.
+    root_owner_ancestors: bool,
+    /// Owners accepted besides the effective user.
+    root_owners: Vec<u32>,
+    one_file_system: bool,
+    /// Device of the root, recorded at build when `one_file_system` is set.
+    root_dev: Option<u64>,
+    deny_hidden: bool,
+    /// Hidden names accepted despite `deny_hidden`.
+    allow_hidden: Vec<String>,
+    dir_cache: Option<DirCache>,
+    trusted_tree: bool,
+    on_reject: Option<RejectHook>,
+    /// Name for the jail in reports.
+    id: Option<String>,
+    /// Shown instead of the root in `Debug` and error messages.
+    redact_root: Option<String>,
+    /// The canonical root `redact_root` stands for, recorded when the
+    /// jail is created.
+    redacted_root: Option<RedactedRoot>,
+    quota: Option<Quota>,
+    max_file_size: Option<u64>,
+    #[cfg(unix)]
+    file_mode: Option<u32>,
+    #[cfg(unix)]
+    dir_mode: Option<u32>,
+    on_create: Option<CreateHook>,
+    append_only: bool,
+    #[cfg(feature = "metrics")]
+    counters: Counters,
+}
+
+/// The root behind a [`JailBuilder::redact_root`] placeholder, kept out
+/// of `Debug` like the placeholder keeps it out of everything else.
+#[derive(Clone, PartialEq, Eq)]
+struct RedactedRoot(PathBuf);
+
+impl fmt::Debug for RedactedRoot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RedactedRoot(..)")
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            percent_decode: false,
+            allow_alternate_streams: false,
+            case_insensitive: false,
+            #[cfg(feature = "unicode")]
+            unicode: None,
+            allowed_extensions: None,
+            deny: Vec::new(),
+            max_depth: None,
+            max_path_len: DEFAULT_MAX_PATH_LEN,
+            deny_symlinks: false,
+            symlink_targets: Vec::new(),
+            reject_hard_links: false,
+            require_secure_root: false,
+            require_root_owner: false,
+            root_owner_ancestors: false,
+            root_owners: Vec::new(),
+            one_file_system: false,
+            root_dev: None,
+            deny_hidden: false,
+            allow_hidden: Vec::new(),
+            dir_cache: None,
+            trusted_tree: false,
+            on_reject: None,
+            id: None,
+            redact_root: None,
+            redacted_root: None,
+            quota: None,
+            max_file_size: None,
+            #[cfg(unix)]
+            file_mode: None,
+            #[cfg(unix)]
+            dir_mode: None,
+            on_create: None,
+            append_only: false,
+            #[cfg(feature = "metrics")]
+            counters: Counters::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Turn untrusted input into the path to validate.
+    pub(crate) fn prepare<'a>(&self, input: &'a Path) -> Result<Cow<'a, Path>, JailError> {
+        self.prepare_unreported(input)
+            .map_err(|err| self.reject(input, err))
+    }
+
+    /// [`prepare`](Self::prepare) without the [`JailBuilder::on_reject`]
+    /// hook.
+    pub(crate) fn prepare_unreported<'a>(
+        &self,
+        input: &'a Path,
+    ) -> Result<Cow<'a, Path>, JailError> {
+        let path = self.decode(input)?;
+        if cfg!(windows) && !self.allow_alternate_streams && names_stream(&path) {
+            return Err(JailError::InvalidPath(
+                "alternate data streams not allowed".into(),
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Pass a refusal of `input` to the [`JailBuilder::on_reject`] hook,
+    /// returning it for the caller.
+    pub(crate) fn reject(&self, input: &Path, err: JailError) -> JailError {
+        self.report(Operation::Validate, input, err)
+    }
+
+    fn report(&self, operation: Operation, input: &Path, err: JailError) -> JailError {
+        #[cfg(feature = "metrics")]
+        self.counters.reject(err.kind());
+        if let Some(hook) = &self.on_reject {
+            hook.report(&RejectionEvent::new(
+                operation,
+                input,
+                &err,
+                self.id.as_deref(),
+            ));
+        }
+        self.redact(err)
+    }
+
+    /// The [`JailBuilder::id`], if set.
+    pub(crate) fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// The [`JailBuilder::redact_root`] placeholder, if set.
+    pub(crate) fn redact_root(&self) -> Option<&str> {
+        self.redact_root.as_deref()
+    }
+
+    /// Record `root` as the path the [`JailBuilder::redact_root`]
+    /// placeholder stands for.
+    pub(crate) fn bind_root(&mut self, root: &Path) {
+        if self.redact_root.is_some() {
+            self.redacted_root = Some(RedactedRoot(root.to_path_buf()));
+        }
+    }
+
+    /// `path` as it may appear in messages: with the root replaced by the
+    /// [`JailBuilder::redact_root`] placeholder if it is set and `path` is
+    /// under the root.
+    pub(crate) fn shown<'a>(&self, path: &'a Path) -> Cow<'a, Path> {
+        let (Some(placeholder), Some(RedactedRoot(root))) =
+            (&self.redact_root, &self.redacted_root)
+        else {
+            return Cow::Borrowed(path);
+        };
+        match path.strip_prefix(root) {
+            Ok(rest) if rest.as_os_str().is_empty() => Cow::Owned(PathBuf::from(placeholder)),
+            Ok(rest) => Cow::Owned(Path::new(placeholder).join(rest)),
+            Err(_) => Cow::Borrowed(path),
+        }
+    }
+
+    /// `err` with the paths under the root it names shown as
+    /// [`shown`](Self::shown) does.
+    pub(crate) fn redact(&self, err: JailError) -> JailError {
+        if self.redacted_root.is_none() {
+            return err;
+        }
+        let shown = |path: PathBuf| self.shown(&path).into_owned();
+        match err {
+            JailError::EscapedRoot { attempted, root } => JailError::EscapedRoot {
+                attempted: shown(attempted),
+                root: shown(root),
+            },
+            JailError::JunctionEscape { path, root } => JailError::JunctionEscape {
+                path: shown(path),
+                root: shown(root),
+            },
+            JailError::HardLinked { path, links } => JailError::HardLinked {
+                path: shown(path),
+                links,
+            },
+            JailError::InsecureRoot { path, reason } => JailError::InsecureRoot {
+                path: shown(path),
+                reason,
+            },
+            JailError::UntrustedOwner { path, uid } => JailError::UntrustedOwner {
+                path: shown(path),
+                uid,
+            },
+            JailError::BrokenSymlink(path) => JailError::BrokenSymlink(shown(path)),
+            JailError::BrokenJunction(path) => JailError::BrokenJunction(shown(path)),
+            JailError::SymlinkNotAllowed(path) => JailError::SymlinkNotAllowed(shown(path)),
+            JailError::MagicLink(path) => JailError::MagicLink(shown(path)),
+            JailError::CrossDevice(path) => JailError::CrossDevice(shown(path)),
+            JailError::InvalidRoot(path) => JailError::InvalidRoot(shown(path)),
+            JailError::AppendOnly(path) => JailError::AppendOnly(shown(path)),
+            err => err,
+        }
+    }
+
+    /// The [`JailBuilder::quota`], if set.
+    pub(crate) fn quota(&self) -> Option<&Quota> {
+        self.quota.as_ref()
+    }
+
+    /// Account for a file growing or shrinking from `old` to `new` bytes,
+    /// failing if the quota does not allow it.
+    pub(crate) fn resize(&self, old: u64, new: u64) -> Result<(), JailError> {
+        match &self.quota {
+            Some(quota) => quota.resize(old, new),
+            None => Ok(()),
+        }
+    }
+
+    /// The [`JailBuilder::max_file_size`], if set.
+    #[cfg(all(feature = "secure-open", unix))]
+    pub(crate) fn max_file_size(&self) -> Option<u64> {
+        self.max_file_size
+    }
+
+    /// Refuse to write a file of `size` bytes if that is over the
+    /// [`JailBuilder::max_file_size`].
+    pub(crate) fn check_file_size(&self, size: u64) -> Result<(), JailError> {
+        match self.max_file_size {
+            Some(max) if size > max => Err(JailError::FileTooLarge { size, max }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Account for `bytes` removed from the jail.
+    pub(crate) fn release(&self, bytes: u64) {
+        if let Some(quota) = &self.quota {
+            quota.release(bytes);
+        }
+    }
+
+    /// Whether the jail is [`JailBuilder::append_only`].
+    pub(crate) fn is_append_only(&self) -> bool {
+        self.append_only
+    }
+
+    /// Refuse to replace, truncate, rename or remove `input` in an
+    /// [`JailBuilder::append_only`] jail.
+    pub(crate) fn check_append_only(&self, input: &Path) -> Result<(), JailError> {
+        if !self.append_only {
+            return Ok(());
+        }
+        Err(self.report(
+            Operation::Modify,
+            input,
+            JailError::AppendOnly(input.to_path_buf()),
+        ))
+    }
+
+    /// In an [`JailBuilder::append_only`] jail, turn the `AlreadyExists`
+    /// error of an exclusive create of `input` into the refusal it is.
+    pub(crate) fn refuse_existing(&self, input: &Path, err: JailError) -> JailError {
+        match err {
+            JailError::Io(err)
+                if self.append_only && err.kind() == std::io::ErrorKind::AlreadyExists =>
+            {
+                self.report(
+                    Operation::Modify,
+                    input,
+                    JailError::AppendOnly(input.to_path_buf()),
+                )
+            }
+            err => err,
+        }
+    }
+
+    /// The [`JailBuilder::file_mode`], if set.
+    #[cfg(unix)]
+    pub(crate) fn file_mode(&self) -> Option<u32> {
+        self.file_mode
+    }
+
+    /// Whether newly created entries need more than the open or `mkdir`:
+    /// an exact mode or the [`JailBuilder::on_create`] hook.
+    pub(crate) fn tracks_creation(&self) -> bool {
+        #[cfg(unix)]
+        if self.file_mode.is_some() || self.dir_mode.is_some() {
+            return true;
+        }
+        self.on_create.is_some()
+    }
+
+    /// Give a file the jail just created its [`JailBuilder::file_mode`],
+    /// and run the [`JailBuilder::on_create`] hook.
+    pub(crate) fn created_file(&self, path: &Path, file: &std::fs::File) -> std::io::Result<()> {
+        #[cfg(unix)]
+        if let Some(mode) = self.file_mode {
+            crate::perms::set_mode(file, mode)?;
+        }
+        #[cfg(not(unix))]
+        let _ = file;
+        self.notify_created(path)
+    }
+
+    /// Create the directory `path`, with `mode` or else the
+    /// [`JailBuilder::dir_mode`] on Unix, and run the
+    /// [`JailBuilder::on_create`] hook.
+    pub(crate) fn create_dir(&self, path: &Path, mode: Option<u32>) -> std::io::Result<()> {
+        #[cfg(unix)]
+        {
+            let mut builder = DirBuilder::new();
+            // An explicit mode keeps its documented umask behavior
+            let exact = if mode.is_some() { None } else { self.dir_mode };
+            if let Some(mode) = mode {
+                builder.mode(mode);
+            } else if let Some(mode) = exact {
+                // Readable by the owner until set_mode, to open it
+                builder.mode(mode | 0o400);
+            }
+            builder.create(path)?;
+            if let Some(mode) = exact {
+                crate::perms::set_mode(&crate::perms::open_dir(path)?, mode)?;
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = mode;
+            DirBuilder::new().create(path)?;
+        }
+        self.notify_created(path)
+    }
+
+    /// Run the [`JailBuilder::on_create`] hook, if set.
+    pub(crate) fn notify_created(&self, path: &Path) -> std::io::Result<()> {
+        match &self.on_create {
+            Some(hook) => hook.call(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Whether writes are counted against a quota.
+    pub(crate) fn has_quota(&self) -> bool {
+        self.quota.is_some()
+    }
+
+    /// The counters behind [`Jail::stats`].
+    #[cfg(feature = "metrics")]
+    pub(crate) fn counters(&self) -> &Counters {
+        &self.counters
+    }
+
+    /// Count a path validation (`metrics` feature).
+    #[inline]
+    pub(crate) fn count_join(&self) {
+        #[cfg(feature = "metrics")]
+        self.counters.join();
+    }
+
+    /// Count a file opened through the jail (`metrics` feature).
+    #[inline]
+    pub(crate) fn count_open(&self) {
+        #[cfg(feature = "metrics")]
+        self.counters.open();
+    }
+
+    /// Count a symlink followed while validating (`metrics` feature).
+    #[inline]
+    pub(crate) fn count_symlink(&self) {
+        #[cfg(feature = "metrics")]
+        self.counters.symlink();
+    }
+
+    /// The rewriting half of [`prepare`](Self::prepare): percent-decoding
+    /// and Unicode normalization, without the checks on the result.
+    pub(crate) fn decode<'a>(&self, input: &'a Path) -> Result<Cow<'a, Path>, JailError> {
+        let path = if self.percent_decode {
+            Cow::Owned(crate::percent::decode_path(input)?)
+        } else {
+            Cow::Borrowed(input)
+        };
+        #[cfg(feature = "unicode")]
+        let path = match self.unicode {
+            Some(form) => form.apply(path),
+            None => path,
+        };
+        Ok(path)
+    }
+
+    /// These options without percent-decoding, for names that are not
+    /// URL input, such as archive entry names.
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    pub(crate) fn undecoded(&self) -> Config {
+        Config {
+            percent_decode: false,
+            ..self.clone()
+        }
+    }
+
+    /// These options without the input preprocessing of
+    /// [`prepare`](Self::prepare), for paths the jail produced itself.
+    pub(crate) fn verbatim(&self) -> Config {
+        Config {
+            percent_decode: false,
+            allow_alternate_streams: true,
+            #[cfg(feature = "unicode")]
+            unicode: None,
+            ..self.clone()
+        }
+    }
+
+    /// Whether [`check_file`](Self::check_file) has anything to check, so
+    /// callers can skip the `is_dir` lookup it needs.
+    pub(crate) fn restricts_files(&self) -> bool {
+        self.allowed_extensions.is_some()
+    }
+
+    /// Apply the file policies to a resolved path that is not a directory.
+    pub(crate) fn check_file(&self, path: &Path) -> Result<(), JailError> {
+        if let Some(allowed) = &self.allowed_extensions {
+            let name = path.file_name().unwrap_or_default();
+            if !has_extension(name, allowed) {
+                return Err(JailError::InvalidPath(format!(
+                    "file extension not allowed: '{}'",
+                    name.to_string_lossy()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reject `path` before any lookup if it has too many components.
+    pub(crate) fn check_depth(&self, path: &Path) -> Result<(), JailError> {
+        let Some(max) = self.max_depth else {
+            return Ok(());
+        };
+        let depth = path
+            .components()
+            .filter(|c| !matches!(c, Component::CurDir))
+            .count();
+        if depth > max {
+            return Err(JailError::TooDeep { depth, max });
+        }
+        Ok(())
+    }
+
+    /// Reject a joined path longer than the configured limit.
+    pub(crate) fn check_len(&self, path: &Path) -> Result<(), JailError> {
+        if let Some(max) = self.max_path_len {
+            let len = path_len(path);
+            if len > max {
+                return Err(JailError::TooLong { len, max });
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether symlinks are rejected even when they resolve inside.
+    #[inline]
+    pub(crate) fn denies_symlinks(&self) -> bool {
+        self.deny_symlinks
+    }
+
+    /// Apply the hidden-file policy and the deny rules to one path
+    /// component.
+    #[inline]
+    pub(crate) fn check_name(&self, name: &OsStr) -> Result<(), JailError> {
+        if self.deny_hidden
+            && name.as_encoded_bytes().starts_with(b".")
+            && !self
+                .allow_hidden
+                .iter()
+                .any(|allowed| name == allowed.as_str())
+        {
+            return Err(JailError::InvalidPath(format!(
+                "hidden file '{}' not allowed",
+                name.to_string_lossy()
+            )));
+        }
+        deny::check(&self.deny, name)
+    }
+
+    /// Whether [`check_name`](Self::check_name) can reject anything.
+    #[inline]
+    pub(crate) fn denies_names(&self) -> bool {
+        self.deny_hidden || !self.deny.is_empty()
+    }
+
+    /// Reject an opened regular file with more than one link.
+    pub(crate) fn check_links(
+        &self,
+        path: &Path,
+        meta: &std::fs::Metadata,
+    ) -> Result<(), JailError> {
+        #[cfg(unix)]
+        if self.reject_hard_links && meta.is_file() {
+            let links = std::os::unix::fs::MetadataExt::nlink(meta);
+            if links > 1 {
+                return Err(self.report(
+                    Operation::Open,
+                    path,
+                    JailError::HardLinked {
+                        path: path.to_path_buf(),
+                        links,
+                    },
+                ));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = (path, meta);
+        Ok(())
+    }
+
+    /// Whether [`JailBuilder::trusted_tree`] replaces resolution with
+    /// lexical joining.
+    #[inline]
+    pub(crate) fn trusts_tree(&self) -> bool {
+        self.trusted_tree
+    }
+
+    /// The [`JailBuilder::verification_cache`], if enabled.
+    #[inline]
+    pub(crate) fn dir_cache(&self) -> Option<&DirCache> {
+        self.dir_cache.as_ref()
+    }
+
+    /// Reject an existing path on a different device than the root.
+    pub(crate) fn check_device<L: Lookup>(&self, fs: &L, path: &Path) -> Result<(), JailError> {
+        match self.root_dev {
+            Some(dev) if fs.device(path).is_some_and(|other| other != dev) => {
+                Err(JailError::CrossDevice(path.to_path_buf()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply [`JailBuilder::require_secure_root`] and
+    /// [`JailBuilder::require_root_owner`] to a new root.
+    pub(crate) fn check_root(&self, root: &Path) -> Result<(), JailError> {
+        if self.require_secure_root {
+            crate::perms::check_root(root)?;
+        }
+        if self.require_root_owner {
+            crate::perms::check_owner(root, &self.root_owners, self.root_owner_ancestors)?;
+        }
+        Ok(())
+    }
+
+    /// Whether any [`JailBuilder::allow_symlink_target`] was set.
+    #[cfg(all(feature = "openat", unix))]
+    #[inline]
+    pub(crate) fn has_symlink_targets(&self) -> bool {
+        !self.symlink_targets.is_empty()
+    }
+
+    /// Whether a canonical path outside the root is under one of the
+    /// allowed symlink targets.
+    pub(crate) fn allows_symlink_target(&self, canonical: &Path) -> bool {
+        self.symlink_targets
+            .iter()
+            .any(|target| self.strip_base(canonical, target).is_some())
+    }
+
+    /// The part of an absolute `path` below `base`, or `None` if it is not
+    /// under `base`. Honors [`JailBuilder::case_insensitive`].
+    pub(crate) fn strip_base<'a>(&self, path: &'a Path, base: &Path) -> Option<&'a Path> {
+        if let Ok(rest) = path.strip_prefix(base) {
+            return Some(rest);
+        }
+        let mut components = path.components();
+        for expected in base.components() {
+            let same = match (components.next()?, expected) {
+                (Component::Prefix(a), Component::Prefix(b)) => same_volume(a.kind(), b.kind()),
+                (found, expected) => self.same_name(found.as_os_str(), expected.as_os_str()),
+            };
+            if !same {
+                return None;
+            }
+        }
+        Some(components.as_path())
+    }
+
+    /// Whether two path components name the same entry.
+    pub(crate) fn same_name(&self, a: &OsStr, b: &OsStr) -> bool {
+        if a == b {
+            return true;
+        }
+        match (self.case_insensitive, a.to_str(), b.to_str()) {
+            (true, Some(a), Some(b)) => a.to_lowercase() == b.to_lowercase(),
+            _ => false,
+        }
+    }
+
+    /// Bring a root-relative path read back from disk into the configured form.
+    pub(crate) fn relative_output(&self, path: PathBuf) -> PathBuf {
+        #[cfg(feature = "unicode")]
+        if let Some(form) = self.unicode {
+            return form.apply(Cow::Owned(path)).into_owned();
+        }
+        path
+    }
+}
+
+/// Whether two Windows path prefixes name the same volume, in plain or
+/// verbatim spelling: `C:` and `\\?\C:`, or `\\server\share` and
+/// `\\?\UNC\server\share`. Drive letters, servers and shares are
+/// compared ignoring ASCII case, as Windows does; a different share on the
+/// same server is a different volume. Other prefixes (`\\.\pipe`,
+/// `\\?\GLOBALROOT`) must match exactly.
+fn same_volume(a: Prefix<'_>, b: Prefix<'_>) -> bool {
+    match (a, b) {
+        (Prefix::Disk(a) | Prefix::VerbatimDisk(a), Prefix::Disk(b) | Prefix::VerbatimDisk(b)) => {
+            a.eq_ignore_ascii_case(&b)
+        }
+        (
+            Prefix::UNC(a_server, a_share) | Prefix::VerbatimUNC(a_server, a_share),
+            Prefix::UNC(b_server, b_share) | Prefix::VerbatimUNC(b_server, b_share),
+        ) => a_server.eq_ignore_ascii_case(b_server) && a_share.eq_ignore_ascii_case(b_share),
+        (a, b) => a == b,
+    }
+}
+
+/// Whether a component uses NTFS stream syntax (`file.txt:hidden:$DATA`).
+/// Drive prefixes are `Component::Prefix`, not `Normal`, so they don't count.
+fn names_stream(path: &Path) -> bool {
+    path.components().any(|c| match c {
+        Component::Normal(name) => contains_unit(name, b':'),
+        _ => false,
+    })
+}
+
+/// Length of `path` as the OS measures it: bytes on Unix, UTF-16 code
+/// units on Windows.
+fn path_len(path: &Path) -> usize {
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        path.as_os_str().encode_wide().count()
+    }
+    #[cfg(not(windows))]
+    path.as_os_str().len()
+}
+
+/// Whether `name` ends in one of `allowed` (lowercase, no leading dot).
+///
+/// Only the full suffix counts, so `photo.png.exe` is an `exe` and `.png`
+/// (a hidden file with no extension) matches nothing. Names ending in a dot
+/// or space never match: Windows drops those, so `shell.php.` would be
+/// stored as `shell.php`.
+fn has_extension(name: &OsStr, allowed: &[String]) -> bool {
+    let Some(name) = name.to_str() else {
+        return false;
+    };
+    if name.ends_with(['.', ' ']) {
+        return false;
+    }
+    let name = name.to_ascii_lowercase();
+    allowed.iter().any(|ext| {
+        name.len() > ext.len() + 1
+            && name.ends_with(ext.as_str())
+            && name[..name.len() - ext.len()].ends_with('.')
+    })
+}
+
+/// Builder for a [`Jail`] with non-default options.
+///
+/// Created by [`Jail::builder`], or by [`Jail::to_builder`] to start from
+/// an existing jail's options. [`Jail::new`] is equivalent to
+/// `Jail::builder(root).build()`. Every option is a method here rather
+/// than a constructor variant on [`Jail`]; they fall into these groups:
+///
+/// - input preprocessing: [`percent_decode`](Self::percent_decode),
+///   `normalize_unicode` (`unicode` feature)
+/// - how paths are compared: [`case_insensitive`](Self::case_insensitive),
+///   [`allow_alternate_streams`](Self::allow_alternate_streams),
+///   [`deny_symlinks`](Self::deny_symlinks),
+///   [`allow_symlink_target`](Self::allow_symlink_target),
+///   [`one_file_system`](Self::one_file_system)
+/// - what is accepted: [`max_depth`](Self::max_depth),
+///   [`max_path_len`](Self::max_path_len),
+///   [`allowed_extensions`](Self::allowed_extensions),
+///   [`reject_hard_links`](Self::reject_hard_links),
+///   [`require_secure_root`](Self::require_secure_root),
+///   [`require_root_owner`](Self::require_root_owner),
+///   [`deny_hidden`](Self::deny_hidden), [`deny`](Self::deny)
+/// - performance: [`verification_cache`](Self::verification_cache),
+///   [`trusted_tree`](Self::trusted_tree)
+/// - setup: [`create_root`](Self::create_root)
+///
+/// Options travel with the jail: clones, [`Jail::read_only`] and
+/// [`Jail::subjail`] keep them.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::Jail;
+///
+/// let jail = Jail::builder("/var/uploads")
+///     .percent_decode(true)
+///     .build()?;
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone)]
+#[must_use = "call build() to create the jail"]
+pub struct JailBuilder {
+    root: PathBuf,
+    config: Config,
+    // Validated in build(), which is where errors can be reported
+    deny_patterns: Vec<String>,
+    symlink_targets: Vec<PathBuf>,
+    create_root: bool,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    root_mode: Option<u32>,
+}
+
+impl JailBuilder {
+    /// Percent-decode every relative path once before validation
+    /// (default `false`).
+    ///
+    /// For services that receive raw URL segments: `%2e%2e%2f` is decoded
+    /// to `../` and rejected like any other traversal. Input that still
+    /// contains an encoded `.`, `/`, `\`, `%` or NUL after decoding was
+    /// encoded twice and is rejected with [`JailError::InvalidPath`], as
+    /// are malformed escapes. Applies to every method taking a relative
+    /// path, including [`Jail::join`].
+    pub fn percent_decode(mut self, enable: bool) -> Self {
+        self.config.percent_decode = enable;
+        self
+    }
+
+    /// Accept `:` in path components on Windows (default `false`).
+    ///
+    /// On NTFS, `file.txt:hidden` or `file.txt:hidden:$DATA` names an
+    /// alternate data stream of `file.txt`: data written there is invisible
+    /// to directory listings and size checks. By default such paths are
+    /// rejected with [`JailError::InvalidPath`]. Has no effect on other
+    /// platforms, where `:` is an ordinary filename character.
+    pub fn allow_alternate_streams(mut self, allow: bool) -> Self {
+        self.config.allow_alternate_streams = allow;
+        self
+    }
+
+    /// Compare absolute paths against the root case-insensitively
+    /// (default `false`).
+    ///
+    /// For jails on case-insensitive filesystems (Windows, default macOS),
+    /// where an absolute path handed to [`Jail::contains`],
+    /// [`Jail::relative`] or used as a symlink target may spell the root
+    /// with different casing than the canonical root. Names below the root
+    /// are still resolved by the filesystem, so this never makes two
+    /// different entries equal. Comparison uses Unicode lowercase; do not
+    /// enable it on case-sensitive filesystems.
+    pub fn case_insensitive(mut self, enable: bool) -> Self {
+        self.config.case_insensitive = enable;
+        self
+    }
+
+    /// Normalize relative paths to `form` before validation (`unicode`
+    /// feature; default `None`).
+    ///
+    /// Applied after [`percent_decode`](Self::percent_decode), and to the
+    /// result of [`Jail::relative`], so `café` typed as NFC and read back
+    /// from a macOS directory listing as NFD is one and the same path.
+    /// Names on disk are not renamed: pick the form your files are stored
+    /// in. Paths that are not valid UTF-8 are left unchanged.
+    #[cfg(feature = "unicode")]
+    pub fn normalize_unicode(mut self, form: Option<UnicodeForm>) -> Self {
+        self.config.unicode = form;
+        self
+    }
+
+    /// Only accept files whose name ends in one of `extensions`
+    /// (default: any name).
+    ///
+    /// Checked on the resolved path by [`Jail::join`] and everything built
+    /// on it, so a symlink named `photo.png` pointing at `shell.php` is
+    /// rejected too; operations on the entry itself (rename, remove,
+    /// symlink creation) check its own name, and `read_dir`, `walk` and
+    /// `glob` leave other files out. Existing directories are exempt.
+    /// Extensions are
+    /// compared ASCII case-insensitively, may be given with or without the
+    /// leading dot, and may span several dots (`"tar.gz"`). Only the final
+    /// suffix counts: `photo.png.exe` is rejected when `png` is allowed,
+    /// as are `.png` (no extension at all), names ending in a dot or space,
+    /// and names that are not valid UTF-8. Failures are
+    /// [`JailError::InvalidPath`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/var/uploads")
+    ///     .allowed_extensions(["png", "jpg", "pdf"])
+    ///     .build()?;
+    /// assert!(jail.join("alice/photo.PNG").is_ok());
+    /// assert!(jail.join("alice/photo.png.exe").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn allowed_extensions<I, S>(mut self, extensions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let extensions = extensions
+            .into_iter()
+            .map(|ext| ext.as_ref().trim_start_matches('.').to_ascii_lowercase())
+            .collect();
+        self.config.allowed_extensions = Some(extensions);
+        self
+    }
+
+    /// Reject relative paths with more than `max` components
+    /// (default: no limit).
+    ///
+    /// [`Jail::join`] looks up every component on disk, so a path thousands
+    /// of components deep costs thousands of system calls. With a limit,
+    /// such input fails with [`JailError::TooDeep`] before the first one.
+    /// Every component counts, `..` included; `.` does not.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let jail = Jail::builder("/var/uploads").max_depth(16).build()?;
+    /// let deep = "a/".repeat(1000);
+    /// assert!(matches!(jail.join(&deep), Err(JailError::TooDeep { .. })));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.config.max_depth = Some(max);
+        self
+    }
+
+    /// Reject joined paths longer than `max`, or set `None` for no limit.
+    ///
+    /// The length is that of the full path [`Jail::join`] returns, root
+    /// included, in bytes (UTF-16 code units on Windows). The default is
+    /// the platform limit: 4095 on Linux, 1023 on other Unix systems
+    /// (`PATH_MAX` without the NUL), 32,767 on Windows (`\\?\` paths).
+    /// Longer paths fail with [`JailError::TooLong`] at validation time
+    /// instead of `ENAMETOOLONG` at first use. Lower it when paths are
+    /// also stored elsewhere, e.g. in a database column.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let jail = Jail::builder("/var/uploads")
+    ///     .max_path_len(Some(255))
+    ///     .build()?;
+    /// let long = "x".repeat(300);
+    /// assert!(matches!(jail.join(&long), Err(JailError::TooLong { .. })));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn max_path_len(mut self, max: Option<usize>) -> Self {
+        self.config.max_path_len = max;
+        self
+    }
+
+    /// Reject any path that passes through a symlink, even one resolving
+    /// inside the jail (default `false`).
+    ///
+    /// By default a symlink is fine as long as its target is inside the
+    /// root. With this set, every component of the path must be a real
+    /// directory or file: [`Jail::join`] fails with
+    /// [`JailError::SymlinkNotAllowed`] at the first symlink (or, on
+    /// Windows, junction), [`Jail::read_dir`] and [`Jail::walk`] yield the
+    /// same error for symlinked entries, and creating symlinks with
+    /// `Jail::symlink` is refused. Removing or renaming a symlink entry
+    /// itself is still possible, so existing links can be cleaned up.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let jail = Jail::builder("/var/uploads").deny_symlinks(true).build()?;
+    /// match jail.join("alice/latest/report.pdf") {
+    ///     Err(JailError::SymlinkNotAllowed(link)) => eprintln!("link at {}", link.display()),
+    ///     other => println!("{:?}", other),
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn deny_symlinks(mut self, enable: bool) -> Self {
+        self.config.deny_symlinks = enable;
+        self
+    }
+
+    /// Refuse a root that other users can write to (default `false`;
+    /// Unix only).
+    ///
+    /// Path validation assumes nobody else can rearrange the tree between
+    /// [`Jail::join`] and the actual I/O. If the root, or any directory
+    /// above it, is world-writable without the sticky bit, or
+    /// group-writable by a group other than the process's effective
+    /// group, that assumption fails and symlink swaps become trivial, so
+    /// [`build`](Self::build) fails with [`JailError::InsecureRoot`].
+    /// Sticky directories such as `/tmp` pass. [`Jail::subjail`] checks
+    /// its new root the same way. ACLs are not inspected.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/var/uploads")
+    ///     .require_secure_root(true)
+    ///     .build()?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn require_secure_root(mut self, enable: bool) -> Self {
+        self.config.require_secure_root = enable;
+        self
+    }
+
+    /// Refuse a root that is not owned by the current user (default
+    /// `false`; Unix only).
+    ///
+    /// The root must be owned by the effective uid or by a uid passed to
+    /// [`allow_root_owner`](Self::allow_root_owner), or
+    /// [`build`](Self::build) fails with [`JailError::UntrustedOwner`].
+    /// With `ancestors`, every directory above the root is checked too,
+    /// and directories owned by uid 0 also pass, the rule OpenSSH applies
+    /// to `~/.ssh`. [`Jail::subjail`] checks its new root the same way.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// // /srv/app/uploads owned by us, /srv and /srv/app by root or us
+    /// let jail = Jail::builder("/srv/app/uploads")
+    ///     .require_root_owner(true, true)
+    ///     .build()?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn require_root_owner(mut self, enable: bool, ancestors: bool) -> Self {
+        self.config.require_root_owner = enable;
+        self.config.root_owner_ancestors = ancestors;
+        self
+    }
+
+    /// Also accept `uid` as an owner for
+    /// [`require_root_owner`](Self::require_root_owner), e.g. a deploy
+    /// user that owns the tree the service reads. Call once per uid.
+    pub fn allow_root_owner(mut self, uid: u32) -> Self {
+        self.config.root_owners.push(uid);
+        self
+    }
+
+    /// Reject paths that lead onto a different filesystem than the root
+    /// (default `false`; Unix only).
+    ///
+    /// Like `find -xdev` or `rsync --one-file-system`: the root's device
+    /// id is recorded by [`build`](Self::build), and every existing
+    /// component a path resolves through must be on the same device, or
+    /// [`Jail::join`] fails with [`JailError::CrossDevice`]. This stops
+    /// escapes through bind mounts (or any other filesystem) mounted
+    /// inside the jail, which path checks alone cannot see.
+    /// [`Jail::read_dir`] and [`Jail::walk`] report mounted directories
+    /// with the same error. Directories under
+    /// [`allow_symlink_target`](Self::allow_symlink_target) are trusted
+    /// and exempt. Has no effect on other platforms or for in-memory
+    /// jails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let jail = Jail::builder("/var/uploads").one_file_system(true).build()?;
+    /// match jail.join("alice/mnt/etc/passwd") {
+    ///     Err(JailError::CrossDevice(mount)) => eprintln!("mount at {}", mount.display()),
+    ///     other => println!("{:?}", other),
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn one_file_system(mut self, enable: bool) -> Self {
+        self.config.one_file_system = enable;
+        self
+    }
+
+    /// Accept symlinks from inside the jail to anywhere under `dir`
+    /// (default: none).
+    ///
+    /// For shared read-only assets linked into every user's jail, such as
+    /// `/usr/share/fonts`. A symlink whose target resolves under `dir` is
+    /// accepted instead of failing with [`JailError::EscapedRoot`]; the
+    /// returned path goes through the link (`<root>/fonts/DejaVuSans.ttf`),
+    /// so it still names the file from inside the root and
+    /// [`Jail::relative`] keeps working. Everything else is checked as
+    /// usual: symlinks inside `dir` that lead elsewhere, and `..` back out
+    /// of it, are rejected. Absolute paths under `dir` given to
+    /// [`Jail::contains`] are not accepted; only paths reached through a
+    /// link are. Call once per directory. Each must exist; it is
+    /// canonicalized by [`build`](Self::build), which fails with
+    /// [`JailError::InvalidPath`] otherwise. Has no effect with
+    /// [`deny_symlinks`](Self::deny_symlinks).
+    ///
+    /// The jail does not make anything read-only: pair it with
+    /// [`Jail::read_only`] or filesystem permissions if `dir` must not be
+    /// written through the link.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// // /home/alice/fonts -> /usr/share/fonts
+    /// let jail = Jail::builder("/home/alice")
+    ///     .allow_symlink_target("/usr/share/fonts")
+    ///     .build()?;
+    /// assert!(jail.join("fonts/DejaVuSans.ttf").is_ok());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn allow_symlink_target<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.symlink_targets.push(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Refuse to open, read, write or report metadata of regular files
+    /// with more than one hard link (default `false`; Unix only).
+    ///
+    /// Hard links are invisible to path checks: if `/etc/shadow` has been
+    /// hard-linked into the jail (by someone with access to both), the
+    /// link is an ordinary file inside the root. Such files fail with
+    /// [`JailError::HardLinked`]. The check runs on the opened file, so it
+    /// cannot be raced, and before anything is truncated. Covers
+    /// [`Jail::read`], [`Jail::write`], [`Jail::metadata`] and, with
+    /// `secure-open`, `open`, `create_or_truncate`, `open_append` and
+    /// `copy`. Files that legitimately share links (e.g. deduplicated
+    /// stores) can't be used with this set; to only flag them, leave it
+    /// off and inspect `nlink()` from [`Jail::metadata`].
+    pub fn reject_hard_links(mut self, enable: bool) -> Self {
+        self.config.reject_hard_links = enable;
+        self
+    }
+
+    /// Reject any path with a component starting with `.` (default
+    /// `false`).
+    ///
+    /// Dotfiles such as `.git`, `.ssh` or `.env` are a common source of
+    /// leaks from served or shared directories. Like [`deny`](Self::deny),
+    /// this is checked on every component of the requested path and of the
+    /// resolved path; `.` and `..` themselves are not affected. Names
+    /// passed to [`allow_hidden`](Self::allow_hidden) are exempt. Failures
+    /// are [`JailError::InvalidPath`]. `read_dir`, `walk` and `glob` leave
+    /// hidden entries out.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/var/www")
+    ///     .deny_hidden(true)
+    ///     .allow_hidden(".well-known")
+    ///     .build()?;
+    /// assert!(jail.join(".git/config").is_err());
+    /// assert!(jail.join("../www/.env").is_err());
+    /// assert!(jail.join(".well-known/security.txt").is_ok());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn deny_hidden(mut self, enable: bool) -> Self {
+        self.config.deny_hidden = enable;
+        self
+    }
+
+    /// Accept the hidden name `name` despite
+    /// [`deny_hidden`](Self::deny_hidden). Compared exactly, case included.
+    pub fn allow_hidden<S: Into<String>>(mut self, name: S) -> Self {
+        self.config.allow_hidden.push(name.into());
+        self
+    }
+
+    /// Reject any path with a component matching `pattern`, e.g.
+    /// `.htaccess`, `.env` or `*.php`.
+    ///
+    /// The pattern is a single name, optionally with the wildcards of
+    /// [`Jail::glob`] (`*`, `?`, `[a-z]`, `[!a]`); unlike `glob`, `*` also
+    /// matches names starting with `.`. Matching ignores case, so `*.php`
+    /// also stops `shell.PHP`. Every component of the requested path is
+    /// checked, as is every component below the root of the resolved path,
+    /// so a symlink named `notes.txt` pointing at `.env` is rejected too.
+    /// `read_dir`, `walk` and `glob` leave matching entries out, and do not
+    /// descend into matching directories. Failures are
+    /// [`JailError::InvalidPath`]; so is a pattern containing a separator
+    /// or an unclosed `[`, reported by [`build`](Self::build).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/var/www/uploads")
+    ///     .deny(".htaccess")
+    ///     .deny("*.php")
+    ///     .build()?;
+    /// assert!(jail.join("alice/shell.php").is_err());
+    /// assert!(jail.join(".htaccess").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn deny<S: Into<String>>(mut self, pattern: S) -> Self {
+        self.deny_patterns.push(pattern.into());
+        self
+    }
+
+    /// Remember, for `ttl`, the canonical form of each directory that
+    /// validation resolves (default off).
+    ///
+    /// Every [`Jail::join`] canonicalizes each existing component of the
+    /// path, so a server joining many paths under the same directories
+    /// repeats the same lookups. With the cache, a directory checked within
+    /// the last `ttl` is not looked up again; the final component of a
+    /// path is still looked up unless it is itself a cached directory. The
+    /// cache is shared by clones of the jail and bounded in size.
+    ///
+    /// This widens the window in which changes to the tree go unnoticed:
+    /// a cached directory replaced by a symlink is trusted until its entry
+    /// expires. Only enable it for trees that untrusted parties cannot
+    /// modify, or where files are then opened with the `secure-open` and
+    /// `openat` protections. Removing and renaming through the jail drops
+    /// the affected entries; after changing the tree by other means, call
+    /// [`Jail::clear_verification_cache`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::time::Duration;
+    ///
+    /// let jail = Jail::builder("/srv/static")
+    ///     .verification_cache(Duration::from_secs(5))
+    ///     .build()?;
+    /// let a = jail.join("assets/img/logo.png")?; // looks up assets, assets/img
+    /// let b = jail.join("assets/img/icon.png")?; // only icon.png
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn verification_cache(mut self, ttl: std::time::Duration) -> Self {
+        self.config.dir_cache = Some(DirCache::new(ttl));
+        self
+    }
+
+    /// Declare that the tree under the root contains no symlinks and
+    /// cannot be modified by anyone untrusted (default `false`).
+    ///
+    /// [`Jail::join`] then does what [`Jail::join_lexical`] does: `.` and
+    /// `..` are resolved on the string and the result is checked to stay
+    /// under the root, with no lookups on disk (one `stat` of the final
+    /// path if [`allowed_extensions`](Self::allowed_extensions) is set, to
+    /// exempt directories). Input preprocessing, name rules and limits
+    /// still apply; [`deny_symlinks`](Self::deny_symlinks) and
+    /// [`one_file_system`](Self::one_file_system) are not checked. The
+    /// root itself is still canonicalized and checked at build time.
+    ///
+    /// Meant for read-only asset trees baked into an image. **If the
+    /// assertion is wrong, a symlink in the tree escapes the jail**: only
+    /// set this for directories your own build produced.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let assets = Jail::builder("/app/static")
+    ///     .trusted_tree(true)
+    ///     .build()?
+    ///     .read_only();
+    /// let css = assets.join("css/../css/site.css")?; // no filesystem access
+    /// assert!(assets.join("../secrets").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn trusted_tree(mut self, enable: bool) -> Self {
+        self.config.trusted_tree = enable;
+        self
+    }
+
+    /// Call `hook` for every path the jail refuses (default none).
+    ///
+    /// The hook sees each refusal from validation and from the checks
+    /// when opening files, with the input, where validation stopped and
+    /// the error the caller gets back; I/O errors are not refusals and are
+    /// not reported. It runs on the calling thread before the error is
+    /// returned, so keep it quick (log, count, queue an alert). Clones of
+    /// the jail, sub-jails and read-only views share the hook.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/var/uploads")
+    ///     .on_reject(|event| {
+    ///         if event.reason().is_escape_attempt() {
+    ///             eprintln!("ALERT: traversal attempt {:?}", event.input());
+    ///         }
+    ///     })
+    ///     .build()?;
+    /// assert!(jail.join("../../etc/passwd").is_err()); // alerts
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn on_reject<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&RejectionEvent<'_>) + Send + Sync + 'static,
+    {
+        self.config.on_reject = Some(RejectHook::new(hook));
+        self
+    }
+
+    /// Name the jail in [`on_reject`](Self::on_reject) events and
+    /// [`SecurityEvent`](crate::SecurityEvent)s, e.g. a tenant or volume
+    /// name (default none). Unlike the root, the id can be shown to log
+    /// readers without revealing the server's layout.
+    pub fn id<S: Into<String>>(mut self, id: S) -> Self {
+        self.config.id = Some(id.into());
+        self
+    }
+
+    /// Show `placeholder`, such as `<jail>`, instead of the root in the
+    /// `Debug` output of the jail and its [`JailedPath`](crate::JailedPath)s
+    /// and in the `Display` of [`JailError`]s (default: the real path).
+    ///
+    /// Keeps the server's directory layout out of logs, panic messages and
+    /// error responses that format these values without thinking about
+    /// it. Paths keep their part below the root: `<jail>/alice/a.txt`.
+    /// Errors this jail returns carry the placeholder in their path fields
+    /// and messages; a sub-jail's paths show the part below this root.
+    /// Other jails, the [`on_reject`](Self::on_reject) hook,
+    /// [`Jail::root`](crate::Jail::root) and
+    /// [`JailedPath`](crate::JailedPath)'s `Display` see the real paths.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/srv/customers/acme")
+    ///     .redact_root("<jail>")
+    ///     .build()?;
+    /// let err = jail.join("../globex").unwrap_err();
+    /// assert_eq!(err.to_string(), "path '../globex' escapes jail root '<jail>'");
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn redact_root<S: Into<String>>(mut self, placeholder: S) -> Self {
+        self.config.redact_root = Some(placeholder.into());
+        self
+    }
+
+    /// Limit the total size of the files in the jail to `max_bytes`
+    /// (default unlimited).
+    ///
+    /// The files already under the root are counted when the jail is
+    /// built. From then on, writes through the jail that would go over the
+    /// limit fail with [`JailError::QuotaExceeded`] before writing, and
+    /// removals give the space back. Clones of the jail, sub-jails and
+    /// read-only views share the budget.
+    ///
+    /// Counted are [`Jail::write`], `Jail::copy`,
+    /// [`Jail::write_atomic`] and [`AtomicWriter`](crate::AtomicWriter),
+    /// hard links, archive extraction and
+    /// [`EntrySink`](crate::EntrySink) files, and writes and `set_len` on
+    /// files returned by `create`, `create_or_truncate`, `open_append` and
+    /// `lock_exclusive`. An archive member that goes over the limit is
+    /// removed. Temporary and anonymous files count once they are given a
+    /// name with `persist` or `link`, at their size then. Other data
+    /// written through a plain [`File`](std::fs::File)
+    /// (directory handles, io_uring) or by other
+    /// processes is only seen by [`Jail::rescan_quota`]. Sizes are
+    /// apparent sizes of regular files, so a file with two hard links in
+    /// the jail counts twice.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let tenant = Jail::builder("/var/uploads/acme")
+    ///     .quota(10 * 1024 * 1024)
+    ///     .build()?;
+    /// match tenant.write("big.bin", vec![0u8; 20 * 1024 * 1024]) {
+    ///     Err(JailError::QuotaExceeded { .. }) => eprintln!("over quota"),
+    ///     other => other?,
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn quota(mut self, max_bytes: u64) -> Self {
+        self.config.quota = Some(Quota::new(max_bytes));
+        self
+    }
+
+    /// Limit how large a single file written through the jail may grow
+    /// (default unlimited).
+    ///
+    /// Writes that would make a file bigger than `max_bytes` fail with
+    /// [`JailError::FileTooLarge`] before writing, so one upload cannot
+    /// fill the disk. This covers the same writes as
+    /// [`quota`](Self::quota): [`Jail::write`], `Jail::copy`,
+    /// [`Jail::write_atomic`] and [`AtomicWriter`](crate::AtomicWriter),
+    /// archive extraction and [`EntrySink`](crate::EntrySink) files, and
+    /// writes and `set_len` on files returned by `create`,
+    /// `create_or_truncate`, `open_append` and `lock_exclusive`, and
+    /// temporary and anonymous files when they are given a name with
+    /// `persist` or `link`. Files already bigger can still be read,
+    /// truncated and rewritten in place.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let uploads = Jail::builder("/var/uploads")
+    ///     .max_file_size(5 * 1024 * 1024)
+    ///     .build()?;
+    /// let err = uploads.write("avatar.png", vec![0u8; 6 * 1024 * 1024]).unwrap_err();
+    /// assert!(matches!(err, JailError::FileTooLarge { .. }));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn max_file_size(mut self, max_bytes: u64) -> Self {
+        self.config.max_file_size = Some(max_bytes);
+        self
+    }
+
+    /// Permission bits for files the jail creates, such as `0o640` (default:
+    /// `0o666` less the process umask; Unix only).
+    ///
+    /// Applied exactly, whatever the umask, to files that
+    /// [`Jail::write`], [`Jail::write_atomic`], [`Jail::atomic_writer`],
+    /// [`Jail::copy_from`], archive extraction and
+    /// [`EntrySink`](crate::EntrySink) files (in place of the member's
+    /// mode) and, with `secure-open`, [`Jail::create`],
+    /// `create_or_truncate`, `open_append`, `copy` and `lock` bring into
+    /// existence, and with `openat` to `JailedDir::create_at`. Files opened
+    /// without being created keep their permissions, and so do files
+    /// replaced with an atomic write. The file is
+    /// opened with the mode (less the umask) and then set to it through
+    /// the handle, so it is never more open than asked for.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let uploads = Jail::builder("/var/uploads")
+    ///     .file_mode(0o640)
+    ///     .dir_mode(0o750)
+    ///     .build()?;
+    /// uploads.write("alice/report.pdf", b"...")?; // 0o640 under alice/, 0o750
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn file_mode(mut self, mode: u32) -> Self {
+        self.config.file_mode = Some(mode);
+        self
+    }
+
+    /// Permission bits for directories the jail creates, such as `0o750`
+    /// (default: `0o777` less the process umask; Unix only).
+    ///
+    /// Applied exactly, whatever the umask, by [`Jail::create_dir`],
+    /// [`Jail::create_dir_all`] and [`Jail::create_subjail`]. The
+    /// `*_with_mode` variants use the mode they are given instead.
+    #[cfg(unix)]
+    pub fn dir_mode(mut self, mode: u32) -> Self {
+        self.config.dir_mode = Some(mode);
+        self
+    }
+
+    /// Call `hook` with the path of every file and directory the jail
+    /// creates (default none), after [`file_mode`](Self::file_mode) or
+    /// [`dir_mode`](Self::dir_mode) are applied.
+    ///
+    /// For what modes cannot express, such as setting an ACL or security
+    /// descriptor on Windows, or a label. An error from the hook is
+    /// returned from the call that created the entry; the entry is left
+    /// in place. Files written atomically are reported at their final
+    /// path once renamed. Clones of the jail and sub-jails share the hook.
+    pub fn on_create<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path) -> std::io::Result<()> + Send + Sync + 'static,
+    {
+        self.config.on_create = Some(CreateHook::new(hook));
+        self
+    }
+
+    /// Only let files be created and appended to, never overwritten,
+    /// truncated, renamed or removed (default `false`).
+    ///
+    /// For audit logs and other history the application itself must not
+    /// be able to rewrite, even if the code choosing paths is fooled.
+    /// [`Jail::write`], [`Jail::write_atomic`], [`Jail::atomic_writer`],
+    /// [`Jail::copy_from`] and, with `secure-open`, `create_or_truncate`
+    /// and `copy` still create new files, exclusively, but fail with
+    /// [`JailError::AppendOnly`] when the file already exists; an atomic
+    /// writer is linked into place instead of renamed, so it never replaces
+    /// a file created meanwhile. [`Jail::rename`], the `remove_*` methods
+    /// and temporary files always fail, and shrinking a `JailedFile` with
+    /// `set_len` is refused. Files from
+    /// `create`, `open_append` and the lock methods are opened with
+    /// `O_APPEND`, so every write goes to the end. Directories can still
+    /// be created, and refusals reach the
+    /// [`on_reject`](Self::on_reject) hook.
+    ///
+    /// This binds code going through the jail. A handle taken out with
+    /// `into_inner()`, other processes, and `std::fs` are not affected;
+    /// for that, use the filesystem's own append-only attribute
+    /// (`chattr +a` on Linux, `chflags uappnd` on the BSDs and macOS).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailError};
+    ///
+    /// let audit = Jail::builder("/var/log/audit").append_only(true).build()?;
+    /// audit.write("2025-07-01.log", b"")?;
+    /// assert!(matches!(audit.write("2025-07-01.log", b""), Err(JailError::AppendOnly(_))));
+    /// assert!(matches!(audit.remove_file("2025-07-01.log"), Err(JailError::AppendOnly(_))));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn append_only(mut self, enable: bool) -> Self {
+        self.config.append_only = enable;
+        self
+    }
+
+    /// Reject any path with a component matching `regex` (`regex`
+    /// feature).
+    ///
+    /// Like [`deny`](Self::deny), but the whole component name is matched
+    /// against `regex` as written: anchor it (`^...$`) to match full names
+    /// and add `(?i)` for case-insensitivity. Names that are not valid
+    /// UTF-8 are matched in their lossy form.
+    #[cfg(feature = "regex")]
+    pub fn deny_regex(mut self, regex: regex::Regex) -> Self {
+        self.config.deny.push(DenyRule::Regex(regex));
+        self
+    }
+
+    /// Create the root directory, and any missing parents, if it does not
+    /// exist (default `false`).
+    ///
+    /// Replaces a `create_dir_all` before [`Jail::new`]: [`build`](Self::build)
+    /// creates the directories and then canonicalizes and checks the root
+    /// as usual, and another process creating the same root at the same
+    /// time is not an error. On Unix, `mode` sets the permission bits of
+    /// each directory created, such as `0o700` (the umask still applies);
+    /// `None` leaves them to the umask. An existing root keeps its
+    /// permissions, so combine with
+    /// [`require_secure_root`](Self::require_secure_root) if they matter.
+    /// `mode` is ignored on other platforms.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let cache = Jail::builder("/var/cache/app/thumbnails")
+    ///     .create_root(true, Some(0o700))
+    ///     .build()?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn create_root(mut self, enable: bool, mode: Option<u32>) -> Self {
+        self.create_root = enable;
+        self.root_mode = mode;
+        self
+    }
+
+    /// Canonicalize the root and create the jail.
+    ///
+    /// Fails for the same reasons as [`Jail::new`], or with
+    /// [`JailError::InvalidPath`] for an invalid [`deny`](Self::deny)
+    /// pattern, or [`JailError::Io`] if [`create_root`](Self::create_root)
+    /// cannot create the root.
+    pub fn build(self) -> Result<Jail, JailError> {
+        if self.create_root {
+            let mut builder = DirBuilder::new();
+            builder.recursive(true);
+            #[cfg(unix)]
+            if let Some(mode) = self.root_mode {
+                builder.mode(mode);
+            }
+            builder.create(&self.root)?;
+        }
+        let (root, config) = self.into_parts(&Disk)?;
+        let jail = Jail::with_config(&root, config)?;
+        jail.rescan_quota()?;
+        Ok(jail)
+    }
+
+    /// Create a [`MemoryJail`](crate::MemoryJail) with these options over
+    /// an in-memory tree (`test-util` feature).
+    #[cfg(feature = "test-util")]
+    pub fn build_in_memory(self, mut fs: crate::MemoryFs) -> Result<crate::MemoryJail, JailError> {
+        if self.create_root && fs.canonicalize(&self.root).is_err() {
+            fs.dir(&self.root);
+        }
+        let (root, config) = self.into_parts(&fs)?;
+        crate::MemoryJail::with_config(fs, &root, config)
+    }
+
+    fn into_parts<L: Lookup>(mut self, fs: &L) -> Result<(PathBuf, Config), JailError> {
+        for pattern in &self.deny_patterns {
+            self.config.deny.push(DenyRule::glob(pattern)?);
+        }
+        for target in &self.symlink_targets {
+            let canonical = fs
+                .canonicalize(target)
+                .ok()
+                .filter(|canonical| canonical.parent().is_some())
+                .ok_or_else(|| {
+                    JailError::InvalidPath(format!(
+                        "symlink target directory '{}' must exist and not be a filesystem root",
+                        target.display()
+                    ))
+                })?;
+            self.config.symlink_targets.push(canonical);
+        }
+        self.config.root_dev = if self.config.one_file_system {
+            fs.device(&self.root)
+        } else {
+            None
+        };
+        Ok((self.root, self.config))
+    }
+}
+
+impl Jail {
+    /// Start configuring a jail rooted at `root`.
+    pub fn builder<P: AsRef<Path>>(root: P) -> JailBuilder {
+        JailBuilder {
+            root: root.as_ref().to_path_buf(),
+            config: Config::default(),
+            deny_patterns: Vec::new(),
+            symlink_targets: Vec::new(),
+            create_root: false,
+            root_mode: None,
+        }
+    }
+
+    /// A builder with this jail's root and options, to derive a jail with
+    /// some options changed.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let uploads = Jail::builder("/var/uploads").max_depth(16).build()?;
+    /// let public = uploads.to_builder().deny_hidden(true).build()?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn to_builder(&self) -> JailBuilder {
+        JailBuilder {
+            root: self.root().to_path_buf(),
+            config: self.config().clone(),
+            deny_patterns: Vec::new(),
+            symlink_targets: Vec::new(),
+            create_root: false,
+            root_mode: None,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Memoized directory verification
+//! ([`JailBuilder::verification_cache`](crate::JailBuilder::verification_cache)).
+
+use crate::jail::{Lookup, Node};
+use crate::{Access, Jail};
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Entries kept before expired ones are dropped.
+const MAX_ENTRIES: usize = 16_384;
+
+impl<A: Access> Jail<A> {
+    /// Forget every directory remembered by the
+    /// [`verification_cache`](crate::JailBuilder::verification_cache),
+    /// for example after moving directories without going through the
+    /// jail. Clones of this jail share the cache and are cleared too. Does
+    /// nothing if the cache is not enabled.
+    pub fn clear_verification_cache(&self) {
+        if let Some(cache) = self.config().dir_cache() {
+            cache.clear();
+        }
+    }
+
+    /// Drop cached directories at or below `path` after removing or
+    /// renaming it.
+    pub(crate) fn forget_dirs(&self, path: &Path) {
+        if let Some(cache) = self.config().dir_cache() {
+            cache.forget(path);
+        }
+    }
+}
+
+/// Canonical forms of directories seen while resolving, each trusted for
+/// `ttl` after it was looked up. Shared by every clone of a jail.
+#[derive(Debug, Clone)]
+pub(crate) struct DirCache {
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<PathBuf, (PathBuf, Instant)>>>,
+}
+
+impl DirCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Arc::default(),
+        }
+    }
+
+    /// `fs` with directory lookups answered from this cache. Only
+    /// directories that resolve under `root` are remembered.
+    pub(crate) fn over<'a, L: Lookup>(&'a self, fs: &'a L, root: &'a Path) -> Cached<'a, L> {
+        Cached {
+            fs,
+            cache: self,
+            root,
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<PathBuf> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(path) {
+            Some((canonical, at)) if at.elapsed() < self.ttl => Some(canonical.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert(&self, path: &Path, canonical: &Path) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        if entries.len() >= MAX_ENTRIES {
+            entries.retain(|_, (_, at)| at.elapsed() < self.ttl);
+            if entries.len() >= MAX_ENTRIES {
+                entries.clear();
+            }
+        }
+        entries.insert(
+            path.to_path_buf(),
+            (canonical.to_path_buf(), Instant::now()),
+        );
+    }
+
+    /// Drop entries at or below `path`, by either spelling.
+    pub(crate) fn forget(&self, path: &Path) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries
+            .retain(|key, (canonical, _)| !key.starts_with(path) && !canonical.starts_with(path));
+    }
+
+    pub(crate) fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+// Jails compare by options; the cache contents are not an option
+impl PartialEq for DirCache {
+    fn eq(&self, other: &Self) -> bool {
+        self.ttl == other.ttl
+    }
+}
+
+impl Eq for DirCache {}
+
+/// A [`Lookup`] consulting a [`DirCache`] before the filesystem.
+pub(crate) struct Cached<'a, L> {
+    fs: &'a L,
+    cache: &'a DirCache,
+    root: &'a Path,
+}
+
+impl<L: Lookup> Lookup for Cached<'_, L> {
+    fn exists(&self, path: &Path) -> bool {
+        self.cache.get(path).is_some() || self.fs.exists(path)
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        self.fs.is_symlink(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.cache.get(path).is_some() || self.fs.is_dir(path)
+    }
+
+    fn is_junction(&self, path: &Path) -> bool {
+        self.fs.is_junction(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if let Some(canonical) = self.cache.get(path) {
+            return Ok(canonical);
+        }
+        let canonical = self.fs.canonicalize(path)?;
+        // Only directories: a file at the end of a path is always looked up
+        if canonical.starts_with(self.root) && self.fs.is_dir(&canonical) {
+            self.cache.insert(path, &canonical);
+        }
+        Ok(canonical)
+    }
+
+    fn device(&self, path: &Path) -> Option<u64> {
+        self.fs.device(path)
+    }
+
+    fn node(&self, path: &Path) -> Node {
+        match self.cache.get(path) {
+            Some(canonical) if canonical == path => Node::Dir,
+            Some(canonical) => Node::Link(canonical),
+            None => {
+                let node = self.fs.node(path);
+                if node == Node::Dir && path.starts_with(self.root) {
+                    self.cache.insert(path, path);
+                }
+                node
+            }
+        }
+    }
+}
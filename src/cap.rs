@@ -0,0 +1,75 @@
+//! Conversions to and from `cap-std` capability handles (`cap-std` feature).
+//!
+//! path_jail validates paths; cap-std holds a directory handle and resolves
+//! everything relative to it in the kernel. A project can use both: hand a
+//! [`Dir`] to code written against cap-std, and a [`Jail`] to code that
+//! wants plain paths, for the same root.
+
+#![cfg(feature = "cap-std")]
+
+use crate::{Jail, JailError};
+use cap_std::ambient_authority;
+use cap_std::fs::Dir;
+use std::path::Path;
+
+impl Jail {
+    /// Open the jail root as a cap-std [`Dir`].
+    ///
+    /// The root was canonicalized and checked when the jail was built, so
+    /// it is opened directly. Operations through the `Dir` are confined by
+    /// cap-std, not by this jail's builder options (deny rules, allowed
+    /// extensions and so on do not apply). Only read-write jails convert:
+    /// a `Dir` can write, which a [`Jail<ReadOnly>`](crate::ReadOnly) must
+    /// not hand out.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let dir = Jail::new("/var/uploads")?.into_cap_dir()?;
+    /// let report = dir.read_to_string("alice/report.txt")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn into_cap_dir(self) -> Result<Dir, JailError> {
+        Ok(Dir::open_ambient_dir(self.root(), ambient_authority())?)
+    }
+
+    /// A jail for the directory behind `dir`, which was opened from `root`.
+    ///
+    /// cap-std handles do not remember their path, so the caller passes
+    /// the one it opened. The root is validated like [`Jail::new`], and on
+    /// Unix the handle must refer to the same directory (same device and
+    /// inode), or this fails with [`JailError::InvalidRoot`]; that catches
+    /// a root that was replaced after the handle was opened. Use
+    /// [`to_builder`](Self::to_builder) on the result to add options.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use cap_std::{ambient_authority, fs::Dir};
+    /// use path_jail::Jail;
+    ///
+    /// let dir = Dir::open_ambient_dir("/var/uploads", ambient_authority())?;
+    /// let jail = Jail::from_cap_dir(&dir, "/var/uploads")?;
+    /// let path = jail.join("alice/report.txt")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn from_cap_dir<P: AsRef<Path>>(dir: &Dir, root: P) -> Result<Jail, JailError> {
+        let jail = Jail::new(root)?;
+        #[cfg(unix)]
+        {
+            use cap_std::fs::MetadataExt as _;
+            use std::os::unix::fs::MetadataExt as _;
+
+            let handle = dir.dir_metadata()?;
+            let path = std::fs::metadata(jail.root())?;
+            if (handle.dev(), handle.ino()) != (path.dev(), path.ino()) {
+                return Err(JailError::InvalidRoot(jail.root().to_path_buf()));
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = dir;
+        Ok(jail)
+    }
+}
@@ -0,0 +1,90 @@
+//! Entering a jail with `chroot(2)` (`chroot` feature, Unix, privileged
+//! processes).
+//!
+//! Daemons that start as root can pair path validation with a real chroot.
+//! The order of the steps matters and is easy to get wrong: the working
+//! directory has to move inside the new root (or `..` from it still reaches
+//! the old tree), and privileges have to be dropped afterwards, group
+//! before user, or the process can simply chroot back out.
+
+#![cfg(all(feature = "chroot", unix))]
+
+use crate::{Access, Jail, JailError};
+use std::io;
+use std::path::PathBuf;
+
+fn cvt(ret: libc::c_int) -> io::Result<()> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// `chroot` the whole process into the jail root and return a jail for
+    /// the new `/`.
+    ///
+    /// Changes into the root, chroots to it, then changes to the new `/`,
+    /// so no working directory is left outside. Needs `CAP_SYS_CHROOT`
+    /// (usually root). The returned jail keeps this jail's options and
+    /// validates paths as seen from inside the chroot; paths returned by
+    /// this jail before the call no longer name anything.
+    ///
+    /// This affects every thread and cannot be undone, and a process that
+    /// keeps root privileges can leave a chroot again. Use
+    /// [`chroot_and_enter_as`](Self::chroot_and_enter_as) to drop them in
+    /// the same step.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/srv/ftp")?.chroot_and_enter()?;
+    /// assert_eq!(jail.root(), std::path::Path::new("/"));
+    /// let path = jail.join("pub/readme.txt")?; // "/pub/readme.txt"
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn chroot_and_enter(&self) -> Result<Jail<A>, JailError> {
+        std::env::set_current_dir(self.root())?;
+        std::os::unix::fs::chroot(".")?;
+        std::env::set_current_dir("/")?;
+        self.narrow(PathBuf::from("/"))
+    }
+
+    /// Like [`chroot_and_enter`](Self::chroot_and_enter), then drop
+    /// privileges to `uid` and `gid`.
+    ///
+    /// Clears the supplementary groups, sets the group, then the user, in
+    /// that order, and fails if root privileges can be regained afterwards.
+    /// Look up `uid` and `gid` before calling: `/etc/passwd` is usually not
+    /// reachable from inside the chroot.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// // uid/gid of an unprivileged "ftp" user
+    /// let jail = Jail::new("/srv/ftp")?.chroot_and_enter_as(14, 50)?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn chroot_and_enter_as(&self, uid: u32, gid: u32) -> Result<Jail<A>, JailError> {
+        let jail = self.chroot_and_enter()?;
+        // SAFETY: plain libc calls; the group list is empty.
+        unsafe {
+            cvt(libc::setgroups(0, std::ptr::null()))?;
+            cvt(libc::setgid(gid))?;
+            cvt(libc::setuid(uid))?;
+            if uid != 0 && (libc::setuid(0) == 0 || libc::geteuid() == 0) {
+                return Err(io::Error::new(
+                    io::ErrorKind::PermissionDenied,
+                    "root privileges could be regained after dropping them",
+                )
+                .into());
+            }
+        }
+        Ok(jail)
+    }
+}
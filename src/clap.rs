@@ -0,0 +1,115 @@
+//! `clap` value parsers (`clap` feature).
+//!
+//! Turns command line arguments into a validated [`Jail`] or [`JailedPath`]
+//! at argument-parsing time, so bad input is reported as a usage error.
+
+#![cfg(feature = "clap")]
+
+use crate::{Jail, JailError, JailedPath};
+use clap::builder::{TypedValueParser, ValueParserFactory};
+use clap::error::ErrorKind;
+use clap::{Arg, Command};
+use std::ffi::OsStr;
+use std::path::Path;
+
+/// Parses an argument into a [`Jail`] rooted at the given directory.
+///
+/// This is also what `value_parser!(Jail)` uses, so with clap's derive API a
+/// `Jail` field works without any extra attributes.
+///
+/// # Example
+///
+/// ```no_run
+/// use clap::{Arg, Command};
+/// use path_jail::{Jail, JailValueParser};
+///
+/// let matches = Command::new("serve")
+///     .arg(Arg::new("root").long("root").value_parser(JailValueParser))
+///     .get_matches();
+/// let jail: &Jail = matches.get_one("root").expect("required");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JailValueParser;
+
+impl TypedValueParser for JailValueParser {
+    type Value = Jail;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        Jail::new(value).map_err(|err| invalid_value(cmd, arg, value, err))
+    }
+}
+
+impl ValueParserFactory for Jail {
+    type Parser = JailValueParser;
+
+    fn value_parser() -> Self::Parser {
+        JailValueParser
+    }
+}
+
+/// Parses an argument as a path relative to a known [`Jail`].
+///
+/// clap parses each argument on its own, so the jail has to exist before
+/// the command is built, e.g. from a fixed location or an environment
+/// variable. When the root itself is an argument, parse it first with
+/// [`JailValueParser`] and validate the user paths afterwards with
+/// [`Jail::join_typed`].
+///
+/// # Example
+///
+/// ```no_run
+/// use clap::{Arg, Command};
+/// use path_jail::{Jail, JailedPath, JailedPathValueParser};
+///
+/// let jail = Jail::new("/var/uploads")?;
+/// let matches = Command::new("fetch")
+///     .arg(Arg::new("file").value_parser(JailedPathValueParser::new(jail)))
+///     .get_matches();
+/// let file: &JailedPath = matches.get_one("file").expect("required");
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct JailedPathValueParser {
+    jail: Jail,
+}
+
+impl JailedPathValueParser {
+    /// Create a parser validating paths against `jail`.
+    pub fn new(jail: Jail) -> Self {
+        Self { jail }
+    }
+}
+
+impl TypedValueParser for JailedPathValueParser {
+    type Value = JailedPath;
+
+    fn parse_ref(
+        &self,
+        cmd: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        self.jail
+            .join_typed(value)
+            .map_err(|err| invalid_value(cmd, arg, value, err))
+    }
+}
+
+fn invalid_value(cmd: &Command, arg: Option<&Arg>, value: &OsStr, err: JailError) -> clap::Error {
+    let arg = arg.map_or_else(|| "...".to_owned(), ToString::to_string);
+    clap::Error::raw(
+        ErrorKind::ValueValidation,
+        format!(
+            "invalid value '{}' for '{}': {}\n",
+            Path::new(value).display(),
+            arg,
+            err
+        ),
+    )
+    .with_cmd(cmd)
+}
@@ -0,0 +1,170 @@
+//! Age-based removal of old files ([`Jail::cleanup`]).
+
+use crate::{Jail, JailError, JailedPath};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Which timestamp [`CleanupPolicy`] measures a file's age by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Timestamp {
+    /// Last modification (`mtime`).
+    #[default]
+    Modified,
+    /// Last access (`atime`). Many filesystems are mounted `noatime` or
+    /// `relatime`, where this is only updated loosely or not at all.
+    Accessed,
+}
+
+/// What [`Jail::cleanup`] removes.
+///
+/// Regular files whose [`Timestamp`] is at least `max_age` in the past
+/// are removed; directories, symlinks and other entries are left alone.
+/// Without any [`path`](Self::path) the whole jail is swept.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::{CleanupPolicy, Timestamp};
+/// use std::time::Duration;
+///
+/// let policy = CleanupPolicy::new(Duration::from_secs(24 * 60 * 60))
+///     .path("tmp")
+///     .path("uploads/incomplete")
+///     .timestamp(Timestamp::Accessed)
+///     .dry_run(true);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CleanupPolicy {
+    max_age: Duration,
+    timestamp: Timestamp,
+    paths: Vec<PathBuf>,
+    dry_run: bool,
+}
+
+impl CleanupPolicy {
+    /// Remove files older than `max_age`.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            timestamp: Timestamp::default(),
+            paths: Vec::new(),
+            dry_run: false,
+        }
+    }
+
+    /// Sweep this directory, relative to the jail. Can be called more than
+    /// once; each path is validated with [`Jail::join`].
+    pub fn path<P: AsRef<Path>>(mut self, relative: P) -> Self {
+        self.paths.push(relative.as_ref().to_path_buf());
+        self
+    }
+
+    /// Which timestamp to measure age by (default [`Timestamp::Modified`]).
+    pub fn timestamp(mut self, timestamp: Timestamp) -> Self {
+        self.timestamp = timestamp;
+        self
+    }
+
+    /// Only report what would be removed (default `false`).
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    fn is_expired(&self, meta: &fs::Metadata, now: SystemTime) -> io::Result<bool> {
+        let time = match self.timestamp {
+            Timestamp::Modified => meta.modified()?,
+            Timestamp::Accessed => meta.accessed()?,
+        };
+        // Timestamps in the future are not old
+        Ok(now
+            .duration_since(time)
+            .is_ok_and(|age| age >= self.max_age))
+    }
+}
+
+/// Summary of a [`Jail::cleanup`] run.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct CleanupReport {
+    /// Files removed, or that would have been in a dry run.
+    pub removed: Vec<JailedPath>,
+    /// Their combined size in bytes.
+    pub freed: u64,
+}
+
+impl Jail {
+    /// Remove files older than the policy allows.
+    ///
+    /// Each directory is walked like [`walk`](Self::walk) without following
+    /// symlinks, and files are removed with [`remove_file`](Self::remove_file),
+    /// so a symlink planted in a swept directory is never followed out of
+    /// the jail nor deleted itself. Entries that fail verification are
+    /// skipped, and so are files that disappear before they are removed.
+    /// Space freed is given back to the [`quota`](crate::JailBuilder::quota).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{CleanupPolicy, Jail};
+    /// use std::time::Duration;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let policy = CleanupPolicy::new(Duration::from_secs(3600)).path("partial");
+    /// let report = jail.cleanup(&policy)?;
+    /// println!("freed {} bytes", report.freed);
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Fails if a swept path does not validate or a directory cannot be
+    /// read. Files removed before the error stay removed.
+    pub fn cleanup(&self, policy: &CleanupPolicy) -> Result<CleanupReport, JailError> {
+        let now = SystemTime::now();
+        let mut report = CleanupReport::default();
+        let root = [PathBuf::new()];
+        let paths = if policy.paths.is_empty() {
+            &root[..]
+        } else {
+            &policy.paths[..]
+        };
+
+        for path in paths {
+            for entry in self.walk(path)? {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(JailError::Io(err)) => return Err(JailError::Io(err)),
+                    // Escaping or broken symlinks; never removed anyway
+                    Err(_) => continue,
+                };
+                if !entry.file_type().is_file() {
+                    continue;
+                }
+                let meta = match fs::symlink_metadata(entry.path()) {
+                    Ok(meta) => meta,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(err) => return Err(err.into()),
+                };
+                if !policy.is_expired(&meta, now)? {
+                    continue;
+                }
+                if !policy.dry_run {
+                    match entry.path().remove_file() {
+                        Ok(()) => {}
+                        Err(JailError::Io(err)) if err.kind() == io::ErrorKind::NotFound => {
+                            continue
+                        }
+                        Err(err) => return Err(err),
+                    }
+                }
+                report.freed = report.freed.saturating_add(meta.len());
+                report.removed.push(entry.into_path());
+            }
+        }
+        Ok(report)
+    }
+}
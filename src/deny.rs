@@ -0,0 +1,82 @@
+//! Filename deny rules applied to every component during validation.
+
+use crate::glob::{match_from, validate_classes};
+use crate::JailError;
+use std::ffi::OsStr;
+
+/// One rule set through [`JailBuilder::deny`](crate::JailBuilder::deny) or
+/// [`JailBuilder::deny_regex`](crate::JailBuilder::deny_regex).
+#[derive(Debug, Clone)]
+pub(crate) enum DenyRule {
+    /// Glob pattern for a single name, stored lowercase.
+    Glob(String),
+    #[cfg(feature = "regex")]
+    Regex(regex::Regex),
+}
+
+impl DenyRule {
+    /// A glob rule. Literal names are globs without wildcards.
+    pub(crate) fn glob(pattern: &str) -> Result<Self, JailError> {
+        if pattern.is_empty() || pattern.contains(['/', '\\', '\0']) {
+            return Err(JailError::InvalidPath(format!(
+                "deny pattern '{}' must be a single non-empty name",
+                pattern
+            )));
+        }
+        validate_classes(pattern)?;
+        Ok(DenyRule::Glob(pattern.to_lowercase()))
+    }
+
+    /// Whether this rule rejects `name`. Names that are not valid UTF-8
+    /// are compared in their lossy form.
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            DenyRule::Glob(pattern) => {
+                let pattern: Vec<char> = pattern.chars().collect();
+                let name: Vec<char> = name.to_lowercase().chars().collect();
+                match_from(&pattern, &name)
+            }
+            #[cfg(feature = "regex")]
+            DenyRule::Regex(regex) => regex.is_match(name),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            DenyRule::Glob(pattern) => pattern,
+            #[cfg(feature = "regex")]
+            DenyRule::Regex(regex) => regex.as_str(),
+        }
+    }
+}
+
+/// Reject `name` if any of `rules` matches it.
+pub(crate) fn check(rules: &[DenyRule], name: &OsStr) -> Result<(), JailError> {
+    if rules.is_empty() {
+        return Ok(());
+    }
+    let name = name.to_string_lossy();
+    match rules.iter().find(|rule| rule.matches(&name)) {
+        Some(rule) => Err(JailError::InvalidPath(format!(
+            "'{}' matches deny rule '{}'",
+            name,
+            rule.as_str()
+        ))),
+        None => Ok(()),
+    }
+}
+
+// `Regex` has no `PartialEq`; rules are equal when their sources are.
+impl PartialEq for DenyRule {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DenyRule::Glob(a), DenyRule::Glob(b)) => a == b,
+            #[cfg(feature = "regex")]
+            (DenyRule::Regex(a), DenyRule::Regex(b)) => a.as_str() == b.as_str(),
+            #[cfg(feature = "regex")]
+            _ => false,
+        }
+    }
+}
+
+impl Eq for DenyRule {}
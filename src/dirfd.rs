@@ -0,0 +1,346 @@
+//! Directory file descriptors and `*at()` syscalls.
+//!
+//! Everything here operates relative to an open directory handle and never
+//! follows symlinks, so a directory swapped for a symlink mid-operation makes
+//! the call fail instead of redirecting it outside the jail. Only available
+//! on Unix with the `openat` feature.
+
+#![cfg(all(feature = "openat", unix))]
+
+use std::ffi::{CStr, CString, OsStr, OsString};
+use std::io;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path};
+
+/// Flags for opening a directory without following a final symlink.
+const DIR_FLAGS: libc::c_int =
+    libc::O_RDONLY | libc::O_DIRECTORY | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+
+fn cvt(ret: libc::c_int) -> io::Result<libc::c_int> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+fn c_name(name: &OsStr) -> io::Result<CString> {
+    CString::new(name.as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "null byte in path"))
+}
+
+/// An open directory used as the anchor for `*at()` calls.
+#[derive(Debug)]
+pub(crate) struct DirFd {
+    fd: OwnedFd,
+}
+
+impl DirFd {
+    /// Open a directory by absolute path. The final component must not be
+    /// a symlink.
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let path = c_name(path.as_os_str())?;
+        // SAFETY: `path` is a valid NUL-terminated string.
+        let fd = cvt(unsafe { libc::open(path.as_ptr(), DIR_FLAGS) })?;
+        // SAFETY: `fd` was just returned by open() and is owned by us.
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// The underlying descriptor.
+    pub(crate) fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+
+    /// Open the subdirectory `name` of this directory.
+    pub(crate) fn open_dir(&self, name: &OsStr) -> io::Result<Self> {
+        let name = c_name(name)?;
+        // SAFETY: valid directory fd and NUL-terminated name.
+        let fd = cvt(unsafe { libc::openat(self.fd.as_raw_fd(), name.as_ptr(), DIR_FLAGS) })?;
+        // SAFETY: `fd` was just returned by openat() and is owned by us.
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+        })
+    }
+
+    /// Walk `relative` one component at a time from this directory.
+    ///
+    /// Every component must be a plain name; `..`, absolute components and
+    /// symlinks all cause an error.
+    pub(crate) fn open_path(&self, relative: &Path) -> io::Result<Self> {
+        let mut dir = self.try_clone()?;
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => dir = dir.open_dir(name)?,
+                Component::CurDir => {}
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "only plain path components can be walked",
+                    ))
+                }
+            }
+        }
+        Ok(dir)
+    }
+
+    pub(crate) fn try_clone(&self) -> io::Result<Self> {
+        Ok(Self {
+            fd: self.fd.try_clone()?,
+        })
+    }
+
+    /// Whether `name` is a directory (not a symlink to one).
+    pub(crate) fn is_dir(&self, name: &OsStr) -> io::Result<bool> {
+        let name = c_name(name)?;
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: valid fd, NUL-terminated name, and `stat` is writable.
+        cvt(unsafe {
+            libc::fstatat(
+                self.fd.as_raw_fd(),
+                name.as_ptr(),
+                stat.as_mut_ptr(),
+                libc::AT_SYMLINK_NOFOLLOW,
+            )
+        })?;
+        // SAFETY: fstatat() succeeded, so `stat` is initialized.
+        let stat = unsafe { stat.assume_init() };
+        Ok(stat.st_mode & libc::S_IFMT == libc::S_IFDIR)
+    }
+
+    /// Open the file `name` in this directory with `flags`, never following
+    /// a symlink in `name`. New files get mode `0o666` minus the umask.
+    pub(crate) fn open_file(
+        &self,
+        name: &OsStr,
+        flags: libc::c_int,
+        mode: u32,
+    ) -> io::Result<std::fs::File> {
+        let name = c_name(name)?;
+        let flags = flags | libc::O_NOFOLLOW | libc::O_CLOEXEC;
+        // SAFETY: valid directory fd and NUL-terminated name.
+        let fd = cvt(unsafe {
+            libc::openat(
+                self.fd.as_raw_fd(),
+                name.as_ptr(),
+                flags,
+                mode as libc::c_uint,
+            )
+        })?;
+        // SAFETY: `fd` was just returned by openat() and is owned by us.
+        Ok(unsafe { std::fs::File::from_raw_fd(fd) })
+    }
+
+    /// Remove the non-directory entry `name`.
+    pub(crate) fn unlink(&self, name: &OsStr) -> io::Result<()> {
+        let name = c_name(name)?;
+        // SAFETY: valid fd and NUL-terminated name.
+        cvt(unsafe { libc::unlinkat(self.fd.as_raw_fd(), name.as_ptr(), 0) })?;
+        Ok(())
+    }
+
+    /// Remove the empty directory `name`.
+    pub(crate) fn rmdir(&self, name: &OsStr) -> io::Result<()> {
+        let name = c_name(name)?;
+        // SAFETY: valid fd and NUL-terminated name.
+        cvt(unsafe { libc::unlinkat(self.fd.as_raw_fd(), name.as_ptr(), libc::AT_REMOVEDIR) })?;
+        Ok(())
+    }
+
+    /// Create a hard link `to_name` in `to_dir` to the entry `name` here.
+    ///
+    /// A symlink in `name` is linked itself, not followed.
+    pub(crate) fn link(&self, name: &OsStr, to_dir: &DirFd, to_name: &OsStr) -> io::Result<()> {
+        let (name, to_name) = (c_name(name)?, c_name(to_name)?);
+        // SAFETY: valid fds and NUL-terminated names.
+        cvt(unsafe {
+            libc::linkat(
+                self.fd.as_raw_fd(),
+                name.as_ptr(),
+                to_dir.fd.as_raw_fd(),
+                to_name.as_ptr(),
+                0,
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Create a symlink `name` in this directory pointing at `target`.
+    pub(crate) fn symlink(&self, target: &OsStr, name: &OsStr) -> io::Result<()> {
+        let (target, name) = (c_name(target)?, c_name(name)?);
+        // SAFETY: valid fd and NUL-terminated strings.
+        cvt(unsafe { libc::symlinkat(target.as_ptr(), self.fd.as_raw_fd(), name.as_ptr()) })?;
+        Ok(())
+    }
+
+    /// Rename `name` in this directory to `to_name` in `to_dir`.
+    pub(crate) fn rename(&self, name: &OsStr, to_dir: &DirFd, to_name: &OsStr) -> io::Result<()> {
+        let (name, to_name) = (c_name(name)?, c_name(to_name)?);
+        // SAFETY: valid fds and NUL-terminated names.
+        cvt(unsafe {
+            libc::renameat(
+                self.fd.as_raw_fd(),
+                name.as_ptr(),
+                to_dir.fd.as_raw_fd(),
+                to_name.as_ptr(),
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Like [`rename`](Self::rename), but fails with `AlreadyExists` instead
+    /// of replacing an existing `to_name`.
+    ///
+    /// Uses `renameat2(RENAME_NOREPLACE)` on Linux and
+    /// `renameatx_np(RENAME_EXCL)` on macOS. Elsewhere non-directories are
+    /// moved with `linkat()` + `unlinkat()`, which is equally atomic about
+    /// not clobbering; directories are unsupported.
+    pub(crate) fn rename_noreplace(
+        &self,
+        name: &OsStr,
+        to_dir: &DirFd,
+        to_name: &OsStr,
+    ) -> io::Result<()> {
+        let (c_from, c_to) = (c_name(name)?, c_name(to_name)?);
+        let (from_fd, to_fd) = (self.fd.as_raw_fd(), to_dir.fd.as_raw_fd());
+
+        #[cfg(all(target_os = "linux", any(target_env = "gnu", target_env = "musl")))]
+        {
+            // SAFETY: valid fds and NUL-terminated names.
+            cvt(unsafe {
+                libc::renameat2(
+                    from_fd,
+                    c_from.as_ptr(),
+                    to_fd,
+                    c_to.as_ptr(),
+                    libc::RENAME_NOREPLACE,
+                )
+            })?;
+            Ok(())
+        }
+
+        #[cfg(target_vendor = "apple")]
+        {
+            // SAFETY: valid fds and NUL-terminated names.
+            cvt(unsafe {
+                libc::renameatx_np(
+                    from_fd,
+                    c_from.as_ptr(),
+                    to_fd,
+                    c_to.as_ptr(),
+                    libc::RENAME_EXCL,
+                )
+            })?;
+            Ok(())
+        }
+
+        #[cfg(not(any(
+            all(target_os = "linux", any(target_env = "gnu", target_env = "musl")),
+            target_vendor = "apple"
+        )))]
+        {
+            if self.is_dir(name)? {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "no-replace rename of directories is not supported on this platform",
+                ));
+            }
+            // SAFETY: valid fds and NUL-terminated names.
+            cvt(unsafe { libc::linkat(from_fd, c_from.as_ptr(), to_fd, c_to.as_ptr(), 0) })?;
+            self.unlink(name)
+        }
+    }
+
+    /// List the names in this directory, excluding `.` and `..`.
+    pub(crate) fn entries(&self) -> io::Result<Vec<OsString>> {
+        // fdopendir() takes ownership of the fd, so hand it a duplicate
+        let dup = self.fd.try_clone()?;
+        // SAFETY: `dup` is a valid directory fd whose ownership moves to the DIR*.
+        let dir = unsafe { libc::fdopendir(dup.into_raw_fd()) };
+        if dir.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        // The duplicate shares our file offset; start from the beginning
+        // SAFETY: `dir` is a valid DIR*.
+        unsafe { libc::rewinddir(dir) };
+
+        let mut names = Vec::new();
+        loop {
+            // SAFETY: `dir` is a valid DIR*.
+            let entry = unsafe { libc::readdir(dir) };
+            if entry.is_null() {
+                // End of stream. A read error here leaves entries behind,
+                // which the caller's final rmdir() reports as ENOTEMPTY.
+                break;
+            }
+            // SAFETY: readdir() returned a valid dirent with a NUL-terminated name.
+            let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+            let name = name.to_bytes();
+            if name != b"." && name != b".." {
+                names.push(OsStr::from_bytes(name).to_os_string());
+            }
+        }
+        // SAFETY: `dir` is valid and not used after this.
+        unsafe { libc::closedir(dir) };
+        Ok(names)
+    }
+
+    /// Device and inode of this directory.
+    fn id(&self) -> io::Result<(libc::dev_t, libc::ino_t)> {
+        let mut stat = std::mem::MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: valid fd, and `stat` is writable.
+        cvt(unsafe { libc::fstat(self.fd.as_raw_fd(), stat.as_mut_ptr()) })?;
+        // SAFETY: fstat() succeeded, so `stat` is initialized.
+        let stat = unsafe { stat.assume_init() };
+        Ok((stat.st_dev, stat.st_ino))
+    }
+
+    /// Recursively remove `name` without ever following a symlink.
+    ///
+    /// Each level is opened with `O_NOFOLLOW | O_DIRECTORY` relative to its
+    /// parent, so swapping any directory for a symlink while the deletion is
+    /// in progress makes it fail rather than escape. Only one level is held
+    /// open at a time, and the walk is a loop rather than recursion, so
+    /// the depth of the tree is bounded by neither the descriptor limit
+    /// nor the stack. The way back up is through `..`, checked against the
+    /// directory that was walked down, so a level moved meanwhile makes
+    /// the removal fail.
+    pub(crate) fn remove_tree(&self, name: &OsStr) -> io::Result<()> {
+        if !self.is_dir(name)? {
+            return self.unlink(name);
+        }
+        // Name and identity of each level below `self`, down to `dir`
+        let mut dir = self.open_dir(name)?;
+        let mut levels = vec![(name.to_os_string(), dir.id()?)];
+        loop {
+            // Remove everything but the first subdirectory, then descend
+            let mut subdir = None;
+            for entry in dir.entries()? {
+                if dir.is_dir(&entry)? {
+                    subdir = Some(entry);
+                    break;
+                }
+                dir.unlink(&entry)?;
+            }
+            if let Some(entry) = subdir {
+                dir = dir.open_dir(&entry)?;
+                levels.push((entry, dir.id()?));
+                continue;
+            }
+
+            // `dir` is empty: climb back up and remove it
+            let (name, _) = levels.pop().expect("the top level is popped last");
+            let Some((_, parent_id)) = levels.last() else {
+                return self.rmdir(&name);
+            };
+            let parent = dir.open_dir(OsStr::new(".."))?;
+            if parent.id()? != *parent_id {
+                return Err(io::Error::other("directory moved during removal"));
+            }
+            parent.rmdir(&name)?;
+            dir = parent;
+        }
+    }
+}
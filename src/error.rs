@@ -1,7 +1,12 @@
 use std::error::Error;
 use std::fmt;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 
+/// Errors returned by this crate.
+///
+/// New variants may be added; match on [`kind`](Self::kind) to handle
+/// whole classes of errors without listing every variant.
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum JailError {
@@ -9,33 +14,395 @@ pub enum JailError {
     EscapedRoot { attempted: PathBuf, root: PathBuf },
     /// Path contains a broken symlink (cannot verify target is safe).
     BrokenSymlink(PathBuf),
+    /// Path reaches outside the jail through an NTFS junction or volume
+    /// mount point (Windows).
+    JunctionEscape { path: PathBuf, root: PathBuf },
+    /// Path contains a junction or mount point whose target does not
+    /// exist (Windows).
+    BrokenJunction(PathBuf),
+    /// Path contains a symlink (or junction) and the jail was built with
+    /// [`JailBuilder::deny_symlinks`](crate::JailBuilder::deny_symlinks).
+    SymlinkNotAllowed(PathBuf),
+    /// Path goes through a procfs magic link such as `/proc/<pid>/root`
+    /// or `/proc/<pid>/fd/<n>`. The kernel follows these to the object
+    /// itself, not to the path they read as, so their target cannot be
+    /// checked.
+    MagicLink(PathBuf),
+    /// Path leads onto a different filesystem than the root and the jail
+    /// was built with
+    /// [`JailBuilder::one_file_system`](crate::JailBuilder::one_file_system).
+    CrossDevice(PathBuf),
+    /// Regular file has other hard links, which may live outside the jail,
+    /// and the jail was built with
+    /// [`JailBuilder::reject_hard_links`](crate::JailBuilder::reject_hard_links).
+    HardLinked { path: PathBuf, links: u64 },
+    /// Path has more components than
+    /// [`JailBuilder::max_depth`](crate::JailBuilder::max_depth) allows.
+    TooDeep { depth: usize, max: usize },
+    /// Joined path is longer than
+    /// [`JailBuilder::max_path_len`](crate::JailBuilder::max_path_len)
+    /// allows (by default, the platform limit).
+    TooLong { len: usize, max: usize },
     /// Path is invalid (e.g., contains absolute components).
     InvalidPath(String),
+    /// Jail root (or a directory above it) can be written by other users,
+    /// and the jail was built with
+    /// [`JailBuilder::require_secure_root`](crate::JailBuilder::require_secure_root).
+    InsecureRoot { path: PathBuf, reason: String },
+    /// Jail root (or, if requested, a directory above it) is owned by a
+    /// user the jail was not told to trust; see
+    /// [`JailBuilder::require_root_owner`](crate::JailBuilder::require_root_owner).
+    UntrustedOwner { path: PathBuf, uid: u32 },
     /// Jail root is invalid (e.g., filesystem root like `/` or `C:\`).
     InvalidRoot(PathBuf),
+    /// Writing `requested` more bytes would take the jail over the
+    /// [`JailBuilder::quota`](crate::JailBuilder::quota) of `limit` bytes,
+    /// of which `used` are taken.
+    QuotaExceeded {
+        limit: u64,
+        used: u64,
+        requested: u64,
+    },
+    /// Writing would make a file `size` bytes long, more than
+    /// [`JailBuilder::max_file_size`](crate::JailBuilder::max_file_size)
+    /// allows.
+    FileTooLarge { size: u64, max: u64 },
+    /// The operation would change or remove an existing file in a jail
+    /// built with
+    /// [`JailBuilder::append_only`](crate::JailBuilder::append_only).
+    AppendOnly(PathBuf),
+    /// No jail is installed on this thread; see
+    /// [`ScopedJail`](crate::ScopedJail).
+    NoCurrentJail,
     /// Underlying I/O error.
     Io(std::io::Error),
 }
 
-impl fmt::Display for JailError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+/// What kind of failure a [`JailError`] is, for matching that keeps
+/// compiling as new variants are added to `JailError`.
+///
+/// Every `JailError` variant, current and future, maps to one of these.
+/// New rejection reasons join an existing kind where one fits, so a
+/// `match` on the kind keeps meaning the same thing across releases.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{Jail, JailErrorKind};
+///
+/// let jail = Jail::new("/var/uploads")?;
+/// match jail.join("../etc/passwd") {
+///     Ok(path) => println!("{}", path.display()),
+///     Err(err) => match err.kind() {
+///         JailErrorKind::Escape => eprintln!("traversal attempt: {err}"),
+///         JailErrorKind::InvalidInput => eprintln!("bad request: {err}"),
+///         _ => eprintln!("error: {err}"),
+///     },
+/// }
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum JailErrorKind {
+    /// The path leads outside the root: `..`, a symlink or junction to
+    /// elsewhere, or a procfs magic link.
+    Escape,
+    /// The path goes through a symlink or junction whose target does not
+    /// exist, so where it leads cannot be checked.
+    BrokenLink,
+    /// The path stays inside but breaks an option the jail was built
+    /// with (no symlinks, one filesystem, no hard links, a quota).
+    Policy,
+    /// The input itself is unacceptable: absolute, malformed, denied by
+    /// name, or over a depth, length or file size limit.
+    InvalidInput,
+    /// The root cannot be used as a jail, or there is no jail to use.
+    Root,
+    /// The filesystem reported an error.
+    Io,
+}
+
+impl JailError {
+    /// Which [`JailErrorKind`] this error is.
+    pub fn kind(&self) -> JailErrorKind {
+        match self {
+            Self::EscapedRoot { .. } | Self::JunctionEscape { .. } | Self::MagicLink(_) => {
+                JailErrorKind::Escape
+            }
+            Self::BrokenSymlink(_) | Self::BrokenJunction(_) => JailErrorKind::BrokenLink,
+            Self::SymlinkNotAllowed(_)
+            | Self::CrossDevice(_)
+            | Self::HardLinked { .. }
+            | Self::QuotaExceeded { .. }
+            | Self::AppendOnly(_) => JailErrorKind::Policy,
+            Self::TooDeep { .. }
+            | Self::TooLong { .. }
+            | Self::FileTooLarge { .. }
+            | Self::InvalidPath(_) => JailErrorKind::InvalidInput,
+            Self::InsecureRoot { .. }
+            | Self::UntrustedOwner { .. }
+            | Self::InvalidRoot(_)
+            | Self::NoCurrentJail => JailErrorKind::Root,
+            Self::Io(_) => JailErrorKind::Io,
+        }
+    }
+
+    /// Whether the path tried to leave the jail
+    /// ([`JailErrorKind::Escape`]). These are the ones worth alerting on.
+    pub fn is_escape_attempt(&self) -> bool {
+        self.kind() == JailErrorKind::Escape
+    }
+
+    /// Whether the input was rejected as malformed
+    /// ([`JailErrorKind::InvalidInput`]).
+    pub fn is_invalid_input(&self) -> bool {
+        self.kind() == JailErrorKind::InvalidInput
+    }
+
+    /// Whether this is a filesystem error ([`JailErrorKind::Io`]) rather
+    /// than a path the jail refused.
+    pub fn is_io(&self) -> bool {
+        self.kind() == JailErrorKind::Io
+    }
+
+    /// The path validation stopped at, for errors that record one other
+    /// than the input or the root.
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match self {
+            Self::BrokenSymlink(path)
+            | Self::JunctionEscape { path, .. }
+            | Self::BrokenJunction(path)
+            | Self::SymlinkNotAllowed(path)
+            | Self::MagicLink(path)
+            | Self::CrossDevice(path)
+            | Self::HardLinked { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The closest [`io::ErrorKind`], for code that handles `JailError`
+    /// alongside plain I/O errors.
+    ///
+    /// [`Io`](Self::Io) errors report their own kind. Paths the jail
+    /// refused (escapes, symlinks, hard links, insecure roots...) are
+    /// [`PermissionDenied`](io::ErrorKind::PermissionDenied), and
+    /// malformed input or limits exceeded are
+    /// [`InvalidInput`](io::ErrorKind::InvalidInput). An exceeded quota or
+    /// a missing current jail is [`Other`](io::ErrorKind::Other).
+    pub fn io_kind(&self) -> io::ErrorKind {
+        match self {
+            Self::Io(err) => err.kind(),
+            Self::QuotaExceeded { .. } | Self::NoCurrentJail => io::ErrorKind::Other,
+            Self::TooDeep { .. }
+            | Self::TooLong { .. }
+            | Self::FileTooLarge { .. }
+            | Self::InvalidPath(_)
+            | Self::InvalidRoot(_) => io::ErrorKind::InvalidInput,
+            Self::EscapedRoot { .. }
+            | Self::BrokenSymlink(_)
+            | Self::JunctionEscape { .. }
+            | Self::BrokenJunction(_)
+            | Self::SymlinkNotAllowed(_)
+            | Self::MagicLink(_)
+            | Self::CrossDevice(_)
+            | Self::HardLinked { .. }
+            | Self::AppendOnly(_)
+            | Self::InsecureRoot { .. }
+            | Self::UntrustedOwner { .. } => io::ErrorKind::PermissionDenied,
+        }
+    }
+
+    /// The HTTP status code to answer with when a request failed with this
+    /// error, so handlers and framework integrations respond alike.
+    ///
+    /// Paths the jail refused (escapes, broken links, policy violations)
+    /// are 404, the same as a missing file, so probing the server cannot
+    /// tell "outside the jail" from "not there". Malformed input is 400,
+    /// an exceeded size limit or quota 413, and a misconfigured root 500.
+    /// [`Io`](Self::Io) errors map by their kind: `NotFound` 404,
+    /// `PermissionDenied` 403, `AlreadyExists` 409, `InvalidInput` 400,
+    /// anything else 500.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let status = match jail.read("../../etc/passwd") {
+    ///     Ok(_) => 200,
+    ///     Err(err) => err.http_status(),
+    /// };
+    /// assert_eq!(status, 404);
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn http_status(&self) -> u16 {
+        match self {
+            Self::Io(err) => match err.kind() {
+                io::ErrorKind::NotFound => 404,
+                io::ErrorKind::PermissionDenied => 403,
+                io::ErrorKind::AlreadyExists => 409,
+                io::ErrorKind::InvalidInput => 400,
+                _ => 500,
+            },
+            Self::QuotaExceeded { .. } | Self::FileTooLarge { .. } => 413,
+            _ => match self.kind() {
+                JailErrorKind::Escape | JailErrorKind::BrokenLink | JailErrorKind::Policy => 404,
+                JailErrorKind::InvalidInput => 400,
+                JailErrorKind::Root | JailErrorKind::Io => 500,
+            },
+        }
+    }
+
+    /// The OS error code (`errno` on Unix) of an [`Io`](Self::Io) error.
+    /// `None` for errors raised by the jail itself.
+    pub fn os_error(&self) -> Option<i32> {
+        match self {
+            Self::Io(err) => err.raw_os_error(),
+            _ => None,
+        }
+    }
+
+    /// Whether the filesystem reported that the path does not exist.
+    ///
+    /// A path the jail rejected is never "not found", even if it names
+    /// nothing: check [`io_kind`](Self::io_kind) to treat both alike.
+    pub fn is_not_found(&self) -> bool {
+        matches!(self, Self::Io(err) if err.kind() == io::ErrorKind::NotFound)
+    }
+
+    /// Whether the filesystem refused access to the path.
+    ///
+    /// Only true for [`Io`](Self::Io) errors: a path the jail itself
+    /// rejected is not reported here, although its
+    /// [`io_kind`](Self::io_kind) is also `PermissionDenied`.
+    pub fn is_permission_denied(&self) -> bool {
+        matches!(self, Self::Io(err) if err.kind() == io::ErrorKind::PermissionDenied)
+    }
+
+    /// Display this error with paths relative to `root`, so messages shown
+    /// to users or written to logs don't reveal where the jail lives on
+    /// the server.
+    ///
+    /// Paths under `root` are shown relative to it (the root itself as
+    /// `.`), relative paths as they are, and other absolute paths as
+    /// `<outside jail>`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/srv/customers/acme")?;
+    /// let err = jail.join("../globex/secrets").unwrap_err();
+    /// assert_eq!(
+    ///     err.display_relative(jail.root()).to_string(),
+    ///     "path '../globex/secrets' escapes jail root '.'"
+    /// );
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn display_relative<'a>(&'a self, root: &'a Path) -> impl fmt::Display + 'a {
+        Relative { err: self, root }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter<'_>, root: Option<&Path>) -> fmt::Result {
+        let show = |path| Shown { path, root };
         match self {
             Self::EscapedRoot { attempted, root } => {
                 write!(
                     f,
                     "path '{}' escapes jail root '{}'",
-                    attempted.display(),
-                    root.display()
+                    show(attempted),
+                    show(root)
                 )
             }
             Self::BrokenSymlink(path) => {
                 write!(
                     f,
                     "broken symlink at '{}' (cannot verify target)",
-                    path.display()
+                    show(path)
+                )
+            }
+            Self::JunctionEscape { path, root } => {
+                write!(
+                    f,
+                    "junction at '{}' points outside jail root '{}'",
+                    show(path),
+                    show(root)
+                )
+            }
+            Self::BrokenJunction(path) => {
+                write!(
+                    f,
+                    "broken junction at '{}' (cannot verify target)",
+                    show(path)
+                )
+            }
+            Self::SymlinkNotAllowed(path) => {
+                write!(f, "symlink at '{}' not allowed", show(path))
+            }
+            Self::MagicLink(path) => {
+                write!(
+                    f,
+                    "'{}' is a procfs magic link (target cannot be verified)",
+                    show(path)
+                )
+            }
+            Self::CrossDevice(path) => {
+                write!(
+                    f,
+                    "'{}' is on a different filesystem than the jail root",
+                    show(path)
+                )
+            }
+            Self::HardLinked { path, links } => {
+                write!(
+                    f,
+                    "'{}' has {} hard links (others may be outside the jail)",
+                    show(path),
+                    links
+                )
+            }
+            Self::TooDeep { depth, max } => {
+                write!(
+                    f,
+                    "path has {} components, more than the limit of {}",
+                    depth, max
+                )
+            }
+            Self::TooLong { len, max } => {
+                write!(f, "path length {} exceeds the limit of {}", len, max)
+            }
+            Self::FileTooLarge { size, max } => {
+                write!(
+                    f,
+                    "file would be {} bytes, more than the limit of {}",
+                    size, max
                 )
             }
             Self::InvalidPath(reason) => write!(f, "invalid path: {}", reason),
+            Self::InsecureRoot { path, reason } => {
+                write!(f, "insecure jail root: '{}' is {}", show(path), reason)
+            }
+            Self::UntrustedOwner { path, uid } => {
+                write!(
+                    f,
+                    "'{}' is owned by uid {}, not the current user or an allowed owner",
+                    show(path),
+                    uid
+                )
+            }
+            Self::QuotaExceeded {
+                limit,
+                used,
+                requested,
+            } => {
+                write!(
+                    f,
+                    "writing {} more bytes would exceed the jail quota ({} of {} bytes used)",
+                    requested, used, limit
+                )
+            }
             Self::InvalidRoot(path) => {
                 let reason = if path.parent().is_none() {
                     "cannot use filesystem root"
@@ -44,13 +411,58 @@ impl fmt::Display for JailError {
                 } else {
                     "invalid"
                 };
-                write!(f, "invalid jail root '{}' ({})", path.display(), reason)
+                write!(f, "invalid jail root '{}' ({})", show(path), reason)
             }
+            Self::AppendOnly(path) => {
+                write!(
+                    f,
+                    "'{}' cannot be changed or removed in an append-only jail",
+                    show(path)
+                )
+            }
+            Self::NoCurrentJail => write!(f, "no jail is in scope on this thread"),
             Self::Io(err) => write!(f, "io error: {}", err),
         }
     }
 }
 
+impl fmt::Display for JailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, None)
+    }
+}
+
+struct Relative<'a> {
+    err: &'a JailError,
+    root: &'a Path,
+}
+
+impl fmt::Display for Relative<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.err.write(f, Some(self.root))
+    }
+}
+
+/// A path in an error message, relative to `root` if one is given.
+pub(crate) struct Shown<'a> {
+    pub(crate) path: &'a Path,
+    pub(crate) root: Option<&'a Path>,
+}
+
+impl fmt::Display for Shown<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(root) = self.root else {
+            return self.path.display().fmt(f);
+        };
+        match self.path.strip_prefix(root) {
+            Ok(relative) if relative.as_os_str().is_empty() => f.write_str("."),
+            Ok(relative) => relative.display().fmt(f),
+            Err(_) if self.path.is_relative() => self.path.display().fmt(f),
+            Err(_) => f.write_str("<outside jail>"),
+        }
+    }
+}
+
 impl std::error::Error for JailError {
     fn source(&self) -> Option<&(dyn Error + 'static)> {
         match self {
@@ -62,6 +474,12 @@ impl std::error::Error for JailError {
 
 impl From<std::io::Error> for JailError {
     fn from(err: std::io::Error) -> Self {
+        // A JailError passed back through an io::Write implementation,
+        // such as an exceeded quota
+        if err.get_ref().is_some_and(|inner| inner.is::<JailError>()) {
+            let inner = err.into_inner().expect("checked above");
+            return *inner.downcast::<JailError>().expect("checked above");
+        }
         JailError::Io(err)
     }
 }
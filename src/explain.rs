@@ -0,0 +1,213 @@
+//! Step-by-step diagnosis of why a path is accepted or rejected.
+
+use crate::{Access, Jail, JailError};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Why [`Jail::explain`] accepts or rejects a path.
+///
+/// Holds the error [`Jail::join`] would return, the component of the
+/// input it failed at, and how far the walk got before it: the verified
+/// prefix, resolved and inside the root. When the failing component is a
+/// symlink, the link text and where it leads are included, since that is
+/// usually the question. `Display` prints all of it as a short report,
+/// for logs and for answering "why was this path rejected?".
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::Jail;
+///
+/// let jail = Jail::new("/srv/jail")?;
+/// let diagnosis = jail.explain("uploads/escape/passwd");
+/// if !diagnosis.is_allowed() {
+///     eprintln!("{diagnosis}");
+///     // 'uploads/escape/passwd' rejected (containment)
+///     //   verified: /srv/jail/uploads
+///     //   failed:   component 2, 'escape'
+///     //   link:     ../../etc -> /etc
+///     //   error:    path '/srv/jail/uploads/escape' escapes jail root '/srv/jail'
+/// }
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug)]
+pub struct Diagnosis {
+    input: PathBuf,
+    verified: PathBuf,
+    error: Option<JailError>,
+    failed: Option<(usize, OsString)>,
+    link_target: Option<PathBuf>,
+    resolved: Option<PathBuf>,
+}
+
+impl Diagnosis {
+    /// The path that was explained, as given.
+    pub fn input(&self) -> &Path {
+        &self.input
+    }
+
+    /// Whether [`Jail::join`] accepts the path.
+    pub fn is_allowed(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// The error [`Jail::join`] returns for the path, if it is rejected.
+    pub fn error(&self) -> Option<&JailError> {
+        self.error.as_ref()
+    }
+
+    /// The longest leading part of the input that passed every check,
+    /// resolved to an absolute path inside the root. For an accepted path,
+    /// the whole path as [`Jail::join`] returns it.
+    pub fn verified_prefix(&self) -> &Path {
+        &self.verified
+    }
+
+    /// The position (counting from 1) and text of the component the walk
+    /// rejected, such as `(2, "escape")` or `(1, "..")`. `None` if the path
+    /// is allowed, or was rejected as a whole (before the walk, or by a
+    /// check on the joined result).
+    pub fn failed_component(&self) -> Option<(usize, &OsStr)> {
+        self.failed
+            .as_ref()
+            .map(|(index, name)| (*index, name.as_os_str()))
+    }
+
+    /// The target of the failed component, as stored in the link, if it is
+    /// a symlink.
+    pub fn link_target(&self) -> Option<&Path> {
+        self.link_target.as_deref()
+    }
+
+    /// Where the failed component leads once every link is followed, if
+    /// it exists: for a symlink that escapes, the outside path.
+    pub fn resolved(&self) -> Option<&Path> {
+        self.resolved.as_deref()
+    }
+
+    /// The check that rejected the path: the [`JailBuilder`] option for a
+    /// configured limit (`"deny_symlinks"`, `"max_depth"`, ...), or one of
+    /// `"containment"`, `"broken link"`, `"magic link"`, `"input"` (the
+    /// path itself is malformed or denied by name) and `"io"` for checks
+    /// every jail makes.
+    ///
+    /// [`JailBuilder`]: crate::JailBuilder
+    pub fn rule(&self) -> Option<&'static str> {
+        self.error.as_ref().map(|err| match err {
+            JailError::EscapedRoot { .. } | JailError::JunctionEscape { .. } => "containment",
+            JailError::BrokenSymlink(_) | JailError::BrokenJunction(_) => "broken link",
+            JailError::MagicLink(_) => "magic link",
+            JailError::SymlinkNotAllowed(_) => "deny_symlinks",
+            JailError::CrossDevice(_) => "one_file_system",
+            JailError::HardLinked { .. } => "reject_hard_links",
+            JailError::TooDeep { .. } => "max_depth",
+            JailError::TooLong { .. } => "max_path_len",
+            JailError::QuotaExceeded { .. } => "quota",
+            JailError::FileTooLarge { .. } => "max_file_size",
+            JailError::AppendOnly(_) => "append_only",
+            JailError::InsecureRoot { .. } => "require_secure_root",
+            JailError::UntrustedOwner { .. } => "require_root_owner",
+            JailError::InvalidPath(_) => "input",
+            _ => "io",
+        })
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(error) = &self.error else {
+            return write!(
+                f,
+                "'{}' allowed: {}",
+                self.input.display(),
+                self.verified.display()
+            );
+        };
+        writeln!(
+            f,
+            "'{}' rejected ({})",
+            self.input.display(),
+            self.rule().unwrap_or("io")
+        )?;
+        writeln!(f, "  verified: {}", self.verified.display())?;
+        if let Some((index, name)) = &self.failed {
+            writeln!(
+                f,
+                "  failed:   component {}, '{}'",
+                index,
+                Path::new(name).display()
+            )?;
+        }
+        match (&self.link_target, &self.resolved) {
+            (Some(target), Some(resolved)) => writeln!(
+                f,
+                "  link:     {} -> {}",
+                target.display(),
+                resolved.display()
+            )?,
+            (Some(target), None) => writeln!(f, "  link:     {} (missing)", target.display())?,
+            (None, Some(resolved)) => writeln!(f, "  resolved: {}", resolved.display())?,
+            (None, None) => {}
+        }
+        write!(f, "  error:    {error}")
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Explain how [`join`](Self::join) treats `relative`, component by
+    /// component, without using the result.
+    ///
+    /// The input goes through the same decoding and checks as `join`, so
+    /// the verdict is the same, but a rejection is not passed to the
+    /// [`on_reject`](crate::JailBuilder::on_reject) hook, and no file is
+    /// opened. A rejected path is walked again one component at a time to
+    /// find where it fails, so this costs a few times a `join`; it is
+    /// meant for debugging and support tooling, not the request path.
+    /// See [`Diagnosis`].
+    pub fn explain<P: AsRef<Path>>(&self, relative: P) -> Diagnosis {
+        let input = relative.as_ref();
+        let mut diagnosis = Diagnosis {
+            input: input.to_path_buf(),
+            verified: self.root().to_path_buf(),
+            error: None,
+            failed: None,
+            link_target: None,
+            resolved: None,
+        };
+        let path = match self.config().prepare_unreported(input) {
+            Ok(path) => path,
+            Err(err) => {
+                diagnosis.error = Some(err);
+                return diagnosis;
+            }
+        };
+        let err = match self.resolve_unreported(&path, PathBuf::new()) {
+            Ok(joined) => {
+                diagnosis.verified = joined;
+                return diagnosis;
+            }
+            Err(err) => err,
+        };
+
+        let mut prefix = PathBuf::new();
+        for (index, component) in path.components().enumerate() {
+            prefix.push(component);
+            match self.resolve_unreported(&prefix, PathBuf::new()) {
+                Ok(verified) => diagnosis.verified = verified,
+                Err(_) => {
+                    if let Component::Normal(name) = component {
+                        let candidate = diagnosis.verified.join(name);
+                        diagnosis.link_target = fs::read_link(&candidate).ok();
+                        diagnosis.resolved = fs::canonicalize(&candidate).ok();
+                    }
+                    diagnosis.failed = Some((index + 1, component.as_os_str().to_os_string()));
+                    break;
+                }
+            }
+        }
+        diagnosis.error = Some(err);
+        diagnosis
+    }
+}
@@ -0,0 +1,347 @@
+//! Archive extraction into a [`Jail`].
+//!
+//! Format-specific readers (the `tar` and `zip` features) hand every entry
+//! to [`Extractor`], which decides what to do with the entry name according
+//! to [`ExtractOptions`] and writes through the same validated operations
+//! as the [`EntrySink`](crate::EntrySink) implementation for [`Jail`].
+
+#![cfg(any(feature = "tar", feature = "zip"))]
+
+use crate::{Jail, JailError, JailedPath};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// What to do with symlink and hard link entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum LinkPolicy {
+    /// Leave links out and record them as skipped.
+    #[default]
+    Skip,
+    /// Fail the extraction when the archive contains a link.
+    Reject,
+    /// Create links whose target stays inside the jail. Hard links may only
+    /// point at files extracted earlier from the same archive; anything
+    /// else fails the extraction.
+    InJail,
+}
+
+/// What to do with entry names that point outside the jail: absolute
+/// paths, `..` components, or paths reaching through a symlink that
+/// escapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum EscapePolicy {
+    /// Fail the extraction with [`JailError::EscapedRoot`].
+    #[default]
+    Reject,
+    /// Leave the entry out and record it as skipped.
+    Skip,
+    /// Drop the leading `/` and every `..` component, like GNU tar does,
+    /// and extract what remains. The result is still validated.
+    Strip,
+}
+
+/// Options for archive extraction.
+///
+/// The defaults are the conservative choice: escaping entries fail the
+/// extraction, links are skipped, and existing files are not replaced.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::{EscapePolicy, ExtractOptions, LinkPolicy};
+///
+/// let options = ExtractOptions::new()
+///     .links(LinkPolicy::InJail)
+///     .escapes(EscapePolicy::Skip)
+///     .overwrite(true);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExtractOptions {
+    links: LinkPolicy,
+    escapes: EscapePolicy,
+    overwrite: bool,
+}
+
+impl ExtractOptions {
+    /// Default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How to handle symlink and hard link entries.
+    pub fn links(mut self, policy: LinkPolicy) -> Self {
+        self.links = policy;
+        self
+    }
+
+    /// How to handle entries that would land outside the jail.
+    pub fn escapes(mut self, policy: EscapePolicy) -> Self {
+        self.escapes = policy;
+        self
+    }
+
+    /// Replace existing files (default `false`).
+    ///
+    /// The old entry is unlinked and a new file created, so an existing
+    /// symlink is replaced rather than written through.
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.overwrite = overwrite;
+        self
+    }
+}
+
+/// Summary of an archive extraction.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct ExtractReport {
+    /// Entries written to the jail, in archive order.
+    pub extracted: Vec<JailedPath>,
+    /// Names of entries left out by the [`LinkPolicy`] or [`EscapePolicy`],
+    /// and of entry types that are never extracted (devices, FIFOs).
+    pub skipped: Vec<String>,
+}
+
+/// Entry-by-entry extraction state shared by the archive formats.
+pub(crate) struct Extractor<'a> {
+    jail: &'a Jail,
+    // `jail` without percent-decoding: entry names are not URL input
+    names: Jail,
+    options: &'a ExtractOptions,
+    report: ExtractReport,
+    // Files written by this extraction; the only valid hard link targets
+    written: HashSet<PathBuf>,
+}
+
+impl<'a> Extractor<'a> {
+    pub(crate) fn new(jail: &'a Jail, options: &'a ExtractOptions) -> Self {
+        Self {
+            jail,
+            names: jail.undecoded(),
+            options,
+            report: ExtractReport::default(),
+            written: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn finish(self) -> ExtractReport {
+        self.report
+    }
+
+    pub(crate) fn skip(&mut self, name: &str) {
+        self.report.skipped.push(name.to_owned());
+    }
+
+    /// Map an archive entry name to a relative path, applying the escape
+    /// policy. `None` means the entry is skipped.
+    pub(crate) fn entry_name(&mut self, name: &str) -> Result<Option<PathBuf>, JailError> {
+        let (relative, escapes) = split_name(name)?;
+        if escapes {
+            match self.options.escapes {
+                EscapePolicy::Reject => return Err(self.escaped(name)),
+                EscapePolicy::Skip => {
+                    self.skip(name);
+                    return Ok(None);
+                }
+                EscapePolicy::Strip => {}
+            }
+        }
+        if relative.as_os_str().is_empty() {
+            // "./" and friends: the root itself already exists
+            return Ok(None);
+        }
+        Ok(Some(relative))
+    }
+
+    pub(crate) fn dir(&mut self, name: &str, relative: &Path) -> Result<(), JailError> {
+        let result = self
+            .names
+            .create_dir_all(relative)
+            .map(|path| JailedPath::new(self.jail, path.into_inner()));
+        self.record(name, result)
+    }
+
+    pub(crate) fn file<R: Read>(
+        &mut self,
+        name: &str,
+        relative: &Path,
+        mode: u32,
+        contents: &mut R,
+    ) -> Result<(), JailError> {
+        let result = self.write_file(relative, mode, contents);
+        self.record(name, result)
+    }
+
+    pub(crate) fn symlink(
+        &mut self,
+        name: &str,
+        relative: &Path,
+        target: &Path,
+    ) -> Result<(), JailError> {
+        if !self.allow_link(name)? {
+            return Ok(());
+        }
+        #[cfg(unix)]
+        let result = self.names.create_entry_parent(relative).and_then(|()| {
+            self.replace_existing(relative)?;
+            self.names.symlink(target, relative)?;
+            let link = self.names.entry_path(relative)?;
+            Ok(JailedPath::new(self.jail, link))
+        });
+        #[cfg(not(unix))]
+        let result = {
+            let _ = (relative, target);
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "symlink entries are only supported on Unix",
+            )
+            .into())
+        };
+        self.record(name, result)
+    }
+
+    /// `target` is the archive name of the file being linked to.
+    #[cfg(feature = "tar")]
+    pub(crate) fn hard_link(
+        &mut self,
+        name: &str,
+        relative: &Path,
+        target: &str,
+    ) -> Result<(), JailError> {
+        if !self.allow_link(name)? {
+            return Ok(());
+        }
+        let (target, escapes) = split_name(target)?;
+        if escapes && self.options.escapes == EscapePolicy::Reject {
+            return Err(self.escaped(name));
+        }
+        if escapes && self.options.escapes == EscapePolicy::Skip {
+            self.skip(name);
+            return Ok(());
+        }
+        let result = self.names.create_entry_parent(relative).and_then(|()| {
+            let original = self.names.join(&target)?;
+            if !self.written.contains(&original) {
+                return Err(JailError::InvalidPath(format!(
+                    "hard link '{}' must point at a file extracted from the same archive",
+                    name
+                )));
+            }
+            self.replace_existing(relative)?;
+            let link = self.names.entry_path(relative)?;
+            // Counted again, as a rescan would
+            let size = fs::metadata(&original)?.len();
+            self.jail.config().resize(0, size)?;
+            if let Err(err) = fs::hard_link(&original, &link) {
+                self.jail.config().release(size);
+                return Err(err.into());
+            }
+            Ok(JailedPath::new(self.jail, link))
+        });
+        self.record(name, result)
+    }
+
+    fn allow_link(&mut self, name: &str) -> Result<bool, JailError> {
+        match self.options.links {
+            LinkPolicy::InJail => Ok(true),
+            LinkPolicy::Skip => {
+                self.skip(name);
+                Ok(false)
+            }
+            LinkPolicy::Reject => Err(JailError::InvalidPath(format!(
+                "archive contains link entry '{}'",
+                name
+            ))),
+        }
+    }
+
+    fn write_file<R: Read>(
+        &mut self,
+        relative: &Path,
+        mode: u32,
+        contents: &mut R,
+    ) -> Result<JailedPath, JailError> {
+        self.replace_existing(relative)?;
+        let mut file = self.names.create_entry_file(relative, mode)?;
+        if let Err(err) = io::copy(contents, &mut file) {
+            // Don't leave a truncated member, or its bytes in the quota
+            file.discard();
+            return Err(err.into());
+        }
+        let path = file.path().to_path_buf();
+        self.written.insert(path.clone());
+        Ok(JailedPath::new(self.jail, path))
+    }
+
+    /// With `overwrite`, unlink an existing non-directory at `relative`.
+    fn replace_existing(&self, relative: &Path) -> Result<(), JailError> {
+        let path = self.names.entry_path(relative)?;
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if self.options.overwrite && !meta.is_dir() => {
+                self.names.remove_file(relative)
+            }
+            // Leave it to the create call to report AlreadyExists
+            _ => Ok(()),
+        }
+    }
+
+    /// Apply the escape policy to errors found while writing (e.g. a path
+    /// that reaches outside through a symlink).
+    fn record(
+        &mut self,
+        name: &str,
+        result: Result<JailedPath, JailError>,
+    ) -> Result<(), JailError> {
+        match result {
+            Ok(path) => {
+                self.report.extracted.push(path);
+                Ok(())
+            }
+            Err(
+                JailError::EscapedRoot { .. }
+                | JailError::BrokenSymlink(_)
+                | JailError::JunctionEscape { .. }
+                | JailError::BrokenJunction(_),
+            ) if self.options.escapes != EscapePolicy::Reject => {
+                self.skip(name);
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    fn escaped(&self, name: &str) -> JailError {
+        JailError::EscapedRoot {
+            attempted: PathBuf::from(name),
+            root: self.jail.root().to_path_buf(),
+        }
+    }
+}
+
+/// Split an archive name on `/` into a relative path. The flag reports
+/// whether anything was dropped because it pointed outside: a leading `/`,
+/// `..`, or (on Windows) a drive prefix.
+fn split_name(name: &str) -> Result<(PathBuf, bool), JailError> {
+    if name.contains('\0') {
+        return Err(JailError::InvalidPath("null bytes not allowed".into()));
+    }
+    let mut relative = PathBuf::new();
+    let mut escapes = name.starts_with('/');
+    for part in name.split('/') {
+        if part.is_empty() || part == "." {
+            continue;
+        }
+        if Path::new(part)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_)))
+        {
+            relative.push(part);
+        } else {
+            escapes = true;
+        }
+    }
+    Ok((relative, escapes))
+}
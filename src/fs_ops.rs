@@ -0,0 +1,326 @@
+//! The [`FsOps`] trait, for writing code against a jail that tests can
+//! swap out.
+
+use crate::{Jail, JailError};
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(feature = "test-util")]
+use crate::LexicalJail;
+#[cfg(feature = "test-util")]
+use std::collections::BTreeMap;
+#[cfg(feature = "test-util")]
+use std::io;
+#[cfg(feature = "test-util")]
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// The file operations of a [`Jail`], as a trait.
+///
+/// Application code that takes `&dyn FsOps` (or `impl FsOps`) instead of
+/// a [`Jail`] can be handed the real jail in production and a test double
+/// in unit tests, such as [`MemoryOps`] with the `test-util` feature. Every
+/// path is relative to the jail root and validated by the implementation.
+///
+/// [`Jail`] implements it with its own methods: [`Jail::join`],
+/// [`Jail::read_dir`], [`Jail::remove_file`], and, with `secure-open` on
+/// Unix, `Jail::open` and `Jail::create`. Without `secure-open`, files
+/// are opened by path after [`Jail::join`], with the same link and
+/// creation handling as [`Jail::read`] and [`Jail::write`].
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{FsOps, Jail, JailError};
+/// use std::io::Write;
+/// use std::path::Path;
+///
+/// fn save_report(fs: &dyn FsOps, name: &str, body: &[u8]) -> Result<(), JailError> {
+///     let mut file = fs.create(Path::new(name))?;
+///     file.write_all(body)?;
+///     Ok(())
+/// }
+///
+/// let jail = Jail::new("/var/reports")?;
+/// save_report(&jail, "2025-q1.txt", b"...")?;
+/// # Ok::<(), JailError>(())
+/// ```
+pub trait FsOps {
+    /// Validate `relative` and return the absolute path it names.
+    fn join(&self, relative: &Path) -> Result<PathBuf, JailError>;
+
+    /// Open an existing file for reading.
+    fn open(&self, relative: &Path) -> Result<Box<dyn Read + Send>, JailError>;
+
+    /// Create a new file for writing. Fails with
+    /// [`AlreadyExists`](std::io::ErrorKind::AlreadyExists) if something
+    /// is already at `relative`; its directory must exist.
+    fn create(&self, relative: &Path) -> Result<Box<dyn Write + Send>, JailError>;
+
+    /// The names of the entries in a directory, in no particular order.
+    fn read_dir(&self, relative: &Path) -> Result<Vec<OsString>, JailError>;
+
+    /// Remove a file. Directories are refused.
+    fn remove(&self, relative: &Path) -> Result<(), JailError>;
+}
+
+impl FsOps for Jail {
+    fn join(&self, relative: &Path) -> Result<PathBuf, JailError> {
+        Jail::join(self, relative)
+    }
+
+    fn open(&self, relative: &Path) -> Result<Box<dyn Read + Send>, JailError> {
+        #[cfg(all(feature = "secure-open", unix))]
+        return Ok(Box::new(Jail::open(self, relative)?));
+
+        #[cfg(not(all(feature = "secure-open", unix)))]
+        {
+            let path = Jail::join(self, relative)?;
+            let file = std::fs::File::open(&path)?;
+            self.config().count_open();
+            self.config().check_links(&path, &file.metadata()?)?;
+            Ok(Box::new(file))
+        }
+    }
+
+    fn create(&self, relative: &Path) -> Result<Box<dyn Write + Send>, JailError> {
+        #[cfg(all(feature = "secure-open", unix))]
+        return Ok(Box::new(Jail::create(self, relative)?));
+
+        #[cfg(not(all(feature = "secure-open", unix)))]
+        {
+            let path = Jail::join(self, relative)?;
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true).create_new(true);
+            #[cfg(unix)]
+            if let Some(mode) = self.config().file_mode() {
+                std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+            }
+            let file = options.open(&path)?;
+            self.config().count_open();
+            if self.config().tracks_creation() {
+                self.config().created_file(&path, &file)?;
+            }
+            Ok(Box::new(file))
+        }
+    }
+
+    fn read_dir(&self, relative: &Path) -> Result<Vec<OsString>, JailError> {
+        Jail::read_dir(self, relative)?
+            .map(|entry| Ok(entry?.file_name().to_os_string()))
+            .collect()
+    }
+
+    fn remove(&self, relative: &Path) -> Result<(), JailError> {
+        self.remove_file(relative)
+    }
+}
+
+/// An in-memory [`FsOps`] for unit tests (`test-util` feature).
+///
+/// Paths are validated lexically like [`LexicalJail::join`], so escapes
+/// are rejected with the same errors as a [`Jail`]; there are no
+/// symlinks to follow. Files and directories live in a shared tree:
+/// clones see the same contents, so a test can keep one to seed and
+/// inspect while the code under test uses another. `read_dir` lists
+/// names in sorted order.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::{FsOps, MemoryOps};
+/// use std::io::Write;
+/// use std::path::Path;
+///
+/// let fs = MemoryOps::new("/srv/reports")?;
+/// fs.create_dir_all("2025")?;
+/// fs.create(Path::new("2025/q1.txt"))?.write_all(b"ok")?;
+///
+/// assert_eq!(fs.contents("2025/q1.txt").as_deref(), Some(&b"ok"[..]));
+/// assert!(fs.create(Path::new("../escape.txt")).is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[cfg(feature = "test-util")]
+#[derive(Debug, Clone)]
+pub struct MemoryOps {
+    jail: LexicalJail,
+    nodes: Arc<Mutex<BTreeMap<PathBuf, Node>>>,
+}
+
+#[cfg(feature = "test-util")]
+#[derive(Debug)]
+enum Node {
+    Dir,
+    File(Vec<u8>),
+}
+
+#[cfg(feature = "test-util")]
+impl MemoryOps {
+    /// An empty tree rooted at `root`, which must be absolute and not `/`.
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, JailError> {
+        Ok(Self {
+            jail: LexicalJail::new(root)?,
+            nodes: Arc::default(),
+        })
+    }
+
+    /// Returns the root path.
+    pub fn root(&self) -> &Path {
+        self.jail.root()
+    }
+
+    /// Create a directory and any missing parents.
+    pub fn create_dir_all<P: AsRef<Path>>(&self, relative: P) -> Result<(), JailError> {
+        let path = self.jail.join(relative)?;
+        let mut nodes = self.lock();
+        for dir in path.ancestors().take_while(|dir| *dir != self.root()) {
+            if let Some(Node::File(_)) = nodes.get(dir) {
+                return Err(not_a_directory(dir).into());
+            }
+        }
+        for dir in path.ancestors().take_while(|dir| *dir != self.root()) {
+            nodes.insert(dir.to_path_buf(), Node::Dir);
+        }
+        Ok(())
+    }
+
+    /// Create or replace a file, creating missing parent directories.
+    /// Seeds the tree for a test without going through [`FsOps::create`].
+    pub fn insert_file<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        relative: P,
+        contents: C,
+    ) -> Result<(), JailError> {
+        let path = self.jail.join(relative)?;
+        if let Some(parent) = path.parent().filter(|dir| *dir != self.root()) {
+            self.create_dir_all(self.relative(parent))?;
+        }
+        let mut nodes = self.lock();
+        if let Some(Node::Dir) = nodes.get(&path) {
+            return Err(is_a_directory(&path).into());
+        }
+        nodes.insert(path, Node::File(contents.as_ref().to_vec()));
+        Ok(())
+    }
+
+    /// The contents of the file at `relative`, or `None` if there is no
+    /// file there.
+    pub fn contents<P: AsRef<Path>>(&self, relative: P) -> Option<Vec<u8>> {
+        let path = self.jail.join(relative).ok()?;
+        match self.lock().get(&path) {
+            Some(Node::File(contents)) => Some(contents.clone()),
+            _ => None,
+        }
+    }
+
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        path.strip_prefix(self.root()).unwrap_or(path)
+    }
+
+    /// Require `path` to be the root or an existing directory.
+    fn check_dir(&self, nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> io::Result<()> {
+        match nodes.get(path) {
+            _ if path == self.root() => Ok(()),
+            Some(Node::Dir) => Ok(()),
+            Some(Node::File(_)) => Err(not_a_directory(path)),
+            None => Err(io::ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<PathBuf, Node>> {
+        self.nodes.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+#[cfg(feature = "test-util")]
+impl FsOps for MemoryOps {
+    fn join(&self, relative: &Path) -> Result<PathBuf, JailError> {
+        self.jail.join(relative)
+    }
+
+    fn open(&self, relative: &Path) -> Result<Box<dyn Read + Send>, JailError> {
+        let path = self.jail.join(relative)?;
+        match self.lock().get(&path) {
+            Some(Node::File(contents)) => Ok(Box::new(io::Cursor::new(contents.clone()))),
+            Some(Node::Dir) => Err(is_a_directory(&path).into()),
+            None => Err(io::Error::from(io::ErrorKind::NotFound).into()),
+        }
+    }
+
+    fn create(&self, relative: &Path) -> Result<Box<dyn Write + Send>, JailError> {
+        let path = self.jail.join(relative)?;
+        let mut nodes = self.lock();
+        if let Some(parent) = path.parent() {
+            self.check_dir(&nodes, parent)?;
+        }
+        if path == self.root() || nodes.contains_key(&path) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists).into());
+        }
+        nodes.insert(path.clone(), Node::File(Vec::new()));
+        Ok(Box::new(MemoryWriter {
+            nodes: Arc::clone(&self.nodes),
+            path,
+        }))
+    }
+
+    fn read_dir(&self, relative: &Path) -> Result<Vec<OsString>, JailError> {
+        let path = self.jail.join(relative)?;
+        let nodes = self.lock();
+        self.check_dir(&nodes, &path)?;
+        Ok(nodes
+            .keys()
+            .filter(|entry| entry.parent() == Some(&path))
+            .filter_map(|entry| entry.file_name().map(OsString::from))
+            .collect())
+    }
+
+    fn remove(&self, relative: &Path) -> Result<(), JailError> {
+        let path = self.jail.join(relative)?;
+        let mut nodes = self.lock();
+        match nodes.get(&path) {
+            Some(Node::File(_)) => {
+                nodes.remove(&path);
+                Ok(())
+            }
+            _ if path == self.root() => Err(is_a_directory(&path).into()),
+            Some(Node::Dir) => Err(is_a_directory(&path).into()),
+            None => Err(io::Error::from(io::ErrorKind::NotFound).into()),
+        }
+    }
+}
+
+/// Appends to a [`MemoryOps`] file as it is written.
+#[cfg(feature = "test-util")]
+struct MemoryWriter {
+    nodes: Arc<Mutex<BTreeMap<PathBuf, Node>>>,
+    path: PathBuf,
+}
+
+#[cfg(feature = "test-util")]
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut nodes = self.nodes.lock().unwrap_or_else(|err| err.into_inner());
+        match nodes.get_mut(&self.path) {
+            Some(Node::File(contents)) => {
+                contents.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            // Removed while open; like an unlinked file, writes go nowhere
+            _ => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "test-util")]
+fn not_a_directory(path: &Path) -> io::Error {
+    io::Error::other(format!("'{}' is not a directory", path.display()))
+}
+
+#[cfg(feature = "test-util")]
+fn is_a_directory(path: &Path) -> io::Error {
+    io::Error::other(format!("'{}' is a directory", path.display()))
+}
@@ -0,0 +1,315 @@
+//! Glob pattern expansion restricted to the jail.
+
+use crate::read_dir::{is_listed, verify_entry};
+use crate::{Access, Jail, JailError, JailedPath, ReadWrite};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// One `/`-separated component of a glob pattern.
+#[derive(Debug)]
+enum Segment {
+    /// Plain name, no wildcards.
+    Literal(String),
+    /// Name containing `*`, `?` or `[...]`.
+    Pattern(String),
+    /// `**`: zero or more directories.
+    AnyDirs,
+}
+
+/// Iterator over the paths matching a glob pattern inside a [`Jail`].
+///
+/// Created by [`Jail::glob`]. Matches are verified exactly like
+/// [`Jail::read_dir`] entries; symlinks that would escape the jail are
+/// yielded as errors if their name matches the pattern, and names the
+/// jail refuses are neither matched nor descended into.
+#[derive(Debug)]
+pub struct Glob<'a, A: Access = ReadWrite> {
+    jail: &'a Jail<A>,
+    segments: Vec<Segment>,
+    // Directories still to expand, with the index of the next segment
+    pending: Vec<(PathBuf, usize)>,
+    ready: VecDeque<Result<JailedPath<A>, JailError>>,
+}
+
+impl<A: Access> Iterator for Glob<'_, A> {
+    type Item = Result<JailedPath<A>, JailError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.ready.pop_front() {
+                return Some(item);
+            }
+            let (dir, index) = self.pending.pop()?;
+            if let Err(err) = self.expand(dir, index) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+impl<A: Access> Glob<'_, A> {
+    fn expand(&mut self, dir: PathBuf, index: usize) -> Result<(), JailError> {
+        let Some(segment) = self.segments.get(index) else {
+            self.ready.push_back(Ok(JailedPath::new(self.jail, dir)));
+            return Ok(());
+        };
+        let last = index + 1 == self.segments.len();
+
+        match segment {
+            Segment::Literal(name) => {
+                let candidate = dir.join(name);
+                let Ok(meta) = candidate.symlink_metadata() else {
+                    return Ok(());
+                };
+                if meta.is_symlink() && self.jail.config().denies_symlinks() {
+                    return Err(JailError::SymlinkNotAllowed(candidate));
+                }
+                // Same checks join() applies to an existing component
+                let resolved = if candidate.exists() {
+                    self.jail.verify_inside(candidate)?
+                } else {
+                    return Err(JailError::BrokenSymlink(candidate));
+                };
+                if !is_listed(self.jail, &resolved) {
+                    return Ok(());
+                }
+                if last {
+                    self.ready
+                        .push_back(Ok(JailedPath::new(self.jail, resolved)));
+                } else if resolved.is_dir() {
+                    self.pending.push((resolved, index + 1));
+                }
+            }
+            Segment::Pattern(pattern) => {
+                let mut matched = Vec::new();
+                for entry in fs::read_dir(&dir)? {
+                    let entry = entry?;
+                    let name = entry.file_name();
+                    if name.to_str().is_some_and(|n| matches(pattern, n)) {
+                        matched.push(entry);
+                    }
+                }
+                matched.sort_by_key(|e| e.file_name());
+                let mut subdirs = Vec::new();
+                for entry in matched {
+                    match verify_entry(self.jail, entry) {
+                        Ok(Some(entry)) if last => self.ready.push_back(Ok(entry.into_path())),
+                        Ok(Some(entry)) if entry.path().is_dir() => {
+                            subdirs.push((entry.into_path().into_inner(), index + 1));
+                        }
+                        Ok(_) => {}
+                        Err(err) => self.ready.push_back(Err(err)),
+                    }
+                }
+                // Reverse so the stack pops them in sorted order
+                self.pending.extend(subdirs.into_iter().rev());
+            }
+            Segment::AnyDirs => {
+                let mut subdirs = Vec::new();
+                for entry in fs::read_dir(&dir)? {
+                    let entry = entry?;
+                    let name = entry.file_name();
+                    let hidden = name.to_string_lossy().starts_with('.');
+                    let denied = self.jail.config().check_name(&name).is_err();
+                    // Like shells, `**` does not descend through symlinks or dotfiles
+                    if entry.file_type()?.is_dir() && !hidden && !denied {
+                        subdirs.push(entry.path());
+                    }
+                }
+                subdirs.sort();
+                self.pending
+                    .extend(subdirs.into_iter().rev().map(|d| (d, index)));
+                // Zero directories: try the rest of the pattern right here
+                self.pending.push((dir, index + 1));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Split and validate a glob pattern.
+fn parse(pattern: &str) -> Result<Vec<Segment>, JailError> {
+    if pattern.contains('\0') {
+        return Err(JailError::InvalidPath("null bytes not allowed".into()));
+    }
+    let path = Path::new(pattern);
+    if path.is_absolute() {
+        return Err(JailError::InvalidPath("absolute paths not allowed".into()));
+    }
+
+    let mut segments = Vec::new();
+    for component in path.components() {
+        let name = match component {
+            Component::Normal(name) => name.to_str().unwrap_or_default(),
+            Component::CurDir => continue,
+            Component::ParentDir => {
+                return Err(JailError::InvalidPath(
+                    "'..' not allowed in glob patterns".into(),
+                ))
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(JailError::InvalidPath(
+                    "absolute components not allowed".into(),
+                ))
+            }
+        };
+        let segment = if name == "**" {
+            // Consecutive `**` are equivalent to one
+            if matches!(segments.last(), Some(Segment::AnyDirs)) {
+                continue;
+            }
+            Segment::AnyDirs
+        } else if name.contains(['*', '?', '[']) {
+            validate_classes(name)?;
+            Segment::Pattern(name.to_owned())
+        } else {
+            Segment::Literal(name.to_owned())
+        };
+        segments.push(segment);
+    }
+
+    if segments.is_empty() {
+        return Err(JailError::InvalidPath("empty glob pattern".into()));
+    }
+    Ok(segments)
+}
+
+pub(crate) fn validate_classes(pattern: &str) -> Result<(), JailError> {
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c == '[' && parse_class(&mut chars).is_none() {
+            return Err(JailError::InvalidPath(format!(
+                "unclosed '[' in glob pattern '{}'",
+                pattern
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Parse a `[...]` class body (after the `[`). Returns the negation flag and
+/// the ranges, advancing `chars` past the closing `]`.
+fn parse_class(chars: &mut std::str::Chars<'_>) -> Option<(bool, Vec<(char, char)>)> {
+    let mut negated = false;
+    let mut ranges = Vec::new();
+    let mut first = true;
+    loop {
+        let c = chars.next()?;
+        match c {
+            '!' | '^' if first && !negated => {
+                negated = true;
+                continue;
+            }
+            // A `]` right after `[` or `[!` is a literal
+            ']' if !first => return Some((negated, ranges)),
+            _ => {}
+        }
+        first = false;
+        let mut lookahead = chars.clone();
+        if lookahead.next() == Some('-') {
+            if let Some(end) = lookahead.next().filter(|&e| e != ']') {
+                *chars = lookahead;
+                ranges.push((c, end));
+                continue;
+            }
+        }
+        ranges.push((c, c));
+    }
+}
+
+/// Match a single file name against a single pattern component.
+///
+/// Wildcards never match a leading `.`, so `*` does not pick up dotfiles
+/// unless the pattern itself starts with `.`.
+fn matches(pattern: &str, name: &str) -> bool {
+    if name.starts_with('.') && !pattern.starts_with('.') {
+        return false;
+    }
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    match_from(&pattern, &name)
+}
+
+pub(crate) fn match_from(pattern: &[char], name: &[char]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    // Position to resume from after the most recent `*`
+    let mut star: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+                continue;
+            }
+            Some('?') => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            Some('[') => {
+                let rest: String = pattern[p + 1..].iter().collect();
+                let mut chars = rest.chars();
+                if let Some((negated, ranges)) = parse_class(&mut chars) {
+                    let c = name[n];
+                    let hit = ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+                    if hit != negated {
+                        p = pattern.len() - chars.count();
+                        n += 1;
+                        continue;
+                    }
+                }
+            }
+            Some(&c) if c == name[n] => {
+                p += 1;
+                n += 1;
+                continue;
+            }
+            _ => {}
+        }
+        // Mismatch: let the last `*` swallow one more character
+        match star {
+            Some((sp, sn)) => {
+                star = Some((sp, sn + 1));
+                p = sp + 1;
+                n = sn + 1;
+            }
+            None => return false,
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl<A: Access> Jail<A> {
+    /// Expand a glob pattern inside the jail.
+    ///
+    /// Supports `*`, `?`, `[abc]`, `[a-z]`, `[!a]` within a component and
+    /// `**` as a whole component matching zero or more directories.
+    /// Wildcards do not match names starting with `.`, and `**` does not
+    /// descend through symlinks.
+    ///
+    /// Patterns containing `..`, absolute prefixes, or null bytes are
+    /// rejected with [`JailError::InvalidPath`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// for path in jail.glob("reports/**/*.pdf")? {
+    ///     println!("{}", path?);
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn glob(&self, pattern: &str) -> Result<Glob<'_, A>, JailError> {
+        let segments = parse(pattern)?;
+        Ok(Glob {
+            jail: self,
+            segments,
+            pending: vec![(self.root().to_path_buf(), 0)],
+            ready: VecDeque::new(),
+        })
+    }
+}
@@ -1,11 +1,43 @@
+use crate::access::{Access, ReadOnly, ReadWrite};
+use crate::builder::Config;
 use crate::error::JailError;
 use crate::jailed_path::JailedPath;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::marker::PhantomData;
 use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
 
 /// A filesystem sandbox that restricts paths to a root directory.
-#[derive(Debug, Clone)]
-pub struct Jail {
-    root: PathBuf,
+///
+/// Use [`Jail::builder`] for non-default options. Two jails are equal if
+/// they have the same canonical root and options.
+///
+/// The type parameter says what the handle may do. `Jail` is
+/// `Jail<ReadWrite>`; [`read_only`](Self::read_only) returns a
+/// `Jail<ReadOnly>`, which has no methods that create, modify or delete
+/// anything, and hands out read-only [`JailedPath`]s.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Jail<A: Access = ReadWrite> {
+    // Shared so that cloning a jail (e.g. into every JailedPath) is cheap
+    root: Arc<Path>,
+    config: Arc<Config>,
+    access: PhantomData<A>,
+}
+
+impl<A: Access> fmt::Debug for Jail<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Jail");
+        match self.config.redact_root() {
+            Some(placeholder) => debug.field("root", &Path::new(placeholder)),
+            None => debug.field("root", &self.root),
+        };
+        debug
+            .field("config", &self.config)
+            .field("access", &self.access)
+            .finish()
+    }
 }
 
 impl Jail {
@@ -15,21 +47,112 @@ impl Jail {
     /// - Root does not exist
     /// - Root is not a directory
     /// - Root is a filesystem root (`/`, `C:\`, `\\server\share`)
+    ///
+    /// A directory on a UNC share (`\\server\share\dir`) is a valid root;
+    /// paths under it are compared by volume, so another share on the same
+    /// server is outside the jail.
     pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, JailError> {
-        let root = root.as_ref().canonicalize()?;
+        Self::with_config(root.as_ref(), Config::default())
+    }
+
+    pub(crate) fn with_config(root: &Path, config: Config) -> Result<Self, JailError> {
+        let root = canonicalize(root)?;
         // Reject filesystem roots (/, C:\) - they have no parent
         // Reject non-directories (files, etc.)
         if root.parent().is_none() || !root.is_dir() {
             return Err(JailError::InvalidRoot(root));
         }
-        Ok(Self { root })
+        config.check_root(&root)?;
+        Ok(Self::from_canonical(root, config))
+    }
+
+    /// A jail over an already validated root.
+    pub(crate) fn from_canonical(root: PathBuf, mut config: Config) -> Self {
+        config.bind_root(&root);
+        Self {
+            root: root.into(),
+            config: Arc::new(config),
+            access: PhantomData,
+        }
+    }
+
+    /// A handle to the same jail that can only validate and read.
+    ///
+    /// Hand it to code that must not write, such as plugins: the type has
+    /// no `write`, `create`, `remove_*` or similar methods, and neither do
+    /// the [`JailedPath`]s it returns.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, ReadOnly};
+    ///
+    /// fn plugin(jail: &Jail<ReadOnly>) -> Result<Vec<u8>, path_jail::JailError> {
+    ///     // jail.write("x", b"y") would not compile
+    ///     jail.read("config.toml")
+    /// }
+    ///
+    /// let jail = Jail::new("/var/app")?;
+    /// plugin(&jail.read_only())?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn read_only(&self) -> Jail<ReadOnly> {
+        self.with_access()
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// The same jail with another capability marker. Crate-internal: this
+    /// is how read-only handles are made, and upgrades must stay private.
+    pub(crate) fn with_access<B: Access>(&self) -> Jail<B> {
+        Jail {
+            root: Arc::clone(&self.root),
+            config: Arc::clone(&self.config),
+            access: PhantomData,
+        }
+    }
+
+    pub(crate) fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// This jail without input preprocessing (percent-decoding, Unicode
+    /// normalization), for re-validating paths it already returned.
+    pub(crate) fn verbatim(&self) -> Jail<A> {
+        Jail {
+            root: Arc::clone(&self.root),
+            config: Arc::new(self.config.verbatim()),
+            access: PhantomData,
+        }
+    }
+
+    /// This jail without percent-decoding, for archive entry names.
+    #[cfg(any(feature = "tar", feature = "zip"))]
+    pub(crate) fn undecoded(&self) -> Jail<A> {
+        Jail {
+            root: Arc::clone(&self.root),
+            config: Arc::new(self.config.undecoded()),
+            access: PhantomData,
+        }
     }
 
     /// Returns the canonicalized root path.
+    ///
+    /// On Windows this is a verbatim path (`\\?\C:\...` or
+    /// `\\?\UNC\server\share\...`), as are the paths returned by
+    /// [`join`](Self::join), so results longer than `MAX_PATH` work with
+    /// every Win32 API. [`contains`](Self::contains) and
+    /// [`relative`](Self::relative) accept both the verbatim and the plain
+    /// spelling.
     pub fn root(&self) -> &Path {
         &self.root
     }
 
+    /// The [`id`](crate::JailBuilder::id) given to this jail, if any.
+    pub fn id(&self) -> Option<&str> {
+        self.config.id()
+    }
+
     /// Safely join a relative path to the jail root.
     ///
     /// Resolves `..` components, follows symlinks, verifies containment.
@@ -38,11 +161,208 @@ impl Jail {
     /// Rejects absolute paths, null bytes, and paths that would escape the jail.
     #[must_use = "use the returned path, not the original input"]
     pub fn join<P: AsRef<Path>>(&self, relative: P) -> Result<PathBuf, JailError> {
-        let path = relative.as_ref();
+        let path = self.config.prepare(relative.as_ref())?;
+        self.resolve(&path)
+    }
 
+    /// [`join`](Self::join) for a name received as a raw [`OsStr`].
+    ///
+    /// `join` accepts an `OsStr` as well; this spells out that nothing
+    /// converts the input to UTF-8 on the way. All checks look at the
+    /// bytes on Unix and the UTF-16 units on Windows, so a name that is not
+    /// valid Unicode is validated as it is, never through a lossy copy
+    /// with replacement characters.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join_os(&self, relative: &OsStr) -> Result<PathBuf, JailError> {
+        self.join(relative)
+    }
+
+    /// [`join`](Self::join) for a path received as bytes, such as a file
+    /// name from an FTP or SFTP client.
+    ///
+    /// On Unix the bytes are the path, whatever their encoding. Elsewhere
+    /// there is no byte form of a path, so they must be UTF-8.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/srv/ftp")?;
+    /// # #[cfg(unix)]
+    /// let path = jail.join_bytes(b"incoming/caf\xe9.txt")?; // Latin-1 name
+    /// assert!(jail.join_bytes(b"../etc/passwd").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// As [`join`](Self::join), and [`JailError::InvalidPath`] for bytes
+    /// that are not UTF-8 on platforms other than Unix.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join_bytes(&self, relative: &[u8]) -> Result<PathBuf, JailError> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            self.join(OsStr::from_bytes(relative))
+        }
+        #[cfg(not(unix))]
+        match std::str::from_utf8(relative) {
+            Ok(relative) => self.join(relative),
+            Err(_) => Err(JailError::InvalidPath("path is not valid UTF-8".into())),
+        }
+    }
+
+    /// Like [`join`](Self::join), but the path must exist.
+    ///
+    /// # Errors
+    ///
+    /// Besides what `join` returns, [`JailError::Io`] with
+    /// [`NotFound`](io::ErrorKind::NotFound) if nothing is there, so
+    /// [`is_not_found`](JailError::is_not_found) tells a missing file from
+    /// a refused path.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join_existing<P: AsRef<Path>>(&self, relative: P) -> Result<PathBuf, JailError> {
+        let path = self.join(relative)?;
+        std::fs::symlink_metadata(&path)?;
+        Ok(path)
+    }
+
+    /// Like [`join`](Self::join), but the path must not exist yet and its
+    /// parent must be an existing directory: the checks to make before
+    /// creating a file or directory there.
+    ///
+    /// The checks are a snapshot; to create the file without a race, open
+    /// it with `create_new` (or [`create`](Self::create) with the
+    /// `secure-open` feature).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// match jail.join_new("alice/report.pdf") {
+    ///     Ok(path) => println!("free: {}", path.display()),
+    ///     Err(e) if e.io_kind() == std::io::ErrorKind::AlreadyExists => println!("taken"),
+    ///     Err(e) => return Err(e),
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Besides what `join` returns, [`JailError::Io`] with
+    /// [`AlreadyExists`](io::ErrorKind::AlreadyExists) if something is
+    /// there, with [`NotFound`](io::ErrorKind::NotFound) if the parent is
+    /// missing, and [`JailError::InvalidPath`] if the parent is not a
+    /// directory.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join_new<P: AsRef<Path>>(&self, relative: P) -> Result<PathBuf, JailError> {
+        let path = self.join(relative)?;
+        if std::fs::symlink_metadata(&path).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("'{}' already exists", self.config.shown(&path).display()),
+            )
+            .into());
+        }
+        // Never the root itself, which exists
+        let parent = path.parent().unwrap_or(&path);
+        if !std::fs::metadata(parent)?.is_dir() {
+            return Err(JailError::InvalidPath(format!(
+                "parent of '{}' is not a directory",
+                self.config.shown(&path).display()
+            )));
+        }
+        Ok(path)
+    }
+
+    /// Like [`join`](Self::join), but builds the result in `buf` instead
+    /// of a new `PathBuf`.
+    ///
+    /// `buf` is cleared first, and its allocation is reused: once it has
+    /// grown to fit, a jail without deny rules or a verification cache
+    /// validates a path without symlinks allocating nothing. For servers
+    /// that validate a path per request, keep one buffer per worker. On
+    /// error `buf` is left empty.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::path::PathBuf;
+    ///
+    /// let jail = Jail::new("/var/www")?;
+    /// let mut buf = PathBuf::with_capacity(256);
+    /// for request in ["index.html", "css/main.css"] {
+    ///     jail.join_into(request, &mut buf)?;
+    ///     println!("{}", buf.display());
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn join_into<P: AsRef<Path>>(
+        &self,
+        relative: P,
+        buf: &mut PathBuf,
+    ) -> Result<(), JailError> {
+        let path = self.config.prepare(relative.as_ref())?;
+        *buf = self.resolve_buf(&path, std::mem::take(buf))?;
+        Ok(())
+    }
+
+    /// The validation behind [`join`](Self::join), for paths that are not
+    /// user input (e.g. derived from an already validated path) and so
+    /// must not be preprocessed again.
+    pub(crate) fn resolve(&self, path: &Path) -> Result<PathBuf, JailError> {
+        self.resolve_buf(path, PathBuf::new())
+    }
+
+    /// [`resolve`](Self::resolve), building the result in `buf`.
+    fn resolve_buf(&self, path: &Path, buf: PathBuf) -> Result<PathBuf, JailError> {
+        self.config.count_join();
+        self.resolve_unreported(path, buf)
+            .map_err(|err| self.config.reject(path, err))
+    }
+
+    /// [`resolve_buf`](Self::resolve_buf) without the
+    /// [`on_reject`](crate::JailBuilder::on_reject) hook.
+    pub(crate) fn resolve_unreported(
+        &self,
+        path: &Path,
+        buf: PathBuf,
+    ) -> Result<PathBuf, JailError> {
+        if self.config.trusts_tree() {
+            let joined = self.resolve_lexical_buf(path, buf)?;
+            if self.config.restricts_files() && !joined.is_dir() {
+                self.config.check_file(&joined)?;
+            }
+            return Ok(joined);
+        }
+        match self.config.dir_cache() {
+            Some(cache) => self.resolve_with(&cache.over(&Disk, &self.root), path, buf),
+            None => self.resolve_with(&Disk, path, buf),
+        }
+    }
+
+    /// [`resolve`](Self::resolve) against any [`Lookup`].
+    pub(crate) fn resolve_in<L: Lookup>(&self, fs: &L, path: &Path) -> Result<PathBuf, JailError> {
+        self.config.count_join();
+        self.resolve_with(fs, path, PathBuf::new())
+            .map_err(|err| self.config.reject(path, err))
+    }
+
+    /// [`resolve_in`](Self::resolve_in), reusing the allocation of `buf`
+    /// for the result.
+    fn resolve_with<L: Lookup>(
+        &self,
+        fs: &L,
+        path: &Path,
+        mut current: PathBuf,
+    ) -> Result<PathBuf, JailError> {
         // Reject null bytes (C string terminator attack)
         // These would be truncated by the OS, causing path confusion
-        if path.to_string_lossy().contains('\0') {
+        if contains_unit(path.as_os_str(), b'\0') {
             return Err(JailError::InvalidPath("null bytes not allowed".into()));
         }
 
@@ -50,32 +370,79 @@ impl Jail {
             return Err(JailError::InvalidPath("absolute paths not allowed".into()));
         }
 
-        let mut current = self.root.clone();
+        self.config.check_depth(path)?;
+
+        current.as_mut_os_string().clear();
+        current.push(&self.root);
+        // While `current` is canonical, each component costs one lstat and
+        // `..` is a plain pop. Windows (junctions, 8.3 names) and paths
+        // through an allowed outside symlink target canonicalize instead.
+        let mut exact = !cfg!(windows);
         for component in path.components() {
             match component {
+                Component::Normal(name) if exact => {
+                    self.config.check_name(name)?;
+                    current.push(name);
+                    match fs.node(&current) {
+                        Node::Missing => {}
+                        Node::Dir | Node::File => self.config.check_device(fs, &current)?,
+                        Node::Link(target) => {
+                            if self.config.denies_symlinks() {
+                                return Err(JailError::SymlinkNotAllowed(current));
+                            }
+                            if let Some(link) = magic_link(&current) {
+                                return Err(JailError::MagicLink(link));
+                            }
+                            let Ok(canonical) = follow(fs, &current, &target, &mut 0) else {
+                                return Err(JailError::BrokenSymlink(current));
+                            };
+                            self.config.count_symlink();
+                            // An allowed outside target continues from the link
+                            exact = self.strip_root(&canonical).is_some();
+                            current = self.check_inside(fs, current, canonical)?;
+                        }
+                    }
+                }
                 Component::Normal(name) => {
+                    self.config.check_name(name)?;
                     current.push(name);
+                    if self.config.denies_symlinks()
+                        && (fs.is_symlink(&current) || fs.is_junction(&current))
+                    {
+                        return Err(JailError::SymlinkNotAllowed(current));
+                    }
                     // If it exists, resolve symlinks and check bounds
-                    if current.exists() {
-                        current = self.verify_inside(current)?;
-                    } else if current.is_symlink() {
+                    // (canonicalize also expands 8.3 short names on Windows)
+                    if fs.exists(&current) {
+                        current = self.verify_inside_in(fs, current)?;
+                    } else if fs.is_symlink(&current) {
                         return Err(JailError::BrokenSymlink(current));
+                    } else if fs.is_junction(&current) {
+                        return Err(JailError::BrokenJunction(current));
+                    } else if cfg!(windows) && is_short_name(name) {
+                        // Could later alias a different long name
+                        return Err(JailError::InvalidPath(format!(
+                            "'{}' looks like an 8.3 short name",
+                            name.to_string_lossy()
+                        )));
                     }
                 }
                 Component::ParentDir => {
-                    current.pop();
-                    // Check we haven't escaped the jail
-                    if !current.starts_with(&self.root) {
+                    // Check we haven't escaped the jail (a root of `/`
+                    // after chroot_and_enter has no parent to pop to)
+                    if !current.pop() || self.strip_root(&current).is_none() {
                         return Err(JailError::EscapedRoot {
                             attempted: path.to_path_buf(),
-                            root: self.root.clone(),
+                            root: self.root.to_path_buf(),
                         });
                     }
                     // Re-verify after pop (parent might be a symlink)
-                    if current.exists() {
-                        current = self.verify_inside(current)?;
-                    } else if current.is_symlink() {
-                        return Err(JailError::BrokenSymlink(current));
+                    if !exact {
+                        if fs.exists(&current) {
+                            current = self.verify_inside_in(fs, current)?;
+                        } else if fs.is_symlink(&current) {
+                            return Err(JailError::BrokenSymlink(current));
+                        }
                     }
                 }
                 Component::CurDir => {} // Ignore "."
@@ -87,18 +454,89 @@ impl Jail {
             }
         }
 
+        // Symlinks may have led to names the input never mentioned
+        if self.config.denies_names() {
+            if let Some(rest) = self.strip_root(&current) {
+                for name in rest.iter() {
+                    self.config.check_name(name)?;
+                }
+            }
+        }
+
+        self.config.check_len(&current)?;
+
+        // File policies apply to what will be opened, not to directories
+        if self.config.restricts_files() && !fs.is_dir(&current) {
+            self.config.check_file(&current)?;
+        }
+
         Ok(current)
     }
 
+    /// The part of an absolute `path` below the root, or `None` if it is
+    /// not under the root. Honors [`JailBuilder::case_insensitive`].
+    ///
+    /// [`JailBuilder::case_insensitive`]: crate::JailBuilder::case_insensitive
+    pub(crate) fn strip_root<'a>(&self, path: &'a Path) -> Option<&'a Path> {
+        self.config.strip_base(path, &self.root)
+    }
+
     /// Verify a path is inside the jail.
-    fn verify_inside(&self, path: PathBuf) -> Result<PathBuf, JailError> {
-        let canonical = path.canonicalize()?;
-        if !canonical.starts_with(&self.root) {
+    pub(crate) fn verify_inside(&self, path: PathBuf) -> Result<PathBuf, JailError> {
+        self.verify_inside_in(&Disk, path)
+    }
+
+    fn verify_inside_in<L: Lookup>(&self, fs: &L, path: PathBuf) -> Result<PathBuf, JailError> {
+        // canonicalize() reads these as strings; the kernel does not
+        if let Some(link) = magic_link(&path).filter(|link| fs.is_symlink(link)) {
+            return Err(JailError::MagicLink(link));
+        }
+        let canonical = fs.canonicalize(&path)?;
+        self.check_inside(fs, path, canonical)
+    }
+
+    /// Verify an absolute path that may not exist yet. Its deepest existing
+    /// ancestor is canonicalized and must be inside; the rest is resolved
+    /// from there like [`join`](Self::join) input.
+    pub(crate) fn verify_future(&self, path: &Path) -> Result<PathBuf, JailError> {
+        let existing = path
+            .ancestors()
+            .find(|ancestor| ancestor.exists())
+            .unwrap_or(path);
+        let verified = self.verify_inside(existing.to_path_buf())?;
+        let rest = path.strip_prefix(existing).unwrap_or(Path::new(""));
+        // verify_inside() only returns paths under the root
+        let inside = self.strip_root(&verified).unwrap_or(Path::new(""));
+        self.resolve(&inside.join(rest))
+    }
+
+    /// Check that `canonical`, what `path` resolves to, is inside the jail.
+    /// Returns the path to continue from: `canonical`, or `path` itself
+    /// for an allowed outside symlink target.
+    fn check_inside<L: Lookup>(
+        &self,
+        fs: &L,
+        path: PathBuf,
+        canonical: PathBuf,
+    ) -> Result<PathBuf, JailError> {
+        if self.strip_root(&canonical).is_none() {
+            // An allowed outside target is reached through the link, so
+            // the path keeps naming it from inside the root
+            if self.strip_root(&path).is_some() && self.config.allows_symlink_target(&canonical) {
+                return Ok(path);
+            }
+            if fs.is_junction(&path) {
+                return Err(JailError::JunctionEscape {
+                    path,
+                    root: self.root.to_path_buf(),
+                });
+            }
             return Err(JailError::EscapedRoot {
                 attempted: path,
-                root: self.root.clone(),
+                root: self.root.to_path_buf(),
             });
         }
+        self.config.check_device(fs, &canonical)?;
         Ok(canonical)
     }
 
@@ -114,14 +552,42 @@ impl Jail {
         self.verify_inside(absolute.to_path_buf())
     }
 
+    /// Like [`contains`](Self::contains), but the path need not exist yet,
+    /// for vetting a path before creating it.
+    ///
+    /// The deepest ancestor that exists is canonicalized and must be inside
+    /// the jail; the components after it are checked the way
+    /// [`join`](Self::join) checks its input. Returns the path `join`
+    /// would have returned.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let path = jail.contains_future("/var/uploads/2025/new.pdf")?;
+    /// std::fs::create_dir_all(path.parent().unwrap())?;
+    /// assert!(jail.contains_future("/var/uploads/2025/../../etc/x").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    #[must_use = "use the returned path, not the original input"]
+    pub fn contains_future<P: AsRef<Path>>(&self, absolute: P) -> Result<PathBuf, JailError> {
+        let absolute = absolute.as_ref();
+        if !absolute.is_absolute() {
+            return Err(JailError::InvalidPath("path must be absolute".into()));
+        }
+        self.verify_future(absolute)
+    }
+
     /// Get the relative path from an absolute path inside the jail.
     ///
     /// This is the inverse of [`join`](Self::join): it takes an absolute path
     /// and returns the relative portion within the jail. Useful for storing
     /// portable paths in a database.
     ///
-    /// The path must exist (for symlink resolution). For non-existent paths,
-    /// keep the original relative path you passed to [`join`](Self::join).
+    /// An absolute path must exist (for symlink resolution); use
+    /// [`relative_future`](Self::relative_future) for one that may not.
     ///
     /// # Example
     ///
@@ -149,17 +615,48 @@ impl Jail {
         };
 
         // Strip the jail root to get the relative path
-        resolved
-            .strip_prefix(&self.root)
-            .map(|p| p.to_path_buf())
-            .map_err(|_| JailError::EscapedRoot {
+        self.strip_root(&resolved)
+            .map(|p| self.config.relative_output(p.to_path_buf()))
+            .ok_or_else(|| JailError::EscapedRoot {
+                attempted: path.to_path_buf(),
+                root: self.root.to_path_buf(),
+            })
+    }
+
+    /// Like [`relative`](Self::relative), but an absolute path need not
+    /// exist yet; it is checked like
+    /// [`contains_future`](Self::contains_future) does, so `..` cannot
+    /// climb out and the result is the relative path `join` would have
+    /// been given.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let abs = jail.root().join("2025/q3/report.pdf");  // not created yet
+    /// let rel = jail.relative_future(&abs)?;
+    /// assert_eq!(rel, std::path::Path::new("2025/q3/report.pdf"));
+    /// assert!(jail.relative_future("/var/uploads/../secrets").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn relative_future<P: AsRef<Path>>(&self, absolute: P) -> Result<PathBuf, JailError> {
+        let path = absolute.as_ref();
+        if !path.is_absolute() {
+            return self.relative(path);
+        }
+        let resolved = self.contains_future(path)?;
+        self.strip_root(&resolved)
+            .map(|p| self.config.relative_output(p.to_path_buf()))
+            .ok_or_else(|| JailError::EscapedRoot {
                 attempted: path.to_path_buf(),
-                root: self.root.clone(),
+                root: self.root.to_path_buf(),
             })
     }
 }
 
-impl Jail {
+impl<A: Access> Jail<A> {
     /// Like [`join`](Self::join), but returns a [`JailedPath`] for type-safe handling.
     ///
     /// Use this when you want compile-time guarantees that a path has been validated.
@@ -180,8 +677,122 @@ impl Jail {
     /// # Ok::<(), path_jail::JailError>(())
     /// ```
     #[must_use = "use the returned JailedPath, not the original input"]
-    pub fn join_typed<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath, JailError> {
-        self.join(relative).map(JailedPath::new)
+    pub fn join_typed<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath<A>, JailError> {
+        self.join(relative).map(|p| JailedPath::new(self, p))
+    }
+
+    /// Like [`contains`](Self::contains), but returns a [`JailedPath`].
+    ///
+    /// For absolute paths produced outside the jail's API, such as by a
+    /// file watcher, a directory walker or a database, so they can be
+    /// passed to functions taking `JailedPath`. Besides being inside, the
+    /// path must pass the jail's name and file rules, as if it had come
+    /// from [`join_typed`](Self::join_typed). The path must exist.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailedPath};
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// // e.g. reported by a filesystem watcher
+    /// let changed = std::path::Path::new("/var/uploads/alice/report.pdf");
+    /// let path: JailedPath = jail.contains_typed(changed)?;
+    /// assert_eq!(path.relative(), std::path::Path::new("alice/report.pdf"));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    #[must_use = "use the returned JailedPath, not the original input"]
+    pub fn contains_typed<P: AsRef<Path>>(&self, absolute: P) -> Result<JailedPath<A>, JailError> {
+        let path = self.contains(absolute)?;
+        let relative = self
+            .strip_root(&path)
+            .ok_or_else(|| JailError::EscapedRoot {
+                attempted: path.clone(),
+                root: self.root.to_path_buf(),
+            })?;
+        self.resolve(relative).map(|p| JailedPath::new(self, p))
+    }
+
+    /// A narrower jail rooted at an existing subdirectory.
+    ///
+    /// The subdirectory is validated like [`join`](Self::join) and must be
+    /// a directory. The new jail has the same options as this one; paths
+    /// it returns are inside both jails.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let uploads = Jail::new("/var/uploads")?;
+    /// let alice = uploads.subjail("users/alice")?;
+    /// assert!(alice.join("../bob/secret.txt").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn subjail<P: AsRef<Path>>(&self, relative: P) -> Result<Jail<A>, JailError> {
+        let path = self.join(relative)?;
+        self.narrow(path)
+    }
+
+    /// Whether `other`'s root is this jail's root or a directory beneath
+    /// it, so every path `other` accepts is also inside this jail.
+    ///
+    /// Roots are canonical, so this compares paths; on Unix it also
+    /// compares device and inode numbers, which catches the same directory
+    /// reached through a bind mount. A jail contains itself.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let uploads = Jail::new("/var/uploads")?;
+    /// let alice = uploads.subjail("users/alice")?;
+    /// assert!(uploads.contains_jail(&alice));
+    /// assert!(alice.is_subjail_of(&uploads));
+    /// assert!(!alice.contains_jail(&uploads));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn contains_jail<B: Access>(&self, other: &Jail<B>) -> bool {
+        if self.strip_root(other.root()).is_some() {
+            return true;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+
+            if let Ok(root) = std::fs::metadata(self.root()) {
+                return other.root().ancestors().any(|dir| {
+                    std::fs::metadata(dir)
+                        .is_ok_and(|meta| (meta.dev(), meta.ino()) == (root.dev(), root.ino()))
+                });
+            }
+        }
+        false
+    }
+
+    /// Whether this jail's root is `other`'s root or beneath it; the
+    /// reverse of [`contains_jail`](Self::contains_jail).
+    pub fn is_subjail_of<B: Access>(&self, other: &Jail<B>) -> bool {
+        other.contains_jail(self)
+    }
+
+    /// Whether either jail contains the other. Two jails that should be
+    /// separate, such as those of two tenants, must not overlap.
+    pub fn overlaps<B: Access>(&self, other: &Jail<B>) -> bool {
+        self.contains_jail(other) || other.contains_jail(self)
+    }
+
+    pub(crate) fn narrow(&self, root: PathBuf) -> Result<Jail<A>, JailError> {
+        if !root.is_dir() {
+            return Err(JailError::InvalidRoot(root));
+        }
+        self.config.check_root(&root)?;
+        Ok(Self {
+            root: root.into(),
+            config: Arc::clone(&self.config),
+            access: PhantomData,
+        })
     }
 
     /// Join multiple path segments safely.
@@ -257,16 +868,228 @@ impl Jail {
     /// # Ok::<(), path_jail::JailError>(())
     /// ```
     #[must_use = "use the returned JailedPath, not the original input"]
-    pub fn segments<I, S>(&self, segments: I) -> Result<JailedPath, JailError>
+    pub fn segments<I, S>(&self, segments: I) -> Result<JailedPath<A>, JailError>
     where
         I: IntoIterator<Item = S>,
         S: AsRef<str>,
     {
-        self.join_segments(segments).map(JailedPath::new)
+        self.join_segments(segments)
+            .map(|p| JailedPath::new(self, p))
+    }
+}
+
+/// The filesystem queries made while resolving a path, so the same walk
+/// can run against an in-memory tree (`test-util` feature).
+pub(crate) trait Lookup {
+    fn exists(&self, path: &Path) -> bool;
+    fn is_symlink(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn is_junction(&self, path: &Path) -> bool;
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf>;
+    /// Device id of an existing path, where the platform has one.
+    fn device(&self, path: &Path) -> Option<u64>;
+    /// What is at `path`, without following it if it is a symlink. The
+    /// parent of `path` is canonical.
+    fn node(&self, path: &Path) -> Node;
+}
+
+/// An entry as seen by `lstat`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Node {
+    /// Nothing there, or not reachable (e.g. the parent is a file).
+    Missing,
+    /// A symlink, with its target as stored.
+    Link(PathBuf),
+    Dir,
+    File,
+}
+
+/// Symlinks followed while resolving one link before giving up, like
+/// Linux's `ELOOP` limit.
+const MAX_SYMLINK_HOPS: u32 = 40;
+
+/// Resolve the symlink at `link`, whose parent is canonical, to the
+/// canonical path it points at, walking `target` from the link's
+/// directory one component at a time. Fails if the target does not exist
+/// or the links loop.
+fn follow<L: Lookup>(fs: &L, link: &Path, target: &Path, hops: &mut u32) -> io::Result<PathBuf> {
+    *hops += 1;
+    if *hops > MAX_SYMLINK_HOPS {
+        return Err(io::Error::other("too many levels of symbolic links"));
+    }
+    let mut current = link.parent().unwrap_or(link).to_path_buf();
+    let mut components = target.components().peekable();
+    while let Some(component) = components.next() {
+        match component {
+            Component::RootDir | Component::Prefix(_) => current.push(component),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                current.pop();
+            }
+            Component::Normal(name) => {
+                current.push(name);
+                match fs.node(&current) {
+                    Node::Missing => return Err(io::ErrorKind::NotFound.into()),
+                    Node::File if components.peek().is_some() => {
+                        return Err(io::Error::other("not a directory"));
+                    }
+                    Node::Link(next) => current = follow(fs, &current, &next, hops)?,
+                    Node::Dir | Node::File => {}
+                }
+            }
+        }
+    }
+    Ok(current)
+}
+
+/// [`std::fs::canonicalize`], which WASI does not implement. There the
+/// path is made absolute against the current directory and resolved one
+/// component at a time with `lstat` and `readlink`, as [`follow`] resolves
+/// a link target; the result is the same.
+pub(crate) fn canonicalize(path: &Path) -> io::Result<PathBuf> {
+    #[cfg(target_os = "wasi")]
+    {
+        let absolute = std::env::current_dir()?.join(path);
+        follow(&Disk, Path::new("/"), &absolute, &mut 0)
+    }
+    #[cfg(not(target_os = "wasi"))]
+    path.canonicalize()
+}
+
+/// The real filesystem.
+pub(crate) struct Disk;
+
+impl Lookup for Disk {
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        path.is_symlink()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_junction(&self, path: &Path) -> bool {
+        is_junction(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        canonicalize(path)
+    }
+
+    fn node(&self, path: &Path) -> Node {
+        match std::fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                std::fs::read_link(path).map_or(Node::Missing, Node::Link)
+            }
+            Ok(meta) if meta.is_dir() => Node::Dir,
+            Ok(_) => Node::File,
+            Err(_) => Node::Missing,
+        }
+    }
+
+    fn device(&self, path: &Path) -> Option<u64> {
+        #[cfg(unix)]
+        return std::fs::metadata(path)
+            .ok()
+            .map(|meta| std::os::unix::fs::MetadataExt::dev(&meta));
+        #[cfg(not(unix))]
+        {
+            let _ = path;
+            None
+        }
+    }
+}
+
+/// Whether `path` itself is an NTFS junction or mount point. Always
+/// `false` outside Windows.
+fn is_junction(path: &Path) -> bool {
+    #[cfg(windows)]
+    return crate::reparse::is_junction(path);
+    #[cfg(not(windows))]
+    {
+        let _ = path;
+        false
     }
 }
 
-impl AsRef<Path> for Jail {
+/// The first possible procfs magic link in `path`, judged by shape:
+/// `proc/<pid>/{root,cwd,exe}`, `proc/<pid>/{fd,map_files}/<name>`, and the
+/// same under `proc/<pid>/task/<tid>/`. `<pid>` may also be `self` or
+/// `thread-self`. Matched wherever a `proc` directory appears, since procfs
+/// can be mounted anywhere (a container root's `proc`, or `/proc` itself
+/// after `chroot_and_enter`). Callers confirm the match is a symlink.
+fn magic_link(path: &Path) -> Option<PathBuf> {
+    let names: Vec<&OsStr> = path.iter().collect();
+    let is_pid = |name: &OsStr| {
+        name == "self"
+            || name == "thread-self"
+            || name
+                .to_str()
+                .is_some_and(|s| s.bytes().all(|b| b.is_ascii_digit()))
+    };
+    // Length of the magic link prefix starting at a `<pid>` directory
+    let link_len = |rest: &[&OsStr]| -> Option<usize> {
+        match rest {
+            [pid, ..] if !is_pid(pid) => None,
+            [_, name, ..] if *name == "root" || *name == "cwd" || *name == "exe" => Some(2),
+            [_, dir, _, ..] if *dir == "fd" || *dir == "map_files" => Some(3),
+            _ => None,
+        }
+    };
+    for (i, name) in names.iter().enumerate() {
+        if *name != "proc" {
+            continue;
+        }
+        let rest = &names[i + 1..];
+        let len = match rest {
+            [pid, task, ..] if is_pid(pid) && *task == "task" => {
+                link_len(&rest[2..]).map(|len| len + 2)
+            }
+            _ => None,
+        }
+        .or_else(|| link_len(rest));
+        if let Some(len) = len {
+            return Some(names[..=i + len].iter().collect());
+        }
+    }
+    None
+}
+
+/// Whether `s` contains the ASCII character `ch`, looking at raw bytes on
+/// Unix and UTF-16 units on Windows rather than a lossy UTF-8 copy.
+pub(crate) fn contains_unit(s: &OsStr, ch: u8) -> bool {
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        s.encode_wide().any(|unit| unit == u16::from(ch))
+    }
+    #[cfg(not(windows))]
+    {
+        // The raw bytes on Unix; other platforms encode as UTF-8 (or
+        // WTF-8), where an ASCII byte is always that character
+        s.as_encoded_bytes().contains(&ch)
+    }
+}
+
+/// Whether `name` has the shape of a Windows 8.3 short name such as
+/// `PROGRA~1` or `REPORT~12.PDF`.
+fn is_short_name(name: &OsStr) -> bool {
+    let name = name.to_string_lossy();
+    let (base, ext) = name.rsplit_once('.').unwrap_or((&name, ""));
+    let Some((_, digits)) = base.rsplit_once('~') else {
+        return false;
+    };
+    base.len() <= 8
+        && ext.len() <= 3
+        && !digits.is_empty()
+        && digits.bytes().all(|b| b.is_ascii_digit())
+}
+
+impl<A: Access> AsRef<Path> for Jail<A> {
     fn as_ref(&self) -> &Path {
         &self.root
     }
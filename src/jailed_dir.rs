@@ -0,0 +1,268 @@
+//! An open directory inside a [`Jail`] (`openat` feature, Unix).
+
+#![cfg(all(feature = "openat", unix))]
+
+use crate::access::{Access, ReadWrite};
+use crate::dirfd::DirFd;
+use crate::jail::contains_unit;
+use crate::{Jail, JailError};
+use std::ffi::OsString;
+use std::fs::File;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::path::{Component, Path, PathBuf};
+
+/// A directory inside a [`Jail`], held open as an `O_DIRECTORY` descriptor.
+///
+/// Created by [`Jail::open_dir`]. The descriptor pins the directory: if it
+/// is renamed or replaced by a symlink afterwards, the handle keeps
+/// referring to the directory that was validated, and everything opened
+/// through it stays inside that directory. Paths given to the handle are
+/// resolved relative to the descriptor, not from the jail root, so a batch
+/// of operations in one directory only validates the directory once.
+///
+/// Relative paths given to a `JailedDir` may not contain `..` and are
+/// walked without following symlinks, which is what keeps them below the
+/// handle without re-resolving from the root. Builder options that look at
+/// names (deny rules, hidden files, limits) still apply.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::Jail;
+///
+/// let jail = Jail::new("/var/uploads")?;
+/// let alice = jail.open_dir("users/alice")?;
+/// let photos = alice.open_dir("photos")?;
+/// assert_eq!(photos.relative(), std::path::Path::new("users/alice/photos"));
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug)]
+pub struct JailedDir<A: Access = ReadWrite> {
+    dir: DirFd,
+    path: PathBuf,
+    jail: Jail<A>,
+}
+
+impl<A: Access> Jail<A> {
+    /// Open a directory inside the jail and keep it open.
+    ///
+    /// The path is validated like [`join`](Self::join), then opened one
+    /// component at a time from the jail root with
+    /// `openat(O_NOFOLLOW | O_DIRECTORY)`, so a component swapped for a
+    /// symlink in between makes this fail instead of opening something
+    /// else. Directories reached through an
+    /// [`allow_symlink_target`](crate::JailBuilder::allow_symlink_target)
+    /// link are opened by their resolved path instead.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let dir = jail.open_dir("users/alice")?;
+    /// assert_eq!(dir.path(), jail.root().join("users/alice"));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn open_dir<P: AsRef<Path>>(&self, relative: P) -> Result<JailedDir<A>, JailError> {
+        let path = self.join(relative)?;
+        let dir = match self.strip_root(&path) {
+            Some(below) if !self.config().has_symlink_targets() => {
+                DirFd::open(self.root())?.open_path(below)?
+            }
+            _ => DirFd::open(&path.canonicalize()?)?,
+        };
+        Ok(JailedDir {
+            dir,
+            path,
+            jail: self.clone(),
+        })
+    }
+}
+
+impl<A: Access> JailedDir<A> {
+    /// The directory's path, as validated when it was opened.
+    ///
+    /// The descriptor, not this path, is what the handle operates on; if
+    /// the directory has since been moved, the path is stale.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory's path relative to the jail root.
+    pub fn relative(&self) -> &Path {
+        self.jail.strip_root(&self.path).unwrap_or(Path::new(""))
+    }
+
+    /// The jail this directory belongs to.
+    pub fn jail(&self) -> &Jail<A> {
+        &self.jail
+    }
+
+    /// Open a subdirectory, relative to this one.
+    ///
+    /// `relative` may not contain `..` or absolute components, and no
+    /// component may be a symlink.
+    pub fn open_dir<P: AsRef<Path>>(&self, relative: P) -> Result<JailedDir<A>, JailError> {
+        let relative = self.check_relative(relative.as_ref())?;
+        Ok(JailedDir {
+            dir: self.dir.open_path(&relative)?,
+            path: self.path.join(relative),
+            jail: self.jail.clone(),
+        })
+    }
+
+    /// A second handle to the same directory.
+    pub fn try_clone(&self) -> Result<JailedDir<A>, JailError> {
+        Ok(JailedDir {
+            dir: self.dir.try_clone()?,
+            path: self.path.clone(),
+            jail: self.jail.clone(),
+        })
+    }
+
+    /// Open a file for reading, relative to this directory.
+    ///
+    /// Every component is opened relative to the handle with `O_NOFOLLOW`,
+    /// so nothing is re-resolved from the jail root and a symlink anywhere
+    /// in `relative` makes this fail. Allowed extensions and
+    /// [`reject_hard_links`](crate::JailBuilder::reject_hard_links) apply as
+    /// for [`Jail::read`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Read;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let alice = jail.open_dir("users/alice")?;
+    /// let mut text = String::new();
+    /// alice.open_at("notes.txt")?.read_to_string(&mut text)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_at<P: AsRef<Path>>(&self, relative: P) -> Result<File, JailError> {
+        self.open_file(relative.as_ref(), libc::O_RDONLY)
+    }
+
+    /// List the names in a directory relative to this one (`"."` for this
+    /// directory itself), in no particular order.
+    ///
+    /// Unlike [`Jail::read_dir`], entries are not resolved: a symlinked
+    /// entry is listed by name and would be refused by the other `*_at`
+    /// methods.
+    pub fn read_dir_at<P: AsRef<Path>>(&self, relative: P) -> Result<Vec<OsString>, JailError> {
+        let relative = self.check_relative(relative.as_ref())?;
+        Ok(self.dir.open_path(&relative)?.entries()?)
+    }
+
+    /// Open `relative` with `flags`, walking its parent from the handle.
+    fn open_file(&self, relative: &Path, flags: libc::c_int) -> Result<File, JailError> {
+        let (parent, name) = self.split(relative)?;
+        let path = self.path.join(&parent).join(&name);
+        let config = self.jail.config();
+        config.check_file(&path)?;
+        let perm = config.file_mode().unwrap_or(0o666);
+        let file = self.dir.open_path(&parent)?.open_file(&name, flags, perm)?;
+        config.count_open();
+        // Only `create_at` passes O_CREAT, always with O_EXCL
+        if flags & libc::O_CREAT != 0 && config.tracks_creation() {
+            config.created_file(&path, &file)?;
+        }
+        config.check_links(&path, &file.metadata()?)?;
+        Ok(file)
+    }
+
+    /// Split a checked relative path into its parent and final name.
+    fn split(&self, relative: &Path) -> Result<(PathBuf, OsString), JailError> {
+        let relative = self.check_relative(relative)?;
+        match (relative.parent(), relative.file_name()) {
+            (Some(parent), Some(name)) => Ok((parent.to_path_buf(), name.to_os_string())),
+            _ => Err(JailError::InvalidPath(
+                "path must name an entry below the directory".into(),
+            )),
+        }
+    }
+
+    /// Validate a path given relative to this directory and return it
+    /// with preprocessing applied. Only plain names are allowed: with no
+    /// `..` and no symlinks followed, the walk cannot leave the directory.
+    pub(crate) fn check_relative(&self, relative: &Path) -> Result<PathBuf, JailError> {
+        let config = self.jail.config();
+        let relative = config.prepare(relative)?;
+        if contains_unit(relative.as_os_str(), b'\0') {
+            return Err(JailError::InvalidPath("null bytes not allowed".into()));
+        }
+        let mut clean = PathBuf::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(name) => {
+                    config.check_name(name)?;
+                    clean.push(name);
+                }
+                Component::CurDir => {}
+                _ => {
+                    return Err(JailError::InvalidPath(
+                        "paths below a JailedDir must not contain '..' or absolute components"
+                            .into(),
+                    ))
+                }
+            }
+        }
+        let full = self.path.join(&clean);
+        if let Some(below) = self.jail.strip_root(&full) {
+            config.check_depth(below)?;
+        }
+        config.check_len(&full)?;
+        Ok(clean)
+    }
+}
+
+impl<A: Access> AsFd for JailedDir<A> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.dir.as_fd()
+    }
+}
+
+impl<A: Access> AsRawFd for JailedDir<A> {
+    fn as_raw_fd(&self) -> RawFd {
+        self.dir.as_fd().as_raw_fd()
+    }
+}
+
+impl JailedDir {
+    /// Create a new file relative to this directory, failing if it exists
+    /// (`O_CREAT | O_EXCL | O_NOFOLLOW`).
+    ///
+    /// The parent is walked from the handle like
+    /// [`open_at`](Self::open_at).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Write;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let inbox = jail.open_dir("users/alice/inbox")?;
+    /// inbox.create_at("message-1.txt")?.write_all(b"hello")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn create_at<P: AsRef<Path>>(&self, relative: P) -> Result<File, JailError> {
+        let mut flags = libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL;
+        if self.jail.config().is_append_only() {
+            flags |= libc::O_APPEND;
+        }
+        self.open_file(relative.as_ref(), flags)
+    }
+
+    /// Remove a file (or symlink) relative to this directory with
+    /// `unlinkat()`. Directories are refused.
+    pub fn unlink_at<P: AsRef<Path>>(&self, relative: P) -> Result<(), JailError> {
+        self.jail
+            .config()
+            .check_append_only(&self.relative().join(relative.as_ref()))?;
+        let (parent, name) = self.split(relative.as_ref())?;
+        Ok(self.dir.open_path(&parent)?.unlink(&name)?)
+    }
+}
@@ -1,7 +1,12 @@
 //! A validated path guaranteed to be inside a [`Jail`](crate::Jail).
 
+use crate::access::{Access, ReadOnly, ReadWrite};
+use crate::error::Shown;
+use crate::{Jail, JailError};
+use std::cmp::Ordering;
 use std::ffi::OsStr;
 use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 
@@ -27,19 +32,32 @@ use std::path::{Path, PathBuf};
 /// save_file(path, b"data")?;
 /// # Ok::<(), path_jail::JailError>(())
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct JailedPath {
+///
+/// A `JailedPath` remembers the jail it came from, so navigation methods
+/// such as [`join`](Self::join) and [`parent`](Self::parent) can re-check
+/// containment and keep the type guarantee. Comparison and hashing only
+/// look at the path itself.
+///
+/// Like [`Jail`], the type parameter says what the path may be used for:
+/// paths from a [`Jail<ReadOnly>`](crate::Jail::read_only) have no write
+/// shorthands.
+#[derive(Clone)]
+pub struct JailedPath<A: Access = ReadWrite> {
     inner: PathBuf,
+    jail: Jail<A>,
 }
 
-impl JailedPath {
+impl<A: Access> JailedPath<A> {
     /// Create from a validated PathBuf.
     ///
     /// This is crate-internal only. External code must use
     /// [`Jail::join_typed`](crate::Jail::join_typed) or
     /// [`Jail::segments`](crate::Jail::segments).
-    pub(crate) fn new(path: PathBuf) -> Self {
-        Self { inner: path }
+    pub(crate) fn new(jail: &Jail<A>, path: PathBuf) -> Self {
+        Self {
+            inner: path,
+            jail: jail.clone(),
+        }
     }
 
     /// Consumes the `JailedPath` and returns the underlying [`PathBuf`].
@@ -55,9 +73,174 @@ impl JailedPath {
     pub fn as_path(&self) -> &Path {
         &self.inner
     }
+
+    /// Join a relative path onto this one, re-checking containment.
+    ///
+    /// Unlike [`Path::join`] (reachable through `Deref`), the result is
+    /// validated against the originating jail exactly like
+    /// [`Jail::join_typed`], so `..` and symlinks cannot leave it.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let user_dir = jail.join_typed("alice")?;
+    /// let report = user_dir.join("report.pdf")?;
+    /// assert!(user_dir.join("../../etc/passwd").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> Result<JailedPath<A>, JailError> {
+        let path = self.jail.config().prepare(path.as_ref())?;
+        let joined = self.jail.resolve(&self.relative().join(path))?;
+        Ok(JailedPath::new(&self.jail, joined))
+    }
+
+    /// Returns the parent directory, re-checking containment.
+    ///
+    /// The jail root has no parent inside the jail, so calling this on the
+    /// root returns [`JailError::EscapedRoot`].
+    pub fn parent(&self) -> Result<JailedPath<A>, JailError> {
+        self.rejoin(
+            self.inner
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns a sibling path with the file name replaced, re-checking containment.
+    ///
+    /// See [`Path::with_file_name`].
+    pub fn with_file_name<S: AsRef<OsStr>>(
+        &self,
+        file_name: S,
+    ) -> Result<JailedPath<A>, JailError> {
+        self.rejoin(self.inner.with_file_name(file_name))
+    }
+
+    /// Returns the path with the extension replaced, re-checking containment.
+    ///
+    /// See [`Path::with_extension`].
+    pub fn with_extension<S: AsRef<OsStr>>(
+        &self,
+        extension: S,
+    ) -> Result<JailedPath<A>, JailError> {
+        self.rejoin(self.inner.with_extension(extension))
+    }
+
+    /// Returns the [`Jail`] this path was validated against.
+    ///
+    /// Use it to run jail-scoped operations without passing the jail around
+    /// separately, or to check which jail a path belongs to.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailedPath};
+    ///
+    /// fn discard(path: &JailedPath) -> Result<(), path_jail::JailError> {
+    ///     // No need to pass the jail alongside the path
+    ///     path.jail().rename(path.relative(), "trash/latest")
+    /// }
+    /// ```
+    #[inline]
+    pub fn jail(&self) -> &Jail<A> {
+        &self.jail
+    }
+
+    /// Returns this path relative to the jail root.
+    ///
+    /// The jail root itself is the empty path. The result can be passed to
+    /// any [`Jail`] method taking a relative path, unless the jail was built
+    /// with [`percent_decode`](crate::JailBuilder::percent_decode) or Unicode
+    /// normalization: those apply to every relative input, so use the
+    /// shorthands on `JailedPath` (such as [`read`](Self::read)), which
+    /// don't preprocess again.
+    pub fn relative(&self) -> &Path {
+        self.inner
+            .strip_prefix(self.jail.root())
+            .unwrap_or(Path::new(""))
+    }
+
+    /// Display this path relative to the jail root, for logs and messages
+    /// that should not reveal where the jail lives on the server. The
+    /// root itself is shown as `.`.
+    ///
+    /// The [`Display`](fmt::Display) impl shows the full path; see also
+    /// [`JailError::display_relative`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/srv/customers/acme")?;
+    /// let path = jail.join_typed("invoices/2024.pdf")?;
+    /// assert_eq!(path.display_relative().to_string(), "invoices/2024.pdf");
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn display_relative(&self) -> impl fmt::Display + '_ {
+        Shown {
+            path: &self.inner,
+            root: Some(self.jail.root()),
+        }
+    }
+
+    /// Validate an absolute path derived from this one.
+    fn rejoin(&self, candidate: PathBuf) -> Result<JailedPath<A>, JailError> {
+        match candidate.strip_prefix(self.jail.root()) {
+            Ok(relative) => Ok(JailedPath::new(&self.jail, self.jail.resolve(relative)?)),
+            Err(_) => Err(JailError::EscapedRoot {
+                attempted: candidate,
+                root: self.jail.root().to_path_buf(),
+            }),
+        }
+    }
+}
+
+impl<A: Access> fmt::Debug for JailedPath<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let redacted = self.jail.config().redact_root().map(|placeholder| {
+            match self.jail.strip_root(&self.inner) {
+                Some(rest) if !rest.as_os_str().is_empty() => Path::new(placeholder).join(rest),
+                _ => PathBuf::from(placeholder),
+            }
+        });
+        f.debug_struct("JailedPath")
+            .field("inner", redacted.as_ref().unwrap_or(&self.inner))
+            .finish()
+    }
+}
+
+impl<A: Access> PartialEq for JailedPath<A> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
 }
 
-impl Deref for JailedPath {
+impl<A: Access> Eq for JailedPath<A> {}
+
+impl<A: Access> PartialOrd for JailedPath<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<A: Access> Ord for JailedPath<A> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.inner.cmp(&other.inner)
+    }
+}
+
+impl<A: Access> Hash for JailedPath<A> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+    }
+}
+
+impl<A: Access> Deref for JailedPath<A> {
     type Target = Path;
 
     #[inline]
@@ -66,29 +249,39 @@ impl Deref for JailedPath {
     }
 }
 
-impl AsRef<Path> for JailedPath {
+impl<A: Access> AsRef<Path> for JailedPath<A> {
     #[inline]
     fn as_ref(&self) -> &Path {
         &self.inner
     }
 }
 
-impl AsRef<OsStr> for JailedPath {
+impl<A: Access> AsRef<OsStr> for JailedPath<A> {
     #[inline]
     fn as_ref(&self) -> &OsStr {
         self.inner.as_os_str()
     }
 }
 
-impl fmt::Display for JailedPath {
+impl<A: Access> fmt::Display for JailedPath<A> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.inner.display())
     }
 }
 
-impl From<JailedPath> for PathBuf {
+impl<A: Access> From<JailedPath<A>> for PathBuf {
     #[inline]
-    fn from(path: JailedPath) -> Self {
+    fn from(path: JailedPath<A>) -> Self {
         path.inner
     }
 }
+
+impl JailedPath {
+    /// The same path with a read-only handle to its jail.
+    pub fn read_only(&self) -> JailedPath<ReadOnly> {
+        JailedPath {
+            inner: self.inner.clone(),
+            jail: self.jail.read_only(),
+        }
+    }
+}
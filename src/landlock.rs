@@ -0,0 +1,224 @@
+//! Kernel enforcement of jail roots with Landlock (Linux, `landlock` feature).
+//!
+//! Path validation only protects the code that goes through a [`Jail`]. A
+//! Landlock ruleset makes the kernel refuse filesystem access outside the
+//! jail roots for the calling thread, whatever code asks for it, so a bug
+//! elsewhere in the process (or in a dependency) cannot reach other files.
+
+#![cfg(all(feature = "landlock", target_os = "linux"))]
+
+use crate::{Access, Jail, JailError};
+use std::ffi::CString;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+const LANDLOCK_CREATE_RULESET_VERSION: libc::c_uint = 1;
+const LANDLOCK_RULE_PATH_BENEATH: libc::c_uint = 1;
+
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+/// `WRITE_FILE` through `MAKE_SYM` (ABI 1), without `EXECUTE`.
+const ACCESS_FS_V1: u64 = ((1 << 13) - 1) & !1;
+const ACCESS_FS_REFER: u64 = 1 << 13;
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+const ACCESS_FS_IOCTL_DEV: u64 = 1 << 15;
+
+/// Rights granted under a read-only jail.
+const ACCESS_FS_READ: u64 = ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: libc::c_int,
+}
+
+/// Outcome of [`Landlock::enforce`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LandlockStatus {
+    /// The ruleset is in force, using this Landlock ABI version.
+    Enforced { abi: u32 },
+    /// The kernel has no Landlock support (or it is disabled); nothing
+    /// was restricted.
+    Unsupported,
+}
+
+/// A Landlock ruleset restricting the calling thread to a set of jails.
+///
+/// Each jail's root is allowed with the rights its handle has: reading
+/// files and listing directories for a [`ReadOnly`](crate::ReadOnly) jail,
+/// everything (create, write, truncate, remove, rename) for a
+/// [`ReadWrite`](crate::ReadWrite) one. Everything outside the roots is
+/// denied by the kernel once [`enforce`](Self::enforce) returns, including
+/// files the process has not opened yet, such as shared libraries loaded
+/// later. Executing programs is not restricted.
+///
+/// Landlock applies to the calling thread and to threads and processes it
+/// creates afterwards, and cannot be lifted. Call it early in `main`,
+/// before spawning threads, to cover the whole process.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{Jail, Landlock, LandlockStatus};
+///
+/// let uploads = Jail::new("/var/uploads")?;
+/// let assets = Jail::new("/usr/share/app")?.read_only();
+/// match Landlock::new().allow(&uploads).allow(&assets).enforce()? {
+///     LandlockStatus::Enforced { abi } => eprintln!("landlock ABI {abi} enforced"),
+///     _ => eprintln!("landlock unavailable, relying on path checks"),
+/// }
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Landlock {
+    /// Jail roots and whether they are writable.
+    roots: Vec<(PathBuf, bool)>,
+}
+
+impl Landlock {
+    /// An empty ruleset. Enforcing it denies all filesystem access.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow access under `jail`'s root, with the rights of its handle.
+    pub fn allow<A: Access>(mut self, jail: &Jail<A>) -> Self {
+        self.roots.push((jail.root().to_path_buf(), A::WRITABLE));
+        self
+    }
+
+    /// Restrict the calling thread to the allowed roots.
+    ///
+    /// Returns [`LandlockStatus::Unsupported`] without restricting
+    /// anything if the kernel lacks Landlock; check the status if running
+    /// unconfined is not acceptable. Sets `PR_SET_NO_NEW_PRIVS`, which
+    /// Landlock requires for unprivileged processes.
+    pub fn enforce(&self) -> Result<LandlockStatus, JailError> {
+        // SAFETY: a NULL attribute with size 0 queries the ABI version.
+        let abi = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                LANDLOCK_CREATE_RULESET_VERSION,
+            )
+        };
+        if abi < 0 {
+            let err = io::Error::last_os_error();
+            return match err.raw_os_error() {
+                Some(libc::ENOSYS | libc::EOPNOTSUPP) => Ok(LandlockStatus::Unsupported),
+                _ => Err(err.into()),
+            };
+        }
+        let abi = abi as u32;
+        let handled = handled_access(abi);
+
+        let attr = RulesetAttr {
+            handled_access_fs: handled,
+        };
+        // SAFETY: `attr` is a valid ruleset attribute of the given size.
+        let ruleset = cvt(unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                &attr as *const RulesetAttr,
+                std::mem::size_of::<RulesetAttr>(),
+                0 as libc::c_uint,
+            )
+        })?;
+        // SAFETY: the syscall returned a new file descriptor we own.
+        let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset as libc::c_int) };
+
+        for (root, writable) in &self.roots {
+            let dir = open_path(root)?;
+            let rule = PathBeneathAttr {
+                allowed_access: if *writable { handled } else { ACCESS_FS_READ },
+                parent_fd: dir.as_raw_fd(),
+            };
+            // SAFETY: valid ruleset fd and a path-beneath rule with an open fd.
+            cvt(unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset.as_raw_fd(),
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0 as libc::c_uint,
+                )
+            })?;
+        }
+
+        // SAFETY: prctl with integer arguments only.
+        cvt(unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } as libc::c_long)?;
+        // SAFETY: valid ruleset fd, no flags.
+        cvt(unsafe {
+            libc::syscall(
+                libc::SYS_landlock_restrict_self,
+                ruleset.as_raw_fd(),
+                0 as libc::c_uint,
+            )
+        })?;
+        Ok(LandlockStatus::Enforced { abi })
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Restrict the calling thread to this jail with Landlock.
+    ///
+    /// Shorthand for `Landlock::new().allow(self).enforce()`; see
+    /// [`Landlock`] for what is restricted and how long it lasts. Use
+    /// [`Landlock`] directly to allow several roots.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.enforce_landlock()?;
+    /// assert!(std::fs::read("/etc/passwd").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn enforce_landlock(&self) -> Result<LandlockStatus, JailError> {
+        Landlock::new().allow(self).enforce()
+    }
+}
+
+/// Every right this ABI version can restrict, except `EXECUTE`.
+fn handled_access(abi: u32) -> u64 {
+    let mut handled = ACCESS_FS_V1;
+    if abi >= 2 {
+        handled |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled |= ACCESS_FS_TRUNCATE;
+    }
+    if abi >= 5 {
+        handled |= ACCESS_FS_IOCTL_DEV;
+    }
+    handled
+}
+
+fn open_path(path: &std::path::Path) -> io::Result<OwnedFd> {
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "null byte in path"))?;
+    // SAFETY: `path` is a valid NUL-terminated string.
+    let fd =
+        cvt(unsafe { libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) } as libc::c_long)?;
+    // SAFETY: `fd` was just returned by open() and is owned by us.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as libc::c_int) })
+}
+
+fn cvt(ret: libc::c_long) -> io::Result<libc::c_long> {
+    if ret == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
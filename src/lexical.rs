@@ -0,0 +1,213 @@
+//! Path validation without filesystem access.
+
+use crate::jail::contains_unit;
+use crate::virtual_jail::{traverse, Normalized, Step};
+use crate::{Access, Jail, JailError, LexicalError};
+use std::ffi::OsStr;
+use std::path::{Component, Path, PathBuf};
+
+/// A jail that validates paths purely lexically.
+///
+/// Unlike [`Jail`], neither the root nor the joined paths are looked up on
+/// disk: `.` and `..` are resolved by string manipulation, and nothing is
+/// canonicalized. Useful for virtual path spaces (object storage keys,
+/// archive layouts) and for paths that will be created later, possibly on
+/// another machine.
+///
+/// **Symlinks are not followed.** A lexically valid path can still escape
+/// through a symlink on a real filesystem; use [`Jail::join`] before
+/// touching disk.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::LexicalJail;
+///
+/// let jail = LexicalJail::new("/srv/tenants/acme")?;
+/// let path = jail.join("reports/../2025/q1.pdf")?;
+/// assert_eq!(path, std::path::Path::new("/srv/tenants/acme/2025/q1.pdf"));
+/// assert!(jail.join("../other").is_err());
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LexicalJail {
+    root: PathBuf,
+}
+
+impl LexicalJail {
+    /// Create a lexical jail rooted at `root`.
+    ///
+    /// The root does not need to exist. Errors if it is relative or a
+    /// filesystem root (`/`, `C:\`).
+    pub fn new<P: AsRef<Path>>(root: P) -> Result<Self, JailError> {
+        let root = root.as_ref();
+        if !root.is_absolute() {
+            return Err(JailError::InvalidRoot(root.to_path_buf()));
+        }
+        let root = normalize_from(root, root, true)
+            .map_err(|_| JailError::InvalidRoot(root.to_path_buf()))?;
+        if root.parent().is_none() {
+            return Err(JailError::InvalidRoot(root));
+        }
+        Ok(Self { root })
+    }
+
+    /// Returns the root path, with `.` and `..` resolved.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Lexically join a relative path to the root.
+    ///
+    /// Rejects absolute paths, null bytes, and `..` components that climb
+    /// above the root.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join<P: AsRef<Path>>(&self, relative: P) -> Result<PathBuf, JailError> {
+        join_lexical(&self.root, relative.as_ref())
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Like [`join`](Self::join), but without touching the filesystem.
+    ///
+    /// `.` and `..` are resolved lexically and symlinks are **not**
+    /// followed, so the result is only as trustworthy as the tree is free
+    /// of symlinks. Options from [`Jail::builder`] (percent-decoding,
+    /// normalization, depth and length limits, deny rules) still apply;
+    /// the allowed extensions don't, as they exempt directories. For hot paths where
+    /// the directory layout is known; see also [`LexicalJail`], and
+    /// [`JailBuilder::trusted_tree`](crate::JailBuilder::trusted_tree) to
+    /// make [`join`](Self::join) itself lexical.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join_lexical<P: AsRef<Path>>(&self, relative: P) -> Result<PathBuf, JailError> {
+        let path = self.config().prepare(relative.as_ref())?;
+        self.config().count_join();
+        self.resolve_lexical(&path)
+            .map_err(|err| self.config().reject(&path, err))
+    }
+
+    /// The validation behind [`join_lexical`](Self::join_lexical), for
+    /// an already prepared path.
+    pub(crate) fn resolve_lexical(&self, path: &Path) -> Result<PathBuf, JailError> {
+        self.resolve_lexical_buf(path, PathBuf::new())
+    }
+
+    /// [`resolve_lexical`](Self::resolve_lexical), building the result in
+    /// `buf`.
+    pub(crate) fn resolve_lexical_buf(
+        &self,
+        path: &Path,
+        buf: PathBuf,
+    ) -> Result<PathBuf, JailError> {
+        self.config().check_depth(path)?;
+        for component in path.components() {
+            if let Component::Normal(name) = component {
+                self.config().check_name(name)?;
+            }
+        }
+        let joined = join_lexical_buf(self.root(), path, buf)?;
+        self.config().check_len(&joined)?;
+        Ok(joined)
+    }
+}
+
+/// Normalize a relative path lexically, the way [`Jail::join_lexical`]
+/// and [`LexicalJail::join`] do before adding the root.
+///
+/// `.` components and repeated separators are dropped, and each `..`
+/// removes the name before it; nothing is looked up on disk, so symlinks
+/// are not followed. The result is relative, with no `.` or `..`
+/// components, and empty for a path that leads back to where it started.
+/// Useful to store relative paths in one canonical spelling and compare
+/// them: `a/./b`, `a//b` and `a/c/../b` all become `a/b`.
+///
+/// Errors like [`LexicalJail::join`] for absolute paths, null bytes, and
+/// `..` that climbs above the start. A [`Jail`]'s own input options, such
+/// as percent-decoding, are not applied.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::normalize;
+/// use std::path::Path;
+///
+/// assert_eq!(normalize("reports/./2025//../q1.pdf")?, Path::new("reports/q1.pdf"));
+/// assert_eq!(normalize("a/..")?, Path::new(""));
+/// assert!(normalize("../etc").is_err());
+/// assert!(normalize("/etc").is_err());
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[must_use = "use the returned path, not the original input"]
+pub fn normalize<P: AsRef<Path>>(path: P) -> Result<PathBuf, JailError> {
+    join_lexical(Path::new(""), path.as_ref()).map_err(|err| match err {
+        JailError::EscapedRoot { attempted, .. } => JailError::EscapedRoot {
+            attempted,
+            root: PathBuf::from("."),
+        },
+        err => err,
+    })
+}
+
+fn join_lexical(root: &Path, path: &Path) -> Result<PathBuf, JailError> {
+    join_lexical_buf(root, path, PathBuf::new())
+}
+
+/// [`join_lexical`], normalizing straight into `buf` (cleared first).
+fn join_lexical_buf(root: &Path, path: &Path, mut buf: PathBuf) -> Result<PathBuf, JailError> {
+    buf.as_mut_os_string().clear();
+    buf.push(root);
+    normalize_onto(&mut buf, path, root, false)?;
+    Ok(buf)
+}
+
+/// Resolve `.` and `..` in `path` without looking at the disk; `..` may not
+/// climb above the start. Prefix and root components are only allowed at
+/// the start, and only if `absolute`. `root` is reported in errors.
+fn normalize_from(path: &Path, root: &Path, absolute: bool) -> Result<PathBuf, JailError> {
+    let mut normalized = PathBuf::new();
+    normalize_onto(&mut normalized, path, root, absolute)?;
+    Ok(normalized)
+}
+
+/// [`normalize_from`], pushing onto `normalized` with the rules of
+/// [`VirtualJail`](crate::VirtualJail); `..` never pops what was already
+/// there.
+fn normalize_onto(
+    normalized: &mut PathBuf,
+    path: &Path,
+    root: &Path,
+    absolute: bool,
+) -> Result<(), JailError> {
+    let steps = path.components().map(|component| match component {
+        Component::Prefix(_) | Component::RootDir => Step::Root(component.as_os_str()),
+        Component::CurDir => Step::Current,
+        Component::ParentDir => Step::Parent,
+        Component::Normal(name) => Step::Name(name),
+    });
+    traverse(normalized, steps, absolute).map_err(|err| match err {
+        LexicalError::NullByte => JailError::InvalidPath("null bytes not allowed".into()),
+        LexicalError::EscapedRoot => JailError::EscapedRoot {
+            attempted: path.to_path_buf(),
+            root: root.to_path_buf(),
+        },
+        LexicalError::Absolute | LexicalError::InvalidRoot => {
+            JailError::InvalidPath("absolute paths not allowed".into())
+        }
+    })
+}
+
+impl Normalized for PathBuf {
+    type Name = OsStr;
+
+    fn has_null(name: &OsStr) -> bool {
+        contains_unit(name, b'\0')
+    }
+
+    fn push_name(&mut self, name: &OsStr) {
+        self.push(name);
+    }
+
+    fn pop_name(&mut self) {
+        self.pop();
+    }
+}
@@ -52,23 +52,245 @@
 //! - Broken symlinks (cannot verify target)
 //!
 //! See [`Jail`] for details on the security model.
+//!
+//! # `no_std`
+//!
+//! Everything that touches the filesystem needs the default `std`
+//! feature. Without it the crate is `no_std` (with `alloc`) and only
+//! exports [`VirtualJail`], the lexical traversal rules for `/`-separated
+//! virtual path spaces.
+
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod access;
+#[cfg(feature = "std")]
+mod atomic;
+#[cfg(feature = "std")]
+mod audit;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+mod cache;
+#[cfg(feature = "std")]
+mod cleanup;
+#[cfg(feature = "std")]
+mod deny;
+#[cfg(feature = "std")]
 mod error;
+#[cfg(feature = "std")]
+mod explain;
+#[cfg(feature = "std")]
+mod fs_ops;
+#[cfg(feature = "std")]
+mod glob;
+#[cfg(feature = "std")]
 mod jail;
+#[cfg(feature = "std")]
 mod jailed_path;
+#[cfg(feature = "std")]
+mod lexical;
+#[cfg(feature = "std")]
+mod manager;
+#[cfg(feature = "std")]
+mod ops;
+#[cfg(feature = "std")]
+mod percent;
+#[cfg(feature = "std")]
+mod perms;
+#[cfg(feature = "std")]
+mod quota;
+#[cfg(feature = "std")]
+mod read_dir;
+#[cfg(feature = "std")]
+mod sanitize;
+#[cfg(feature = "std")]
+mod scoped;
+#[cfg(feature = "std")]
+mod sink;
+#[cfg(feature = "std")]
+mod staging;
+#[cfg(feature = "std")]
+mod temp;
+#[cfg(feature = "std")]
+mod typed_path;
+mod virtual_jail;
+#[cfg(feature = "std")]
+mod walk;
+
+#[cfg(all(feature = "std", windows))]
+mod reparse;
+
+#[cfg(all(feature = "chroot", unix))]
+mod chroot;
 
 #[cfg(feature = "secure-open")]
 mod open;
 
+#[cfg(feature = "secure-open")]
+mod lock;
+
+#[cfg(all(feature = "secure-open", target_os = "linux"))]
+mod anonymous;
+
+#[cfg(all(feature = "openat", unix))]
+mod dirfd;
+
+#[cfg(all(feature = "openat", unix))]
+mod jailed_dir;
+
+#[cfg(all(feature = "landlock", target_os = "linux"))]
+mod landlock;
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+mod uring;
+
+#[cfg(feature = "serde")]
+mod serde;
+
+#[cfg(feature = "clap")]
+mod clap;
+
+#[cfg(feature = "tower")]
+mod serve;
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod extract;
+
+#[cfg(feature = "tar")]
+mod tar;
+
+#[cfg(feature = "zip")]
+mod zip;
+
+#[cfg(feature = "unicode")]
+mod unicode;
+
+#[cfg(feature = "test-util")]
+mod adversarial;
+#[cfg(feature = "test-util")]
+mod memory;
+
+#[cfg(feature = "metrics")]
+mod stats;
+
+#[cfg(feature = "cap-std")]
+mod cap;
+
+#[cfg(feature = "watch")]
+mod watch;
+
+#[cfg(feature = "pyo3")]
+mod python;
+
+#[cfg(feature = "camino")]
+mod utf8;
+
+#[cfg(feature = "std")]
 use std::path::{Path, PathBuf};
 
-pub use error::JailError;
+#[cfg(feature = "std")]
+pub use access::{Access, ReadOnly, ReadWrite};
+#[cfg(feature = "std")]
+pub use atomic::AtomicWriter;
+#[cfg(feature = "std")]
+pub use audit::{Operation, RejectionEvent, SecurityEvent};
+#[cfg(feature = "std")]
+pub use builder::JailBuilder;
+#[cfg(feature = "std")]
+pub use cleanup::{CleanupPolicy, CleanupReport, Timestamp};
+#[cfg(feature = "std")]
+pub use error::{JailError, JailErrorKind};
+#[cfg(feature = "std")]
+pub use explain::Diagnosis;
+#[cfg(feature = "std")]
+pub use fs_ops::FsOps;
+#[cfg(feature = "std")]
+pub use glob::Glob;
+#[cfg(feature = "std")]
 pub use jail::Jail;
+#[cfg(feature = "std")]
 pub use jailed_path::JailedPath;
+#[cfg(feature = "std")]
+pub use lexical::{normalize, LexicalJail};
+#[cfg(feature = "std")]
+pub use manager::JailManager;
+#[cfg(feature = "std")]
+pub use quota::QuotaUsage;
+#[cfg(feature = "std")]
+pub use read_dir::{DirEntry, ReadDir};
+#[cfg(feature = "std")]
+pub use sanitize::sanitize_file_name;
+#[cfg(feature = "std")]
+pub use scoped::{current_jail, current_jail_join, ScopedJail};
+#[cfg(feature = "std")]
+pub use sink::{EntryFile, EntrySink};
+#[cfg(feature = "std")]
+pub use staging::Staging;
+#[cfg(feature = "std")]
+pub use temp::{TempDir, TempFile};
+#[cfg(feature = "std")]
+pub use typed_path::{JailedDirPath, JailedFilePath};
+pub use virtual_jail::{LexicalError, VirtualJail};
+#[cfg(feature = "std")]
+pub use walk::Walk;
 
-#[cfg(feature = "secure-open")]
+#[cfg(all(feature = "secure-open", unix))]
 pub use open::JailedFile;
 
+#[cfg(all(feature = "secure-open", unix))]
+pub use lock::FileLock;
+
+#[cfg(all(feature = "secure-open", target_os = "linux"))]
+pub use anonymous::AnonymousFile;
+
+#[cfg(all(feature = "openat", unix))]
+pub use jailed_dir::JailedDir;
+
+#[cfg(all(feature = "landlock", target_os = "linux"))]
+pub use landlock::{Landlock, LandlockStatus};
+
+#[cfg(all(feature = "io-uring", target_os = "linux"))]
+pub use uring::JailRing;
+
+#[cfg(feature = "serde")]
+pub use serde::JailSeed;
+
+#[cfg(feature = "clap")]
+pub use clap::{JailValueParser, JailedPathValueParser};
+
+#[cfg(feature = "tower")]
+pub use serve::{ServeBody, ServeJail};
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+pub use extract::{EscapePolicy, ExtractOptions, ExtractReport, LinkPolicy};
+
+#[cfg(feature = "unicode")]
+pub use unicode::UnicodeForm;
+
+#[cfg(feature = "test-util")]
+pub use adversarial::{adversarial_path, AdversarialPath};
+#[cfg(feature = "test-util")]
+pub use fs_ops::MemoryOps;
+#[cfg(feature = "test-util")]
+pub use memory::{MemoryFs, MemoryJail};
+
+#[cfg(feature = "metrics")]
+pub use stats::JailStats;
+
+#[cfg(feature = "watch")]
+pub use watch::{JailWatcher, WatchEvent, WatchKind};
+
+#[cfg(feature = "pyo3")]
+pub use python::register_python;
+
+#[cfg(feature = "camino")]
+pub use utf8::Utf8JailedPath;
+
 /// Validate a path in one shot.
 ///
 /// This is a convenience wrapper around [`Jail::new`] and [`Jail::join`].
@@ -97,6 +319,7 @@ pub use open::JailedFile;
 /// std::fs::write(&safe, data)?;
 /// # Ok::<(), path_jail::JailError>(())
 /// ```
+#[cfg(feature = "std")]
 pub fn join<R, P>(root: R, path: P) -> Result<PathBuf, JailError>
 where
     R: AsRef<Path>,
@@ -0,0 +1,114 @@
+//! Advisory file locks inside a jail (`secure-open` feature, Unix only).
+//!
+//! Locks are taken with `flock()`. They are advisory: they coordinate
+//! processes that all lock, and do not stop anyone from opening the file.
+//! There is no Windows implementation (`LockFileEx`); the methods do not
+//! exist there.
+
+#![cfg(all(feature = "secure-open", unix))]
+
+use crate::open::Mode;
+use crate::{Jail, JailError, JailedFile};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+
+impl Jail {
+    /// Open `relative` and take an exclusive lock on it, waiting until no
+    /// other process holds a lock on the file.
+    ///
+    /// The file is opened for reading and writing with `O_NOFOLLOW` after
+    /// validation, and created if missing, so a lock file needs no setup.
+    /// The lock is released when the returned guard is dropped.
+    ///
+    /// Unix only: Windows has no `flock()`, and `LockFileEx` is not wired up.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Write;
+    ///
+    /// let jail = Jail::new("/var/spool")?;
+    /// let mut lock = jail.lock_exclusive("queue/state.json")?;
+    /// lock.set_len(0)?;
+    /// lock.write_all(b"{}")?;
+    /// drop(lock); // other workers may proceed
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn lock_exclusive<P: AsRef<Path>>(&self, relative: P) -> Result<FileLock, JailError> {
+        self.lock(relative.as_ref(), libc::LOCK_EX)
+    }
+
+    /// Like [`lock_exclusive`](Self::lock_exclusive), but takes a shared
+    /// lock: any number of shared locks can be held at once, and they
+    /// exclude exclusive locks.
+    pub fn lock_shared<P: AsRef<Path>>(&self, relative: P) -> Result<FileLock, JailError> {
+        self.lock(relative.as_ref(), libc::LOCK_SH)
+    }
+
+    fn lock(&self, relative: &Path, operation: libc::c_int) -> Result<FileLock, JailError> {
+        let path = self.join(relative)?;
+        let file = self.open_validated(&path, Mode::Update)?;
+        self.config().check_links(&path, &file.metadata()?)?;
+        loop {
+            // SAFETY: valid open file descriptor.
+            if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+                break;
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err.into());
+            }
+        }
+        Ok(FileLock {
+            file: JailedFile::metered(self, file, false),
+        })
+    }
+}
+
+/// A file inside a jail with an advisory lock held on it, from
+/// [`Jail::lock_exclusive`] or [`Jail::lock_shared`].
+///
+/// Dereferences to the [`JailedFile`], so the file can be read and written
+/// while locked. Dropping the guard releases the lock.
+#[derive(Debug)]
+pub struct FileLock {
+    file: JailedFile,
+}
+
+impl FileLock {
+    /// Release the lock, reporting any error that dropping would ignore.
+    /// (The drop that follows unlocks again, which does nothing.)
+    pub fn unlock(self) -> Result<(), JailError> {
+        // SAFETY: valid open file descriptor.
+        if unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) } != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        Ok(())
+    }
+}
+
+impl Deref for FileLock {
+    type Target = JailedFile;
+
+    fn deref(&self) -> &Self::Target {
+        &self.file
+    }
+}
+
+impl DerefMut for FileLock {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.file
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Closing the descriptor releases it too; unlock explicitly in
+        // case the file was duplicated
+        // SAFETY: valid open file descriptor.
+        unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) };
+    }
+}
@@ -0,0 +1,227 @@
+//! Per-tenant jails under a shared parent ([`JailManager`]).
+
+use crate::{sanitize_file_name, Jail, JailError};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Lazily created, cached jails, one per key, in subdirectories of a
+/// parent jail: `/data/acme`, `/data/globex`, and so on.
+///
+/// [`get`](Self::get) returns the jail for a key, creating the directory
+/// the first time if asked to. A key must be a single, plain directory
+/// name (`acme`, `user-42`); anything [`sanitize_file_name`] would change,
+/// such as `../acme` or `a/b`, is rejected, unless
+/// [`sanitize_keys`](Self::sanitize_keys) is on. Each tenant's jail is a
+/// [`subjail`](Jail::subjail) of the parent, with the parent's options.
+///
+/// The manager is `Sync`; share it behind an `Arc`. Jails are cheap to
+/// clone and stay valid after they are evicted from the cache; eviction
+/// only bounds memory and never touches the directories.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{Jail, JailManager};
+/// use std::time::Duration;
+///
+/// let tenants = JailManager::new(Jail::new("/data")?)
+///     .max_jails(10_000)
+///     .idle_timeout(Duration::from_secs(600));
+///
+/// let acme = tenants.get("acme")?; // creates /data/acme if needed
+/// acme.write("invoices/2025.pdf", b"...")?;
+/// assert!(tenants.get("../etc").is_err());
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug)]
+pub struct JailManager {
+    parent: Jail,
+    create: bool,
+    #[cfg(unix)]
+    mode: Option<u32>,
+    sanitize: bool,
+    max_jails: Option<usize>,
+    idle_timeout: Option<Duration>,
+    cache: Mutex<HashMap<String, Slot>>,
+}
+
+#[derive(Debug)]
+struct Slot {
+    jail: Jail,
+    last_used: Instant,
+}
+
+impl JailManager {
+    /// Manage jails in subdirectories of `parent`.
+    pub fn new(parent: Jail) -> Self {
+        Self {
+            parent,
+            create: true,
+            #[cfg(unix)]
+            mode: None,
+            sanitize: false,
+            max_jails: None,
+            idle_timeout: None,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Create a missing tenant directory on first use (default `true`).
+    /// When off, [`get`](Self::get) fails with [`JailError::InvalidRoot`]
+    /// for keys whose directory does not exist.
+    pub fn create(mut self, create: bool) -> Self {
+        self.create = create;
+        self
+    }
+
+    /// Permission bits for tenant directories it creates, such as `0o700`
+    /// (Unix only; default: the process umask decides). The umask still
+    /// applies, as with [`Jail::create_dir_with_mode`].
+    #[cfg(unix)]
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Map keys that are not plain directory names through
+    /// [`sanitize_file_name`] instead of rejecting them (default `false`).
+    ///
+    /// Sanitizing is not one-to-one: `a/b` and `a_b` both become `a_b`
+    /// and so share a jail. Only turn this on when keys come from a
+    /// trusted source, or when that is acceptable.
+    pub fn sanitize_keys(mut self, sanitize: bool) -> Self {
+        self.sanitize = sanitize;
+        self
+    }
+
+    /// Keep at most `max` jails cached, dropping the least recently used
+    /// one to make room (default: unbounded).
+    pub fn max_jails(mut self, max: usize) -> Self {
+        self.max_jails = Some(max);
+        self
+    }
+
+    /// Drop cached jails not used for `timeout` (default: never). Checked
+    /// whenever a jail is added to the cache.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// The parent jail.
+    pub fn parent(&self) -> &Jail {
+        &self.parent
+    }
+
+    /// The jail for `key`, from the cache or opened (and, if configured,
+    /// created) now.
+    ///
+    /// # Errors
+    ///
+    /// [`JailError::InvalidPath`] for a key that is not a plain directory
+    /// name, [`JailError::InvalidRoot`] if the directory is missing or is
+    /// not a directory, and I/O errors from creating it.
+    pub fn get(&self, key: &str) -> Result<Jail, JailError> {
+        let name = self.name(key)?;
+        if let Some(slot) = self.lock().get_mut(&name) {
+            slot.last_used = Instant::now();
+            return Ok(slot.jail.clone());
+        }
+
+        // Opened without the lock held, so one slow filesystem does not
+        // stall every other tenant
+        let jail = self.open(&name)?;
+
+        let now = Instant::now();
+        let mut cache = self.lock();
+        if let Some(slot) = cache.get_mut(&name) {
+            // Another thread got there first; keep a single handle
+            slot.last_used = now;
+            return Ok(slot.jail.clone());
+        }
+        if let Some(timeout) = self.idle_timeout {
+            cache.retain(|_, slot| now.duration_since(slot.last_used) < timeout);
+        }
+        if let Some(max) = self.max_jails {
+            while cache.len() >= max.max(1) {
+                let Some(oldest) = cache
+                    .iter()
+                    .min_by_key(|(_, slot)| slot.last_used)
+                    .map(|(name, _)| name.clone())
+                else {
+                    break;
+                };
+                cache.remove(&oldest);
+            }
+        }
+        cache.insert(
+            name,
+            Slot {
+                jail: jail.clone(),
+                last_used: now,
+            },
+        );
+        Ok(jail)
+    }
+
+    /// Drop the cached jail for `key`, if any; the next
+    /// [`get`](Self::get) opens it again. Returns whether one was cached.
+    pub fn evict(&self, key: &str) -> bool {
+        match self.name(key) {
+            Ok(name) => self.lock().remove(&name).is_some(),
+            Err(_) => false,
+        }
+    }
+
+    /// Drop every cached jail.
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Number of cached jails.
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether no jails are cached.
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// The directory name for `key`.
+    fn name(&self, key: &str) -> Result<String, JailError> {
+        let name = sanitize_file_name(key)?;
+        if !self.sanitize && name != key {
+            return Err(JailError::InvalidPath(format!(
+                "jail key '{}' is not a plain directory name",
+                key
+            )));
+        }
+        Ok(name)
+    }
+
+    fn open(&self, name: &str) -> Result<Jail, JailError> {
+        // The key is the directory name as-is: create and resolve it
+        // without the input preprocessing (percent-decoding) of `join`
+        if self.create {
+            let parent = self.parent.verbatim();
+            #[cfg(unix)]
+            if let Some(mode) = self.mode {
+                parent.create_dir_all_with_mode(name, mode)?;
+            } else {
+                parent.create_dir_all(name)?;
+            }
+            #[cfg(not(unix))]
+            parent.create_dir_all(name)?;
+        }
+        let path = self.parent.resolve(Path::new(name))?;
+        self.parent.narrow(path)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, Slot>> {
+        // The map stays consistent even if a holder panicked
+        self.cache.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
@@ -0,0 +1,241 @@
+//! In-memory filesystem for testing traversal logic (`test-util` feature).
+//!
+//! [`MemoryJail`] runs the exact component walk behind [`Jail::join`]
+//! against a [`MemoryFs`] tree, so tests for traversal and symlink
+//! handling need no tempdirs and no symlink privileges (an issue on
+//! Windows CI runners).
+
+#![cfg(feature = "test-util")]
+
+use crate::builder::Config;
+use crate::jail::{Lookup, Node};
+use crate::{Jail, JailError};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Symlinks followed before giving up, like Linux's `ELOOP` limit.
+const MAX_SYMLINK_HOPS: u32 = 40;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Entry {
+    Dir,
+    File,
+    Symlink(PathBuf),
+}
+
+/// An in-memory tree of directories, files and symlinks.
+///
+/// Paths are absolute and use `/` as the root on every platform. Missing
+/// parent directories are created by every method.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::{JailError, MemoryFs, MemoryJail};
+///
+/// let mut fs = MemoryFs::new();
+/// fs.dir("/srv/jail/uploads")
+///     .file("/etc/passwd")
+///     .symlink("/srv/jail/uploads/escape", "../../../etc");
+///
+/// let jail = MemoryJail::new(fs, "/srv/jail")?;
+/// assert!(jail.join("uploads/new.txt").is_ok());
+/// assert!(matches!(
+///     jail.join("uploads/escape/passwd"),
+///     Err(JailError::EscapedRoot { .. })
+/// ));
+/// # Ok::<(), JailError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFs {
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+impl MemoryFs {
+    /// An empty tree containing only `/`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a directory.
+    pub fn dir<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.insert(path.as_ref(), Entry::Dir)
+    }
+
+    /// Add an empty file.
+    pub fn file<P: AsRef<Path>>(&mut self, path: P) -> &mut Self {
+        self.insert(path.as_ref(), Entry::File)
+    }
+
+    /// Add a symlink at `path` pointing at `target`. Relative targets are
+    /// resolved against the link's directory; the target need not exist.
+    pub fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(&mut self, path: P, target: Q) -> &mut Self {
+        self.insert(path.as_ref(), Entry::Symlink(target.as_ref().to_path_buf()))
+    }
+
+    fn insert(&mut self, path: &Path, entry: Entry) -> &mut Self {
+        let path = absolute(path);
+        for parent in path.ancestors().skip(1) {
+            if parent.parent().is_some() {
+                self.entries.insert(parent.to_path_buf(), Entry::Dir);
+            }
+        }
+        self.entries.insert(path, entry);
+        self
+    }
+
+    fn get(&self, path: &Path) -> Option<&Entry> {
+        if path.parent().is_none() {
+            return Some(&Entry::Dir);
+        }
+        self.entries.get(path)
+    }
+
+    fn resolve(&self, path: &Path, hops: &mut u32) -> io::Result<PathBuf> {
+        let mut current = PathBuf::from("/");
+        let mut components = path.components().peekable();
+        while let Some(component) = components.next() {
+            match component {
+                Component::Prefix(_) | Component::RootDir => current = PathBuf::from("/"),
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    current.pop();
+                }
+                Component::Normal(name) => {
+                    current.push(name);
+                    match self.get(&current) {
+                        None => return Err(io::ErrorKind::NotFound.into()),
+                        Some(Entry::File) if components.peek().is_some() => {
+                            return Err(io::Error::other("not a directory"));
+                        }
+                        Some(Entry::Symlink(target)) => {
+                            *hops += 1;
+                            if *hops > MAX_SYMLINK_HOPS {
+                                return Err(io::Error::other("too many levels of symbolic links"));
+                            }
+                            current.pop();
+                            let target = current.join(target);
+                            current = self.resolve(&target, hops)?;
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+        Ok(current)
+    }
+}
+
+impl Lookup for MemoryFs {
+    fn exists(&self, path: &Path) -> bool {
+        self.canonicalize(path).is_ok()
+    }
+
+    fn is_symlink(&self, path: &Path) -> bool {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return false;
+        };
+        self.canonicalize(parent)
+            .is_ok_and(|dir| matches!(self.get(&dir.join(name)), Some(Entry::Symlink(_))))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.canonicalize(path)
+            .is_ok_and(|path| self.get(&path) == Some(&Entry::Dir))
+    }
+
+    fn is_junction(&self, _path: &Path) -> bool {
+        false
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        self.resolve(path, &mut 0)
+    }
+
+    fn device(&self, _path: &Path) -> Option<u64> {
+        None
+    }
+
+    fn node(&self, path: &Path) -> Node {
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Node::Dir;
+        };
+        match self
+            .canonicalize(parent)
+            .map(|dir| self.get(&dir.join(name)))
+        {
+            Ok(Some(Entry::Dir)) => Node::Dir,
+            Ok(Some(Entry::File)) => Node::File,
+            Ok(Some(Entry::Symlink(target))) => Node::Link(target.clone()),
+            Ok(None) | Err(_) => Node::Missing,
+        }
+    }
+}
+
+/// A [`Jail`] over a [`MemoryFs`], for testing traversal behavior.
+///
+/// [`join`](Self::join) performs the same walk as [`Jail::join`], including
+/// builder options, but every filesystem query goes to the in-memory tree.
+#[derive(Debug, Clone)]
+pub struct MemoryJail {
+    jail: Jail,
+    fs: MemoryFs,
+}
+
+impl MemoryJail {
+    /// Create a jail rooted at `root` inside `fs`.
+    ///
+    /// Errors like [`Jail::new`] if the root is missing, not a directory,
+    /// or `/`.
+    pub fn new<P: AsRef<Path>>(fs: MemoryFs, root: P) -> Result<Self, JailError> {
+        Self::with_config(fs, root.as_ref(), Config::default())
+    }
+
+    pub(crate) fn with_config(
+        fs: MemoryFs,
+        root: &Path,
+        config: Config,
+    ) -> Result<Self, JailError> {
+        let root = fs.canonicalize(root)?;
+        if root.parent().is_none() || fs.get(&root) != Some(&Entry::Dir) {
+            return Err(JailError::InvalidRoot(root));
+        }
+        Ok(Self {
+            jail: Jail::from_canonical(root, config),
+            fs,
+        })
+    }
+
+    /// Returns the canonicalized root path.
+    pub fn root(&self) -> &Path {
+        self.jail.root()
+    }
+
+    /// The underlying tree, e.g. to add entries between checks.
+    pub fn fs_mut(&mut self) -> &mut MemoryFs {
+        &mut self.fs
+    }
+
+    /// Validate `relative` like [`Jail::join`].
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join<P: AsRef<Path>>(&self, relative: P) -> Result<PathBuf, JailError> {
+        let path = self.jail.config().prepare(relative.as_ref())?;
+        self.jail.resolve_in(&self.fs, &path)
+    }
+}
+
+/// Anchor `path` at `/`, resolving `.` and `..` lexically.
+fn absolute(path: &Path) -> PathBuf {
+    let mut absolute = PathBuf::from("/");
+    for component in path.components() {
+        match component {
+            Component::Normal(name) => absolute.push(name),
+            Component::ParentDir => {
+                absolute.pop();
+            }
+            _ => {}
+        }
+    }
+    absolute
+}
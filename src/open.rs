@@ -6,38 +6,70 @@
 //!
 //! # Limitations
 //!
-//! This uses `O_NOFOLLOW` on the final open, which protects against symlink swaps
-//! on the target file. It does NOT protect against symlink swaps on intermediate
-//! directories (that would require `openat()` walking, which needs `libc`).
+//! On its own this uses `O_NOFOLLOW` on the final open, which protects against
+//! symlink swaps on the target file. It does NOT protect against symlink swaps
+//! on intermediate directories.
 //!
-//! For full TOCTOU protection against local attackers, use [`cap-std`](https://docs.rs/cap-std).
+//! With the `openat` feature as well, every directory from the jail root down is
+//! opened with `openat(O_NOFOLLOW | O_DIRECTORY)` and the file is opened relative
+//! to its parent, so swapping any component for a symlink makes the open fail.
+//! This needs only `openat()`, not Linux's `openat2()`. Paths that go through an
+//! [`allow_symlink_target`](crate::JailBuilder::allow_symlink_target) link keep
+//! the final-component protection only, since their walk must follow that link.
 
 #![cfg(all(feature = "secure-open", unix))]
 
-use crate::{Jail, JailError, JailedPath};
+use crate::quota::Quota;
+use crate::{Access, Jail, JailError, JailedPath};
 use std::fs::{File, OpenOptions};
 use std::io;
-use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
 use std::path::Path;
 
 // O_NOFOLLOW values by platform (from POSIX/system headers)
-#[cfg(target_os = "linux")]
-const O_NOFOLLOW: i32 = 0o0400000;
+// Linux uses a different value on ARM, PowerPC and m68k
+#[cfg(all(
+    target_os = "linux",
+    any(
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "powerpc",
+        target_arch = "powerpc64",
+        target_arch = "m68k"
+    )
+))]
+pub(crate) const O_NOFOLLOW: i32 = 0o0100000;
+
+#[cfg(all(
+    target_os = "linux",
+    not(any(
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "powerpc",
+        target_arch = "powerpc64",
+        target_arch = "m68k"
+    ))
+))]
+pub(crate) const O_NOFOLLOW: i32 = 0o0400000;
+
+// Checked against the C headers wherever libc is linked in
+#[cfg(all(target_os = "linux", feature = "openat"))]
+const _: () = assert!(O_NOFOLLOW == libc::O_NOFOLLOW);
 
 #[cfg(target_os = "macos")]
-const O_NOFOLLOW: i32 = 0x0100;
+pub(crate) const O_NOFOLLOW: i32 = 0x0100;
 
 #[cfg(target_os = "freebsd")]
-const O_NOFOLLOW: i32 = 0x0100;
+pub(crate) const O_NOFOLLOW: i32 = 0x0100;
 
 #[cfg(target_os = "openbsd")]
-const O_NOFOLLOW: i32 = 0x0100;
+pub(crate) const O_NOFOLLOW: i32 = 0x0100;
 
 #[cfg(target_os = "netbsd")]
-const O_NOFOLLOW: i32 = 0x0100;
+pub(crate) const O_NOFOLLOW: i32 = 0x0100;
 
 #[cfg(target_os = "dragonfly")]
-const O_NOFOLLOW: i32 = 0x0100;
+pub(crate) const O_NOFOLLOW: i32 = 0x0100;
 
 // Fallback for other Unix-like systems
 #[cfg(not(any(
@@ -48,24 +80,192 @@ const O_NOFOLLOW: i32 = 0x0100;
     target_os = "netbsd",
     target_os = "dragonfly"
 )))]
-const O_NOFOLLOW: i32 = 0;
+pub(crate) const O_NOFOLLOW: i32 = 0;
+
+/// How the helpers in this module open a file.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Mode {
+    Read,
+    CreateNew,
+    /// Write, creating if missing, without truncating.
+    Write,
+    Append,
+    /// Read and write, creating if missing, without truncating.
+    Update,
+}
+
+impl Mode {
+    /// Open with `open`, passed whether to add `O_EXCL`, and tell whether
+    /// this call created the file. Only reliable when `track` is set.
+    fn open<F>(self, track: bool, open: F) -> io::Result<(File, bool)>
+    where
+        F: Fn(bool) -> io::Result<File>,
+    {
+        match self {
+            Mode::Read => Ok((open(false)?, false)),
+            Mode::CreateNew => Ok((open(false)?, true)),
+            Mode::Write | Mode::Append | Mode::Update => crate::ops::open_creating(track, open),
+        }
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Open an already validated `path` without following symlinks in its
+    /// final component or, with `openat`, in any component below the root.
+    pub(crate) fn open_validated(&self, path: &Path, mode: Mode) -> Result<File, JailError> {
+        let config = self.config();
+        let track = config.tracks_creation();
+        let file_mode = config.file_mode();
+
+        #[cfg(feature = "openat")]
+        if path != self.root() && !config.has_symlink_targets() {
+            let mut flags = match mode {
+                Mode::Read => libc::O_RDONLY,
+                Mode::CreateNew => libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL,
+                Mode::Write => libc::O_WRONLY | libc::O_CREAT,
+                Mode::Append => libc::O_WRONLY | libc::O_APPEND | libc::O_CREAT,
+                Mode::Update => libc::O_RDWR | libc::O_CREAT,
+            };
+            if config.is_append_only() && !matches!(mode, Mode::Read) {
+                flags |= libc::O_APPEND;
+            }
+            let (dir, name) = self.entry_at(path)?;
+            let perm = file_mode.unwrap_or(0o666);
+            let (file, created) = mode.open(track, |exclusive| {
+                let excl = if exclusive { libc::O_EXCL } else { 0 };
+                dir.open_file(&name, flags | excl, perm)
+            })?;
+            config.count_open();
+            if created && track {
+                config.created_file(path, &file)?;
+            }
+            return Ok(file);
+        }
+
+        let mut options = OpenOptions::new();
+        match mode {
+            Mode::Read => options.read(true),
+            Mode::CreateNew => options.write(true).create_new(true),
+            Mode::Write => options.write(true).create(true).truncate(false),
+            Mode::Append => options.append(true).create(true),
+            Mode::Update => options.read(true).write(true).create(true).truncate(false),
+        };
+        if config.is_append_only() && !matches!(mode, Mode::Read) {
+            options.append(true);
+        }
+        if let Some(perm) = file_mode {
+            options.mode(perm);
+        }
+        options.custom_flags(O_NOFOLLOW);
+        let (file, created) = mode.open(track, |exclusive| {
+            if exclusive {
+                options.clone().create_new(true).open(path)
+            } else {
+                options.open(path)
+            }
+        })?;
+        config.count_open();
+        if created && track {
+            config.created_file(path, &file)?;
+        }
+        Ok(file)
+    }
+}
 
 /// A file opened with TOCTOU-safe semantics.
 ///
 /// This is a thin wrapper around [`std::fs::File`] that guarantees the file
 /// was opened with `O_NOFOLLOW`, preventing symlink attacks on the final path
 /// component.
+///
+/// Files opened for writing from a jail with a
+/// [`quota`](crate::JailBuilder::quota) or a
+/// [`max_file_size`](crate::JailBuilder::max_file_size) are held to them
+/// when written through [`io::Write`] or resized with
+/// [`set_len`](Self::set_len); writes to the underlying [`File`] are not
+/// checked.
 #[derive(Debug)]
 pub struct JailedFile {
-    inner: File,
+    pub(crate) inner: File,
+    meter: Option<Meter>,
+}
+
+/// The limits a [`JailedFile`] writes against.
+#[derive(Debug)]
+struct Meter {
+    quota: Option<Quota>,
+    max_size: Option<u64>,
+    /// Opened with `O_APPEND`: writes go to the end, not the position.
+    append: bool,
+    /// From an [`append_only`](crate::JailBuilder::append_only) jail.
+    append_only: bool,
+}
+
+impl Meter {
+    /// Check that the file may go from `len` to `size` bytes and count
+    /// the difference against the quota.
+    fn resize(&self, len: u64, size: u64) -> Result<(), JailError> {
+        match self.max_size {
+            Some(max) if size > max && size > len => Err(JailError::FileTooLarge { size, max }),
+            _ => match &self.quota {
+                Some(quota) => quota.resize(len, size),
+                None => Ok(()),
+            },
+        }
+    }
 }
 
 impl JailedFile {
+    pub(crate) fn new(inner: File) -> Self {
+        Self { inner, meter: None }
+    }
+
+    /// A file opened for writing from `jail`, held to its quota and
+    /// file size limit.
+    pub(crate) fn metered<A: Access>(jail: &Jail<A>, inner: File, append: bool) -> Self {
+        let config = jail.config();
+        let meter = (config.quota().is_some()
+            || config.max_file_size().is_some()
+            || config.is_append_only())
+        .then(|| Meter {
+            quota: config.quota().cloned(),
+            max_size: config.max_file_size(),
+            append: append || config.is_append_only(),
+            append_only: config.is_append_only(),
+        });
+        Self { inner, meter }
+    }
+
     /// Returns the underlying [`File`].
     #[inline]
     pub fn into_inner(self) -> File {
         self.inner
     }
+
+    /// Truncate or extend the file to `size` bytes, like
+    /// [`File::set_len`], counting growth against the jail's quota and
+    /// refusing to extend it past the
+    /// [`max_file_size`](crate::JailBuilder::max_file_size). In an
+    /// [`append_only`](crate::JailBuilder::append_only) jail, truncating
+    /// fails with [`PermissionDenied`](io::ErrorKind::PermissionDenied).
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        let Some(meter) = &self.meter else {
+            return self.inner.set_len(size);
+        };
+        let len = self.inner.metadata()?.len();
+        if meter.append_only && size < len {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "cannot truncate a file in an append-only jail",
+            ));
+        }
+        meter.resize(len, size).map_err(io::Error::other)?;
+        let result = self.inner.set_len(size);
+        if result.is_err() {
+            let _ = meter.resize(size, len);
+        }
+        result
+    }
 }
 
 impl std::ops::Deref for JailedFile {
@@ -92,9 +292,24 @@ impl io::Read for JailedFile {
 }
 
 impl io::Write for JailedFile {
-    #[inline]
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.inner.write(buf)
+        let Some(meter) = &self.meter else {
+            return self.inner.write(buf);
+        };
+        // Only bytes past the current end make the file bigger
+        let len = self.inner.metadata()?.len();
+        let start = if meter.append {
+            len
+        } else {
+            io::Seek::stream_position(&mut self.inner)?
+        };
+        let end = |n: usize| len.max(start + n as u64);
+        let planned = end(buf.len());
+        meter.resize(len, planned).map_err(io::Error::other)?;
+        let result = self.inner.write(buf);
+        // Shrinking back never fails
+        let _ = meter.resize(planned, result.as_ref().map_or(len, |&n| end(n)));
+        result
     }
 
     #[inline]
@@ -110,7 +325,7 @@ impl io::Seek for JailedFile {
     }
 }
 
-impl Jail {
+impl<A: Access> Jail<A> {
     /// Open a file for reading with `O_NOFOLLOW` protection.
     ///
     /// This is TOCTOU-safe for the final path component: even if an attacker
@@ -139,13 +354,13 @@ impl Jail {
     /// - Permission denied
     pub fn open<P: AsRef<Path>>(&self, relative: P) -> Result<JailedFile, JailError> {
         let path = self.join(relative)?;
-        let file = OpenOptions::new()
-            .read(true)
-            .custom_flags(O_NOFOLLOW)
-            .open(&path)?;
-        Ok(JailedFile { inner: file })
+        let file = self.open_validated(&path, Mode::Read)?;
+        self.config().check_links(&path, &file.metadata()?)?;
+        Ok(JailedFile::new(file))
     }
+}
 
+impl Jail {
     /// Create a new file with `O_NOFOLLOW | O_CREAT | O_EXCL`.
     ///
     /// The file must not exist. This prevents symlink attacks where an attacker
@@ -172,12 +387,41 @@ impl Jail {
     /// - Permission denied
     pub fn create<P: AsRef<Path>>(&self, relative: P) -> Result<JailedFile, JailError> {
         let path = self.join(relative)?;
-        let file = OpenOptions::new()
-            .write(true)
-            .create_new(true) // O_CREAT | O_EXCL
-            .custom_flags(O_NOFOLLOW)
-            .open(&path)?;
-        Ok(JailedFile { inner: file })
+        let file = self.open_validated(&path, Mode::CreateNew)?;
+        Ok(JailedFile::metered(self, file, false))
+    }
+
+    /// Like [`create`](Self::create), creating missing parent directories
+    /// first.
+    ///
+    /// The whole path is validated before anything is created, so a
+    /// rejected path leaves no directories behind. Parents are made like
+    /// [`create_dir_all`](Self::create_dir_all) (with the
+    /// [`dir_mode`](crate::JailBuilder::dir_mode), and never following an
+    /// intermediate swapped for a symlink out of the jail), then the file
+    /// is created with `O_EXCL` as usual.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Write;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let mut file = jail.create_with_parents("alice/2025/07/report.pdf")?;
+    /// file.write_all(b"...")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn create_with_parents<P: AsRef<Path>>(
+        &self,
+        relative: P,
+    ) -> Result<JailedFile, JailError> {
+        let relative = relative.as_ref();
+        self.join(relative)?;
+        if let Some(parent) = relative.parent().filter(|p| !p.as_os_str().is_empty()) {
+            self.create_dir_all(parent)?;
+        }
+        self.create(relative)
     }
 
     /// Open a file for writing, truncating if it exists.
@@ -196,14 +440,20 @@ impl Jail {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn create_or_truncate<P: AsRef<Path>>(&self, relative: P) -> Result<JailedFile, JailError> {
+        if self.config().is_append_only() {
+            // There is nothing to truncate in a file that is new
+            return self
+                .create(relative.as_ref())
+                .map_err(|err| self.config().refuse_existing(relative.as_ref(), err));
+        }
         let path = self.join(relative)?;
-        let file = OpenOptions::new()
-            .write(true)
-            .create(true)
-            .truncate(true)
-            .custom_flags(O_NOFOLLOW)
-            .open(&path)?;
-        Ok(JailedFile { inner: file })
+        // Truncate only after the link check
+        let file = self.open_validated(&path, Mode::Write)?;
+        let meta = file.metadata()?;
+        self.config().check_links(&path, &meta)?;
+        file.set_len(0)?;
+        self.config().release(meta.len());
+        Ok(JailedFile::metered(self, file, false))
     }
 
     /// Open a file for appending.
@@ -211,36 +461,105 @@ impl Jail {
     /// Uses `O_NOFOLLOW` to prevent symlink attacks.
     pub fn open_append<P: AsRef<Path>>(&self, relative: P) -> Result<JailedFile, JailError> {
         let path = self.join(relative)?;
-        let file = OpenOptions::new()
-            .append(true)
-            .create(true)
-            .custom_flags(O_NOFOLLOW)
-            .open(&path)?;
-        Ok(JailedFile { inner: file })
+        let file = self.open_validated(&path, Mode::Append)?;
+        self.config().check_links(&path, &file.metadata()?)?;
+        Ok(JailedFile::metered(self, file, true))
+    }
+
+    /// Copy a regular file to another location inside the jail.
+    ///
+    /// Both ends are opened with `O_NOFOLLOW` after validation, so swapping
+    /// either path for a symlink makes the copy fail instead of reading or
+    /// writing through it. Once open, the copy works on the file descriptors
+    /// and is unaffected by later path changes. Permissions are copied as
+    /// with [`std::fs::copy`].
+    ///
+    /// Returns the number of bytes copied.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let bytes = jail.copy("alice/report.pdf", "shared/report.pdf")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - Either path would escape the jail
+    /// - Either final component is a symlink (blocked by `O_NOFOLLOW`)
+    /// - The source is not a regular file
+    /// - Source and destination are the same file
+    pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<u64, JailError> {
+        let mut src = self.open(from)?;
+        let src_meta = src.metadata()?;
+        if !src_meta.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "source is not a regular file",
+            )
+            .into());
+        }
+
+        self.config().check_file_size(src_meta.len())?;
+
+        // Don't truncate on open: copying a file onto itself would destroy it.
+        // An append-only jail may only copy to a new file.
+        let dst_path = self.join(to.as_ref())?;
+        let mode = if self.config().is_append_only() {
+            Mode::CreateNew
+        } else {
+            Mode::Write
+        };
+        let mut dst = self
+            .open_validated(&dst_path, mode)
+            .map_err(|err| self.config().refuse_existing(to.as_ref(), err))?;
+        let dst_meta = dst.metadata()?;
+        self.config().check_links(&dst_path, &dst_meta)?;
+        if (dst_meta.dev(), dst_meta.ino()) == (src_meta.dev(), src_meta.ino()) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "source and destination are the same file",
+            )
+            .into());
+        }
+        self.config().resize(dst_meta.len(), src_meta.len())?;
+        let copied = dst.set_len(0).and_then(|()| io::copy(&mut src, &mut dst));
+        if copied.is_err() {
+            // Count what made it to disk instead
+            let written = dst.metadata().map_or(0, |meta| meta.len());
+            let _ = self.config().resize(src_meta.len(), written);
+        }
+        let copied = copied?;
+        dst.set_permissions(src_meta.permissions())?;
+        Ok(copied)
     }
 }
 
-impl JailedPath {
+impl<A: Access> JailedPath<A> {
     /// Open this path for reading with `O_NOFOLLOW` protection.
     ///
     /// See [`Jail::open`] for details.
     pub fn open(&self) -> Result<JailedFile, JailError> {
-        let file = OpenOptions::new()
-            .read(true)
-            .custom_flags(O_NOFOLLOW)
-            .open(self.as_path())?;
-        Ok(JailedFile { inner: file })
+        let file = self.jail().open_validated(self.as_path(), Mode::Read)?;
+        self.jail()
+            .config()
+            .check_links(self.as_path(), &file.metadata()?)?;
+        Ok(JailedFile::new(file))
     }
+}
 
+impl JailedPath {
     /// Create a new file at this path with `O_NOFOLLOW | O_CREAT | O_EXCL`.
     ///
     /// See [`Jail::create`] for details.
     pub fn create(&self) -> Result<JailedFile, JailError> {
-        let file = OpenOptions::new()
-            .write(true)
-            .create_new(true)
-            .custom_flags(O_NOFOLLOW)
-            .open(self.as_path())?;
-        Ok(JailedFile { inner: file })
+        let file = self
+            .jail()
+            .open_validated(self.as_path(), Mode::CreateNew)?;
+        Ok(JailedFile::metered(self.jail(), file, false))
     }
 }
@@ -0,0 +1,864 @@
+//! Filesystem operations on validated paths.
+//!
+//! Each operation validates its input with [`Jail::join`] first and then
+//! re-checks anything that could have been swapped for a symlink in between.
+
+use crate::jail::contains_unit;
+use crate::{Access, Jail, JailError, JailedPath};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+impl Jail {
+    /// Create a single directory inside the jail.
+    ///
+    /// The parent must already exist. Returns the path of the new directory.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let dir = jail.create_dir("alice")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn create_dir<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath, JailError> {
+        let path = self.join(relative)?;
+        self.config().create_dir(&path, None)?;
+        Ok(JailedPath::new(self, path))
+    }
+
+    /// Create a directory and all missing parents inside the jail.
+    ///
+    /// Existing intermediates are reused. If an intermediate is swapped for
+    /// a symlink while directories are being created, it is only followed
+    /// when it still resolves to a directory inside the jail.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let dir = jail.create_dir_all("alice/2025/07")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn create_dir_all<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath, JailError> {
+        self.create_dirs(relative.as_ref(), None)
+    }
+
+    /// Like [`create_dir`](Self::create_dir), with explicit permission bits.
+    ///
+    /// The process umask still applies, as with [`DirBuilderExt::mode`].
+    #[cfg(unix)]
+    pub fn create_dir_with_mode<P: AsRef<Path>>(
+        &self,
+        relative: P,
+        mode: u32,
+    ) -> Result<JailedPath, JailError> {
+        let path = self.join(relative)?;
+        self.config().create_dir(&path, Some(mode))?;
+        Ok(JailedPath::new(self, path))
+    }
+
+    /// Like [`create_dir_all`](Self::create_dir_all), with explicit permission
+    /// bits for every directory created.
+    ///
+    /// The process umask still applies, as with [`DirBuilderExt::mode`].
+    #[cfg(unix)]
+    pub fn create_dir_all_with_mode<P: AsRef<Path>>(
+        &self,
+        relative: P,
+        mode: u32,
+    ) -> Result<JailedPath, JailError> {
+        self.create_dirs(relative.as_ref(), Some(mode))
+    }
+
+    /// Like [`subjail`](Self::subjail), creating the subdirectory and any
+    /// missing parents first with [`create_dir_all`](Self::create_dir_all).
+    pub fn create_subjail<P: AsRef<Path>>(&self, relative: P) -> Result<Jail, JailError> {
+        let dir = self.create_dir_all(relative)?;
+        // Already decoded/normalized: resolve, don't join
+        let path = self.resolve(dir.relative())?;
+        self.narrow(path)
+    }
+
+    /// Write a whole file inside the jail, creating or truncating it.
+    ///
+    /// With the `secure-open` feature on Unix the file is opened with
+    /// `O_NOFOLLOW` (see [`create_or_truncate`](Self::create_or_truncate));
+    /// otherwise this is [`join`](Self::join) followed by [`std::fs::write`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.write("alice/notes.txt", "hello")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        relative: P,
+        contents: C,
+    ) -> Result<(), JailError> {
+        self.config()
+            .check_file_size(contents.as_ref().len() as u64)?;
+        // An append-only jail may only create the file, never replace it
+        let append_only = self.config().is_append_only();
+        let refuse = |err: JailError| self.config().refuse_existing(relative.as_ref(), err);
+
+        #[cfg(all(feature = "secure-open", unix))]
+        {
+            use crate::open::Mode;
+
+            // Truncate only after the link check
+            let path = self.join(relative.as_ref())?;
+            let mode = if append_only {
+                Mode::CreateNew
+            } else {
+                Mode::Write
+            };
+            let mut file = self.open_validated(&path, mode).map_err(refuse)?;
+            self.config().check_links(&path, &file.metadata()?)?;
+            self.replace_contents(&mut file, contents.as_ref())
+        }
+
+        #[cfg(not(all(feature = "secure-open", unix)))]
+        {
+            // Truncate only after the link check
+            let path = self.join(relative.as_ref())?;
+            let mut options = fs::OpenOptions::new();
+            options.write(true).create(true).truncate(false);
+            #[cfg(unix)]
+            if let Some(mode) = self.config().file_mode() {
+                std::os::unix::fs::OpenOptionsExt::mode(&mut options, mode);
+            }
+            let track = self.config().tracks_creation();
+            let (mut file, created) = if append_only {
+                let file = options.create_new(true).open(&path);
+                (file.map_err(|err| refuse(err.into()))?, true)
+            } else {
+                open_creating(track, |exclusive| {
+                    if exclusive {
+                        options.clone().create_new(true).open(&path)
+                    } else {
+                        options.open(&path)
+                    }
+                })?
+            };
+            self.config().count_open();
+            if created && track {
+                self.config().created_file(&path, &file)?;
+            }
+            self.config().check_links(&path, &file.metadata()?)?;
+            self.replace_contents(&mut file, contents.as_ref())
+        }
+    }
+
+    /// Truncate an opened file and write `contents`, counting the change
+    /// against the quota first.
+    fn replace_contents(&self, file: &mut fs::File, contents: &[u8]) -> Result<(), JailError> {
+        use std::io::Write;
+
+        let old = file.metadata()?.len();
+        let new = contents.len() as u64;
+        self.config().resize(old, new)?;
+        let result = file.set_len(0).and_then(|()| file.write_all(contents));
+        if result.is_err() {
+            // Count what made it to disk instead
+            let written = file.metadata().map_or(0, |meta| meta.len());
+            let _ = self.config().resize(new, written);
+        }
+        Ok(result?)
+    }
+
+    /// Change the permissions of a file or directory inside the jail.
+    ///
+    /// With the `secure-open` feature on Unix the entry is opened with
+    /// `O_NOFOLLOW` and changed with `fchmod()` on the descriptor, so
+    /// swapping it for a symlink after validation makes the call fail
+    /// instead of changing the link's target. This needs read access to
+    /// the entry. Otherwise this is [`join`](Self::join) followed by
+    /// [`std::fs::set_permissions`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let mut perms = jail.metadata("alice/report.pdf")?.permissions();
+    /// perms.set_readonly(true);
+    /// jail.set_permissions("alice/report.pdf", perms)?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn set_permissions<P: AsRef<Path>>(
+        &self,
+        relative: P,
+        perms: fs::Permissions,
+    ) -> Result<(), JailError> {
+        #[cfg(all(feature = "secure-open", unix))]
+        {
+            let path = self.join(relative)?;
+            let file = self.open_validated(&path, crate::open::Mode::Read)?;
+            self.config().check_links(&path, &file.metadata()?)?;
+            file.set_permissions(perms)?;
+            Ok(())
+        }
+
+        #[cfg(not(all(feature = "secure-open", unix)))]
+        {
+            let path = self.join(relative)?;
+            self.config().check_links(&path, &fs::metadata(&path)?)?;
+            fs::set_permissions(&path, perms)?;
+            Ok(())
+        }
+    }
+
+    /// Set the permission bits of a file or directory inside the jail, as
+    /// [`set_permissions`](Self::set_permissions) with
+    /// [`PermissionsExt::from_mode`](std::os::unix::fs::PermissionsExt::from_mode).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.set_mode("alice/deploy.sh", 0o750)?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn set_mode<P: AsRef<Path>>(&self, relative: P, mode: u32) -> Result<(), JailError> {
+        use std::os::unix::fs::PermissionsExt;
+
+        self.set_permissions(relative, fs::Permissions::from_mode(mode))
+    }
+
+    /// Remove a file (or symlink) inside the jail.
+    ///
+    /// The parent directory is validated with [`join`](Self::join); the final
+    /// component is removed as-is and never followed, so removing a symlink
+    /// deletes the link, not its target.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.remove_file("alice/old.pdf")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn remove_file<P: AsRef<Path>>(&self, relative: P) -> Result<(), JailError> {
+        self.config().check_append_only(relative.as_ref())?;
+        let path = self.entry_path(relative.as_ref())?;
+        let size = self.quota_size(&path);
+        fs::remove_file(&path)?;
+        self.config().release(size);
+        self.forget_dirs(&path);
+        Ok(())
+    }
+
+    /// Remove an empty directory inside the jail.
+    ///
+    /// Like [`remove_file`](Self::remove_file), the final component is not
+    /// followed: a symlink to a directory is rejected rather than resolved.
+    /// The jail root itself cannot be removed.
+    pub fn remove_dir<P: AsRef<Path>>(&self, relative: P) -> Result<(), JailError> {
+        self.config().check_append_only(relative.as_ref())?;
+        let path = self.entry_path(relative.as_ref())?;
+        fs::remove_dir(&path)?;
+        self.forget_dirs(&path);
+        Ok(())
+    }
+
+    /// Recursively remove a directory and everything beneath it.
+    ///
+    /// The parent is validated with [`join`](Self::join). As with
+    /// [`std::fs::remove_dir_all`], a symlink in the final component is
+    /// removed itself rather than followed.
+    ///
+    /// With the `openat` feature on Unix, the deletion walks directory file
+    /// descriptors from the jail root using `openat(O_NOFOLLOW)` and
+    /// `unlinkat()`, so swapping any directory on the way for a symlink
+    /// (CVE-2022-21658 style) makes the call fail instead of deleting outside
+    /// the jail. Without it, the path to the directory is only checked at
+    /// validation time.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.remove_dir_all("alice/tmp")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn remove_dir_all<P: AsRef<Path>>(&self, relative: P) -> Result<(), JailError> {
+        self.config().check_append_only(relative.as_ref())?;
+        let path = self.entry_path(relative.as_ref())?;
+        let size = self.quota_size(&path);
+
+        #[cfg(all(feature = "openat", unix))]
+        {
+            let (dir, name) = self.entry_at(&path)?;
+            dir.remove_tree(&name)?;
+        }
+
+        #[cfg(not(all(feature = "openat", unix)))]
+        fs::remove_dir_all(&path)?;
+
+        self.config().release(size);
+        self.forget_dirs(&path);
+        Ok(())
+    }
+
+    /// Rename a file or directory within the jail.
+    ///
+    /// Both endpoints are validated like [`remove_file`](Self::remove_file):
+    /// parents must resolve inside the jail and the final components are
+    /// never followed, so renaming a symlink moves the link itself. Since both
+    /// paths are relative to this jail, a rename can never move an entry into
+    /// or out of another jail. An existing destination is replaced, as with
+    /// [`std::fs::rename`]; use [`rename_noreplace`](Self::rename_noreplace)
+    /// to avoid clobbering.
+    ///
+    /// With the `openat` feature on Unix, both parents are opened from the
+    /// jail root without following symlinks and the rename uses `renameat()`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.rename("incoming/upload.tmp", "alice/report.pdf")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(&self, from: P, to: Q) -> Result<(), JailError> {
+        self.config().check_append_only(from.as_ref())?;
+        let from = self.entry_path(from.as_ref())?;
+        let to = self.entry_path(to.as_ref())?;
+        self.rename_entry(&from, &to)
+    }
+
+    /// The move behind [`rename`](Self::rename), for entry paths already
+    /// validated.
+    pub(crate) fn rename_entry(&self, from: &Path, to: &Path) -> Result<(), JailError> {
+        let replaced = if from == to { 0 } else { self.quota_size(to) };
+
+        #[cfg(all(feature = "openat", unix))]
+        {
+            let (from_dir, from_name) = self.entry_at(from)?;
+            let (to_dir, to_name) = self.entry_at(to)?;
+            from_dir.rename(&from_name, &to_dir, &to_name)?;
+        }
+
+        #[cfg(not(all(feature = "openat", unix)))]
+        fs::rename(from, to)?;
+
+        self.config().release(replaced);
+        self.forget_dirs(from);
+        self.forget_dirs(to);
+        Ok(())
+    }
+
+    /// Like [`rename`](Self::rename), but fails with
+    /// [`io::ErrorKind::AlreadyExists`] instead of replacing the destination.
+    ///
+    /// The check is atomic: with the `openat` feature it uses
+    /// `renameat2(RENAME_NOREPLACE)` on Linux and `renameatx_np(RENAME_EXCL)`
+    /// on macOS. Otherwise files are moved by hard-linking to the destination
+    /// (which fails if it exists) and unlinking the source; directories are
+    /// not supported by that fallback.
+    pub fn rename_noreplace<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        from: P,
+        to: Q,
+    ) -> Result<(), JailError> {
+        self.config().check_append_only(from.as_ref())?;
+        let from = self.entry_path(from.as_ref())?;
+        let to = self.entry_path(to.as_ref())?;
+        self.rename_entry_noreplace(&from, &to)
+    }
+
+    /// The move behind [`rename_noreplace`](Self::rename_noreplace), for
+    /// entry paths already validated.
+    pub(crate) fn rename_entry_noreplace(&self, from: &Path, to: &Path) -> Result<(), JailError> {
+        #[cfg(all(feature = "openat", unix))]
+        {
+            let (from_dir, from_name) = self.entry_at(from)?;
+            let (to_dir, to_name) = self.entry_at(to)?;
+            from_dir.rename_noreplace(&from_name, &to_dir, &to_name)?;
+        }
+
+        #[cfg(not(all(feature = "openat", unix)))]
+        {
+            if fs::symlink_metadata(from)?.is_dir() {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "no-replace rename of directories requires the `openat` feature",
+                )
+                .into());
+            }
+            fs::hard_link(from, to)?;
+            fs::remove_file(from)?;
+        }
+
+        self.forget_dirs(from);
+        Ok(())
+    }
+
+    /// Create a hard link at `link` to the file at `original`.
+    ///
+    /// `original` is resolved with [`join`](Self::join), so the file being
+    /// linked is verified to be inside the jail; `link` is validated like
+    /// [`remove_file`](Self::remove_file) and must not exist.
+    ///
+    /// Hard links are invisible to path checks: if a file inside the jail is
+    /// already hard-linked from somewhere else (say an attacker linked
+    /// `/etc/shadow` into an upload directory), every new link extends access
+    /// to that outside file. On Unix the source is therefore rejected when it
+    /// already has more than one link.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// jail.hard_link("alice/report.pdf", "shared/report.pdf")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        original: P,
+        link: Q,
+    ) -> Result<(), JailError> {
+        let original = self.join(original)?;
+        let link = self.entry_path(link.as_ref())?;
+
+        let meta = fs::symlink_metadata(&original)?;
+        if meta.is_dir() {
+            return Err(JailError::InvalidPath(
+                "cannot hard link a directory".into(),
+            ));
+        }
+        #[cfg(unix)]
+        if std::os::unix::fs::MetadataExt::nlink(&meta) > 1 {
+            return Err(JailError::InvalidPath(format!(
+                "'{}' already has multiple hard links",
+                self.config().shown(&original).display()
+            )));
+        }
+        // Counted again, as a rescan would
+        let size = if meta.is_file() { meta.len() } else { 0 };
+        self.config().resize(0, size)?;
+
+        let linked = self.link_entry(&original, &link);
+        if linked.is_err() {
+            self.config().release(size);
+        }
+        linked
+    }
+
+    fn link_entry(&self, original: &Path, link: &Path) -> Result<(), JailError> {
+        #[cfg(all(feature = "openat", unix))]
+        {
+            let (from_dir, from_name) = self.entry_at(original)?;
+            let (to_dir, to_name) = self.entry_at(link)?;
+            from_dir.link(&from_name, &to_dir, &to_name)?;
+        }
+
+        #[cfg(not(all(feature = "openat", unix)))]
+        fs::hard_link(original, link)?;
+
+        Ok(())
+    }
+
+    /// Bytes the quota counts at or below `path`, without following a
+    /// symlink there; zero if the jail has no quota.
+    pub(crate) fn quota_size(&self, path: &Path) -> u64 {
+        if self.config().has_quota() {
+            crate::quota::usage(path).unwrap_or(0)
+        } else {
+            0
+        }
+    }
+
+    /// Create a symlink at `link` pointing to `target`.
+    ///
+    /// `target` is interpreted the way the OS will interpret it: relative
+    /// targets are resolved from the directory containing `link`, absolute
+    /// targets must lie under the jail root. Either way the result is
+    /// validated with [`join`](Self::join), so links escaping the jail (or
+    /// through another symlink that escapes) are rejected. The target is
+    /// stored exactly as given.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/build")?;
+    /// jail.symlink("../lib/libfoo.so.1", "out/libfoo.so")?;
+    ///
+    /// // Rejected: resolves outside the jail
+    /// assert!(jail.symlink("../../etc/passwd", "out/passwd").is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        target: P,
+        link: Q,
+    ) -> Result<(), JailError> {
+        self.create_symlink(target.as_ref(), link.as_ref(), false)
+    }
+
+    /// Like [`symlink`](Self::symlink), but rejects absolute targets.
+    ///
+    /// Relative links keep working when the jail directory is moved or
+    /// restored elsewhere, which absolute ones do not.
+    #[cfg(unix)]
+    pub fn symlink_relative<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        target: P,
+        link: Q,
+    ) -> Result<(), JailError> {
+        self.create_symlink(target.as_ref(), link.as_ref(), true)
+    }
+
+    #[cfg(unix)]
+    fn create_symlink(
+        &self,
+        target: &Path,
+        link: &Path,
+        relative_only: bool,
+    ) -> Result<(), JailError> {
+        let link_path = self.entry_path(link)?;
+        if self.config().denies_symlinks() {
+            return Err(JailError::SymlinkNotAllowed(link_path));
+        }
+        let escaped = || JailError::EscapedRoot {
+            attempted: target.to_path_buf(),
+            root: self.root().to_path_buf(),
+        };
+
+        // Express the target relative to the root and let join() decide
+        let resolved = if target.is_absolute() {
+            if relative_only {
+                return Err(JailError::InvalidPath(
+                    "absolute symlink targets not allowed".into(),
+                ));
+            }
+            self.strip_root(target).ok_or_else(escaped)?.to_path_buf()
+        } else {
+            let parent = link_path.parent().unwrap_or(self.root());
+            parent
+                .strip_prefix(self.root())
+                .map_err(|_| escaped())?
+                .join(target)
+        };
+        self.resolve(&resolved)?;
+
+        #[cfg(feature = "openat")]
+        {
+            let (dir, name) = self.entry_at(&link_path)?;
+            dir.symlink(target.as_os_str(), &name)?;
+        }
+
+        #[cfg(not(feature = "openat"))]
+        std::os::unix::fs::symlink(target, link_path)?;
+
+        Ok(())
+    }
+
+    /// Location of the directory entry named by `relative`, without
+    /// following a symlink in the final component.
+    ///
+    /// Operations that act on the entry itself (unlink, rename, link) use
+    /// this instead of [`join`](Self::join), which would resolve the target.
+    pub(crate) fn entry_path(&self, relative: &Path) -> Result<PathBuf, JailError> {
+        let relative = self.config().prepare(relative)?;
+        self.config().check_depth(&relative)?;
+        let name = match relative.components().next_back() {
+            Some(Component::Normal(name)) => name,
+            _ => {
+                return Err(JailError::InvalidPath(
+                    "path must name an entry inside the jail".into(),
+                ))
+            }
+        };
+        if contains_unit(name, b'\0') {
+            return Err(JailError::InvalidPath("null bytes not allowed".into()));
+        }
+        self.config().check_name(name)?;
+        let parent = self.resolve(relative.parent().unwrap_or(Path::new("")))?;
+        let path = parent.join(name);
+        self.config().check_len(&path)?;
+        if self.config().restricts_files() && !fs::symlink_metadata(&path).is_ok_and(|m| m.is_dir())
+        {
+            self.config().check_file(&path)?;
+        }
+        Ok(path)
+    }
+
+    fn create_dirs(&self, relative: &Path, mode: Option<u32>) -> Result<JailedPath, JailError> {
+        // join() resolves the existing prefix, so what's left to create
+        // is a plain sequence of names below a verified directory.
+        let target = self.join(relative)?;
+        let rest = target
+            .strip_prefix(self.root())
+            .map_err(|_| JailError::EscapedRoot {
+                attempted: relative.to_path_buf(),
+                root: self.root().to_path_buf(),
+            })?;
+
+        let mut current = self.root().to_path_buf();
+        for name in rest.iter() {
+            current.push(name);
+            current = self.ensure_dir(current, mode)?;
+        }
+        Ok(JailedPath::new(self, current))
+    }
+
+    /// Create `path` if missing and return the verified directory path.
+    fn ensure_dir(&self, path: PathBuf, mode: Option<u32>) -> Result<PathBuf, JailError> {
+        match self.config().create_dir(&path, mode) {
+            Ok(()) => return Ok(path),
+            Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err.into()),
+            Err(_) => {}
+        }
+
+        // Something is already there: a directory is fine, a symlink must
+        // still land inside the jail, anything else is an error.
+        let meta = fs::symlink_metadata(&path)?;
+        let resolved = if meta.file_type().is_symlink() {
+            if !path.exists() {
+                return Err(JailError::BrokenSymlink(path));
+            }
+            self.verify_inside(path)?
+        } else {
+            path
+        };
+        if !resolved.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "'{}' exists and is not a directory",
+                    self.config().shown(&resolved).display()
+                ),
+            )
+            .into());
+        }
+        Ok(resolved)
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Open the parent of an [`entry_path`](Self::entry_path) result as a
+    /// directory descriptor, walking from the jail root without following
+    /// symlinks, and return it with the entry's name.
+    #[cfg(all(feature = "openat", unix))]
+    pub(crate) fn entry_at(
+        &self,
+        path: &Path,
+    ) -> Result<(crate::dirfd::DirFd, std::ffi::OsString), JailError> {
+        use crate::dirfd::DirFd;
+
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Err(JailError::InvalidPath(
+                "path must name an entry inside the jail".into(),
+            ));
+        };
+        let parent = parent
+            .strip_prefix(self.root())
+            .map_err(|_| JailError::EscapedRoot {
+                attempted: path.to_path_buf(),
+                root: self.root().to_path_buf(),
+            })?;
+        let dir = DirFd::open(self.root())?.open_path(parent)?;
+        Ok((dir, name.to_os_string()))
+    }
+
+    /// Read the entire contents of a file inside the jail.
+    ///
+    /// With the `secure-open` feature on Unix the file is opened with
+    /// `O_NOFOLLOW` (see [`open`](Self::open)); otherwise this is
+    /// [`join`](Self::join) followed by [`std::fs::read`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let bytes = jail.read("alice/avatar.png")?;
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn read<P: AsRef<Path>>(&self, relative: P) -> Result<Vec<u8>, JailError> {
+        #[cfg(all(feature = "secure-open", unix))]
+        {
+            use std::io::Read;
+
+            let mut file = self.open(relative)?;
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)?;
+            Ok(contents)
+        }
+
+        #[cfg(not(all(feature = "secure-open", unix)))]
+        {
+            use std::io::Read;
+
+            let path = self.join(relative)?;
+            let mut file = fs::File::open(&path)?;
+            self.config().count_open();
+            self.config().check_links(&path, &file.metadata()?)?;
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)?;
+            Ok(contents)
+        }
+    }
+
+    /// Metadata of a path inside the jail, following symlinks like
+    /// [`join`](Self::join).
+    ///
+    /// Fails with [`JailError::HardLinked`] for a regular file with other
+    /// hard links when the jail was built with
+    /// [`reject_hard_links`](crate::JailBuilder::reject_hard_links).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let meta = jail.metadata("alice/report.pdf")?;
+    /// #[cfg(unix)]
+    /// if std::os::unix::fs::MetadataExt::nlink(&meta) > 1 {
+    ///     eprintln!("report.pdf is also linked elsewhere");
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn metadata<P: AsRef<Path>>(&self, relative: P) -> Result<fs::Metadata, JailError> {
+        let path = self.join(relative)?;
+        let meta = fs::metadata(&path)?;
+        self.config().check_links(&path, &meta)?;
+        Ok(meta)
+    }
+
+    /// Whether `relative` is a valid path inside the jail that exists.
+    ///
+    /// `false` both for paths that do not exist and for paths the jail
+    /// refuses, including broken symlinks; use
+    /// [`try_exists`](Self::try_exists) to tell them apart.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// if !jail.exists("alice/avatar.png") {
+    ///     jail.write("alice/avatar.png", b"")?;
+    /// }
+    /// assert!(!jail.exists("../etc/passwd"));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn exists<P: AsRef<Path>>(&self, relative: P) -> bool {
+        self.try_exists(relative).unwrap_or(false)
+    }
+
+    /// Whether `relative` exists inside the jail, failing if the jail
+    /// refuses the path or its existence cannot be determined.
+    ///
+    /// The path is validated like [`join`](Self::join), so a symlink
+    /// leading outside is an error rather than `true`. The result of the
+    /// validation is then looked up without following it, so a final
+    /// component replaced by a symlink since then is not followed out of
+    /// the jail either.
+    pub fn try_exists<P: AsRef<Path>>(&self, relative: P) -> Result<bool, JailError> {
+        let path = self.join(relative)?;
+        match fs::symlink_metadata(&path) {
+            Ok(_) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Read the entire contents of a file inside the jail into a string.
+    ///
+    /// Like [`read`](Self::read), but fails if the contents are not UTF-8.
+    pub fn read_to_string<P: AsRef<Path>>(&self, relative: P) -> Result<String, JailError> {
+        let contents = self.read(relative)?;
+        String::from_utf8(contents)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err).into())
+    }
+}
+
+/// Shorthands for running [`Jail`] operations on a path's own jail.
+impl<A: Access> JailedPath<A> {
+    /// Read this file. See [`Jail::read`].
+    pub fn read(&self) -> Result<Vec<u8>, JailError> {
+        self.jail().verbatim().read(self.relative())
+    }
+
+    /// Read this file as UTF-8. See [`Jail::read_to_string`].
+    pub fn read_to_string(&self) -> Result<String, JailError> {
+        self.jail().verbatim().read_to_string(self.relative())
+    }
+
+    /// List this directory. See [`Jail::read_dir`].
+    pub fn read_dir(&self) -> Result<crate::ReadDir<'_, A>, JailError> {
+        let dir = self.jail().resolve(self.relative())?;
+        crate::ReadDir::new(self.jail(), &dir)
+    }
+}
+
+impl JailedPath {
+    /// Write this file. See [`Jail::write`].
+    pub fn write<C: AsRef<[u8]>>(&self, contents: C) -> Result<(), JailError> {
+        self.jail().verbatim().write(self.relative(), contents)
+    }
+
+    /// Remove this file. See [`Jail::remove_file`].
+    pub fn remove_file(&self) -> Result<(), JailError> {
+        self.jail().verbatim().remove_file(self.relative())
+    }
+
+    /// Remove this empty directory. See [`Jail::remove_dir`].
+    pub fn remove_dir(&self) -> Result<(), JailError> {
+        self.jail().verbatim().remove_dir(self.relative())
+    }
+
+    /// Remove this directory and its contents. See [`Jail::remove_dir_all`].
+    pub fn remove_dir_all(&self) -> Result<(), JailError> {
+        self.jail().verbatim().remove_dir_all(self.relative())
+    }
+}
+
+/// Open a file that is created if missing, with `open` passed whether to
+/// create it exclusively, and tell whether this call created it.
+///
+/// Only when `track` is set is that known for sure: then an exclusive
+/// create is tried first, so the answer costs a second open for files
+/// that exist.
+pub(crate) fn open_creating<F>(track: bool, open: F) -> io::Result<(fs::File, bool)>
+where
+    F: Fn(bool) -> io::Result<fs::File>,
+{
+    if !track {
+        return Ok((open(false)?, false));
+    }
+    match open(true) {
+        Ok(file) => Ok((file, true)),
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => Ok((open(false)?, false)),
+        Err(err) => Err(err),
+    }
+}
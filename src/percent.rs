@@ -0,0 +1,64 @@
+//! Percent-decoding of untrusted path input.
+
+use crate::JailError;
+use std::path::{Path, PathBuf};
+
+/// Decode `%XX` escapes once. Returns `None` for a malformed escape.
+pub(crate) fn decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut bytes = input.iter();
+    while let Some(&b) = bytes.next() {
+        if b == b'%' {
+            let hi = hex(*bytes.next()?)?;
+            let lo = hex(*bytes.next()?)?;
+            output.push(hi << 4 | lo);
+        } else {
+            output.push(b);
+        }
+    }
+    Some(output)
+}
+
+fn hex(b: u8) -> Option<u8> {
+    (b as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Whether `bytes` still holds an escape for `.`, `/`, `\`, `%` or NUL,
+/// i.e. the original input was encoded twice.
+fn has_encoded_traversal(bytes: &[u8]) -> bool {
+    bytes.windows(3).any(|w| {
+        w[0] == b'%'
+            && matches!(
+                hex(w[1]).zip(hex(w[2])).map(|(hi, lo)| hi << 4 | lo),
+                Some(b'.' | b'/' | b'\\' | b'%' | 0)
+            )
+    })
+}
+
+/// Decode a path once, rejecting malformed or double-encoded input.
+pub(crate) fn decode_path(path: &Path) -> Result<PathBuf, JailError> {
+    #[cfg(unix)]
+    let bytes = std::os::unix::ffi::OsStrExt::as_bytes(path.as_os_str());
+    #[cfg(not(unix))]
+    let bytes = path
+        .to_str()
+        .ok_or_else(|| JailError::InvalidPath("path is not valid UTF-8".into()))?
+        .as_bytes();
+
+    let decoded =
+        decode(bytes).ok_or_else(|| JailError::InvalidPath("malformed percent-encoding".into()))?;
+    if has_encoded_traversal(&decoded) {
+        return Err(JailError::InvalidPath(
+            "double percent-encoding not allowed".into(),
+        ));
+    }
+
+    #[cfg(unix)]
+    return Ok(PathBuf::from(
+        <std::ffi::OsString as std::os::unix::ffi::OsStringExt>::from_vec(decoded),
+    ));
+    #[cfg(not(unix))]
+    String::from_utf8(decoded)
+        .map(PathBuf::from)
+        .map_err(|_| JailError::InvalidPath("decoded path is not valid UTF-8".into()))
+}
@@ -0,0 +1,144 @@
+//! Permission and ownership checks on the jail root
+//! (`JailBuilder::require_secure_root`, `JailBuilder::require_root_owner`),
+//! and the permissions of entries the jail creates (`JailBuilder::file_mode`,
+//! `JailBuilder::dir_mode`, `JailBuilder::on_create`).
+//!
+//! Anyone who can write to the root, or to a directory above it, can swap
+//! a component for a symlink between validation and use. Refusing such
+//! roots up front keeps that race available only to the owner.
+
+use crate::JailError;
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+#[cfg(unix)]
+extern "C" {
+    // Provided by the C library std already links against
+    fn getegid() -> u32;
+    fn geteuid() -> u32;
+}
+
+/// Reject `root` if it or any directory above it is world-writable
+/// without the sticky bit, or group-writable by a group other than the
+/// process's effective group. Always passes outside Unix.
+pub(crate) fn check_root(root: &Path) -> Result<(), JailError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        // SAFETY: getegid cannot fail and has no preconditions
+        let egid = unsafe { getegid() };
+        for dir in root.ancestors() {
+            let meta = dir.metadata()?;
+            let mode = meta.mode();
+            let reason = if mode & 0o002 != 0 && mode & 0o1000 == 0 {
+                "world-writable without the sticky bit".to_owned()
+            } else if mode & 0o020 != 0 && mode & 0o1000 == 0 && meta.gid() != egid {
+                format!("writable by group {}", meta.gid())
+            } else {
+                continue;
+            };
+            return Err(JailError::InsecureRoot {
+                path: dir.to_path_buf(),
+                reason,
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = root;
+    Ok(())
+}
+
+/// Reject `root` unless it is owned by the effective user or one of
+/// `allowed`. With `ancestors`, every directory above it must be too,
+/// where root-owned (uid 0) directories also pass, as OpenSSH requires
+/// for `~/.ssh`. Always passes outside Unix.
+pub(crate) fn check_owner(root: &Path, allowed: &[u32], ancestors: bool) -> Result<(), JailError> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        // SAFETY: geteuid cannot fail and has no preconditions
+        let euid = unsafe { geteuid() };
+        for (depth, dir) in root.ancestors().enumerate() {
+            if depth > 0 && !ancestors {
+                break;
+            }
+            let uid = dir.metadata()?.uid();
+            if uid == euid || allowed.contains(&uid) || (depth > 0 && uid == 0) {
+                continue;
+            }
+            return Err(JailError::UntrustedOwner {
+                path: dir.to_path_buf(),
+                uid,
+            });
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = (root, allowed, ancestors);
+    Ok(())
+}
+
+type Hook = dyn Fn(&Path) -> io::Result<()> + Send + Sync;
+
+/// The hook set with [`JailBuilder::on_create`](crate::JailBuilder::on_create),
+/// shared by every clone of a jail.
+#[derive(Clone)]
+pub(crate) struct CreateHook(Arc<Hook>);
+
+impl CreateHook {
+    pub(crate) fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&Path) -> io::Result<()> + Send + Sync + 'static,
+    {
+        Self(Arc::new(hook))
+    }
+
+    pub(crate) fn call(&self, path: &Path) -> io::Result<()> {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for CreateHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CreateHook(..)")
+    }
+}
+
+// Same rule as for `RejectHook`: only the same closure is the same option
+impl PartialEq for CreateHook {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CreateHook {}
+
+/// Set the permission bits of an open file or directory exactly, past the
+/// umask. Through the handle, so a swapped path cannot redirect it.
+#[cfg(unix)]
+pub(crate) fn set_mode(file: &std::fs::File, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    file.set_permissions(std::fs::Permissions::from_mode(mode))
+}
+
+/// Open the directory just created at `path` to change its mode, without
+/// following a symlink swapped in for it where the platform allows.
+#[cfg(unix)]
+pub(crate) fn open_dir(path: &Path) -> io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.read(true);
+    #[cfg(feature = "secure-open")]
+    std::os::unix::fs::OpenOptionsExt::custom_flags(&mut options, crate::open::O_NOFOLLOW);
+    let dir = options.open(path)?;
+    if !dir.metadata()?.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("'{}' is no longer a directory", path.display()),
+        ));
+    }
+    Ok(dir)
+}
@@ -0,0 +1,137 @@
+//! Python bindings (`pyo3` feature).
+
+use crate::{Jail, JailError};
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::path::PathBuf;
+
+create_exception!(
+    path_jail,
+    PyJailError,
+    PyValueError,
+    "A path was rejected by the jail."
+);
+
+impl From<JailError> for PyErr {
+    fn from(err: JailError) -> Self {
+        match err {
+            JailError::Io(err) => err.into(),
+            err => PyJailError::new_err(err.to_string()),
+        }
+    }
+}
+
+/// A jail for Python, wrapping [`Jail`].
+#[pyclass(name = "Jail", module = "path_jail", frozen)]
+struct PyJail {
+    inner: Jail,
+}
+
+#[pymethods]
+impl PyJail {
+    #[new]
+    fn new(root: PathBuf) -> PyResult<Self> {
+        Ok(Self {
+            inner: Jail::new(root)?,
+        })
+    }
+
+    #[getter]
+    fn root(&self) -> PathBuf {
+        self.inner.root().to_path_buf()
+    }
+
+    fn join(&self, relative: PathBuf) -> PyResult<PathBuf> {
+        Ok(self.inner.join(relative)?)
+    }
+
+    fn contains(&self, absolute: PathBuf) -> PyResult<PathBuf> {
+        Ok(self.inner.contains(absolute)?)
+    }
+
+    fn relative(&self, absolute: PathBuf) -> PyResult<PathBuf> {
+        Ok(self.inner.relative(absolute)?)
+    }
+
+    fn __contains__(&self, absolute: PathBuf) -> bool {
+        self.inner.contains(absolute).is_ok()
+    }
+
+    /// Open with `O_NOFOLLOW` and return a regular Python file object.
+    #[cfg(unix)]
+    #[pyo3(signature = (relative, mode = "r"))]
+    fn open<'py>(
+        &self,
+        py: Python<'py>,
+        relative: PathBuf,
+        mode: &str,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        use std::os::fd::IntoRawFd;
+
+        // Checked first so that `fdopen` cannot fail and leak the fd
+        let file = match mode {
+            "r" | "rb" => self.inner.open(relative)?,
+            "w" | "wb" => self.inner.create_or_truncate(relative)?,
+            "x" | "xb" => self.inner.create(relative)?,
+            "a" | "ab" => self.inner.open_append(relative)?,
+            _ => {
+                return Err(PyValueError::new_err(format!(
+                    "invalid mode '{mode}': expected one of r, w, x, a, optionally with b"
+                )))
+            }
+        };
+        let fd = file.into_inner().into_raw_fd();
+        py.import("os")?.call_method1("fdopen", (fd, mode))
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Jail({:?})", self.inner.root())
+    }
+}
+
+/// [`crate::join`] for Python.
+#[pyfunction]
+fn join(root: PathBuf, path: PathBuf) -> PyResult<PathBuf> {
+    Ok(crate::join(root, path)?)
+}
+
+/// Add `Jail`, `join` and `JailError` to a Python module (`pyo3` feature).
+///
+/// Rust and Python code handling the same files then enforce the same
+/// rules. `Jail(root)` has `root`, `join()`, `contains()`, `relative()`,
+/// `path in jail`, and on Unix `open(path, mode="r")`, which opens like
+/// [`Jail::open`] (or `create`, `create_or_truncate`, `open_append` for
+/// `x`, `w`, `a`) and returns an ordinary file object. Rejections raise
+/// `path_jail.JailError`, a `ValueError`; I/O failures raise the matching
+/// `OSError`. Paths are accepted as `str` or `os.PathLike` and returned as
+/// `pathlib.Path`.
+///
+/// Build the extension from a `cdylib` crate with pyo3's
+/// `extension-module` feature, for example with maturin.
+///
+/// # Example
+///
+/// ```no_run
+/// use pyo3::prelude::*;
+///
+/// #[pymodule]
+/// #[pyo3(name = "path_jail")]
+/// fn init(m: &Bound<'_, PyModule>) -> PyResult<()> {
+///     path_jail::register_python(m)
+/// }
+/// ```
+///
+/// ```python
+/// from path_jail import Jail, JailError
+///
+/// jail = Jail("/var/uploads")
+/// with jail.open("report.pdf", "rb") as f:
+///     data = f.read()
+/// ```
+pub fn register_python(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyJail>()?;
+    module.add_function(wrap_pyfunction!(join, module)?)?;
+    module.add("JailError", module.py().get_type::<PyJailError>())?;
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Storage quota ([`JailBuilder::quota`](crate::JailBuilder::quota)).
+
+use crate::{Access, Jail, JailError};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// A snapshot of a jail's storage use, from [`Jail::quota`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct QuotaUsage {
+    /// The budget set with [`JailBuilder::quota`](crate::JailBuilder::quota),
+    /// in bytes.
+    pub limit: u64,
+    /// Bytes of regular files in the jail, as counted at build time and
+    /// adjusted for every write and removal through the jail since.
+    pub used: u64,
+}
+
+impl QuotaUsage {
+    /// Bytes that can still be written.
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.used)
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Current storage use against the
+    /// [`quota`](crate::JailBuilder::quota), or `None` if the jail has no
+    /// quota.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::builder("/var/uploads/alice")
+    ///     .quota(100 * 1024 * 1024)
+    ///     .build()?;
+    /// if let Some(usage) = jail.quota() {
+    ///     println!("{} of {} bytes left", usage.remaining(), usage.limit);
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn quota(&self) -> Option<QuotaUsage> {
+        self.config().quota().map(Quota::usage)
+    }
+
+    /// Recount the bytes in the jail from disk, after files were written
+    /// or removed without going through it. Does nothing if the jail has
+    /// no quota.
+    ///
+    /// The count always covers the root the quota was set on, also when
+    /// called on a sub-jail.
+    pub fn rescan_quota(&self) -> Result<(), JailError> {
+        if let Some(quota) = self.config().quota() {
+            quota.scan(self.root())?;
+        }
+        Ok(())
+    }
+}
+
+/// The budget and running count behind [`QuotaUsage`], shared by every
+/// clone of a jail, its sub-jails and read-only views.
+#[derive(Debug, Clone)]
+pub(crate) struct Quota {
+    limit: u64,
+    shared: Arc<Shared>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    used: AtomicU64,
+    /// The root of the jail the quota was built with.
+    root: OnceLock<PathBuf>,
+}
+
+impl Quota {
+    pub(crate) fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            shared: Arc::default(),
+        }
+    }
+
+    fn usage(&self) -> QuotaUsage {
+        QuotaUsage {
+            limit: self.limit,
+            used: self.shared.used.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Count the bytes under the quota's root, which is `root` if the
+    /// quota has none yet.
+    pub(crate) fn scan(&self, root: &Path) -> io::Result<()> {
+        let root = self.shared.root.get_or_init(|| root.to_path_buf());
+        self.shared.used.store(usage(root)?, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Account for `bytes` more, or fail without counting them if that
+    /// would go over the limit.
+    pub(crate) fn reserve(&self, bytes: u64) -> Result<(), JailError> {
+        if bytes == 0 {
+            return Ok(());
+        }
+        self.shared
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(bytes).filter(|&total| total <= self.limit)
+            })
+            .map(drop)
+            .map_err(|used| JailError::QuotaExceeded {
+                limit: self.limit,
+                used,
+                requested: bytes,
+            })
+    }
+
+    /// Account for `bytes` freed.
+    pub(crate) fn release(&self, bytes: u64) {
+        let _ = self
+            .shared
+            .used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                Some(used.saturating_sub(bytes))
+            });
+    }
+
+    /// Account for a file going from `old` to `new` bytes.
+    pub(crate) fn resize(&self, old: u64, new: u64) -> Result<(), JailError> {
+        if new > old {
+            self.reserve(new - old)
+        } else {
+            self.release(old - new);
+            Ok(())
+        }
+    }
+}
+
+// Jails compare by options; the count is not an option
+impl PartialEq for Quota {
+    fn eq(&self, other: &Self) -> bool {
+        self.limit == other.limit
+    }
+}
+
+impl Eq for Quota {}
+
+/// Total size of the regular files at or below `path`, without following
+/// symlinks. Entries removed while counting are skipped.
+pub(crate) fn usage(path: &Path) -> io::Result<u64> {
+    let meta = match fs::symlink_metadata(path) {
+        Ok(meta) => meta,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    if meta.is_file() {
+        return Ok(meta.len());
+    }
+    if !meta.is_dir() {
+        return Ok(0);
+    }
+    let mut total = 0u64;
+    for entry in fs::read_dir(path)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(err),
+        };
+        total = total.saturating_add(usage(&entry.path())?);
+    }
+    Ok(total)
+}
@@ -0,0 +1,175 @@
+//! Directory listing that yields [`JailedPath`] entries.
+
+use crate::jail::Disk;
+use crate::{Access, Jail, JailError, JailedPath, ReadWrite};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, FileType};
+use std::path::Path;
+
+/// An entry returned by [`Jail::read_dir`].
+///
+/// Unlike [`std::fs::DirEntry`], the path is a [`JailedPath`]: symlinked
+/// entries have already been resolved and verified to stay inside the jail.
+#[derive(Debug, Clone)]
+pub struct DirEntry<A: Access = ReadWrite> {
+    path: JailedPath<A>,
+    file_name: OsString,
+    file_type: FileType,
+}
+
+impl<A: Access> DirEntry<A> {
+    /// Returns the verified path of this entry.
+    ///
+    /// For symlinks this is the resolved target, matching what
+    /// [`Jail::join_typed`] would return for the same entry.
+    #[inline]
+    pub fn path(&self) -> &JailedPath<A> {
+        &self.path
+    }
+
+    /// Consumes the entry and returns its verified path.
+    #[inline]
+    pub fn into_path(self) -> JailedPath<A> {
+        self.path
+    }
+
+    /// Returns the bare file name of this entry as listed in the directory.
+    #[inline]
+    pub fn file_name(&self) -> &OsStr {
+        &self.file_name
+    }
+
+    /// Returns the file type of the entry itself (symlinks are not followed).
+    #[inline]
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+}
+
+/// Iterator over the entries of a directory inside a [`Jail`].
+///
+/// Created by [`Jail::read_dir`]. Entries that cannot be verified (symlinks
+/// escaping the jail, broken symlinks) are yielded as errors; iteration
+/// continues with the next entry. Entries whose names the jail refuses
+/// ([`deny_hidden`](crate::JailBuilder::deny_hidden),
+/// [`deny`](crate::JailBuilder::deny)), and files without one of the
+/// [`allowed_extensions`](crate::JailBuilder::allowed_extensions), are left
+/// out, as if they were not there.
+#[derive(Debug)]
+pub struct ReadDir<'a, A: Access = ReadWrite> {
+    jail: &'a Jail<A>,
+    inner: fs::ReadDir,
+}
+
+impl<A: Access> Iterator for ReadDir<'_, A> {
+    type Item = Result<DirEntry<A>, JailError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.inner.next()? {
+                Ok(entry) => entry,
+                Err(err) => return Some(Err(err.into())),
+            };
+            if let Some(result) = verify_entry(self.jail, entry).transpose() {
+                return Some(result);
+            }
+        }
+    }
+}
+
+impl<'a, A: Access> ReadDir<'a, A> {
+    /// List an already-verified directory.
+    pub(crate) fn new(jail: &'a Jail<A>, dir: &Path) -> Result<Self, JailError> {
+        Ok(Self {
+            jail,
+            inner: fs::read_dir(dir)?,
+        })
+    }
+}
+
+/// Verify a raw directory entry the same way [`Jail::join`] would.
+/// `None` if the jail's policy leaves it out of listings.
+pub(crate) fn verify_entry<A: Access>(
+    jail: &Jail<A>,
+    entry: fs::DirEntry,
+) -> Result<Option<DirEntry<A>>, JailError> {
+    let file_name = entry.file_name();
+    if jail.config().check_name(&file_name).is_err() {
+        return Ok(None);
+    }
+    let file_type = entry.file_type()?;
+    let path = entry.path();
+
+    // Regular entries live in an already-verified directory.
+    // Symlinks must be resolved, exactly as join() would.
+    let path = if file_type.is_symlink() {
+        if jail.config().denies_symlinks() {
+            return Err(JailError::SymlinkNotAllowed(path));
+        }
+        if !path.exists() {
+            return Err(JailError::BrokenSymlink(path));
+        }
+        jail.verify_inside(path)?
+    } else {
+        if file_type.is_dir() {
+            jail.config().check_device(&Disk, &path)?;
+        }
+        path
+    };
+    if !is_listed(jail, &path) {
+        return Ok(None);
+    }
+
+    Ok(Some(DirEntry {
+        path: JailedPath::new(jail, path),
+        file_name,
+        file_type,
+    }))
+}
+
+/// Whether the jail's policy lets the verified `path` be listed, as
+/// [`Jail::join`] would let it through. Names are checked below the root,
+/// since a symlink may lead to one the listing never showed.
+pub(crate) fn is_listed<A: Access>(jail: &Jail<A>, path: &Path) -> bool {
+    let config = jail.config();
+    if config.restricts_files() && !path.is_dir() && config.check_file(path).is_err() {
+        return false;
+    }
+    if !config.denies_names() {
+        return true;
+    }
+    // Through an allowed outside target there is nothing below the root
+    // to check beyond the entry's own name
+    match jail.strip_root(path) {
+        Some(rest) => rest.iter().all(|name| config.check_name(name).is_ok()),
+        None => true,
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// List a directory inside the jail.
+    ///
+    /// The directory itself is validated with [`join`](Self::join). Each entry
+    /// is yielded as a [`DirEntry`] carrying a [`JailedPath`]; symlinked
+    /// entries are resolved and rejected with [`JailError::EscapedRoot`] or
+    /// [`JailError::BrokenSymlink`] if they cannot be verified.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// for entry in jail.read_dir("alice")? {
+    ///     let entry = entry?;
+    ///     if entry.file_type().is_file() {
+    ///         println!("{}", entry.path());
+    ///     }
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn read_dir<P: AsRef<Path>>(&self, relative: P) -> Result<ReadDir<'_, A>, JailError> {
+        let dir = self.join(relative)?;
+        ReadDir::new(self, &dir)
+    }
+}
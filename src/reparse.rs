@@ -0,0 +1,67 @@
+//! NTFS junction and volume mount point detection (Windows only).
+//!
+//! Junctions are the usual way to point a directory outside a tree on
+//! Windows (they need no privilege, unlike symlinks), and `std` does not
+//! report them consistently through `is_symlink()`. Components are
+//! classified by their reparse tag instead.
+
+#![cfg(windows)]
+
+use std::ffi::c_void;
+use std::mem::MaybeUninit;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::fs::MetadataExt;
+use std::path::Path;
+
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+// Shared by junctions and volume mount points
+const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+const INVALID_HANDLE_VALUE: isize = -1;
+
+// WIN32_FIND_DATAW
+#[repr(C)]
+#[allow(dead_code)]
+struct FindData {
+    file_attributes: u32,
+    creation_time: [u32; 2],
+    last_access_time: [u32; 2],
+    last_write_time: [u32; 2],
+    file_size_high: u32,
+    file_size_low: u32,
+    // The reparse tag when FILE_ATTRIBUTE_REPARSE_POINT is set
+    reserved0: u32,
+    reserved1: u32,
+    file_name: [u16; 260],
+    alternate_file_name: [u16; 14],
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn FindFirstFileW(name: *const u16, data: *mut FindData) -> *mut c_void;
+    fn FindClose(handle: *mut c_void) -> i32;
+}
+
+/// Whether `path` itself (not its target) is a junction or mount point.
+pub(crate) fn is_junction(path: &Path) -> bool {
+    let is_reparse_point = path
+        .symlink_metadata()
+        .is_ok_and(|m| m.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0);
+    if !is_reparse_point {
+        return false;
+    }
+
+    let name: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut data = MaybeUninit::<FindData>::zeroed();
+    // SAFETY: `name` is NUL-terminated and `data` is valid for writes
+    let handle = unsafe { FindFirstFileW(name.as_ptr(), data.as_mut_ptr()) };
+    if handle as isize == INVALID_HANDLE_VALUE {
+        return false;
+    }
+    // SAFETY: `handle` came from a successful FindFirstFileW, which also
+    // filled in `data`
+    let data = unsafe {
+        FindClose(handle);
+        data.assume_init()
+    };
+    data.reserved0 == IO_REPARSE_TAG_MOUNT_POINT
+}
@@ -0,0 +1,130 @@
+//! Turning user-supplied file names into safe single components.
+
+use crate::{Access, Jail, JailError};
+use std::path::{Path, PathBuf};
+
+/// Longest name most filesystems accept (`NAME_MAX`, in bytes).
+const MAX_NAME_LEN: usize = 255;
+
+/// Extensions longer than this are not kept when a name is truncated.
+const MAX_KEPT_EXTENSION: usize = 16;
+
+/// Turn a user-supplied file name into a single component that is safe on
+/// every platform.
+///
+/// - `/`, `\`, control characters and the characters Windows forbids in
+///   names (`<>:"|?*`) are replaced with `_`
+/// - leading dots are removed, so the result is never `..` or a hidden file
+/// - trailing dots and spaces are removed (Windows would drop them)
+/// - Windows device names (`CON`, `NUL.txt`, `COM1`, ...) get a `_` prefix
+/// - names longer than 255 bytes are shortened, keeping the extension
+///
+/// The result can be passed to [`Jail::join`] as-is; it still has to be
+/// joined, since sanitizing says nothing about symlinks already in the
+/// jail. Fails with [`JailError::InvalidPath`] if nothing usable is left
+/// (e.g. for `""` or `"..."`).
+///
+/// # Example
+///
+/// ```
+/// use path_jail::sanitize_file_name;
+///
+/// assert_eq!(sanitize_file_name("../../etc/passwd")?, "_.._etc_passwd");
+/// assert_eq!(sanitize_file_name("report: Q1?.pdf")?, "report_ Q1_.pdf");
+/// assert_eq!(sanitize_file_name(".env")?, "env");
+/// assert_eq!(sanitize_file_name("CON.txt")?, "_CON.txt");
+/// assert!(sanitize_file_name("..").is_err());
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+pub fn sanitize_file_name(name: &str) -> Result<String, JailError> {
+    let replaced: String = name
+        .chars()
+        .map(|c| if is_forbidden(c) { '_' } else { c })
+        .collect();
+    let mut name = trim(&replaced);
+    if name.len() > MAX_NAME_LEN {
+        // Cutting can leave just the extension, or a device name
+        name = trim(&truncate(&name));
+    }
+    if is_device_name(&name) {
+        name.insert(0, '_');
+        if name.len() > MAX_NAME_LEN {
+            name = truncate(&name);
+        }
+    }
+    if name.is_empty() {
+        return Err(JailError::InvalidPath(
+            "file name is empty after sanitization".into(),
+        ));
+    }
+    Ok(name)
+}
+
+/// Remove leading dots and trailing dots and spaces.
+fn trim(name: &str) -> String {
+    name.trim_start_matches('.')
+        .trim_end_matches(['.', ' '])
+        .to_owned()
+}
+
+fn is_forbidden(c: char) -> bool {
+    c.is_control() || matches!(c, '/' | '\\' | '<' | '>' | ':' | '"' | '|' | '?' | '*')
+}
+
+/// Whether Windows treats `name` as a device, with or without extension.
+fn is_device_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    let upper = stem.to_ascii_uppercase();
+    match upper.as_str() {
+        "CON" | "PRN" | "AUX" | "NUL" | "CONIN$" | "CONOUT$" => true,
+        _ => {
+            let Some(rest) = upper.strip_prefix("COM").or(upper.strip_prefix("LPT")) else {
+                return false;
+            };
+            let mut digits = rest.chars();
+            matches!(digits.next(), Some('0'..='9' | '¹' | '²' | '³')) && digits.next().is_none()
+        }
+    }
+}
+
+/// Shorten `name` to [`MAX_NAME_LEN`] bytes on a character boundary,
+/// keeping a short extension.
+fn truncate(name: &str) -> String {
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 && name.len() - dot <= MAX_KEPT_EXTENSION + 1 => name.split_at(dot),
+        _ => (name, ""),
+    };
+    let mut end = MAX_NAME_LEN - ext.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    let stem = stem[..end].trim_end_matches(['.', ' ']);
+    format!("{}{}", stem, ext)
+}
+
+impl<A: Access> Jail<A> {
+    /// Sanitize a user-supplied file name with [`sanitize_file_name`] and
+    /// join it to the root.
+    ///
+    /// For upload handlers that want to accept any name rather than reject
+    /// odd ones. Builder options apply in the usual order: the name is
+    /// percent-decoded and normalized first, then sanitized, then
+    /// validated like [`join`](Self::join) (deny rules, allowed extensions
+    /// and limits included).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let path = jail.join_sanitized("../../etc/passwd")?;
+    /// assert_eq!(path, jail.root().join("_.._etc_passwd"));
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn join_sanitized(&self, name: &str) -> Result<PathBuf, JailError> {
+        let decoded = self.config().decode(Path::new(name))?;
+        let name = sanitize_file_name(&decoded.to_string_lossy())?;
+        self.resolve(Path::new(&name))
+    }
+}
@@ -0,0 +1,91 @@
+//! A per-thread default jail ([`ScopedJail`]).
+
+use crate::{Access, Jail, JailError, ReadOnly};
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    // Installed jails with the id of their guard, innermost last
+    static CURRENT: RefCell<Vec<(u64, Jail<ReadOnly>)>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Installs a jail as the current thread's default until dropped.
+///
+/// For code deep in a call stack, such as template helpers or plugin
+/// hooks, that has to validate paths but cannot be handed a `&Jail`.
+/// [`current_jail_join`] and [`current_jail`] use the innermost guard
+/// alive on the calling thread. Guards nest; dropping one uninstalls only
+/// its own jail, even if guards created after it are still alive.
+///
+/// The default is per thread, so work moved to another thread does not
+/// see it. The guard is neither `Send` nor `Sync`, which also keeps it
+/// out of futures that may move between threads across an `.await`.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{current_jail_join, Jail, ScopedJail};
+///
+/// fn include(name: &str) -> Result<String, path_jail::JailError> {
+///     let path = current_jail_join(name)?;
+///     Ok(std::fs::read_to_string(path)?)
+/// }
+///
+/// let jail = Jail::new("/srv/templates")?;
+/// let _scope = ScopedJail::new(&jail);
+/// include("header.html")?;
+/// assert!(include("../../etc/passwd").is_err());
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug)]
+#[must_use = "the jail is uninstalled when the guard is dropped"]
+pub struct ScopedJail {
+    id: u64,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl ScopedJail {
+    /// Install `jail` as the current thread's default.
+    ///
+    /// Only validation goes through the default, so the jail is held as
+    /// [`read_only`](Jail::read_only) whatever its access.
+    pub fn new<A: Access>(jail: &Jail<A>) -> Self {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        CURRENT.with(|current| current.borrow_mut().push((id, jail.with_access())));
+        Self {
+            id,
+            _not_send: PhantomData,
+        }
+    }
+}
+
+impl Drop for ScopedJail {
+    fn drop(&mut self) {
+        CURRENT.with(|current| {
+            let mut current = current.borrow_mut();
+            // Usually the last entry, unless guards are dropped out of order
+            if let Some(index) = current.iter().rposition(|(id, _)| *id == self.id) {
+                current.remove(index);
+            }
+        });
+    }
+}
+
+/// The jail installed by the innermost live [`ScopedJail`] on this thread.
+pub fn current_jail() -> Option<Jail<ReadOnly>> {
+    CURRENT.with(|current| current.borrow().last().map(|(_, jail)| jail.clone()))
+}
+
+/// [`Jail::join`] against the [current jail](current_jail).
+///
+/// # Errors
+///
+/// [`JailError::NoCurrentJail`] if no [`ScopedJail`] is alive on this
+/// thread, otherwise whatever `join` returns.
+pub fn current_jail_join<P: AsRef<Path>>(relative: P) -> Result<PathBuf, JailError> {
+    current_jail()
+        .ok_or(JailError::NoCurrentJail)?
+        .join(relative)
+}
@@ -0,0 +1,217 @@
+//! `serde` support (`serde` feature).
+//!
+//! A [`Jail`] deserializes from its root path and runs [`Jail::new`] while
+//! doing so, so a missing or unusable root is reported when the
+//! configuration is loaded rather than at first use. A field typed
+//! `Jail<ReadOnly>` deserializes straight into a read-only handle.
+//!
+//! A [`JailedPath`] has no meaning without its jail, so it deserializes
+//! only against one given by [`Jail::deserializer`]: directly as a
+//! [`DeserializeSeed`], or for whole structs inside [`JailSeed::scope`].
+//! Either way the relative path is validated with [`Jail::join`] as it is
+//! read.
+//!
+//! [`SecurityEvent`]s serialize to flat records for log pipelines.
+
+#![cfg(feature = "serde")]
+
+use crate::{Access, Jail, JailErrorKind, JailedPath, Operation, SecurityEvent};
+use serde::de::{Deserialize, DeserializeSeed, Deserializer, Error};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl<'de, A: Access> Deserialize<'de> for Jail<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let root = PathBuf::deserialize(deserializer)?;
+        Jail::new(&root)
+            .map(|jail| jail.with_access())
+            .map_err(|err| D::Error::custom(format!("invalid jail '{}': {}", root.display(), err)))
+    }
+}
+
+/// Serializes as the canonical root path.
+impl<A: Access> Serialize for Jail<A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.root().serialize(serializer)
+    }
+}
+
+/// Validates relative paths into [`JailedPath`]s while deserializing,
+/// from [`Jail::deserializer`].
+///
+/// As a [`DeserializeSeed`] it reads one path. For `JailedPath` fields in
+/// a `#[derive(Deserialize)]` struct, deserialize the struct inside
+/// [`scope`](Self::scope).
+#[derive(Debug, Clone, Copy)]
+pub struct JailSeed<'a, A: Access = crate::ReadWrite> {
+    jail: &'a Jail<A>,
+}
+
+impl<A: Access> Jail<A> {
+    /// Deserialize [`JailedPath`]s against this jail (`serde` feature).
+    ///
+    /// A path that fails [`join`](Self::join) fails the deserialization
+    /// with the jail's error as the message, so traversal in a request
+    /// body or config file is refused where it is read.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use serde_derive::Deserialize;
+    /// use path_jail::{Jail, JailedPath};
+    ///
+    /// #[derive(Deserialize)]
+    /// struct Thumbnail {
+    ///     source: JailedPath,
+    ///     target: JailedPath,
+    /// }
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let body = r#"{ "source": "a.png", "target": "../../etc/cron.d/x" }"#;
+    /// let request: Result<Thumbnail, _> =
+    ///     jail.deserializer().scope(|| serde_json::from_str(body));
+    /// assert!(request.is_err());
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn deserializer(&self) -> JailSeed<'_, A> {
+        JailSeed { jail: self }
+    }
+}
+
+thread_local! {
+    // Jails of the enclosing JailSeed::scope calls, innermost last, with
+    // whether they may hand out writable paths
+    static SCOPE: RefCell<Vec<(Jail, bool)>> = const { RefCell::new(Vec::new()) };
+}
+
+impl<A: Access> JailSeed<'_, A> {
+    /// Run `f` with this jail as the one [`JailedPath`]'s `Deserialize`
+    /// impl validates against, on the current thread.
+    ///
+    /// Scopes nest; the innermost wins. Outside any scope, deserializing
+    /// a `JailedPath` fails. A read-only jail can only produce
+    /// `JailedPath<ReadOnly>`.
+    pub fn scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        struct Pop;
+        impl Drop for Pop {
+            fn drop(&mut self) {
+                SCOPE.with(|scope| scope.borrow_mut().pop());
+            }
+        }
+
+        SCOPE.with(|scope| {
+            scope
+                .borrow_mut()
+                .push((self.jail.with_access(), A::WRITABLE))
+        });
+        let _pop = Pop;
+        f()
+    }
+}
+
+impl<'de, A: Access> DeserializeSeed<'de> for JailSeed<'_, A> {
+    type Value = JailedPath<A>;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        let relative = PathBuf::deserialize(deserializer)?;
+        self.jail.join_typed(&relative).map_err(|err| {
+            D::Error::custom(format!("invalid path '{}': {}", relative.display(), err))
+        })
+    }
+}
+
+/// Needs an enclosing [`JailSeed::scope`]; see [`Jail::deserializer`].
+impl<'de, A: Access> Deserialize<'de> for JailedPath<A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let jail = SCOPE.with(|scope| scope.borrow().last().cloned());
+        match jail {
+            Some((jail, writable)) if writable || !A::WRITABLE => jail
+                .with_access::<A>()
+                .deserializer()
+                .deserialize(deserializer),
+            Some(_) => Err(D::Error::custom(
+                "a read-only jail cannot deserialize a writable JailedPath",
+            )),
+            None => Err(D::Error::custom(
+                "JailedPath can only be deserialized inside Jail::deserializer().scope()",
+            )),
+        }
+    }
+}
+
+/// Serializes as the path relative to the jail root, the form
+/// [`Jail::deserializer`] reads back.
+impl<A: Access> Serialize for JailedPath<A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.relative().serialize(serializer)
+    }
+}
+
+impl Serialize for SecurityEvent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut record = serializer.serialize_struct("SecurityEvent", 7)?;
+        record.serialize_field("timestamp", &rfc3339(self.timestamp))?;
+        record.serialize_field("operation", &self.operation)?;
+        record.serialize_field("input", &self.input.to_string_lossy())?;
+        record.serialize_field(
+            "path",
+            &self.path.as_ref().map(|path| path.to_string_lossy()),
+        )?;
+        record.serialize_field("kind", &self.kind)?;
+        record.serialize_field("reason", &self.reason)?;
+        record.serialize_field("jail_id", &self.jail_id)?;
+        record.end()
+    }
+}
+
+impl Serialize for Operation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (index, name) = match self {
+            Operation::Validate => (0, "validate"),
+            Operation::Open => (1, "open"),
+            Operation::Modify => (2, "modify"),
+        };
+        serializer.serialize_unit_variant("Operation", index, name)
+    }
+}
+
+impl Serialize for JailErrorKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (index, name) = match self {
+            JailErrorKind::Escape => (0, "escape"),
+            JailErrorKind::BrokenLink => (1, "broken_link"),
+            JailErrorKind::Policy => (2, "policy"),
+            JailErrorKind::InvalidInput => (3, "invalid_input"),
+            JailErrorKind::Root => (4, "root"),
+            JailErrorKind::Io => (5, "io"),
+        };
+        serializer.serialize_unit_variant("JailErrorKind", index, name)
+    }
+}
+
+/// `time` as RFC 3339 in UTC with milliseconds; times before the epoch
+/// are written as the epoch.
+fn rfc3339(time: SystemTime) -> String {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs();
+    let (days, rest) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        rest / 3_600,
+        rest % 3_600 / 60,
+        rest % 60,
+        since.subsec_millis()
+    )
+}
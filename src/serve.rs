@@ -0,0 +1,276 @@
+//! Static file serving as a `tower` service (`tower` feature).
+//!
+//! Request paths are percent-decoded and validated like [`Jail::join`]
+//! (without decoding a second time if the jail was built with
+//! [`percent_decode`](crate::JailBuilder::percent_decode)); on Unix, files
+//! are opened with `O_NOFOLLOW`. Anything that cannot be validated is answered with `404 Not
+//! Found`, so probing requests learn nothing about what lies outside.
+
+#![cfg(feature = "tower")]
+
+use crate::{Access, Jail, JailError, ReadOnly};
+use bytes::Bytes;
+use http::header::{ALLOW, CONTENT_LENGTH, CONTENT_TYPE, LOCATION};
+use http::{HeaderValue, Method, Request, Response, StatusCode};
+use http_body::{Body, Frame, SizeHint};
+use std::convert::Infallible;
+use std::fs::File;
+use std::future::{ready, Ready};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower_service::Service;
+
+/// Size of the chunks a file is streamed in.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// A [`tower_service::Service`] serving files from a [`Jail`].
+///
+/// Only `GET` and `HEAD` are accepted. Directory requests are redirected
+/// to the trailing-slash form and answered with the index file, if any;
+/// directory listings are never generated.
+///
+/// File contents are read with blocking `std::fs` calls while the response
+/// body is polled, in 64 KiB chunks.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{Jail, ServeJail};
+///
+/// let service = ServeJail::new(Jail::new("/var/www")?)
+///     .index_file(Some("index.html"))
+///     .redirect_directories(true);
+/// // Hand `service` to hyper, axum (`Router::fallback_service`), ...
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone)]
+pub struct ServeJail {
+    jail: Jail<ReadOnly>,
+    index_file: Option<String>,
+    redirect_directories: bool,
+}
+
+impl ServeJail {
+    /// Serve files from `jail`, with `index.html` as the index file.
+    ///
+    /// The service only ever reads, so any [`Jail`] is accepted and kept as
+    /// a [`ReadOnly`] handle.
+    pub fn new<A: Access>(jail: Jail<A>) -> Self {
+        Self {
+            jail: jail.with_access(),
+            index_file: Some("index.html".to_owned()),
+            redirect_directories: true,
+        }
+    }
+
+    /// File served for directory requests, or `None` to answer them with 404.
+    pub fn index_file<S: Into<String>>(mut self, name: Option<S>) -> Self {
+        self.index_file = name.map(Into::into);
+        self
+    }
+
+    /// Whether `/dir` is redirected to `/dir/` (default `true`).
+    ///
+    /// Without the redirect, relative links in the index file resolve
+    /// against the parent directory.
+    pub fn redirect_directories(mut self, redirect: bool) -> Self {
+        self.redirect_directories = redirect;
+        self
+    }
+
+    fn respond(&self, method: &Method, uri_path: &str) -> Response<ServeBody> {
+        if method != Method::GET && method != Method::HEAD {
+            let mut response = status(StatusCode::METHOD_NOT_ALLOWED);
+            response
+                .headers_mut()
+                .insert(ALLOW, HeaderValue::from_static("GET, HEAD"));
+            return response;
+        }
+
+        let Some(decoded) = percent_decode(uri_path) else {
+            return status(StatusCode::BAD_REQUEST);
+        };
+        let relative = decoded.trim_start_matches('/');
+        let Ok(path) = self.jail.resolve(Path::new(relative)) else {
+            return status(StatusCode::NOT_FOUND);
+        };
+
+        let mut relative = PathBuf::from(relative);
+        if path.is_dir() {
+            if self.redirect_directories && !uri_path.ends_with('/') {
+                // Rebuilt from the trimmed path so "//host" can't become an
+                // off-site redirect
+                let location = format!("/{}/", uri_path.trim_start_matches('/'));
+                let mut response = status(StatusCode::PERMANENT_REDIRECT);
+                if let Ok(location) = HeaderValue::from_str(&location) {
+                    response.headers_mut().insert(LOCATION, location);
+                }
+                return response;
+            }
+            match &self.index_file {
+                Some(index) => relative.push(index),
+                None => return status(StatusCode::NOT_FOUND),
+            }
+        }
+
+        match open_file(&self.jail, &relative) {
+            Ok((file, len)) => {
+                let mut response = Response::new(if method == Method::HEAD {
+                    ServeBody::empty()
+                } else {
+                    ServeBody::file(file, len)
+                });
+                let headers = response.headers_mut();
+                headers.insert(CONTENT_LENGTH, HeaderValue::from(len));
+                headers.insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_static(content_type(&relative)),
+                );
+                response
+            }
+            Err(err) => status(
+                StatusCode::from_u16(err.http_status())
+                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            ),
+        }
+    }
+}
+
+impl<B> Service<Request<B>> for ServeJail {
+    type Response = Response<ServeBody>;
+    type Error = Infallible;
+    type Future = Ready<Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        ready(Ok(self.respond(request.method(), request.uri().path())))
+    }
+}
+
+/// Response body produced by [`ServeJail`].
+#[derive(Debug)]
+pub struct ServeBody {
+    file: Option<File>,
+    remaining: u64,
+}
+
+impl ServeBody {
+    fn empty() -> Self {
+        Self {
+            file: None,
+            remaining: 0,
+        }
+    }
+
+    fn file(file: File, len: u64) -> Self {
+        Self {
+            file: Some(file).filter(|_| len > 0),
+            remaining: len,
+        }
+    }
+}
+
+impl Body for ServeBody {
+    type Data = Bytes;
+    type Error = io::Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.get_mut();
+        let Some(file) = &mut this.file else {
+            return Poll::Ready(None);
+        };
+
+        // Never send more than the advertised Content-Length
+        let mut buf = vec![0; this.remaining.min(CHUNK_SIZE) as usize];
+        let read = match file.read(&mut buf) {
+            Ok(read) => read,
+            Err(err) => {
+                this.file = None;
+                return Poll::Ready(Some(Err(err)));
+            }
+        };
+        if read == 0 {
+            this.file = None;
+            return Poll::Ready(Some(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file shrank while it was being served",
+            ))));
+        }
+        buf.truncate(read);
+        this.remaining -= read as u64;
+        if this.remaining == 0 {
+            this.file = None;
+        }
+        Poll::Ready(Some(Ok(Frame::data(Bytes::from(buf)))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.file.is_none()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
+
+fn status(code: StatusCode) -> Response<ServeBody> {
+    let mut response = Response::new(ServeBody::empty());
+    *response.status_mut() = code;
+    response
+}
+
+/// Open a regular file, returning it with its length.
+fn open_file(jail: &Jail<ReadOnly>, relative: &Path) -> Result<(File, u64), JailError> {
+    // The request path is already decoded, so validate it as-is
+    let path = jail.resolve(relative)?;
+    #[cfg(unix)]
+    let file = jail.open_validated(&path, crate::open::Mode::Read)?;
+    #[cfg(not(unix))]
+    let file = File::open(path)?;
+
+    let metadata = file.metadata()?;
+    if !metadata.is_file() {
+        // Served as missing, like anything else that is not a file
+        return Err(io::Error::new(io::ErrorKind::NotFound, "not a regular file").into());
+    }
+    Ok((file, metadata.len()))
+}
+
+/// Decode `%XX` escapes. Returns `None` for malformed escapes, non-UTF-8
+/// results, and backslashes (a path separator on Windows).
+fn percent_decode(path: &str) -> Option<String> {
+    let bytes = crate::percent::decode(path.as_bytes())?;
+    String::from_utf8(bytes).ok().filter(|s| !s.contains('\\'))
+}
+
+fn content_type(path: &Path) -> &'static str {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        _ => "application/octet-stream",
+    }
+}
@@ -0,0 +1,167 @@
+//! Format-agnostic sink for streaming archive entries into a [`Jail`].
+
+use crate::atomic::create_new;
+use crate::{Jail, JailError};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Destination for entries read from an archive or a sync protocol.
+///
+/// Lets any archive library unpack into a jail without `path_jail`
+/// depending on it: the library maps its entries onto these calls and the
+/// implementation decides whether each one is allowed. File data is
+/// written to the value returned by [`begin_file`](Self::begin_file).
+///
+/// [`Jail`] implements this with full validation: names go through
+/// [`Jail::join`], missing parents are created with
+/// [`Jail::create_dir_all`], files are created with `O_EXCL` (never
+/// writing through an existing entry or symlink), file data is held to
+/// the jail's [`quota`](crate::JailBuilder::quota) and
+/// [`max_file_size`](crate::JailBuilder::max_file_size), and symlinks must
+/// stay inside the jail.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{EntrySink, Jail};
+/// use std::io::Write;
+/// use std::path::Path;
+///
+/// let mut jail = Jail::new("/var/extract")?;
+/// jail.mkdir(Path::new("docs"))?;
+/// let mut file = jail.begin_file(Path::new("docs/readme.txt"), 0o644)?;
+/// file.write_all(b"hello")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait EntrySink {
+    /// Writer for the contents of a file entry.
+    type File: Write;
+
+    /// Start a regular file at `relative` with permission bits `mode`.
+    fn begin_file(&mut self, relative: &Path, mode: u32) -> Result<Self::File, JailError>;
+
+    /// Create a directory (and missing parents) at `relative`.
+    fn mkdir(&mut self, relative: &Path) -> Result<(), JailError>;
+
+    /// Create a symlink at `relative` pointing to `target`.
+    fn symlink(&mut self, relative: &Path, target: &Path) -> Result<(), JailError>;
+}
+
+/// A file entry being written into a [`Jail`], returned by its
+/// [`EntrySink::begin_file`].
+///
+/// Writes are checked against the jail's
+/// [`max_file_size`](crate::JailBuilder::max_file_size) and counted
+/// against its [`quota`](crate::JailBuilder::quota) before they reach the
+/// file, so an archive cannot write past either; a write that would go
+/// over fails with [`JailError::FileTooLarge`] or
+/// [`JailError::QuotaExceeded`] wrapped in an [`io::Error`] (`?` into a
+/// `JailError` unwraps it).
+#[derive(Debug)]
+pub struct EntryFile {
+    jail: Jail,
+    file: File,
+    path: PathBuf,
+    /// Bytes written so far, as counted against the quota.
+    written: u64,
+}
+
+impl EntryFile {
+    /// Returns the underlying [`File`]. Writes to it are not counted.
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Where the file is being written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Remove the file and give its bytes back to the quota, for an entry
+    /// that failed partway.
+    pub fn discard(self) {
+        if fs::remove_file(&self.path).is_ok() {
+            self.jail.config().release(self.written);
+        }
+    }
+}
+
+impl Write for EntryFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let requested = buf.len() as u64;
+        let config = self.jail.config();
+        config
+            .check_file_size(self.written + requested)
+            .and_then(|()| config.resize(0, requested))
+            .map_err(io::Error::other)?;
+        let result = self.file.write(buf);
+        let written = result.as_ref().map_or(0, |&n| n as u64);
+        config.release(requested - written);
+        self.written += written;
+        result
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl EntrySink for Jail {
+    type File = EntryFile;
+
+    /// Setuid, setgid and sticky bits in `mode` are dropped. Fails with
+    /// `AlreadyExists` if something is already at `relative`.
+    fn begin_file(&mut self, relative: &Path, mode: u32) -> Result<EntryFile, JailError> {
+        self.create_entry_file(relative, mode)
+    }
+
+    fn mkdir(&mut self, relative: &Path) -> Result<(), JailError> {
+        self.create_dir_all(relative).map(drop)
+    }
+
+    /// Unix only; elsewhere this returns an `Unsupported` I/O error.
+    fn symlink(&mut self, relative: &Path, target: &Path) -> Result<(), JailError> {
+        self.create_entry_parent(relative)?;
+        #[cfg(unix)]
+        return Jail::symlink(self, target, relative);
+        #[cfg(not(unix))]
+        {
+            let _ = target;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "symlinks are only supported on Unix",
+            )
+            .into())
+        }
+    }
+}
+
+impl Jail {
+    /// Create the parent directories of an entry.
+    pub(crate) fn create_entry_parent(&self, relative: &Path) -> Result<(), JailError> {
+        if let Some(parent) = relative.parent() {
+            self.create_dir_all(parent)?;
+        }
+        Ok(())
+    }
+
+    /// Create a new file for an archive entry.
+    pub(crate) fn create_entry_file(
+        &self,
+        relative: &Path,
+        mode: u32,
+    ) -> Result<EntryFile, JailError> {
+        self.create_entry_parent(relative)?;
+        let path = self.entry_path(relative)?;
+        // O_EXCL never follows a symlink at the final component
+        let file = create_new(&path, mode & 0o777)?;
+        self.config().created_file(&path, &file)?;
+        Ok(EntryFile {
+            jail: self.clone(),
+            file,
+            path,
+            written: 0,
+        })
+    }
+}
@@ -0,0 +1,106 @@
+//! Write-then-publish staging areas inside the jail.
+
+use crate::{Jail, JailError, JailedPath, TempDir};
+use std::path::Path;
+
+/// A private area inside a [`Jail`] to prepare files in, then publish
+/// them into place with a rename.
+///
+/// Created by [`Jail::stage`]. Write to the area through
+/// [`jail`](Self::jail), a sub-jail with the parent's options (and its
+/// quota), under the same relative paths the files should end up at.
+/// [`publish`](Self::publish) then moves each one from the area to that
+/// path in the parent jail. Readers of the parent see nothing until then,
+/// and then the complete file: the area is a hidden directory in the
+/// parent jail, so the move is a rename on one filesystem. Publishing a
+/// staged directory moves everything in it at once. Whatever is left in
+/// the area is removed when the `Staging` is dropped.
+///
+/// A rename makes the file visible atomically, not durable: call
+/// [`File::sync_all`](std::fs::File::sync_all) on files that must
+/// survive a crash before publishing them.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::Jail;
+///
+/// let uploads = Jail::new("/var/uploads")?;
+/// let staging = uploads.stage()?;
+/// staging.jail().create_dir_all("alice/batch-7")?;
+/// staging.jail().write("alice/batch-7/a.png", b"...")?;
+/// staging.jail().write("alice/batch-7/b.png", b"...")?;
+/// // ...validate, scan, thumbnail...
+/// staging.publish("alice/batch-7")?; // both files appear together
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug)]
+pub struct Staging {
+    parent: Jail,
+    jail: Jail,
+    // Removes the area on drop
+    _dir: TempDir,
+}
+
+impl Staging {
+    /// The staging area, as a jail of its own.
+    pub fn jail(&self) -> &Jail {
+        &self.jail
+    }
+
+    /// Move the staged file or directory at `relative` to the same path in
+    /// the parent jail, creating missing parent directories there.
+    ///
+    /// Both ends are validated, and the move is a [`Jail::rename`], so an
+    /// existing file at the destination is replaced atomically. A
+    /// directory can only replace an empty one.
+    pub fn publish<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath, JailError> {
+        self.publish_with(relative.as_ref(), Jail::rename_entry)
+    }
+
+    /// Like [`publish`](Self::publish), but fails with
+    /// [`AlreadyExists`](std::io::ErrorKind::AlreadyExists) instead of
+    /// replacing the destination; see [`Jail::rename_noreplace`].
+    pub fn publish_noreplace<P: AsRef<Path>>(&self, relative: P) -> Result<JailedPath, JailError> {
+        self.publish_with(relative.as_ref(), Jail::rename_entry_noreplace)
+    }
+
+    fn publish_with(
+        &self,
+        relative: &Path,
+        rename: fn(&Jail, &Path, &Path) -> Result<(), JailError>,
+    ) -> Result<JailedPath, JailError> {
+        // Both ends are checked before anything is created for the
+        // destination, and the input is decoded once, here
+        let from = self.jail.entry_path(relative)?;
+        let to = self.parent.entry_path(relative)?;
+        let Some(to_rel) = self.parent.strip_root(&to) else {
+            return Err(JailError::EscapedRoot {
+                attempted: relative.to_path_buf(),
+                root: self.parent.root().to_path_buf(),
+            });
+        };
+
+        if let Some(parent) = to_rel.parent().filter(|p| !p.as_os_str().is_empty()) {
+            self.parent.verbatim().create_dir_all(parent)?;
+        }
+        // Moved as validated: the area's own name need not pass the
+        // parent's name rules (hidden files, deny patterns)
+        rename(&self.parent, &from, &to)?;
+        Ok(JailedPath::new(&self.parent, to))
+    }
+}
+
+impl Jail {
+    /// Create a [`Staging`] area in the jail root, to write files in and
+    /// [`publish`](Staging::publish) them when they are complete.
+    pub fn stage(&self) -> Result<Staging, JailError> {
+        let dir = self.tempdir()?;
+        let area = self.narrow(dir.path().to_path_buf())?;
+        Ok(Staging {
+            parent: self.clone(),
+            jail: area,
+            _dir: dir,
+        })
+    }
+}
@@ -0,0 +1,124 @@
+//! Operation counters (`metrics` feature).
+
+use crate::{Access, Jail, JailErrorKind};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Number of [`JailErrorKind`] variants.
+const KINDS: usize = 6;
+
+/// A snapshot of a jail's counters, from [`Jail::stats`].
+///
+/// Counters start at zero when the jail is built and are shared by its
+/// clones, sub-jails and read-only views. They only go up; export them as
+/// monotonic counters and let the metrics system compute rates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct JailStats {
+    /// Paths validated, including by the file operations and paths that
+    /// were refused.
+    pub joins: u64,
+    /// Files opened through the jail.
+    pub opens: u64,
+    /// Symlinks followed while validating. On Windows, and after a link
+    /// to an allowed outside target, links are resolved by the OS and not
+    /// counted.
+    pub symlinks: u64,
+    /// Paths refused, of any kind; I/O errors are not counted.
+    pub rejections: u64,
+    by_kind: [u64; KINDS],
+}
+
+impl JailStats {
+    /// Paths refused with an error of `kind`.
+    pub fn rejections_of(&self, kind: JailErrorKind) -> u64 {
+        self.by_kind[index(kind)]
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Current values of this jail's counters (`metrics` feature).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, JailErrorKind};
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let _ = jail.join("../etc/passwd");
+    /// let stats = jail.stats();
+    /// println!(
+    ///     "joins={} escapes={}",
+    ///     stats.joins,
+    ///     stats.rejections_of(JailErrorKind::Escape)
+    /// );
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn stats(&self) -> JailStats {
+        self.config().counters().snapshot()
+    }
+}
+
+/// The live counters behind [`JailStats`], shared by every clone of a
+/// jail.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Counters(Arc<Inner>);
+
+#[derive(Debug, Default)]
+struct Inner {
+    joins: AtomicU64,
+    opens: AtomicU64,
+    symlinks: AtomicU64,
+    rejections: [AtomicU64; KINDS],
+}
+
+impl Counters {
+    pub(crate) fn join(&self) {
+        self.0.joins.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn open(&self) {
+        self.0.opens.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn symlink(&self) {
+        self.0.symlinks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn reject(&self, kind: JailErrorKind) {
+        if kind != JailErrorKind::Io {
+            self.0.rejections[index(kind)].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self) -> JailStats {
+        let by_kind = std::array::from_fn(|i| self.0.rejections[i].load(Ordering::Relaxed));
+        JailStats {
+            joins: self.0.joins.load(Ordering::Relaxed),
+            opens: self.0.opens.load(Ordering::Relaxed),
+            symlinks: self.0.symlinks.load(Ordering::Relaxed),
+            rejections: by_kind.iter().sum(),
+            by_kind,
+        }
+    }
+}
+
+// Jails compare by options; the counts are not an option
+impl PartialEq for Counters {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Counters {}
+
+fn index(kind: JailErrorKind) -> usize {
+    match kind {
+        JailErrorKind::Escape => 0,
+        JailErrorKind::BrokenLink => 1,
+        JailErrorKind::Policy => 2,
+        JailErrorKind::InvalidInput => 3,
+        JailErrorKind::Root => 4,
+        JailErrorKind::Io => 5,
+    }
+}
@@ -0,0 +1,96 @@
+//! Tar extraction (`tar` feature).
+
+#![cfg(feature = "tar")]
+
+use crate::extract::Extractor;
+use crate::{ExtractOptions, ExtractReport, Jail, JailError};
+use std::io::Read;
+use std::path::Path;
+
+impl Jail {
+    /// Extract a tar archive into the jail.
+    ///
+    /// Every entry name is checked before anything is written: absolute
+    /// names and `..` are handled by the options' [`EscapePolicy`], and the
+    /// remaining path is written through the same validation as
+    /// [`join`](Self::join), so a symlink extracted earlier cannot redirect
+    /// later entries outside. Links follow the [`LinkPolicy`]. Device and
+    /// FIFO entries are never created. Entry names must be UTF-8, and are
+    /// used as they are: a [`percent_decode`](crate::JailBuilder::percent_decode)
+    /// jail does not decode them.
+    ///
+    /// Regular files are created with `O_EXCL`, keeping the archive's
+    /// permission bits minus setuid, setgid and sticky. Ownership and
+    /// timestamps are not restored. File data and hard links count
+    /// against the jail's [`quota`](crate::JailBuilder::quota), and each
+    /// file is held to its
+    /// [`max_file_size`](crate::JailBuilder::max_file_size); a member that
+    /// would go over is removed and the extraction fails with
+    /// [`JailError::QuotaExceeded`] or [`JailError::FileTooLarge`].
+    ///
+    /// [`EscapePolicy`]: crate::EscapePolicy
+    /// [`LinkPolicy`]: crate::LinkPolicy
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{ExtractOptions, Jail};
+    /// use std::fs::File;
+    ///
+    /// let jail = Jail::new("/var/extract")?;
+    /// let report = jail.extract_tar(File::open("upload.tar")?, &ExtractOptions::new())?;
+    /// println!("{} entries, {} skipped", report.extracted.len(), report.skipped.len());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Stops at the first entry that fails, leaving earlier entries in place.
+    pub fn extract_tar<R: Read>(
+        &self,
+        reader: R,
+        options: &ExtractOptions,
+    ) -> Result<ExtractReport, JailError> {
+        let mut extractor = Extractor::new(self, options);
+        let mut archive = tar::Archive::new(reader);
+
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            let name = utf8(&entry.path_bytes())?;
+            let Some(relative) = extractor.entry_name(&name)? else {
+                continue;
+            };
+
+            let kind = entry.header().entry_type();
+            if kind.is_dir() {
+                extractor.dir(&name, &relative)?;
+            } else if kind.is_file() || kind.is_contiguous() || kind.is_gnu_sparse() {
+                let mode = entry.header().mode().unwrap_or(0o644);
+                extractor.file(&name, &relative, mode, &mut entry)?;
+            } else if kind.is_symlink() || kind.is_hard_link() {
+                let target = match entry.link_name_bytes() {
+                    Some(target) => utf8(&target)?,
+                    None => {
+                        return Err(JailError::InvalidPath(format!(
+                            "link entry '{}' has no target",
+                            name
+                        )))
+                    }
+                };
+                if kind.is_symlink() {
+                    extractor.symlink(&name, &relative, Path::new(&target))?;
+                } else {
+                    extractor.hard_link(&name, &relative, &target)?;
+                }
+            } else {
+                extractor.skip(&name);
+            }
+        }
+        Ok(extractor.finish())
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<String, JailError> {
+    String::from_utf8(bytes.to_vec())
+        .map_err(|_| JailError::InvalidPath("archive entry name is not UTF-8".into()))
+}
@@ -0,0 +1,225 @@
+//! Temporary files and directories inside the jail.
+
+use crate::atomic::{create_unique, unique_name, MAX_ATTEMPTS};
+use crate::{Jail, JailError, JailedPath};
+use std::ffi::OsStr;
+use std::fs::{DirBuilder, File};
+use std::io::{self, Read, Seek, Write};
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::os::unix::fs::DirBuilderExt;
+
+/// A temporary file inside a [`Jail`], removed when dropped.
+///
+/// Created by [`Jail::tempfile`] or [`Jail::tempfile_in`]. The file is
+/// created with `O_EXCL` under a random hidden name (mode `0600` on Unix).
+#[derive(Debug)]
+pub struct TempFile {
+    jail: Jail,
+    file: File,
+    path: Option<JailedPath>,
+}
+
+impl TempFile {
+    /// Returns the path of the temporary file.
+    pub fn path(&self) -> &JailedPath {
+        self.path.as_ref().expect("path is present until persisted")
+    }
+
+    /// Returns the open file handle.
+    pub fn as_file(&self) -> &File {
+        &self.file
+    }
+
+    /// Returns the open file handle mutably.
+    pub fn as_file_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+
+    /// Move the file to `relative` inside the jail and stop tracking it.
+    ///
+    /// The destination is validated like [`Jail::rename`] and replaced if it
+    /// exists. The file is held to
+    /// [`max_file_size`](crate::JailBuilder::max_file_size) and counted
+    /// against the [`quota`](crate::JailBuilder::quota) from here on.
+    pub fn persist<P: AsRef<Path>>(mut self, relative: P) -> Result<JailedPath, JailError> {
+        let from = self.jail.relative(self.path())?;
+        let size = self.file.metadata()?.len();
+        self.jail.config().check_file_size(size)?;
+        self.jail.config().resize(0, size)?;
+        if let Err(err) = self.jail.rename(from, relative.as_ref()) {
+            self.jail.config().release(size);
+            return Err(err);
+        }
+        self.path = None;
+        self.jail.join_typed(relative)
+    }
+
+    /// Keep the file where it is and stop tracking it.
+    pub fn keep(mut self) -> JailedPath {
+        self.path.take().expect("path is present until persisted")
+    }
+}
+
+impl Read for TempFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for TempFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for TempFile {
+    fn seek(&mut self, pos: io::SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// A temporary directory inside a [`Jail`], removed recursively when dropped.
+///
+/// Created by [`Jail::tempdir`] or [`Jail::tempdir_in`]. Removal goes through
+/// [`Jail::remove_dir_all`], so it is symlink-swap safe with the `openat`
+/// feature.
+#[derive(Debug)]
+pub struct TempDir {
+    jail: Jail,
+    path: Option<JailedPath>,
+}
+
+impl TempDir {
+    /// Returns the path of the temporary directory.
+    pub fn path(&self) -> &JailedPath {
+        self.path.as_ref().expect("path is present until kept")
+    }
+
+    /// A [`Jail`] rooted at this temporary directory.
+    pub fn jail(&self) -> Result<Jail, JailError> {
+        Jail::new(self.path())
+    }
+
+    /// Keep the directory and stop tracking it.
+    pub fn keep(mut self) -> JailedPath {
+        self.path.take().expect("path is present until kept")
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        if let Some(path) = self.path.take() {
+            if let Ok(relative) = self.jail.relative(&path) {
+                let _ = self.jail.remove_dir_all(relative);
+            }
+        }
+    }
+}
+
+impl Jail {
+    /// Create a temporary file in the jail root.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    /// use std::io::Write;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// let mut tmp = jail.tempfile()?;
+    /// tmp.write_all(b"partial upload")?;
+    /// tmp.persist("alice/upload.bin")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tempfile(&self) -> Result<TempFile, JailError> {
+        self.tempfile_in("")
+    }
+
+    /// Create a temporary file in a directory inside the jail.
+    pub fn tempfile_in<P: AsRef<Path>>(&self, relative: P) -> Result<TempFile, JailError> {
+        let dir = self.temp_parent(relative.as_ref())?;
+        let (file, path) = create_unique(&dir, OsStr::new("tmp"), 0o600)?;
+        Ok(TempFile {
+            jail: self.clone(),
+            file,
+            path: Some(JailedPath::new(self, path)),
+        })
+    }
+
+    /// Create a temporary directory in the jail root.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/build")?;
+    /// let scratch = jail.tempdir()?;
+    /// std::fs::write(scratch.path().join("out.o")?, b"...")?;
+    /// // Removed here
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tempdir(&self) -> Result<TempDir, JailError> {
+        self.tempdir_in("")
+    }
+
+    /// Create a temporary directory in a directory inside the jail.
+    pub fn tempdir_in<P: AsRef<Path>>(&self, relative: P) -> Result<TempDir, JailError> {
+        let dir = self.temp_parent(relative.as_ref())?;
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut builder = DirBuilder::new();
+        #[cfg(unix)]
+        builder.mode(0o700);
+
+        for _ in 0..MAX_ATTEMPTS {
+            let path = dir.join(unique_name(OsStr::new("tmp")));
+            match builder.create(&path) {
+                Ok(()) => {
+                    return Ok(TempDir {
+                        jail: self.clone(),
+                        path: Some(JailedPath::new(self, path)),
+                    })
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "could not find a free temporary directory name",
+        )
+        .into())
+    }
+
+    /// Validate the directory temporaries are created in.
+    fn temp_parent(&self, relative: &Path) -> Result<PathBuf, JailError> {
+        let dir = self.join(relative)?;
+        // They are removed or renamed over something when done
+        self.config().check_append_only(&dir)?;
+        if !dir.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "'{}' is not a directory",
+                    self.config().shown(&dir).display()
+                ),
+            )
+            .into());
+        }
+        Ok(dir)
+    }
+}
@@ -0,0 +1,246 @@
+//! [`JailedPath`] variants that also record whether the path is a directory
+//! or a file.
+
+use crate::{Access, Jail, JailError, JailedPath, ReadDir, ReadWrite};
+use std::ffi::OsStr;
+use std::fmt;
+use std::io;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A [`JailedPath`] verified to be an existing directory.
+///
+/// Created by [`Jail::join_dir`] or [`JailedPath::into_dir`]. Directory
+/// operations such as [`read_dir`](Self::read_dir) are only available here,
+/// so a function taking a `JailedDirPath` cannot be handed a file.
+///
+/// The check happens once, when the value is created; if the directory is
+/// later replaced, operations fail at the filesystem level as usual.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::{Jail, JailedDirPath};
+///
+/// fn list(dir: &JailedDirPath) -> Result<(), path_jail::JailError> {
+///     for entry in dir.read_dir()? {
+///         println!("{}", entry?.path());
+///     }
+///     Ok(())
+/// }
+///
+/// let jail = Jail::new("/var/uploads")?;
+/// list(&jail.join_dir("alice")?)?;
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JailedDirPath<A: Access = ReadWrite> {
+    inner: JailedPath<A>,
+}
+
+/// A [`JailedPath`] verified not to be a directory.
+///
+/// Created by [`Jail::join_file`] or [`JailedPath::into_file`]. The file
+/// may not exist yet, so the same type serves for reading and for creating
+/// files. File operations such as [`read`](Self::read) are only available
+/// here.
+///
+/// As with [`JailedDirPath`], the check happens when the value is created.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JailedFilePath<A: Access = ReadWrite> {
+    inner: JailedPath<A>,
+}
+
+impl<A: Access> JailedDirPath<A> {
+    /// Returns the untyped [`JailedPath`].
+    #[inline]
+    pub fn as_jailed_path(&self) -> &JailedPath<A> {
+        &self.inner
+    }
+
+    /// Consumes the value and returns the untyped [`JailedPath`].
+    #[inline]
+    pub fn into_jailed_path(self) -> JailedPath<A> {
+        self.inner
+    }
+
+    /// Join a subdirectory, which must exist.
+    pub fn join_dir<P: AsRef<Path>>(&self, path: P) -> Result<JailedDirPath<A>, JailError> {
+        self.inner.join(path)?.into_dir()
+    }
+
+    /// Join a file path, which must not be a directory.
+    pub fn join_file<P: AsRef<Path>>(&self, path: P) -> Result<JailedFilePath<A>, JailError> {
+        self.inner.join(path)?.into_file()
+    }
+
+    /// List this directory. See [`Jail::read_dir`].
+    pub fn read_dir(&self) -> Result<ReadDir<'_, A>, JailError> {
+        self.inner.read_dir()
+    }
+}
+
+impl JailedDirPath {
+    /// Remove this empty directory. See [`Jail::remove_dir`].
+    pub fn remove_dir(self) -> Result<(), JailError> {
+        self.inner.remove_dir()
+    }
+
+    /// Remove this directory and its contents. See [`Jail::remove_dir_all`].
+    pub fn remove_dir_all(self) -> Result<(), JailError> {
+        self.inner.remove_dir_all()
+    }
+}
+
+impl<A: Access> JailedFilePath<A> {
+    /// Returns the untyped [`JailedPath`].
+    #[inline]
+    pub fn as_jailed_path(&self) -> &JailedPath<A> {
+        &self.inner
+    }
+
+    /// Consumes the value and returns the untyped [`JailedPath`].
+    #[inline]
+    pub fn into_jailed_path(self) -> JailedPath<A> {
+        self.inner
+    }
+
+    /// Returns the directory containing this file.
+    pub fn parent(&self) -> Result<JailedDirPath<A>, JailError> {
+        self.inner.parent()?.into_dir()
+    }
+
+    /// Read this file. See [`Jail::read`].
+    pub fn read(&self) -> Result<Vec<u8>, JailError> {
+        self.inner.read()
+    }
+
+    /// Read this file as UTF-8. See [`Jail::read_to_string`].
+    pub fn read_to_string(&self) -> Result<String, JailError> {
+        self.inner.read_to_string()
+    }
+
+    /// Open this file for reading with `O_NOFOLLOW` protection.
+    ///
+    /// See [`Jail::open`].
+    #[cfg(all(feature = "secure-open", unix))]
+    pub fn open(&self) -> Result<crate::JailedFile, JailError> {
+        self.inner.open()
+    }
+}
+
+impl JailedFilePath {
+    /// Write this file. See [`Jail::write`].
+    pub fn write<C: AsRef<[u8]>>(&self, contents: C) -> Result<(), JailError> {
+        self.inner.write(contents)
+    }
+
+    /// Remove this file. See [`Jail::remove_file`].
+    pub fn remove_file(self) -> Result<(), JailError> {
+        self.inner.remove_file()
+    }
+
+    /// Create this file with `O_NOFOLLOW | O_CREAT | O_EXCL`.
+    ///
+    /// See [`Jail::create`].
+    #[cfg(all(feature = "secure-open", unix))]
+    pub fn create(&self) -> Result<crate::JailedFile, JailError> {
+        self.inner.create()
+    }
+}
+
+impl<A: Access> JailedPath<A> {
+    /// Narrow to a [`JailedDirPath`]. Fails unless this is an existing directory.
+    pub fn into_dir(self) -> Result<JailedDirPath<A>, JailError> {
+        if !self.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "'{}' is not a directory",
+                    self.jail().config().shown(self.as_path()).display()
+                ),
+            )
+            .into());
+        }
+        Ok(JailedDirPath { inner: self })
+    }
+
+    /// Narrow to a [`JailedFilePath`]. Fails if this is a directory.
+    pub fn into_file(self) -> Result<JailedFilePath<A>, JailError> {
+        if self.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "'{}' is a directory",
+                    self.jail().config().shown(self.as_path()).display()
+                ),
+            )
+            .into());
+        }
+        Ok(JailedFilePath { inner: self })
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Like [`join_typed`](Self::join_typed), but the result must be an
+    /// existing directory.
+    pub fn join_dir<P: AsRef<Path>>(&self, relative: P) -> Result<JailedDirPath<A>, JailError> {
+        self.join_typed(relative)?.into_dir()
+    }
+
+    /// Like [`join_typed`](Self::join_typed), but the result must not be a
+    /// directory. It does not have to exist.
+    pub fn join_file<P: AsRef<Path>>(&self, relative: P) -> Result<JailedFilePath<A>, JailError> {
+        self.join_typed(relative)?.into_file()
+    }
+}
+
+macro_rules! path_impls {
+    ($ty:ident) => {
+        impl<A: Access> Deref for $ty<A> {
+            type Target = Path;
+
+            #[inline]
+            fn deref(&self) -> &Self::Target {
+                self.inner.as_path()
+            }
+        }
+
+        impl<A: Access> AsRef<Path> for $ty<A> {
+            #[inline]
+            fn as_ref(&self) -> &Path {
+                self.inner.as_path()
+            }
+        }
+
+        impl<A: Access> AsRef<OsStr> for $ty<A> {
+            #[inline]
+            fn as_ref(&self) -> &OsStr {
+                self.inner.as_os_str()
+            }
+        }
+
+        impl<A: Access> fmt::Display for $ty<A> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Display::fmt(&self.inner, f)
+            }
+        }
+
+        impl<A: Access> From<$ty<A>> for JailedPath<A> {
+            #[inline]
+            fn from(path: $ty<A>) -> Self {
+                path.inner
+            }
+        }
+
+        impl<A: Access> From<$ty<A>> for PathBuf {
+            #[inline]
+            fn from(path: $ty<A>) -> Self {
+                path.inner.into_inner()
+            }
+        }
+    };
+}
+
+path_impls!(JailedDirPath);
+path_impls!(JailedFilePath);
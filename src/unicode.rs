@@ -0,0 +1,38 @@
+//! Unicode normalization of incoming paths (`unicode` feature).
+
+#![cfg(feature = "unicode")]
+
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization};
+
+/// Unicode normalization form applied by
+/// [`JailBuilder::normalize_unicode`](crate::JailBuilder::normalize_unicode).
+///
+/// The same visible name can be spelled with a precomposed character
+/// (`é`, NFC) or a base letter plus combining mark (`e` + `◌́`, NFD).
+/// Most filesystems store names byte for byte, macOS stores NFD, and
+/// input from browsers is usually NFC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum UnicodeForm {
+    /// Canonical composition (`é` as one code point).
+    Nfc,
+    /// Canonical decomposition (`é` as `e` + combining acute accent).
+    Nfd,
+}
+
+impl UnicodeForm {
+    /// Normalize `path`. Paths that are not valid UTF-8 are returned as-is.
+    pub(crate) fn apply<'a>(self, path: Cow<'a, Path>) -> Cow<'a, Path> {
+        let Some(s) = path.to_str() else {
+            return path;
+        };
+        let normalized: String = match self {
+            Self::Nfc if !is_nfc(s) => s.nfc().collect(),
+            Self::Nfd if !is_nfd(s) => s.nfd().collect(),
+            _ => return path,
+        };
+        Cow::Owned(PathBuf::from(normalized))
+    }
+}
@@ -0,0 +1,183 @@
+//! UTF-8 jailed paths (`camino` feature).
+
+use crate::{Access, Jail, JailError, JailedPath, ReadWrite};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::ffi::OsStr;
+use std::fmt;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A [`JailedPath`] that is also valid UTF-8.
+///
+/// Created by [`Jail::join_utf8`] or [`JailedPath::into_utf8`]. Derefs to
+/// a [`camino::Utf8Path`], so the path, and its [`relative`](Self::relative)
+/// form, can be stored in a database column or a JSON string without a
+/// lossy conversion. Both the jail root and the joined part must be UTF-8.
+///
+/// # Example
+///
+/// ```no_run
+/// use path_jail::Jail;
+///
+/// let jail = Jail::new("/var/uploads")?;
+/// let path = jail.join_utf8("alice/report.pdf")?;
+/// let column: &str = path.relative().as_str(); // "alice/report.pdf"
+/// # Ok::<(), path_jail::JailError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Utf8JailedPath<A: Access = ReadWrite> {
+    inner: JailedPath<A>,
+}
+
+impl<A: Access> Utf8JailedPath<A> {
+    /// Returns the path as a [`Utf8Path`].
+    #[inline]
+    pub fn as_utf8_path(&self) -> &Utf8Path {
+        // Checked when the value was created
+        Utf8Path::from_path(self.inner.as_path()).expect("UTF-8 jailed path")
+    }
+
+    /// Returns the path as a `&str`.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        self.as_utf8_path().as_str()
+    }
+
+    /// Returns this path relative to the jail root.
+    ///
+    /// See [`JailedPath::relative`].
+    pub fn relative(&self) -> &Utf8Path {
+        Utf8Path::from_path(self.inner.relative()).expect("UTF-8 jailed path")
+    }
+
+    /// Returns the [`JailedPath`].
+    #[inline]
+    pub fn as_jailed_path(&self) -> &JailedPath<A> {
+        &self.inner
+    }
+
+    /// Consumes the value and returns the [`JailedPath`].
+    #[inline]
+    pub fn into_jailed_path(self) -> JailedPath<A> {
+        self.inner
+    }
+
+    /// Consumes the value and returns the underlying [`Utf8PathBuf`].
+    pub fn into_utf8_path_buf(self) -> Utf8PathBuf {
+        Utf8PathBuf::from_path_buf(self.inner.into_inner()).expect("UTF-8 jailed path")
+    }
+
+    /// Join a relative path onto this one, re-checking containment.
+    ///
+    /// See [`JailedPath::join`].
+    pub fn join<P: AsRef<Utf8Path>>(&self, path: P) -> Result<Utf8JailedPath<A>, JailError> {
+        self.inner.join(path.as_ref())?.into_utf8()
+    }
+}
+
+impl<A: Access> JailedPath<A> {
+    /// Narrow to a [`Utf8JailedPath`] (`camino` feature). Fails with
+    /// [`JailError::InvalidPath`] unless the whole path is valid UTF-8.
+    pub fn into_utf8(self) -> Result<Utf8JailedPath<A>, JailError> {
+        if self.as_path().to_str().is_none() {
+            return Err(JailError::InvalidPath(format!(
+                "'{}' is not valid UTF-8",
+                self.display_relative()
+            )));
+        }
+        Ok(Utf8JailedPath { inner: self })
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Like [`join_typed`](Self::join_typed), but the result must be valid
+    /// UTF-8 (`camino` feature).
+    ///
+    /// The input already is, so this only fails on UTF-8 grounds if the
+    /// root is not, or if a symlink resolves to a name that is not.
+    pub fn join_utf8<P: AsRef<Utf8Path>>(
+        &self,
+        relative: P,
+    ) -> Result<Utf8JailedPath<A>, JailError> {
+        self.join_typed(relative.as_ref())?.into_utf8()
+    }
+}
+
+impl<A: Access> TryFrom<JailedPath<A>> for Utf8JailedPath<A> {
+    type Error = JailError;
+
+    fn try_from(path: JailedPath<A>) -> Result<Self, Self::Error> {
+        path.into_utf8()
+    }
+}
+
+impl<A: Access> Deref for Utf8JailedPath<A> {
+    type Target = Utf8Path;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        self.as_utf8_path()
+    }
+}
+
+impl<A: Access> AsRef<Utf8Path> for Utf8JailedPath<A> {
+    #[inline]
+    fn as_ref(&self) -> &Utf8Path {
+        self.as_utf8_path()
+    }
+}
+
+impl<A: Access> AsRef<Path> for Utf8JailedPath<A> {
+    #[inline]
+    fn as_ref(&self) -> &Path {
+        self.inner.as_path()
+    }
+}
+
+impl<A: Access> AsRef<str> for Utf8JailedPath<A> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<A: Access> AsRef<OsStr> for Utf8JailedPath<A> {
+    #[inline]
+    fn as_ref(&self) -> &OsStr {
+        self.inner.as_os_str()
+    }
+}
+
+impl<A: Access> fmt::Display for Utf8JailedPath<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl<A: Access> From<Utf8JailedPath<A>> for JailedPath<A> {
+    #[inline]
+    fn from(path: Utf8JailedPath<A>) -> Self {
+        path.inner
+    }
+}
+
+impl<A: Access> From<Utf8JailedPath<A>> for Utf8PathBuf {
+    #[inline]
+    fn from(path: Utf8JailedPath<A>) -> Self {
+        path.into_utf8_path_buf()
+    }
+}
+
+impl<A: Access> From<Utf8JailedPath<A>> for String {
+    #[inline]
+    fn from(path: Utf8JailedPath<A>) -> Self {
+        path.into_utf8_path_buf().into_string()
+    }
+}
+
+impl<A: Access> From<Utf8JailedPath<A>> for PathBuf {
+    #[inline]
+    fn from(path: Utf8JailedPath<A>) -> Self {
+        path.inner.into_inner()
+    }
+}
@@ -0,0 +1,234 @@
+//! Lexical validation of `/`-separated paths, without `std`.
+//!
+//! Everything here uses `core` and `alloc` only, so it builds with
+//! `default-features = false` on targets that have no filesystem. The
+//! traversal rules are shared with [`LexicalJail`](crate::LexicalJail) and
+//! [`normalize`](crate::normalize), which map platform path components
+//! onto them.
+
+use alloc::string::String;
+use core::fmt;
+
+/// Why a [`VirtualJail`] rejected a root or a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LexicalError {
+    /// The root is relative, or resolves to `/`.
+    InvalidRoot,
+    /// The path contains a null byte.
+    NullByte,
+    /// The path starts with `/` where a relative one was expected, or
+    /// does not where an absolute one was.
+    Absolute,
+    /// A `..` component climbs above the root.
+    EscapedRoot,
+}
+
+impl fmt::Display for LexicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidRoot => "root must be absolute and not '/'",
+            Self::NullByte => "null bytes not allowed",
+            Self::Absolute => "absolute paths not allowed",
+            Self::EscapedRoot => "path escapes the jail root",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LexicalError {}
+
+/// A jail over a virtual, `/`-separated path space.
+///
+/// The same traversal rules as [`LexicalJail`](crate::LexicalJail), on
+/// plain strings instead of platform paths, and available without `std`:
+/// for object storage keys, archive members, firmware images or any other
+/// tree that is not the host filesystem. Empty and `.` components are
+/// dropped, `..` is resolved by removing the previous component, and no
+/// `..` may climb above the root.
+///
+/// `/` is the only separator. A `\`, a drive letter or a `:` is an
+/// ordinary character here, so translate such paths before joining them
+/// if the space they end up in gives those a meaning.
+///
+/// # Example
+///
+/// ```
+/// use path_jail::{LexicalError, VirtualJail};
+///
+/// let jail = VirtualJail::new("/tenants/acme")?;
+/// assert_eq!(jail.join("reports//./../2025/q1.pdf")?, "/tenants/acme/2025/q1.pdf");
+/// assert_eq!(jail.join("../other"), Err(LexicalError::EscapedRoot));
+/// assert!(jail.contains("/tenants/acme/a/../b"));
+/// assert!(!jail.contains("/tenants/acme-evil"));
+/// # Ok::<(), LexicalError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualJail {
+    root: String,
+}
+
+impl VirtualJail {
+    /// Create a jail rooted at `root`, which must start with `/`.
+    ///
+    /// # Errors
+    ///
+    /// [`LexicalError::InvalidRoot`] if `root` is relative, contains a
+    /// null byte, climbs above `/` or resolves to `/` itself.
+    pub fn new(root: &str) -> Result<Self, LexicalError> {
+        let relative = root.strip_prefix('/').ok_or(LexicalError::InvalidRoot)?;
+        let mut normalized = String::new();
+        normalize_onto(&mut normalized, relative).map_err(|_| LexicalError::InvalidRoot)?;
+        if normalized.is_empty() {
+            return Err(LexicalError::InvalidRoot);
+        }
+        Ok(Self { root: normalized })
+    }
+
+    /// Returns the root, with `.`, `..` and empty components resolved.
+    pub fn root(&self) -> &str {
+        &self.root
+    }
+
+    /// Lexically join a relative path to the root.
+    ///
+    /// # Errors
+    ///
+    /// Rejects absolute paths, null bytes, and `..` components that climb
+    /// above the root.
+    #[must_use = "use the returned path, not the original input"]
+    pub fn join(&self, relative: &str) -> Result<String, LexicalError> {
+        if relative.starts_with('/') {
+            return Err(LexicalError::Absolute);
+        }
+        let mut joined = self.root.clone();
+        normalize_onto(&mut joined, relative)?;
+        Ok(joined)
+    }
+
+    /// Returns `true` if the absolute `path` is the root or lies below it
+    /// once `.` and `..` are resolved.
+    ///
+    /// Relative paths and paths with null bytes are never contained.
+    #[must_use]
+    pub fn contains(&self, path: &str) -> bool {
+        let Some(relative) = path.strip_prefix('/') else {
+            return false;
+        };
+        let mut normalized = String::new();
+        if normalize_onto(&mut normalized, relative).is_err() {
+            return false;
+        }
+        match normalized.strip_prefix(self.root.as_str()) {
+            Some(rest) => rest.is_empty() || rest.starts_with('/'),
+            None => false,
+        }
+    }
+}
+
+/// Append the components of `path` to `normalized`, each as `/name`;
+/// `..` never removes what was already there.
+fn normalize_onto(normalized: &mut String, path: &str) -> Result<(), LexicalError> {
+    let steps = path.split('/').map(|component| match component {
+        "" | "." => Step::Current,
+        ".." => Step::Parent,
+        name => Step::Name(name),
+    });
+    traverse(normalized, steps, false)
+}
+
+impl Normalized for String {
+    type Name = str;
+
+    fn has_null(name: &str) -> bool {
+        name.contains('\0')
+    }
+
+    fn push_name(&mut self, name: &str) {
+        self.push('/');
+        self.push_str(name);
+    }
+
+    fn pop_name(&mut self) {
+        // Only called for a pushed component, which starts with '/'
+        if let Some(slash) = self.rfind('/') {
+            self.truncate(slash);
+        }
+    }
+}
+
+/// One component of a path, as the traversal rules see it.
+pub(crate) enum Step<'a, N: ?Sized> {
+    /// A root or prefix, allowed only at the start of an absolute path.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    Root(&'a N),
+    /// `.`, or the empty name between repeated separators.
+    Current,
+    /// `..`.
+    Parent,
+    /// Anything else.
+    Name(&'a N),
+}
+
+/// A path being built by [`traverse`].
+pub(crate) trait Normalized {
+    /// What a component is made of.
+    type Name: ?Sized;
+
+    /// Whether `name` contains a null byte.
+    fn has_null(name: &Self::Name) -> bool;
+
+    /// Append a root or a name.
+    fn push_name(&mut self, name: &Self::Name);
+
+    /// Remove the last name pushed.
+    fn pop_name(&mut self);
+}
+
+/// The traversal rules shared by [`VirtualJail`],
+/// [`LexicalJail`](crate::LexicalJail) and [`normalize`](crate::normalize):
+/// push the `steps` onto `normalized`, dropping `.` and resolving `..` by
+/// removing the previous name. A `..` may not remove what was already in
+/// `normalized`, and roots are allowed only as the leading steps, and only
+/// if `absolute`.
+pub(crate) fn traverse<'a, S>(
+    normalized: &mut S,
+    steps: impl IntoIterator<Item = Step<'a, S::Name>>,
+    absolute: bool,
+) -> Result<(), LexicalError>
+where
+    S: Normalized + ?Sized,
+    S::Name: 'a,
+{
+    let mut depth = 0usize;
+    let mut leading = true;
+    for step in steps {
+        match step {
+            Step::Root(root) if absolute && leading => {
+                if S::has_null(root) {
+                    return Err(LexicalError::NullByte);
+                }
+                normalized.push_name(root);
+                continue;
+            }
+            Step::Root(_) => return Err(LexicalError::Absolute),
+            Step::Current => {}
+            Step::Parent => {
+                if depth == 0 {
+                    return Err(LexicalError::EscapedRoot);
+                }
+                normalized.pop_name();
+                depth -= 1;
+            }
+            Step::Name(name) => {
+                if S::has_null(name) {
+                    return Err(LexicalError::NullByte);
+                }
+                normalized.push_name(name);
+                depth += 1;
+            }
+        }
+        leading = false;
+    }
+    Ok(())
+}
@@ -0,0 +1,139 @@
+//! Recursive, depth-first traversal of a directory inside the jail.
+
+use crate::read_dir::{DirEntry, ReadDir};
+use crate::{Access, Jail, JailError, ReadWrite};
+use std::path::{Path, PathBuf};
+
+/// Depth-first iterator over a directory tree inside a [`Jail`].
+///
+/// Created by [`Jail::walk`]. Every yielded [`DirEntry`] has been verified
+/// the same way [`Jail::read_dir`] verifies entries. The starting directory
+/// itself is not yielded.
+///
+/// By default symlinks are reported but never descended into. With
+/// [`follow_symlinks(true)`](Self::follow_symlinks), symlinks to directories
+/// inside the jail are traversed; loops are reported as errors instead of
+/// being walked forever.
+#[derive(Debug)]
+pub struct Walk<'a, A: Access = ReadWrite> {
+    jail: &'a Jail<A>,
+    stack: Vec<ReadDir<'a, A>>,
+    // Canonical paths of the directories currently on the stack
+    ancestors: Vec<PathBuf>,
+    follow_symlinks: bool,
+    max_depth: Option<usize>,
+}
+
+impl<'a, A: Access> Walk<'a, A> {
+    fn new(jail: &'a Jail<A>, start: PathBuf) -> Result<Self, JailError> {
+        let mut walk = Self {
+            jail,
+            stack: Vec::new(),
+            ancestors: Vec::new(),
+            follow_symlinks: false,
+            max_depth: None,
+        };
+        walk.push(start)?;
+        Ok(walk)
+    }
+
+    /// Descend into symlinked directories that resolve inside the jail.
+    ///
+    /// Defaults to `false`.
+    pub fn follow_symlinks(mut self, follow: bool) -> Self {
+        self.follow_symlinks = follow;
+        self
+    }
+
+    /// Limit how deep the walk descends.
+    ///
+    /// Depth 1 yields only the direct children of the starting directory.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    fn push(&mut self, dir: PathBuf) -> Result<(), JailError> {
+        let read_dir = ReadDir::new(self.jail, &dir)?;
+        self.stack.push(read_dir);
+        self.ancestors.push(dir);
+        Ok(())
+    }
+
+    fn should_descend(&self, entry: &DirEntry<A>) -> bool {
+        if self.max_depth.is_some_and(|max| self.stack.len() >= max) {
+            return false;
+        }
+        let file_type = entry.file_type();
+        if file_type.is_symlink() {
+            self.follow_symlinks && entry.path().is_dir()
+        } else {
+            file_type.is_dir()
+        }
+    }
+
+    fn is_loop(&self, dir: &Path) -> bool {
+        self.ancestors.iter().any(|a| a == dir)
+    }
+}
+
+impl<A: Access> Iterator for Walk<'_, A> {
+    type Item = Result<DirEntry<A>, JailError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.stack.last_mut()?.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(err)) => return Some(Err(err)),
+                None => {
+                    self.stack.pop();
+                    self.ancestors.pop();
+                    continue;
+                }
+            };
+
+            if self.should_descend(&entry) {
+                let dir = entry.path().to_path_buf();
+                if self.is_loop(&dir) {
+                    return Some(Err(JailError::InvalidPath(format!(
+                        "filesystem loop at '{}'",
+                        self.jail.config().shown(&dir).display()
+                    ))));
+                }
+                if let Err(err) = self.push(dir) {
+                    return Some(Err(err));
+                }
+            }
+
+            return Some(Ok(entry));
+        }
+    }
+}
+
+impl<A: Access> Jail<A> {
+    /// Recursively walk a directory inside the jail, depth-first.
+    ///
+    /// The starting directory is validated with [`join`](Self::join). Each
+    /// entry beneath it is verified like [`read_dir`](Self::read_dir) does,
+    /// so escaping or broken symlinks surface as errors and the walk
+    /// continues past them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::Jail;
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// for entry in jail.walk("")?.follow_symlinks(true).max_depth(8) {
+    ///     let entry = entry?;
+    ///     if entry.file_type().is_file() {
+    ///         println!("{}", entry.path());
+    ///     }
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn walk<P: AsRef<Path>>(&self, relative: P) -> Result<Walk<'_, A>, JailError> {
+        let start = self.join(relative)?;
+        Walk::new(self, start)
+    }
+}
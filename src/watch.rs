@@ -0,0 +1,200 @@
+//! Change notifications limited to the jail (`watch` feature).
+
+use crate::{Access, Jail, JailError, JailedPath, ReadWrite};
+use notify::event::{EventKind, ModifyKind};
+use notify::{Config, RecommendedWatcher, RecursiveMode, Watcher};
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError};
+use std::time::Duration;
+
+/// What happened to the paths of a [`WatchEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WatchKind {
+    /// A file or directory was created.
+    Create,
+    /// Contents or metadata changed.
+    Modify,
+    /// A file or directory was renamed. When the platform reports both
+    /// ends together the old path comes first.
+    Rename,
+    /// A file or directory was removed.
+    Remove,
+    /// Something the platform did not say more about.
+    Other,
+}
+
+/// A change inside the jail, from a [`JailWatcher`].
+#[derive(Debug, Clone)]
+pub struct WatchEvent<A: Access = ReadWrite> {
+    kind: WatchKind,
+    paths: Vec<JailedPath<A>>,
+}
+
+impl<A: Access> WatchEvent<A> {
+    /// What happened.
+    pub fn kind(&self) -> WatchKind {
+        self.kind
+    }
+
+    /// The paths it happened to, verified like [`Jail::join_typed`] would.
+    /// Never empty.
+    pub fn paths(&self) -> &[JailedPath<A>] {
+        &self.paths
+    }
+
+    /// Consumes the event and returns its paths.
+    pub fn into_paths(self) -> Vec<JailedPath<A>> {
+        self.paths
+    }
+}
+
+/// Recursive change notifications for a jail, from [`Jail::watch`].
+///
+/// Iterating blocks until the next event. Paths in events are checked
+/// the way [`Jail::join`] checks input: one that resolves outside the
+/// jail, such as a new symlink to `/etc`, is left out, and events left
+/// with no paths are skipped. Symlinked directories are not watched
+/// through. Dropping the watcher stops the notifications.
+#[derive(Debug)]
+pub struct JailWatcher<A: Access = ReadWrite> {
+    jail: Jail<A>,
+    // Kept alive for as long as events are wanted
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl<A: Access> Jail<A> {
+    /// Watch the whole jail for changes (`watch` feature).
+    ///
+    /// Uses inotify on Linux, FSEvents on macOS, `ReadDirectoryChangesW`
+    /// on Windows and kqueue on the BSDs, through the `notify` crate.
+    /// Events carry [`JailedPath`]s; see [`JailWatcher`] for what is
+    /// filtered out.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{Jail, WatchKind};
+    ///
+    /// let jail = Jail::new("/var/uploads")?;
+    /// for event in jail.watch()? {
+    ///     let event = event?;
+    ///     if event.kind() == WatchKind::Create {
+    ///         for path in event.paths() {
+    ///             println!("new: {}", path.display_relative());
+    ///         }
+    ///     }
+    /// }
+    /// # Ok::<(), path_jail::JailError>(())
+    /// ```
+    pub fn watch(&self) -> Result<JailWatcher<A>, JailError> {
+        let (tx, events) = mpsc::channel();
+        let mut watcher =
+            RecommendedWatcher::new(tx, Config::default().with_follow_symlinks(false))
+                .map_err(into_jail_error)?;
+        watcher
+            .watch(self.root(), RecursiveMode::Recursive)
+            .map_err(into_jail_error)?;
+        Ok(JailWatcher {
+            jail: self.clone(),
+            _watcher: watcher,
+            events,
+        })
+    }
+}
+
+impl<A: Access> JailWatcher<A> {
+    /// The jail being watched.
+    pub fn jail(&self) -> &Jail<A> {
+        &self.jail
+    }
+
+    /// The next event if one is already waiting, without blocking.
+    pub fn try_next(&mut self) -> Option<Result<WatchEvent<A>, JailError>> {
+        loop {
+            let raw = match self.events.try_recv() {
+                Ok(raw) => raw,
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => return None,
+            };
+            if let Some(event) = self.verify(raw) {
+                return Some(event);
+            }
+        }
+    }
+
+    /// The next event, waiting at most `timeout` for one.
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<Result<WatchEvent<A>, JailError>> {
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            let left = deadline.saturating_duration_since(std::time::Instant::now());
+            let raw = match self.events.recv_timeout(left) {
+                Ok(raw) => raw,
+                Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return None,
+            };
+            if let Some(event) = self.verify(raw) {
+                return Some(event);
+            }
+        }
+    }
+
+    /// Keep the paths of `raw` that are inside the jail; `None` if there
+    /// are none or the event is only an access.
+    fn verify(
+        &self,
+        raw: notify::Result<notify::Event>,
+    ) -> Option<Result<WatchEvent<A>, JailError>> {
+        let raw = match raw {
+            Ok(raw) => raw,
+            Err(err) => return Some(Err(into_jail_error(err))),
+        };
+        let kind = match raw.kind {
+            EventKind::Access(_) => return None,
+            EventKind::Create(_) => WatchKind::Create,
+            EventKind::Modify(ModifyKind::Name(_)) => WatchKind::Rename,
+            EventKind::Modify(_) => WatchKind::Modify,
+            EventKind::Remove(_) => WatchKind::Remove,
+            EventKind::Any | EventKind::Other => WatchKind::Other,
+        };
+        let paths: Vec<_> = raw
+            .paths
+            .into_iter()
+            .filter_map(|path| self.inside(path))
+            .collect();
+        if paths.is_empty() {
+            return None;
+        }
+        Some(Ok(WatchEvent { kind, paths }))
+    }
+
+    fn inside(&self, path: PathBuf) -> Option<JailedPath<A>> {
+        let relative = self.jail.strip_root(&path)?;
+        // The root's own events are about the jail, not a path in it
+        if relative.as_os_str().is_empty() {
+            return None;
+        }
+        let resolved = self.jail.resolve(relative).ok()?;
+        Some(JailedPath::new(&self.jail, resolved))
+    }
+}
+
+impl<A: Access> Iterator for JailWatcher<A> {
+    type Item = Result<WatchEvent<A>, JailError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let raw = self.events.recv().ok()?;
+            if let Some(event) = self.verify(raw) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+fn into_jail_error(err: notify::Error) -> JailError {
+    match err.kind {
+        notify::ErrorKind::Io(err) => JailError::Io(err),
+        _ => JailError::Io(io::Error::other(err)),
+    }
+}
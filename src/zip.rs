@@ -0,0 +1,82 @@
+//! Zip extraction (`zip` feature).
+
+#![cfg(feature = "zip")]
+
+use crate::extract::Extractor;
+use crate::{ExtractOptions, ExtractReport, Jail, JailError};
+use std::io::{self, Read, Seek};
+use std::path::Path;
+
+/// Longest symlink target read from an entry, in bytes (`PATH_MAX` on
+/// Linux): the target is decompressed into memory.
+const MAX_LINK_TARGET: u64 = 4096;
+
+impl Jail {
+    /// Extract a zip archive into the jail.
+    ///
+    /// Works like [`extract_tar`](Self::extract_tar) with the same
+    /// [`ExtractOptions`]. Backslashes in entry names are treated as
+    /// separators, since archives created on Windows often use them, so
+    /// `..\..\evil` is caught like `../../evil` on every platform. Names
+    /// are not percent-decoded, even in a
+    /// [`percent_decode`](crate::JailBuilder::percent_decode) jail.
+    ///
+    /// Files are created with `O_EXCL` (and `O_NOFOLLOW` with the
+    /// `secure-open` feature), never through an existing symlink. Symlink
+    /// entries (Unix mode `S_IFLNK`) follow the [`LinkPolicy`].
+    ///
+    /// [`LinkPolicy`]: crate::LinkPolicy
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use path_jail::{ExtractOptions, Jail};
+    /// use std::fs::File;
+    ///
+    /// let jail = Jail::new("/var/extract")?;
+    /// let report = jail.extract_zip(File::open("upload.zip")?, &ExtractOptions::new())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Stops at the first entry that fails, leaving earlier entries in place.
+    /// A symlink entry whose target is over 4096 bytes fails whatever the
+    /// [`LinkPolicy`], as its data is read into memory.
+    pub fn extract_zip<R: Read + Seek>(
+        &self,
+        reader: R,
+        options: &ExtractOptions,
+    ) -> Result<ExtractReport, JailError> {
+        let mut extractor = Extractor::new(self, options);
+        let mut archive = zip::ZipArchive::new(reader).map_err(io::Error::from)?;
+
+        for index in 0..archive.len() {
+            let mut entry = archive.by_index(index).map_err(io::Error::from)?;
+            let name = entry.name().replace('\\', "/");
+            let Some(relative) = extractor.entry_name(&name)? else {
+                continue;
+            };
+
+            if entry.is_dir() {
+                extractor.dir(&name, &relative)?;
+            } else if entry.is_symlink() {
+                let mut target = String::new();
+                (&mut entry)
+                    .take(MAX_LINK_TARGET + 1)
+                    .read_to_string(&mut target)?;
+                if target.len() as u64 > MAX_LINK_TARGET {
+                    return Err(JailError::InvalidPath(format!(
+                        "symlink entry '{}' has a target over {} bytes",
+                        name, MAX_LINK_TARGET
+                    )));
+                }
+                extractor.symlink(&name, &relative, Path::new(&target))?;
+            } else {
+                let mode = entry.unix_mode().unwrap_or(0o644);
+                extractor.file(&name, &relative, mode, &mut entry)?;
+            }
+        }
+        Ok(extractor.finish())
+    }
+}
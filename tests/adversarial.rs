@@ -0,0 +1,95 @@
+//! Tests for the adversarial path generators (test-util feature).
+
+#![cfg(feature = "test-util")]
+
+use path_jail::{adversarial_path, normalize, AdversarialPath, Jail, LexicalJail};
+use proptest::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn join_never_leaves_the_root(path in adversarial_path()) {
+        let outside = tempdir().unwrap();
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b")).unwrap();
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(outside.path(), dir.path().join("a/out")).unwrap();
+            std::os::unix::fs::symlink("../..", dir.path().join("a/b/up")).unwrap();
+        }
+        let jail = Jail::new(dir.path()).unwrap();
+        let decoding = Jail::builder(dir.path()).percent_decode(true).build().unwrap();
+
+        for jail in [&jail, &decoding] {
+            if let Ok(joined) = jail.join(&path) {
+                prop_assert!(joined.starts_with(jail.root()), "{:?} -> {:?}", path, joined);
+            }
+            if let Ok(joined) = jail.join_lexical(&path) {
+                prop_assert!(joined.starts_with(jail.root()), "{:?} -> {:?}", path, joined);
+            }
+        }
+    }
+
+    #[test]
+    fn lexical_join_agrees_with_normalize(path in adversarial_path()) {
+        let jail = LexicalJail::new(std::env::temp_dir().join("jail")).unwrap();
+        match (jail.join(&path), normalize(&path)) {
+            (Ok(joined), Ok(normalized)) => {
+                prop_assert_eq!(joined, jail.root().join(normalized));
+            }
+            (Err(_), Err(_)) => {}
+            (joined, normalized) => {
+                prop_assert!(false, "{:?}: {:?} vs {:?}", path, joined, normalized);
+            }
+        }
+    }
+
+    #[test]
+    fn any_adversarial_path_stays_in_a_lexical_jail(AdversarialPath(path) in any::<AdversarialPath>()) {
+        let jail = LexicalJail::new(std::env::temp_dir().join("jail")).unwrap();
+        if let Ok(joined) = jail.join(&path) {
+            prop_assert!(joined.starts_with(jail.root()), "{:?} -> {:?}", path, joined);
+        }
+    }
+}
+
+#[test]
+fn arbitrary_builds_paths_from_raw_bytes() {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    // Fixed pseudo-random inputs, as a fuzzer would supply
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let paths: Vec<_> = (0..256)
+        .map(|_| {
+            let bytes: Vec<u8> = (0..64)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect();
+            <AdversarialPath as Arbitrary>::arbitrary(&mut Unstructured::new(&bytes))
+                .unwrap()
+                .0
+        })
+        .collect();
+
+    assert!(paths.iter().any(|path| path
+        .components()
+        .any(|c| c == std::path::Component::ParentDir)));
+
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("out")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    for path in &paths {
+        if let Ok(joined) = jail.join(path) {
+            assert!(joined.starts_with(jail.root()), "{path:?} -> {joined:?}");
+        }
+    }
+}
@@ -0,0 +1,148 @@
+//! Tests for atomic writes.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use std::io::Write;
+use tempfile::tempdir;
+
+fn entries(dir: &std::path::Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn write_atomic_replaces_contents() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("config.json"), b"old").unwrap();
+    let path = jail.write_atomic("config.json", b"new").unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), b"new");
+    // No temporary file left behind
+    assert_eq!(entries(dir.path()), ["config.json"]);
+}
+
+#[test]
+fn atomic_writer_is_invisible_until_commit() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("data.csv"), b"old").unwrap();
+
+    let mut writer = jail.atomic_writer("data.csv").unwrap();
+    writer.write_all(b"id,name\n").unwrap();
+    assert_eq!(fs::read(dir.path().join("data.csv")).unwrap(), b"old");
+
+    writer.commit().unwrap();
+    assert_eq!(fs::read(dir.path().join("data.csv")).unwrap(), b"id,name\n");
+}
+
+#[test]
+fn atomic_writer_cleans_up_on_drop() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let mut writer = jail.atomic_writer("abandoned.txt").unwrap();
+    writer.write_all(b"partial").unwrap();
+    drop(writer);
+
+    assert!(entries(dir.path()).is_empty());
+}
+
+#[test]
+fn write_atomic_rejects_traversal() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.write_atomic("../escape.txt", b"x").is_err());
+    assert!(jail.write_atomic("", b"x").is_err());
+    assert!(jail.atomic_writer("/etc/passwd").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn write_atomic_replaces_symlink_instead_of_following() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let victim = outside.path().join("victim.txt");
+    fs::write(&victim, b"keep me").unwrap();
+    std::os::unix::fs::symlink(&victim, dir.path().join("link")).unwrap();
+
+    jail.write_atomic("link", b"new").unwrap();
+    assert_eq!(fs::read(&victim).unwrap(), b"keep me");
+    assert!(fs::symlink_metadata(dir.path().join("link"))
+        .unwrap()
+        .is_file());
+}
+
+#[test]
+fn copy_from_streams_up_to_the_limit() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let copied = jail.copy_from("upload.bin", &[7u8; 100][..], 100).unwrap();
+    assert_eq!(copied, 100);
+    assert_eq!(fs::read(dir.path().join("upload.bin")).unwrap(), [7u8; 100]);
+
+    // Over the limit: nothing is replaced and no temporary is left
+    let err = jail
+        .copy_from("upload.bin", &[8u8; 101][..], 100)
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        JailError::FileTooLarge {
+            size: 101,
+            max: 100
+        }
+    ));
+    assert_eq!(fs::read(dir.path().join("upload.bin")).unwrap(), [7u8; 100]);
+    assert_eq!(entries(dir.path()), ["upload.bin"]);
+
+    // An endless source is cut off just past the limit
+    let mut endless = std::io::repeat(0);
+    assert!(jail.copy_from("endless.bin", &mut endless, 1024).is_err());
+    assert!(!dir.path().join("endless.bin").exists());
+    assert!(jail.copy_from("../escape.bin", &b"x"[..], 10).is_err());
+}
+
+#[test]
+fn write_atomic_accepts_names_as_long_as_write_does() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let name = "a".repeat(250);
+
+    jail.write(&name, b"old").unwrap();
+    jail.write_atomic(&name, b"new").unwrap();
+    assert_eq!(fs::read(dir.path().join(&name)).unwrap(), b"new");
+    assert_eq!(entries(dir.path()), [name]);
+}
+
+#[test]
+#[cfg(unix)]
+fn write_atomic_keeps_the_mode_of_the_replaced_file() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).file_mode(0o644).build().unwrap();
+    let path = dir.path().join("secret.key");
+    fs::write(&path, b"old").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+
+    jail.write_atomic("secret.key", b"new").unwrap();
+    let mode = fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    jail.write_atomic("public.txt", b"new").unwrap();
+    let mode = fs::metadata(dir.path().join("public.txt"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o644);
+}
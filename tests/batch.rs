@@ -0,0 +1,87 @@
+//! Tests for batch validation with `Jail::join_many`.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn join_many_matches_join() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("site/css")).unwrap();
+    fs::write(dir.path().join("site/index.html"), "").unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+
+    let paths = [
+        "site/index.html",
+        "site/css/main.css",
+        "site/new/page.html",
+        "../etc/passwd",
+        "site/shell.php",
+        "/etc/passwd",
+        "site/../site/index.html",
+        "",
+    ];
+    let results = jail.join_many(paths);
+    assert_eq!(results.len(), paths.len());
+    for (path, result) in paths.iter().zip(&results) {
+        match (jail.join(path), result) {
+            (Ok(expected), Ok(actual)) => assert_eq!(&expected, actual, "{path}"),
+            (Err(expected), Err(actual)) => {
+                assert_eq!(expected.to_string(), actual.to_string(), "{path}")
+            }
+            (expected, actual) => panic!("{path}: join gave {expected:?}, join_many {actual:?}"),
+        }
+    }
+    assert!(matches!(results[3], Err(JailError::EscapedRoot { .. })));
+    assert!(jail.join_many(Vec::<&str>::new()).is_empty());
+}
+
+#[test]
+#[cfg(unix)]
+fn join_many_checks_symlinks_in_shared_prefixes() {
+    use std::os::unix::fs::symlink;
+
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    symlink(dir.path().join("real"), dir.path().join("inside")).unwrap();
+    symlink(outside.path(), dir.path().join("escape")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let results = jail.join_many([
+        "inside/a.txt",
+        "escape/a.txt",
+        "inside/b.txt",
+        "escape/b.txt",
+    ]);
+    let root = jail.root();
+    assert_eq!(results[0].as_ref().unwrap(), &root.join("real/a.txt"));
+    assert_eq!(results[2].as_ref().unwrap(), &root.join("real/b.txt"));
+    assert!(matches!(results[1], Err(JailError::EscapedRoot { .. })));
+    assert!(matches!(results[3], Err(JailError::EscapedRoot { .. })));
+}
+
+#[test]
+#[cfg(unix)]
+fn join_many_joins_lexically_in_a_trusted_tree() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    symlink(dir.path().join("real"), dir.path().join("inside")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .trusted_tree(true)
+        .build()
+        .unwrap();
+
+    let results = jail.join_many(["inside/a.txt", "../b.txt"]);
+    assert_eq!(
+        results[0].as_ref().unwrap(),
+        &jail.join("inside/a.txt").unwrap()
+    );
+    assert_eq!(
+        results[0].as_ref().unwrap(),
+        &jail.root().join("inside/a.txt")
+    );
+    assert!(matches!(results[1], Err(JailError::EscapedRoot { .. })));
+}
@@ -0,0 +1,1234 @@
+//! Tests for `JailBuilder` options.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+#[test]
+fn builder_defaults_match_new() {
+    let dir = tempdir().unwrap();
+    let built = Jail::builder(dir.path()).build().unwrap();
+    assert_eq!(built, Jail::new(dir.path()).unwrap());
+    assert_eq!(built.join("a%2e").unwrap(), built.root().join("a%2e"));
+}
+
+#[test]
+fn builder_rejects_invalid_root() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("file"), b"x").unwrap();
+    assert!(matches!(
+        Jail::builder(dir.path().join("file")).build(),
+        Err(JailError::InvalidRoot(_))
+    ));
+}
+
+#[test]
+fn create_root_creates_missing_root() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("a/b");
+    assert!(Jail::new(&root).is_err());
+
+    let jail = Jail::builder(&root)
+        .create_root(true, None)
+        .build()
+        .unwrap();
+    assert!(root.is_dir());
+    assert_eq!(jail.root(), root.canonicalize().unwrap());
+
+    // Existing roots are fine; files are still not roots
+    assert!(Jail::builder(&root).create_root(true, None).build().is_ok());
+    fs::write(dir.path().join("file"), b"x").unwrap();
+    assert!(Jail::builder(dir.path().join("file"))
+        .create_root(true, None)
+        .build()
+        .is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn create_root_applies_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("private");
+    Jail::builder(&root)
+        .create_root(true, Some(0o700))
+        .require_secure_root(true)
+        .build()
+        .unwrap();
+    let mode = fs::metadata(&root).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
+
+#[test]
+fn percent_decode_decodes_once() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+    assert_ne!(jail, Jail::new(dir.path()).unwrap());
+
+    assert_eq!(
+        jail.join("my%20file.txt").unwrap(),
+        jail.root().join("my file.txt")
+    );
+    assert_eq!(jail.join("a%2Fb").unwrap(), jail.root().join("a").join("b"));
+}
+
+#[test]
+fn percent_decode_rejects_encoded_traversal() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        jail.join("%2e%2e%2fsecret"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(matches!(
+        jail.join("%2Fetc%2Fpasswd"),
+        Err(JailError::InvalidPath(_))
+    ));
+    assert!(matches!(jail.join("a%00b"), Err(JailError::InvalidPath(_))));
+}
+
+#[test]
+fn percent_decode_rejects_double_encoding() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    for input in ["%252e%252e%252f", "a%252Fb", "%25252e", "%255c"] {
+        assert!(
+            matches!(jail.join(input), Err(JailError::InvalidPath(_))),
+            "{input}"
+        );
+    }
+    // A literal percent sign followed by ordinary text is fine
+    assert_eq!(jail.join("100%25").unwrap(), jail.root().join("100%"));
+}
+
+#[test]
+fn percent_decode_rejects_malformed_escapes() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    for input in ["%", "a%2", "%zz", "%2g"] {
+        assert!(
+            matches!(jail.join(input), Err(JailError::InvalidPath(_))),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn percent_decode_applies_to_operations() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    jail.write("hello%20world.txt", b"hi").unwrap();
+    assert_eq!(fs::read(dir.path().join("hello world.txt")).unwrap(), b"hi");
+    assert_eq!(jail.read("hello%20world.txt").unwrap(), b"hi");
+    assert!(jail.write("%2e%2e%2fescape.txt", b"x").is_err());
+}
+
+#[test]
+fn percent_decode_does_not_redecode_jailed_paths() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("x%41")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    // "x%2541" decodes to the directory literally named "x%41"
+    let sub = jail.join_typed("x%2541").unwrap();
+    assert_eq!(sub.relative(), std::path::Path::new("x%41"));
+    let file = sub.join("a%20b").unwrap();
+    assert_eq!(file.as_path(), jail.root().join("x%41").join("a b"));
+}
+
+#[test]
+#[cfg(windows)]
+fn alternate_data_streams_rejected_by_default() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    for input in ["file.txt:hidden", "file.txt:hidden:$DATA", "dir:x/file.txt"] {
+        assert!(
+            matches!(jail.join(input), Err(JailError::InvalidPath(_))),
+            "{input}"
+        );
+    }
+
+    let jail = Jail::builder(dir.path())
+        .allow_alternate_streams(true)
+        .build()
+        .unwrap();
+    assert!(jail.join("file.txt:hidden").is_ok());
+}
+
+#[test]
+#[cfg(unix)]
+fn colons_are_ordinary_characters_on_unix() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    assert_eq!(
+        jail.join("file.txt:hidden").unwrap(),
+        jail.root().join("file.txt:hidden")
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn case_insensitive_root_comparison() {
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let shouted = std::path::PathBuf::from(root.to_string_lossy().to_uppercase());
+    assert_ne!(shouted, root);
+
+    // Exact comparison: a differently cased root is outside
+    let jail = Jail::new(&root).unwrap();
+    assert!(matches!(
+        jail.symlink(shouted.join("target"), "link"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+
+    let jail = Jail::builder(&root).case_insensitive(true).build().unwrap();
+    jail.symlink(shouted.join("target"), "link").unwrap();
+    assert!(jail
+        .symlink(shouted.join("..").join("outside"), "other")
+        .is_err());
+}
+
+#[test]
+fn percent_decode_does_not_redecode_jailed_path_operations() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    // "x%2541" decodes once to the literal name "x%41"
+    let path = jail.join_typed("x%2541").unwrap();
+    path.write(b"data").unwrap();
+    assert_eq!(fs::read(dir.path().join("x%41")).unwrap(), b"data");
+    assert_eq!(path.read().unwrap(), b"data");
+    path.remove_file().unwrap();
+    assert!(!dir.path().join("x%41").exists());
+}
+
+#[test]
+fn allowed_extensions_accept_listed_suffixes() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["png", ".PDF", "tar.gz"])
+        .build()
+        .unwrap();
+
+    for ok in ["photo.png", "photo.PNG", "a/b/report.pdf", "backup.tar.gz"] {
+        assert!(jail.join(ok).is_ok(), "{ok}");
+    }
+}
+
+#[test]
+fn allowed_extensions_reject_everything_else() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["png", "tar.gz"])
+        .build()
+        .unwrap();
+
+    for bad in [
+        "shell.php",
+        "photo.png.exe",
+        ".png",
+        "png",
+        "photo.png.",
+        "photo.png ",
+        "photo.",
+        "gz",
+        ".tar.gz",
+        "backup.gz",
+    ] {
+        let err = jail.join(bad).unwrap_err();
+        assert!(
+            matches!(&err, JailError::InvalidPath(msg) if msg.contains("extension")),
+            "{bad}: {err}"
+        );
+    }
+}
+
+#[test]
+fn allowed_extensions_exempt_directories() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+    fs::write(dir.path().join("alice/a.png"), "x").unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["png"])
+        .build()
+        .unwrap();
+
+    assert!(jail.join("").is_ok());
+    assert!(jail.join("alice").is_ok());
+    assert_eq!(jail.read_dir("alice").unwrap().count(), 1);
+    // A directory that doesn't exist yet is indistinguishable from a file
+    assert!(jail.join("bob").is_err());
+    assert!(jail.create_dir_all("bob").is_err());
+}
+
+#[test]
+fn allowed_extensions_apply_to_operations() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["txt"])
+        .build()
+        .unwrap();
+
+    jail.write("notes.txt", "x").unwrap();
+    assert!(jail.write("notes.sh", "x").is_err());
+    assert!(jail.rename("notes.txt", "notes.sh").is_err());
+    assert!(!dir.path().join("notes.sh").exists());
+}
+
+#[test]
+fn allowed_extensions_apply_to_listings() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("a.txt"), "x").unwrap();
+    fs::write(dir.path().join("a.php"), "x").unwrap();
+    fs::write(dir.path().join("docs/b.TXT"), "x").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("a.php", dir.path().join("c.txt")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["txt"])
+        .build()
+        .unwrap();
+
+    let mut listed: Vec<_> = jail
+        .read_dir("")
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_owned())
+        .collect();
+    listed.sort();
+    assert_eq!(listed, ["a.txt", "docs"]);
+
+    let mut walked: Vec<_> = jail
+        .walk("")
+        .unwrap()
+        .map(|e| e.unwrap().path().display_relative().to_string())
+        .collect();
+    walked.sort();
+    assert_eq!(walked, ["a.txt", "docs", "docs/b.TXT"]);
+
+    let globbed: Vec<_> = jail
+        .glob("*")
+        .unwrap()
+        .map(|p| p.unwrap().display_relative().to_string())
+        .collect();
+    assert_eq!(globbed, ["a.txt", "docs"]);
+    assert!(jail.glob("a.php").unwrap().next().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn allowed_extensions_check_symlink_targets() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("shell.php"), "x").unwrap();
+    std::os::unix::fs::symlink("shell.php", dir.path().join("photo.png")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["png"])
+        .build()
+        .unwrap();
+
+    assert!(jail.join("photo.png").is_err());
+}
+
+#[test]
+fn max_depth_rejects_deep_paths() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).max_depth(3).build().unwrap();
+
+    assert!(jail.join("a/b/c").is_ok());
+    assert!(jail.join("./a/b/c").is_ok());
+    assert!(jail.join_lexical("a/b/c").is_ok());
+    for deep in ["a/b/c/d", "a/../b/c", "a/b/c/.."] {
+        assert!(
+            matches!(jail.join(deep), Err(JailError::TooDeep { max: 3, .. })),
+            "{deep}"
+        );
+    }
+
+    let deep = "x/".repeat(10_000);
+    let err = jail.join(&deep).unwrap_err();
+    assert!(matches!(
+        err,
+        JailError::TooDeep {
+            depth: 10_000,
+            max: 3
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "path has 10000 components, more than the limit of 3"
+    );
+    assert!(matches!(
+        jail.join_lexical(&deep),
+        Err(JailError::TooDeep { .. })
+    ));
+    assert!(matches!(
+        jail.remove_file(&deep),
+        Err(JailError::TooDeep { .. })
+    ));
+}
+
+#[test]
+fn max_path_len_rejects_long_paths() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .max_path_len(Some(
+            dir.path().canonicalize().unwrap().as_os_str().len() + 10,
+        ))
+        .build()
+        .unwrap();
+
+    assert!(jail.join("123456789").is_ok());
+    let err = jail.join("1234567890").unwrap_err();
+    assert!(matches!(err, JailError::TooLong { .. }), "{err}");
+    assert!(err.to_string().contains("exceeds the limit"));
+    // The limit applies to the result, so `..` can bring a path back under it
+    assert!(jail.join("1234567890/..").is_ok());
+    assert!(matches!(
+        jail.join_lexical("1234567890"),
+        Err(JailError::TooLong { .. })
+    ));
+    assert!(matches!(
+        jail.remove_file("1234567890"),
+        Err(JailError::TooLong { .. })
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn max_path_len_defaults_to_platform_limit() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let long = "a/".repeat(3000);
+    assert!(matches!(jail.join(&long), Err(JailError::TooLong { .. })));
+
+    let unlimited = Jail::builder(dir.path())
+        .max_path_len(None)
+        .build()
+        .unwrap();
+    assert!(unlimited.join(&long).is_ok());
+}
+
+#[test]
+fn to_builder_keeps_options() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .max_depth(2)
+        .deny("*.php")
+        .build()
+        .unwrap();
+
+    let same = jail.to_builder().build().unwrap();
+    assert_eq!(same, jail);
+
+    let stricter = jail.to_builder().deny_hidden(true).build().unwrap();
+    assert_ne!(stricter, jail);
+    assert!(stricter.join(".env").is_err());
+    assert!(stricter.join("shell.php").is_err());
+    assert!(matches!(
+        stricter.join("a/b/c"),
+        Err(JailError::TooDeep { .. })
+    ));
+    assert!(jail.join(".env").is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn deny_symlinks_rejects_links_inside_the_jail() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    fs::write(dir.path().join("real/file.txt"), "x").unwrap();
+    symlink("real", dir.path().join("link")).unwrap();
+    symlink("real/file.txt", dir.path().join("file-link")).unwrap();
+
+    let lenient = Jail::new(dir.path()).unwrap();
+    assert!(lenient.join("link/file.txt").is_ok());
+
+    let jail = Jail::builder(dir.path())
+        .deny_symlinks(true)
+        .build()
+        .unwrap();
+    assert!(jail.join("real/file.txt").is_ok());
+    assert!(jail.join("real/new.txt").is_ok());
+    for linked in ["link", "link/file.txt", "link/new.txt", "file-link"] {
+        let err = jail.join(linked).unwrap_err();
+        assert!(
+            matches!(&err, JailError::SymlinkNotAllowed(p) if p.ends_with(linked.split('/').next().unwrap())),
+            "{linked}: {err}"
+        );
+    }
+    assert!(matches!(
+        jail.read("file-link"),
+        Err(JailError::SymlinkNotAllowed(_))
+    ));
+
+    // Listings report symlinked entries instead of following them
+    let errors = jail
+        .read_dir("")
+        .unwrap()
+        .filter(|e| matches!(e, Err(JailError::SymlinkNotAllowed(_))))
+        .count();
+    assert_eq!(errors, 2);
+    assert!(jail.glob("link").unwrap().any(|e| e.is_err()));
+
+    // No new links, but existing ones can be removed
+    assert!(matches!(
+        jail.symlink("real", "another"),
+        Err(JailError::SymlinkNotAllowed(_))
+    ));
+    jail.remove_file("link").unwrap();
+    assert!(!dir.path().join("link").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn allow_symlink_target_accepts_links_into_allowed_dirs() {
+    use std::os::unix::fs::symlink;
+
+    let shared = tempdir().unwrap();
+    fs::create_dir(shared.path().join("fonts")).unwrap();
+    fs::write(shared.path().join("fonts/a.ttf"), "font").unwrap();
+    fs::write(shared.path().join("secret.txt"), "secret").unwrap();
+    symlink("../secret.txt", shared.path().join("fonts/escape")).unwrap();
+
+    let dir = tempdir().unwrap();
+    symlink(shared.path().join("fonts"), dir.path().join("fonts")).unwrap();
+    symlink(shared.path().join("secret.txt"), dir.path().join("secret")).unwrap();
+
+    assert!(matches!(
+        Jail::new(dir.path()).unwrap().join("fonts/a.ttf"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+
+    let jail = Jail::builder(dir.path())
+        .allow_symlink_target(shared.path().join("fonts"))
+        .build()
+        .unwrap();
+    let font = jail.join("fonts/a.ttf").unwrap();
+    assert_eq!(font, jail.root().join("fonts/a.ttf"));
+    assert_eq!(fs::read_to_string(&font).unwrap(), "font");
+    assert_eq!(jail.read_to_string("fonts/a.ttf").unwrap(), "font");
+    assert_eq!(
+        jail.relative(&font).unwrap(),
+        std::path::Path::new("fonts/a.ttf")
+    );
+    assert_eq!(
+        jail.join_typed("fonts/a.ttf").unwrap().relative(),
+        std::path::Path::new("fonts/a.ttf")
+    );
+    assert!(jail.join("fonts/new.ttf").is_ok());
+
+    // Only the allowed directory, and only through a link
+    for bad in ["secret", "fonts/escape", "fonts/../../etc"] {
+        assert!(jail.join(bad).is_err(), "{bad}");
+    }
+    assert!(jail.contains(shared.path().join("fonts/a.ttf")).is_err());
+
+    // deny_symlinks wins
+    let strict = jail.to_builder().deny_symlinks(true).build().unwrap();
+    assert!(strict.join("fonts/a.ttf").is_err());
+}
+
+#[test]
+fn allow_symlink_target_must_exist() {
+    let dir = tempdir().unwrap();
+    let err = Jail::builder(dir.path())
+        .allow_symlink_target(dir.path().join("missing"))
+        .build()
+        .unwrap_err();
+    assert!(matches!(err, JailError::InvalidPath(_)), "{err}");
+}
+
+#[cfg(unix)]
+#[test]
+fn reject_hard_links_refuses_shared_files() {
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("shadow"), "secret").unwrap();
+
+    let dir = tempdir().unwrap();
+    fs::hard_link(outside.path().join("shadow"), dir.path().join("shadow")).unwrap();
+    fs::write(dir.path().join("plain.txt"), "ok").unwrap();
+
+    let lax = Jail::new(dir.path()).unwrap();
+    assert_eq!(lax.read_to_string("shadow").unwrap(), "secret");
+    assert_eq!(
+        std::os::unix::fs::MetadataExt::nlink(&lax.metadata("shadow").unwrap()),
+        2
+    );
+
+    let jail = Jail::builder(dir.path())
+        .reject_hard_links(true)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        jail.read("shadow"),
+        Err(JailError::HardLinked { links: 2, .. })
+    ));
+    assert!(matches!(
+        jail.metadata("shadow"),
+        Err(JailError::HardLinked { .. })
+    ));
+    assert!(matches!(
+        jail.write("shadow", "overwritten"),
+        Err(JailError::HardLinked { .. })
+    ));
+    // Rejected before truncating
+    assert_eq!(
+        fs::read_to_string(outside.path().join("shadow")).unwrap(),
+        "secret"
+    );
+
+    assert_eq!(jail.read_to_string("plain.txt").unwrap(), "ok");
+    jail.write("new.txt", "fresh").unwrap();
+    assert!(jail.metadata(".").unwrap().is_dir());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn one_file_system_rejects_mounts_inside_the_jail() {
+    use std::os::unix::fs::MetadataExt;
+
+    // No mounting in tests: use /dev, which usually has other filesystems
+    // (/dev/shm, /dev/pts) mounted on it
+    let Ok(dev) = fs::metadata("/dev") else {
+        return;
+    };
+    let Some(mount) = fs::read_dir("/dev")
+        .unwrap()
+        .filter_map(Result::ok)
+        .find(|entry| {
+            entry.file_type().is_ok_and(|t| t.is_dir())
+                && entry.metadata().is_ok_and(|m| m.dev() != dev.dev())
+        })
+    else {
+        return;
+    };
+    let name = mount.file_name();
+
+    assert!(Jail::new("/dev").unwrap().join(&name).is_ok());
+
+    let jail = Jail::builder("/dev").one_file_system(true).build().unwrap();
+    assert!(
+        matches!(jail.join(&name), Err(JailError::CrossDevice(_))),
+        "{name:?}"
+    );
+    assert!(matches!(
+        jail.join(std::path::Path::new(&name).join("x")),
+        Err(JailError::CrossDevice(_))
+    ));
+    assert!(jail.join("null").is_ok());
+    assert!(jail
+        .read_dir(".")
+        .unwrap()
+        .any(|entry| matches!(entry, Err(JailError::CrossDevice(_)))));
+}
+
+#[test]
+fn one_file_system_accepts_the_root_filesystem() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/a.txt"), "a").unwrap();
+    let jail = Jail::builder(dir.path())
+        .one_file_system(true)
+        .build()
+        .unwrap();
+    assert_eq!(jail.read_to_string("sub/a.txt").unwrap(), "a");
+    assert!(jail.join("sub/new.txt").is_ok());
+    assert!(jail.subjail("sub").unwrap().join("a.txt").is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn require_secure_root_rejects_world_writable_roots() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir(&root).unwrap();
+    fs::create_dir(root.join("shared")).unwrap();
+    let set_mode = |path: &std::path::Path, mode| {
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap()
+    };
+
+    let secure = || Jail::builder(&root).require_secure_root(true).build();
+    set_mode(&root, 0o777);
+    assert!(Jail::new(&root).is_ok());
+    assert!(matches!(
+        secure(),
+        Err(JailError::InsecureRoot { path, .. }) if path == root.canonicalize().unwrap()
+    ));
+
+    // Sticky directories like /tmp are fine
+    set_mode(&root, 0o1777);
+    assert!(secure().is_ok());
+
+    set_mode(&root, 0o755);
+    let jail = secure().unwrap();
+    set_mode(&root.join("shared"), 0o777);
+    assert!(matches!(
+        jail.subjail("shared"),
+        Err(JailError::InsecureRoot { .. })
+    ));
+    assert!(Jail::new(&root).unwrap().subjail("shared").is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn require_root_owner_checks_the_owner() {
+    use std::os::unix::fs::{chown, MetadataExt};
+
+    let dir = tempdir().unwrap();
+    let root = dir.path().join("root");
+    fs::create_dir(&root).unwrap();
+    let me = fs::metadata(&root).unwrap().uid();
+
+    assert!(Jail::builder(&root)
+        .require_root_owner(true, false)
+        .build()
+        .is_ok());
+
+    // Changing owners needs root
+    if me != 0 {
+        return;
+    }
+    chown(&root, Some(4242), None).unwrap();
+    let owned = || Jail::builder(&root).require_root_owner(true, false);
+    assert!(matches!(
+        owned().build(),
+        Err(JailError::UntrustedOwner { uid: 4242, .. })
+    ));
+    assert!(owned().allow_root_owner(4242).build().is_ok());
+
+    // Ancestors are only checked when asked for
+    chown(&root, Some(0), None).unwrap();
+    chown(dir.path(), Some(4242), None).unwrap();
+    assert!(owned().build().is_ok());
+    let err = Jail::builder(&root)
+        .require_root_owner(true, true)
+        .build()
+        .unwrap_err();
+    assert!(
+        matches!(&err, JailError::UntrustedOwner { path, uid: 4242 } if path == &dir.path().canonicalize().unwrap()),
+        "{err}"
+    );
+    chown(dir.path(), Some(0), None).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn verification_cache_remembers_directories_until_cleared() {
+    use std::os::unix::fs::symlink;
+    use std::time::Duration;
+
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .verification_cache(Duration::from_secs(3600))
+        .build()
+        .unwrap();
+    let uncached = Jail::new(dir.path()).unwrap();
+    for path in ["a/b/file.txt", "a/b", "a/../a/b/c", "../x", "a/b/../../.."] {
+        assert_eq!(
+            format!("{:?}", jail.join(path)),
+            format!("{:?}", uncached.join(path)),
+            "{path}"
+        );
+    }
+
+    // Replaced behind the jail's back: trusted until cleared
+    fs::rename(dir.path().join("a"), dir.path().join("old")).unwrap();
+    symlink(outside.path(), dir.path().join("a")).unwrap();
+    assert!(jail.join("a/b/new.txt").is_ok());
+    assert!(uncached.join("a/b/new.txt").is_err());
+    jail.clone().clear_verification_cache();
+    assert!(matches!(
+        jail.join("a/b/new.txt"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+
+    // Changes made through the jail drop the affected entries
+    fs::remove_file(dir.path().join("a")).unwrap();
+    fs::create_dir(dir.path().join("a")).unwrap();
+    assert!(jail.join("a/new.txt").is_ok());
+    jail.remove_dir("a").unwrap();
+    symlink(outside.path(), dir.path().join("a")).unwrap();
+    assert!(jail.join("a/new.txt").is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn verification_cache_expires() {
+    use std::os::unix::fs::symlink;
+    use std::time::Duration;
+
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("a")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .verification_cache(Duration::ZERO)
+        .build()
+        .unwrap();
+    assert!(jail.join("a/file.txt").is_ok());
+    fs::remove_dir(dir.path().join("a")).unwrap();
+    symlink(outside.path(), dir.path().join("a")).unwrap();
+    assert!(jail.join("a/file.txt").is_err());
+    assert_ne!(jail, Jail::new(dir.path()).unwrap());
+}
+
+#[test]
+fn trusted_tree_joins_lexically() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("css")).unwrap();
+    fs::write(dir.path().join("css/site.css"), "").unwrap();
+    let jail = Jail::builder(dir.path())
+        .trusted_tree(true)
+        .deny_hidden(true)
+        .allowed_extensions(["css"])
+        .build()
+        .unwrap();
+    let root = jail.root();
+
+    assert_eq!(
+        jail.join("css/../css/site.css").unwrap(),
+        root.join("css/site.css")
+    );
+    assert_eq!(jail.join("css").unwrap(), root.join("css"));
+    assert_eq!(jail.join("new/a.css").unwrap(), root.join("new/a.css"));
+    assert!(matches!(
+        jail.join("css/../../x.css"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(matches!(
+        jail.join("/etc/a.css"),
+        Err(JailError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        jail.join(".git/a.css"),
+        Err(JailError::InvalidPath(_))
+    ));
+    assert!(matches!(
+        jail.join("css/app.js"),
+        Err(JailError::InvalidPath(_))
+    ));
+    assert_ne!(jail, Jail::new(dir.path()).unwrap());
+
+    // The assertion is the caller's: symlinks are not looked at
+    #[cfg(unix)]
+    {
+        let outside = tempdir().unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        assert_eq!(jail.join("link/a.css").unwrap(), root.join("link/a.css"));
+        assert!(Jail::new(dir.path()).unwrap().join("link/a.css").is_err());
+    }
+}
+
+#[test]
+fn join_into_reuses_the_buffer() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("css")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let mut buf = PathBuf::with_capacity(4096);
+    let capacity = buf.capacity();
+    for path in ["css/main.css", "index.html", "css/../new/page.html", "."] {
+        jail.join_into(path, &mut buf).unwrap();
+        assert_eq!(buf, jail.join(path).unwrap(), "{path}");
+        assert_eq!(buf.capacity(), capacity, "{path}");
+    }
+
+    assert!(matches!(
+        jail.join_into("../etc/passwd", &mut buf),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert_eq!(buf, PathBuf::new());
+
+    let lexical = Jail::builder(dir.path())
+        .trusted_tree(true)
+        .build()
+        .unwrap();
+    let mut buf = PathBuf::with_capacity(4096);
+    lexical.join_into("css/./main.css", &mut buf).unwrap();
+    assert_eq!(buf, jail.root().join("css/main.css"));
+    assert_eq!(buf.capacity(), capacity);
+}
+
+#[test]
+fn on_reject_reports_refusals() {
+    use path_jail::JailErrorKind;
+    use std::path::Path;
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("data")).unwrap();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&seen);
+    let jail = Jail::builder(dir.path())
+        .deny("*.php")
+        .on_reject(move |event| {
+            log.lock().unwrap().push((
+                event.input().to_path_buf(),
+                event.path().map(Path::to_path_buf),
+                event.kind(),
+                event.reason().to_string(),
+            ));
+        })
+        .build()
+        .unwrap();
+
+    assert!(jail.join("data/ok.txt").is_ok());
+    assert!(jail.read("data/missing.txt").is_err()); // I/O, not a refusal
+    assert!(seen.lock().unwrap().is_empty());
+
+    let err = jail.join("data/../../etc/passwd").unwrap_err();
+    assert!(jail.join("data/shell.php").is_err());
+    assert!(jail.read_only().join_typed("/etc/passwd").is_err());
+    let results = jail.join_many(["data/a.txt", "../b"]);
+    assert!(results[1].is_err());
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 4);
+    assert_eq!(seen[0].0, Path::new("data/../../etc/passwd"));
+    assert_eq!(seen[0].1, None);
+    assert_eq!(seen[0].2, JailErrorKind::Escape);
+    assert_eq!(seen[0].3, err.to_string());
+    assert_eq!(seen[1].2, JailErrorKind::InvalidInput);
+    assert_eq!(seen[2].0, Path::new("/etc/passwd"));
+    assert_eq!(seen[3].0, Path::new("../b"));
+}
+
+#[test]
+#[cfg(unix)]
+fn on_reject_names_the_offending_link() {
+    use std::os::unix::fs::symlink;
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    symlink(dir.path().join("real"), dir.path().join("link")).unwrap();
+    let seen = Arc::new(Mutex::new(None));
+    let log = Arc::clone(&seen);
+    let jail = Jail::builder(dir.path())
+        .deny_symlinks(true)
+        .on_reject(move |event| *log.lock().unwrap() = event.path().map(PathBuf::from))
+        .build()
+        .unwrap();
+
+    assert!(matches!(
+        jail.join("link/file.txt"),
+        Err(JailError::SymlinkNotAllowed(_))
+    ));
+    assert_eq!(
+        seen.lock().unwrap().as_deref(),
+        Some(jail.root().join("link").as_path())
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn on_reject_events_carry_operation_and_id() {
+    use path_jail::{JailErrorKind, Operation, SecurityEvent};
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "a").unwrap();
+    fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+    let events: Arc<Mutex<Vec<SecurityEvent>>> = Arc::default();
+    let log = Arc::clone(&events);
+    let jail = Jail::builder(dir.path())
+        .id("tenant-7")
+        .reject_hard_links(true)
+        .on_reject(move |event| log.lock().unwrap().push(event.to_security_event()))
+        .build()
+        .unwrap();
+    assert_eq!(jail.id(), Some("tenant-7"));
+    assert_eq!(Jail::new(dir.path()).unwrap().id(), None);
+
+    assert!(jail.join("../x").is_err());
+    assert!(matches!(
+        jail.read("a.txt"),
+        Err(JailError::HardLinked { links: 2, .. })
+    ));
+
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].operation, Operation::Validate);
+    assert_eq!(events[0].kind, JailErrorKind::Escape);
+    assert_eq!(events[0].jail_id.as_deref(), Some("tenant-7"));
+    assert_eq!(events[1].operation, Operation::Open);
+    assert_eq!(events[1].kind, JailErrorKind::Policy);
+    assert_eq!(
+        events[1].path.as_deref(),
+        Some(jail.root().join("a.txt").as_path())
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn file_and_dir_modes_apply_to_created_entries() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = |path: PathBuf| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("old.txt"), b"x").unwrap();
+    fs::set_permissions(
+        dir.path().join("old.txt"),
+        fs::Permissions::from_mode(0o600),
+    )
+    .unwrap();
+    // Wider than a typical umask allows, so only an exact chmod gets there
+    let jail = Jail::builder(dir.path())
+        .file_mode(0o666)
+        .dir_mode(0o777)
+        .build()
+        .unwrap();
+
+    jail.create_dir_all("a/b").unwrap();
+    jail.write("a/b/new.txt", b"x").unwrap();
+    jail.write_atomic("a/atomic.txt", b"x").unwrap();
+    jail.write("old.txt", b"y").unwrap();
+    jail.create_dir_all_with_mode("explicit", 0o700).unwrap();
+
+    assert_eq!(mode(dir.path().join("a")), 0o777);
+    assert_eq!(mode(dir.path().join("a/b")), 0o777);
+    assert_eq!(mode(dir.path().join("a/b/new.txt")), 0o666);
+    assert_eq!(mode(dir.path().join("a/atomic.txt")), 0o666);
+    assert_eq!(mode(dir.path().join("old.txt")), 0o600);
+    assert_eq!(mode(dir.path().join("explicit")), 0o700);
+}
+
+#[test]
+fn on_create_sees_new_entries_only() {
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("old.txt"), b"x").unwrap();
+    let created = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&created);
+    let jail = Jail::builder(dir.path())
+        .on_create(move |path| {
+            seen.lock().unwrap().push(path.to_path_buf());
+            Ok(())
+        })
+        .build()
+        .unwrap();
+
+    jail.create_dir("d").unwrap();
+    jail.write("d/new.txt", b"x").unwrap();
+    jail.write("d/new.txt", b"y").unwrap();
+    jail.write("old.txt", b"y").unwrap();
+    jail.write_atomic("atomic.txt", b"x").unwrap();
+
+    let root = jail.root();
+    assert_eq!(
+        *created.lock().unwrap(),
+        [
+            root.join("d"),
+            root.join("d/new.txt"),
+            root.join("atomic.txt")
+        ]
+    );
+}
+
+#[test]
+fn on_create_errors_are_returned() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .on_create(|_| Err(std::io::Error::other("no ACL for you")))
+        .build()
+        .unwrap();
+
+    assert!(matches!(jail.create_dir("d"), Err(JailError::Io(_))));
+    assert!(matches!(jail.write("a.txt", b"x"), Err(JailError::Io(_))));
+}
+
+#[test]
+fn append_only_refuses_changes() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("log.txt"), "a").unwrap();
+    let jail = Jail::builder(dir.path()).append_only(true).build().unwrap();
+
+    for result in [
+        jail.write("log.txt", b"b").map(drop),
+        jail.write_atomic("log.txt", b"b").map(drop),
+        jail.remove_file("log.txt"),
+        jail.rename("log.txt", "moved.txt"),
+        jail.tempfile().map(drop),
+    ] {
+        assert!(
+            matches!(result, Err(JailError::AppendOnly(_))),
+            "{result:?}"
+        );
+    }
+    assert_eq!(fs::read_to_string(dir.path().join("log.txt")).unwrap(), "a");
+
+    jail.create_dir_all("2025/07").unwrap();
+    assert!(matches!(
+        jail.remove_dir("2025/07"),
+        Err(JailError::AppendOnly(_))
+    ));
+    assert!(dir.path().join("2025/07").is_dir());
+}
+
+#[test]
+fn append_only_creates_new_files() {
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).append_only(true).build().unwrap();
+
+    jail.write("a.log", b"a").unwrap();
+    jail.write_atomic("b.log", b"b").unwrap();
+    let mut writer = jail.atomic_writer("c.log").unwrap();
+    writer.write_all(b"c").unwrap();
+    writer.commit().unwrap();
+
+    // Once there, they can't be written over
+    for name in ["a.log", "b.log", "c.log"] {
+        assert!(matches!(
+            jail.write(name, b"x"),
+            Err(JailError::AppendOnly(_))
+        ));
+        assert!(matches!(
+            jail.write_atomic(name, b"x"),
+            Err(JailError::AppendOnly(_))
+        ));
+    }
+    assert_eq!(fs::read_to_string(dir.path().join("a.log")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(dir.path().join("b.log")).unwrap(), "b");
+    assert_eq!(fs::read_to_string(dir.path().join("c.log")).unwrap(), "c");
+}
+
+#[test]
+fn append_only_atomic_writer_does_not_replace_files_created_since() {
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).append_only(true).build().unwrap();
+
+    let mut writer = jail.atomic_writer("a.log").unwrap();
+    writer.write_all(b"late").unwrap();
+    fs::write(dir.path().join("a.log"), "first").unwrap();
+    assert!(matches!(writer.commit(), Err(JailError::AppendOnly(_))));
+
+    assert_eq!(
+        fs::read_to_string(dir.path().join("a.log")).unwrap(),
+        "first"
+    );
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+#[cfg(unix)]
+fn append_only_refusals_are_reported() {
+    use path_jail::{JailErrorKind, Operation, SecurityEvent};
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("log.txt"), "a").unwrap();
+    let events: Arc<Mutex<Vec<SecurityEvent>>> = Arc::default();
+    let log = Arc::clone(&events);
+    let jail = Jail::builder(dir.path())
+        .append_only(true)
+        .on_reject(move |event| log.lock().unwrap().push(event.to_security_event()))
+        .build()
+        .unwrap();
+
+    assert!(jail.remove_file("log.txt").is_err());
+    assert!(jail.write("log.txt", b"b").is_err());
+    let events = events.lock().unwrap();
+    assert_eq!(events.len(), 2);
+    for event in events.iter() {
+        assert_eq!(event.operation, Operation::Modify);
+        assert_eq!(event.kind, JailErrorKind::Policy);
+    }
+}
+
+#[test]
+fn redact_root_hides_the_root_in_debug_and_errors() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .redact_root("<jail>")
+        .build()
+        .unwrap();
+    let root = jail.root().to_string_lossy().into_owned();
+
+    let debug = format!("{jail:?}");
+    assert!(debug.contains("\"<jail>\""), "{debug}");
+    assert!(!debug.contains(&root), "{debug}");
+
+    let path = jail.join_typed("alice/a.txt").unwrap();
+    let debug = format!("{path:?}");
+    assert!(debug.contains("<jail>"), "{debug}");
+    assert!(!debug.contains(&root), "{debug}");
+    assert_eq!(path.as_path(), jail.root().join("alice/a.txt"));
+
+    let err = jail.join("../x").unwrap_err();
+    assert_eq!(err.to_string(), "path '../x' escapes jail root '<jail>'");
+    assert!(matches!(err, JailError::EscapedRoot { root, .. } if root == Path::new("<jail>")));
+
+    fs::write(dir.path().join("alice/a.txt"), "a").unwrap();
+    let err = jail.tempfile_in("alice/a.txt").unwrap_err();
+    assert!(matches!(err, JailError::Io(_)));
+    assert!(err.to_string().contains("'<jail>/alice/a.txt'"), "{err}");
+}
+
+#[test]
+fn redact_root_is_off_by_default() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let root = jail.root().to_string_lossy().into_owned();
+
+    assert!(format!("{jail:?}").contains(&root));
+    assert!(jail.join("../x").unwrap_err().to_string().contains(&root));
+}
+
+#[test]
+fn redact_root_only_applies_to_its_own_jail() {
+    let dir = tempdir().unwrap();
+    let redacted = Jail::builder(dir.path())
+        .redact_root("<jail>")
+        .build()
+        .unwrap();
+    let plain = Jail::new(dir.path()).unwrap();
+    let root = plain.root().to_string_lossy().into_owned();
+
+    assert!(redacted
+        .join("../x")
+        .unwrap_err()
+        .to_string()
+        .contains("<jail>"));
+    let err = plain.join("../x").unwrap_err();
+    assert!(err.to_string().contains(&root), "{err}");
+    assert!(!err.to_string().contains("<jail>"), "{err}");
+    assert!(format!("{:?}", plain.join_typed("a").unwrap()).contains(&root));
+}
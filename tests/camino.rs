@@ -0,0 +1,53 @@
+//! Tests for the camino feature.
+
+#![cfg(feature = "camino")]
+
+use camino::{Utf8Path, Utf8PathBuf};
+use path_jail::{Jail, JailError, JailedPath, Utf8JailedPath};
+use tempfile::tempdir;
+
+#[test]
+fn join_utf8_returns_utf8_paths() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let path = jail.join_utf8("alice/report.pdf").unwrap();
+    assert_eq!(path.relative(), Utf8Path::new("alice/report.pdf"));
+    assert_eq!(path.as_std_path(), jail.join("alice/report.pdf").unwrap());
+    assert_eq!(path.file_name(), Some("report.pdf"));
+
+    let nested = jail.join_utf8("alice").unwrap().join("notes.txt").unwrap();
+    assert_eq!(nested.relative(), "alice/notes.txt");
+    assert!(jail.join_utf8("alice").unwrap().join("../..").is_err());
+    assert!(matches!(
+        jail.join_utf8("../etc/passwd"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+
+    let column: String = path.clone().into();
+    assert_eq!(column, path.as_str());
+    let buf: Utf8PathBuf = path.into();
+    assert!(buf.ends_with("alice/report.pdf"));
+}
+
+#[test]
+fn jailed_paths_convert() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let typed = jail.join_typed("a.txt").unwrap();
+    let utf8 = Utf8JailedPath::try_from(typed.clone()).unwrap();
+    assert_eq!(JailedPath::from(utf8), typed);
+}
+
+#[test]
+#[cfg(unix)]
+fn non_utf8_paths_are_rejected() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let typed = jail.join_typed(OsStr::from_bytes(b"caf\xe9.txt")).unwrap();
+    assert!(matches!(typed.into_utf8(), Err(JailError::InvalidPath(_))));
+}
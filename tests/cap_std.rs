@@ -0,0 +1,42 @@
+//! Tests for the cap-std feature.
+
+#![cfg(feature = "cap-std")]
+
+use cap_std::{ambient_authority, fs::Dir};
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn into_cap_dir_opens_the_root() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/a.txt"), "a").unwrap();
+
+    let cap = Jail::new(dir.path()).unwrap().into_cap_dir().unwrap();
+    assert_eq!(cap.read_to_string("sub/a.txt").unwrap(), "a");
+    // cap-std confines the handle on its own
+    assert!(cap.open("../outside").is_err());
+}
+
+#[test]
+fn from_cap_dir_round_trips() {
+    let dir = tempdir().unwrap();
+    let cap = Dir::open_ambient_dir(dir.path(), ambient_authority()).unwrap();
+    let jail = Jail::from_cap_dir(&cap, dir.path()).unwrap();
+    assert_eq!(jail, Jail::new(dir.path()).unwrap());
+    jail.write("b.txt", "b").unwrap();
+    assert_eq!(cap.read_to_string("b.txt").unwrap(), "b");
+}
+
+#[cfg(unix)]
+#[test]
+fn from_cap_dir_rejects_a_different_directory() {
+    let dir = tempdir().unwrap();
+    let other = tempdir().unwrap();
+    let cap = Dir::open_ambient_dir(other.path(), ambient_authority()).unwrap();
+    assert!(matches!(
+        Jail::from_cap_dir(&cap, dir.path()),
+        Err(JailError::InvalidRoot(_))
+    ));
+}
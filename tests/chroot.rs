@@ -0,0 +1,64 @@
+//! Tests for `Jail::chroot_and_enter`.
+//!
+//! A chroot affects the whole process, so the test re-runs itself in a
+//! child process and only the child enters the jail. Needs root; skipped
+//! otherwise.
+
+#![cfg(all(feature = "chroot", unix))]
+
+use path_jail::Jail;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::process::Command;
+use tempfile::tempdir;
+
+const CHILD_ROOT: &str = "PATH_JAIL_CHROOT_TEST_ROOT";
+
+/// In the child: chroot, drop to nobody, and check the view from inside.
+fn child(root: &str) {
+    let jail = Jail::new(root)
+        .unwrap()
+        .chroot_and_enter_as(65534, 65534)
+        .unwrap();
+    assert_eq!(jail.root(), Path::new("/"));
+    assert_eq!(std::env::current_dir().unwrap(), Path::new("/"));
+    assert_eq!(jail.read_to_string("inside.txt").unwrap(), "inside");
+    assert_eq!(
+        jail.join("a/../inside.txt").unwrap(),
+        Path::new("/inside.txt")
+    );
+    assert!(jail.join("../../etc").is_err());
+    // Outside files are gone, and so is root
+    assert!(fs::metadata(root).is_err());
+    assert!(std::os::unix::fs::chroot("/").is_err());
+}
+
+#[test]
+fn chroot_and_enter_as_confines_the_process() {
+    if let Ok(root) = std::env::var(CHILD_ROOT) {
+        child(&root);
+        return;
+    }
+
+    let dir = tempdir().unwrap();
+    if fs::metadata(dir.path()).unwrap().uid() != 0 {
+        return;
+    }
+    let root = dir.path().join("jail");
+    fs::create_dir(&root).unwrap();
+    fs::write(root.join("inside.txt"), "inside").unwrap();
+    fs::set_permissions(&root, std::os::unix::fs::PermissionsExt::from_mode(0o755)).unwrap();
+    fs::set_permissions(
+        root.join("inside.txt"),
+        std::os::unix::fs::PermissionsExt::from_mode(0o644),
+    )
+    .unwrap();
+
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "chroot_and_enter_as_confines_the_process"])
+        .env(CHILD_ROOT, &root)
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
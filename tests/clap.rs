@@ -0,0 +1,62 @@
+//! Tests for the clap feature.
+
+#![cfg(feature = "clap")]
+
+use clap::{value_parser, Arg, Command};
+use path_jail::{Jail, JailValueParser, JailedPath, JailedPathValueParser};
+use tempfile::tempdir;
+
+#[test]
+fn root_argument_parses_into_jail() {
+    let dir = tempdir().unwrap();
+    let cmd = Command::new("tool").arg(Arg::new("root").long("root").value_parser(JailValueParser));
+
+    let matches = cmd
+        .try_get_matches_from(["tool", "--root", dir.path().to_str().unwrap()])
+        .unwrap();
+    let jail: &Jail = matches.get_one("root").unwrap();
+    assert_eq!(jail.root(), dir.path().canonicalize().unwrap());
+}
+
+#[test]
+fn value_parser_macro_picks_up_jail() {
+    let dir = tempdir().unwrap();
+    let cmd = Command::new("tool").arg(Arg::new("root").value_parser(value_parser!(Jail)));
+
+    let matches = cmd
+        .try_get_matches_from(["tool", dir.path().to_str().unwrap()])
+        .unwrap();
+    assert!(matches.get_one::<Jail>("root").is_some());
+}
+
+#[test]
+fn invalid_root_is_a_usage_error() {
+    let dir = tempdir().unwrap();
+    let missing = dir.path().join("missing");
+    let cmd = Command::new("tool").arg(Arg::new("root").long("root").value_parser(JailValueParser));
+
+    let err = cmd
+        .try_get_matches_from(["tool", "--root", missing.to_str().unwrap()])
+        .unwrap_err();
+    assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    assert!(err.to_string().contains("--root"), "{err}");
+}
+
+#[test]
+fn relative_paths_parse_against_jail() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let cmd = || {
+        Command::new("tool")
+            .arg(Arg::new("file").value_parser(JailedPathValueParser::new(jail.clone())))
+    };
+
+    let matches = cmd().try_get_matches_from(["tool", "a/b.txt"]).unwrap();
+    let path: &JailedPath = matches.get_one("file").unwrap();
+    assert!(path.ends_with("a/b.txt"));
+
+    for bad in ["../escape", "/etc/passwd"] {
+        let err = cmd().try_get_matches_from(["tool", bad]).unwrap_err();
+        assert_eq!(err.kind(), clap::error::ErrorKind::ValueValidation);
+    }
+}
@@ -0,0 +1,100 @@
+//! Tests for age-based cleanup.
+
+use path_jail::{CleanupPolicy, Jail, Timestamp};
+use std::fs::{File, FileTimes};
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+use tempfile::tempdir;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+fn age(path: &Path, by: Duration) {
+    let then = SystemTime::now() - by;
+    let times = FileTimes::new().set_modified(then).set_accessed(then);
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_times(times)
+        .unwrap();
+}
+
+#[test]
+fn removes_only_old_files_under_the_given_paths() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    jail.create_dir_all("tmp/nested").unwrap();
+    jail.create_dir_all("keep").unwrap();
+    jail.write("tmp/old.bin", [0u8; 10]).unwrap();
+    jail.write("tmp/nested/old.bin", [0u8; 20]).unwrap();
+    jail.write("tmp/new.bin", b"").unwrap();
+    jail.write("keep/old.bin", b"").unwrap();
+    for old in ["tmp/old.bin", "tmp/nested/old.bin", "keep/old.bin"] {
+        age(&dir.path().join(old), 2 * HOUR);
+    }
+
+    let policy = CleanupPolicy::new(HOUR).path("tmp");
+    let report = jail.cleanup(&policy.clone().dry_run(true)).unwrap();
+    assert_eq!((report.removed.len(), report.freed), (2, 30));
+    assert!(dir.path().join("tmp/old.bin").exists());
+
+    let report = jail.cleanup(&policy).unwrap();
+    let mut removed: Vec<_> = report.removed.iter().map(|p| p.relative()).collect();
+    removed.sort();
+    assert_eq!(
+        removed,
+        [Path::new("tmp/nested/old.bin"), Path::new("tmp/old.bin")]
+    );
+    assert!(!dir.path().join("tmp/old.bin").exists());
+    assert!(dir.path().join("tmp/new.bin").exists());
+    assert!(dir.path().join("tmp/nested").is_dir());
+    assert!(dir.path().join("keep/old.bin").exists());
+
+    // No paths sweeps the whole jail
+    let report = jail.cleanup(&CleanupPolicy::new(HOUR)).unwrap();
+    assert_eq!(report.removed.len(), 1);
+    assert!(!dir.path().join("keep/old.bin").exists());
+}
+
+#[test]
+fn access_time_can_be_used() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    jail.write("cache.bin", b"x").unwrap();
+    let then = SystemTime::now() - 2 * HOUR;
+    File::options()
+        .write(true)
+        .open(dir.path().join("cache.bin"))
+        .unwrap()
+        .set_times(FileTimes::new().set_accessed(then))
+        .unwrap();
+
+    let policy = CleanupPolicy::new(HOUR);
+    assert!(jail.cleanup(&policy).unwrap().removed.is_empty());
+    let policy = policy.timestamp(Timestamp::Accessed);
+    assert_eq!(jail.cleanup(&policy).unwrap().removed.len(), 1);
+}
+
+#[test]
+#[cfg(unix)]
+fn symlinks_are_neither_followed_nor_removed() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    std::fs::write(outside.path().join("precious"), b"data").unwrap();
+    age(&outside.path().join("precious"), 2 * HOUR);
+    let jail = Jail::new(dir.path()).unwrap();
+    jail.create_dir_all("tmp").unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("tmp/escape")).unwrap();
+    std::os::unix::fs::symlink(
+        outside.path().join("precious"),
+        dir.path().join("tmp/file-link"),
+    )
+    .unwrap();
+
+    let report = jail.cleanup(&CleanupPolicy::new(HOUR).path("tmp")).unwrap();
+    assert!(report.removed.is_empty());
+    assert!(outside.path().join("precious").exists());
+    assert!(std::fs::symlink_metadata(dir.path().join("tmp/escape")).is_ok());
+
+    assert!(jail.cleanup(&CleanupPolicy::new(HOUR).path("../")).is_err());
+}
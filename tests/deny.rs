@@ -0,0 +1,156 @@
+//! Tests for filename deny rules.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+fn denied(result: Result<std::path::PathBuf, JailError>) -> bool {
+    matches!(result, Err(JailError::InvalidPath(msg)) if msg.contains("deny rule"))
+}
+
+#[test]
+fn deny_literal_names_and_globs() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .deny(".htaccess")
+        .deny(".env")
+        .deny("*.php")
+        .build()
+        .unwrap();
+
+    for bad in [
+        ".htaccess",
+        "site/.htaccess",
+        ".env",
+        "shell.php",
+        "a/b/shell.PHP",
+        ".hidden.php",
+        ".env/config.txt",
+    ] {
+        assert!(denied(jail.join(bad)), "{bad}");
+    }
+    for ok in [
+        "htaccess",
+        "site/index.html",
+        "php",
+        "env.txt",
+        "shell.php5",
+    ] {
+        assert!(jail.join(ok).is_ok(), "{ok}");
+    }
+}
+
+#[test]
+fn deny_checks_intermediate_components() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny(".git").build().unwrap();
+
+    assert!(denied(jail.join(".git/../README")));
+    assert!(denied(jail.join("repo/.git/config")));
+}
+
+#[test]
+fn deny_patterns_do_not_match_dot_components() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("a")).unwrap();
+    let jail = Jail::builder(dir.path()).deny(".*").build().unwrap();
+
+    assert!(jail.join("a/../b.txt").is_ok());
+    assert!(jail.join("./b.txt").is_ok());
+    assert!(denied(jail.join("a/.profile")));
+}
+
+#[test]
+fn deny_applies_to_entry_operations() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("notes.txt"), "x").unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+
+    assert!(jail.rename("notes.txt", "shell.php").is_err());
+    assert!(jail.write("shell.php", "x").is_err());
+    assert!(!dir.path().join("shell.php").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn deny_checks_symlink_targets() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".env"), "SECRET=1").unwrap();
+    std::os::unix::fs::symlink(".env", dir.path().join("notes.txt")).unwrap();
+    let jail = Jail::builder(dir.path()).deny(".env").build().unwrap();
+
+    assert!(denied(jail.join("notes.txt")));
+}
+
+#[test]
+fn deny_applies_to_lexical_join() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+
+    assert!(denied(jail.join_lexical("a/shell.php")));
+    assert!(jail.join_lexical("a/index.html").is_ok());
+}
+
+#[test]
+fn invalid_deny_patterns_fail_at_build() {
+    let dir = tempdir().unwrap();
+    for bad in ["", "a/b", "[abc"] {
+        let err = Jail::builder(dir.path()).deny(bad).build().unwrap_err();
+        assert!(matches!(err, JailError::InvalidPath(_)), "{bad}: {err}");
+    }
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn deny_regex() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .deny_regex(regex::Regex::new(r"(?i)^.*\.(php\d?|phtml)$").unwrap())
+        .build()
+        .unwrap();
+
+    for bad in ["shell.php", "shell.PHP5", "x/y.phtml"] {
+        assert!(denied(jail.join(bad)), "{bad}");
+    }
+    assert!(jail.join("index.html").is_ok());
+}
+
+#[test]
+fn denied_names_are_not_listed() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("site/cgi-bin")).unwrap();
+    fs::write(dir.path().join("site/index.html"), "").unwrap();
+    fs::write(dir.path().join("site/shell.PHP"), "").unwrap();
+    fs::write(dir.path().join("site/cgi-bin/run.sh"), "").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("shell.PHP", dir.path().join("site/notes.txt")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .deny("*.php")
+        .deny("cgi-bin")
+        .build()
+        .unwrap();
+
+    let listed: Vec<_> = jail
+        .read_dir("site")
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_owned())
+        .collect();
+    assert_eq!(listed, ["index.html"]);
+
+    let mut walked: Vec<_> = jail
+        .walk("")
+        .unwrap()
+        .map(|e| e.unwrap().path().display_relative().to_string())
+        .collect();
+    walked.sort();
+    assert_eq!(walked, ["site", "site/index.html"]);
+
+    let globbed: Vec<_> = jail
+        .glob("**/*")
+        .unwrap()
+        .map(|p| p.unwrap().display_relative().to_string())
+        .collect();
+    assert_eq!(globbed, ["site", "site/index.html"]);
+    assert!(jail.glob("site/*.PHP").unwrap().next().is_none());
+    assert!(jail.glob("site/cgi-bin/run.sh").unwrap().next().is_none());
+}
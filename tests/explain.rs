@@ -0,0 +1,132 @@
+//! Tests for `Jail::explain`.
+
+use path_jail::{Jail, JailError};
+use std::ffi::OsStr;
+use std::fs;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tempfile::tempdir;
+
+#[test]
+fn explain_accepts_what_join_accepts() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("uploads")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let diagnosis = jail.explain("uploads/new.txt");
+    assert!(diagnosis.is_allowed());
+    assert!(diagnosis.error().is_none());
+    assert!(diagnosis.rule().is_none());
+    assert!(diagnosis.failed_component().is_none());
+    assert_eq!(
+        diagnosis.verified_prefix(),
+        jail.join("uploads/new.txt").unwrap()
+    );
+    assert!(diagnosis.to_string().contains("allowed"));
+}
+
+#[test]
+fn explain_names_the_component_that_escapes() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let diagnosis = jail.explain("a/b/../../../etc");
+    assert!(!diagnosis.is_allowed());
+    assert!(matches!(
+        diagnosis.error(),
+        Some(JailError::EscapedRoot { .. })
+    ));
+    assert_eq!(diagnosis.rule(), Some("containment"));
+    assert_eq!(diagnosis.failed_component(), Some((5, OsStr::new(".."))));
+    assert_eq!(diagnosis.verified_prefix(), jail.root());
+}
+
+#[test]
+fn explain_reports_whole_path_rejections() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .max_depth(2)
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    let diagnosis = jail.explain("a/b/c");
+    assert!(matches!(diagnosis.error(), Some(JailError::TooDeep { .. })));
+    assert_eq!(diagnosis.rule(), Some("max_depth"));
+
+    let diagnosis = jail.explain("%zz");
+    assert_eq!(diagnosis.rule(), Some("input"));
+    assert!(diagnosis.failed_component().is_none());
+    assert_eq!(diagnosis.verified_prefix(), jail.root());
+}
+
+#[test]
+fn explain_does_not_report_rejections() {
+    let dir = tempdir().unwrap();
+    let rejected = Arc::new(AtomicUsize::new(0));
+    let counter = Arc::clone(&rejected);
+    let jail = Jail::builder(dir.path())
+        .on_reject(move |_| {
+            counter.fetch_add(1, Ordering::Relaxed);
+        })
+        .build()
+        .unwrap();
+
+    assert!(!jail.explain("../x").is_allowed());
+    assert_eq!(rejected.load(Ordering::Relaxed), 0);
+    assert!(jail.join("../x").is_err());
+    assert_eq!(rejected.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+#[cfg(unix)]
+fn explain_follows_an_escaping_symlink() {
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("uploads")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("uploads/escape")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let diagnosis = jail.explain("uploads/escape/passwd");
+    assert_eq!(diagnosis.rule(), Some("containment"));
+    assert_eq!(
+        diagnosis.failed_component(),
+        Some((2, OsStr::new("escape")))
+    );
+    assert_eq!(diagnosis.verified_prefix(), jail.root().join("uploads"));
+    assert_eq!(diagnosis.link_target(), Some(outside.path()));
+    assert_eq!(
+        diagnosis.resolved(),
+        Some(fs::canonicalize(outside.path()).unwrap().as_path())
+    );
+
+    let report = diagnosis.to_string();
+    assert!(report.contains("rejected (containment)"), "{report}");
+    assert!(report.contains("component 2, 'escape'"), "{report}");
+}
+
+#[test]
+#[cfg(unix)]
+fn explain_reports_broken_and_denied_links() {
+    let dir = tempdir().unwrap();
+    std::os::unix::fs::symlink("missing", dir.path().join("broken")).unwrap();
+    std::os::unix::fs::symlink(".", dir.path().join("here")).unwrap();
+
+    let jail = Jail::new(dir.path()).unwrap();
+    let diagnosis = jail.explain("broken/x");
+    assert_eq!(diagnosis.rule(), Some("broken link"));
+    assert_eq!(
+        diagnosis.link_target(),
+        Some(std::path::Path::new("missing"))
+    );
+    assert!(diagnosis.resolved().is_none());
+
+    let jail = Jail::builder(dir.path())
+        .deny_symlinks(true)
+        .build()
+        .unwrap();
+    let diagnosis = jail.explain("here/x");
+    assert_eq!(diagnosis.rule(), Some("deny_symlinks"));
+    assert_eq!(diagnosis.failed_component(), Some((1, OsStr::new("here"))));
+}
@@ -0,0 +1,117 @@
+//! Tests for the `FsOps` trait and its implementations.
+
+use path_jail::{FsOps, Jail, JailError};
+use std::io::{ErrorKind, Read, Write};
+use std::path::Path;
+use tempfile::tempdir;
+
+/// Application code written against the trait.
+fn archive(fs: &dyn FsOps, name: &str) -> Result<(), JailError> {
+    let mut contents = Vec::new();
+    fs.open(Path::new(name))?.read_to_end(&mut contents)?;
+    fs.create(Path::new(&format!("archive/{name}")))?
+        .write_all(&contents)?;
+    fs.remove(Path::new(name))
+}
+
+fn io_kind(result: Result<impl Sized, JailError>) -> Option<ErrorKind> {
+    match result {
+        Err(JailError::Io(err)) => Some(err.kind()),
+        _ => None,
+    }
+}
+
+/// The behavior every implementation shares, starting from a root with
+/// `a.txt` ("hello") and an empty `archive` directory.
+fn check_contract(fs: &dyn FsOps) {
+    archive(fs, "a.txt").unwrap();
+
+    let mut contents = String::new();
+    fs.open(Path::new("archive/a.txt"))
+        .unwrap()
+        .read_to_string(&mut contents)
+        .unwrap();
+    assert_eq!(contents, "hello");
+    assert_eq!(fs.read_dir(Path::new("archive")).unwrap(), ["a.txt"]);
+    assert_eq!(fs.read_dir(Path::new("")).unwrap(), ["archive"]);
+
+    assert_eq!(
+        io_kind(fs.open(Path::new("a.txt"))),
+        Some(ErrorKind::NotFound)
+    );
+    assert_eq!(
+        io_kind(fs.create(Path::new("archive/a.txt"))),
+        Some(ErrorKind::AlreadyExists)
+    );
+    assert_eq!(
+        io_kind(fs.create(Path::new("missing/b.txt"))),
+        Some(ErrorKind::NotFound)
+    );
+    assert!(fs.remove(Path::new("archive")).is_err());
+
+    for escape in ["../etc/passwd", "/etc/passwd", "archive/../../x"] {
+        assert!(fs.join(Path::new(escape)).is_err());
+        assert!(fs.open(Path::new(escape)).is_err());
+        assert!(fs.create(Path::new(escape)).is_err());
+    }
+}
+
+#[test]
+fn jail_implements_the_contract() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "hello").unwrap();
+    std::fs::create_dir(dir.path().join("archive")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    check_contract(&jail);
+    assert_eq!(
+        FsOps::join(&jail, Path::new("archive/a.txt")).unwrap(),
+        jail.root().join("archive/a.txt")
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("archive/a.txt")).unwrap(),
+        "hello"
+    );
+}
+
+#[cfg(feature = "test-util")]
+mod memory {
+    use super::*;
+    use path_jail::MemoryOps;
+
+    fn seeded() -> MemoryOps {
+        let fs = MemoryOps::new("/srv/jail").unwrap();
+        fs.insert_file("a.txt", "hello").unwrap();
+        fs.create_dir_all("archive").unwrap();
+        fs
+    }
+
+    #[test]
+    fn memory_ops_implements_the_contract() {
+        let fs = seeded();
+        check_contract(&fs);
+        assert_eq!(
+            fs.join(Path::new("archive/a.txt")).unwrap(),
+            Path::new("/srv/jail/archive/a.txt")
+        );
+    }
+
+    #[test]
+    fn clones_share_the_tree() {
+        let fs = seeded();
+        archive(&fs.clone(), "a.txt").unwrap();
+        assert_eq!(fs.contents("a.txt"), None);
+        assert_eq!(fs.contents("archive/a.txt").as_deref(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn seeding_creates_parents() {
+        let fs = MemoryOps::new("/srv/jail").unwrap();
+        fs.insert_file("a/b/c.txt", "x").unwrap();
+        assert_eq!(fs.read_dir(Path::new("a")).unwrap(), ["b"]);
+        assert!(fs.insert_file("a/b", "x").is_err());
+        assert!(fs.create_dir_all("a/b/c.txt/d").is_err());
+        assert!(fs.insert_file("../x", "x").is_err());
+        assert!(MemoryOps::new("relative").is_err());
+    }
+}
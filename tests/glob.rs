@@ -0,0 +1,98 @@
+//! Tests for `Jail::glob`.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+fn matches(jail: &Jail, pattern: &str) -> Vec<String> {
+    jail.glob(pattern)
+        .unwrap()
+        .map(|p| {
+            jail.relative(p.unwrap())
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect()
+}
+
+fn setup() -> (tempfile::TempDir, Jail) {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("reports/2025/q1")).unwrap();
+    fs::write(dir.path().join("reports/summary.pdf"), b"").unwrap();
+    fs::write(dir.path().join("reports/2025/annual.pdf"), b"").unwrap();
+    fs::write(dir.path().join("reports/2025/q1/jan.pdf"), b"").unwrap();
+    fs::write(dir.path().join("reports/2025/q1/notes.txt"), b"").unwrap();
+    fs::write(dir.path().join("reports/.hidden.pdf"), b"").unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    (dir, jail)
+}
+
+#[test]
+fn glob_star_matches_within_directory() {
+    let (_dir, jail) = setup();
+    assert_eq!(matches(&jail, "reports/*.pdf"), ["reports/summary.pdf"]);
+}
+
+#[test]
+fn glob_double_star_matches_recursively() {
+    let (_dir, jail) = setup();
+    assert_eq!(
+        matches(&jail, "reports/**/*.pdf"),
+        [
+            "reports/summary.pdf",
+            "reports/2025/annual.pdf",
+            "reports/2025/q1/jan.pdf",
+        ]
+    );
+}
+
+#[test]
+fn glob_supports_question_mark_and_classes() {
+    let (_dir, jail) = setup();
+    assert_eq!(
+        matches(&jail, "reports/2025/q?/[a-m]*"),
+        ["reports/2025/q1/jan.pdf"]
+    );
+    assert_eq!(
+        matches(&jail, "reports/2025/q1/[!j]*"),
+        ["reports/2025/q1/notes.txt"]
+    );
+}
+
+#[test]
+fn glob_skips_dotfiles_unless_explicit() {
+    let (_dir, jail) = setup();
+    assert!(!matches(&jail, "reports/*").contains(&"reports/.hidden.pdf".to_string()));
+    assert_eq!(matches(&jail, "reports/.*.pdf"), ["reports/.hidden.pdf"]);
+}
+
+#[test]
+fn glob_rejects_traversal_patterns() {
+    let (_dir, jail) = setup();
+
+    for pattern in [
+        "../*",
+        "reports/../../*",
+        "/etc/*",
+        "",
+        "bad\0*",
+        "reports/[a",
+    ] {
+        let err = jail.glob(pattern).unwrap_err();
+        assert!(matches!(err, JailError::InvalidPath(_)), "{pattern:?}");
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn glob_reports_escaping_symlinks() {
+    let (dir, jail) = setup();
+    std::os::unix::fs::symlink("/etc/passwd", dir.path().join("reports/leak.pdf")).unwrap();
+
+    let results: Vec<_> = jail.glob("reports/*.pdf").unwrap().collect();
+    assert!(results
+        .iter()
+        .any(|r| matches!(r, Err(JailError::EscapedRoot { .. }))));
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+}
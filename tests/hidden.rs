@@ -0,0 +1,121 @@
+//! Tests for the hidden-file policy.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+fn hidden(result: Result<std::path::PathBuf, JailError>) -> bool {
+    matches!(result, Err(JailError::InvalidPath(msg)) if msg.contains("hidden file"))
+}
+
+#[test]
+fn hidden_files_allowed_by_default() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    assert!(jail.join(".env").is_ok());
+}
+
+#[test]
+fn deny_hidden_rejects_dot_components() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny_hidden(true).build().unwrap();
+
+    for bad in [
+        ".env",
+        ".git/config",
+        "home/.ssh/id_rsa",
+        "a/.b/../c",
+        "...",
+    ] {
+        assert!(hidden(jail.join(bad)), "{bad}");
+    }
+    for ok in ["env", "a/b.txt", "./a", "a/../b", "a.", "a/b/..", ""] {
+        assert!(jail.join(ok).is_ok(), "{ok}");
+    }
+}
+
+#[test]
+fn allow_hidden_exempts_exact_names() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .deny_hidden(true)
+        .allow_hidden(".well-known")
+        .build()
+        .unwrap();
+
+    assert!(jail.join(".well-known/security.txt").is_ok());
+    assert!(hidden(jail.join(".well-known/.secret")));
+    assert!(hidden(jail.join(".Well-Known")));
+    assert!(hidden(jail.join(".well-known2")));
+}
+
+#[test]
+fn deny_hidden_applies_to_operations() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join(".env"), "SECRET=1").unwrap();
+    fs::write(dir.path().join("notes.txt"), "x").unwrap();
+    let jail = Jail::builder(dir.path()).deny_hidden(true).build().unwrap();
+
+    assert!(jail.read(".env").is_err());
+    assert!(jail.rename("notes.txt", ".notes.txt").is_err());
+    assert!(jail.join_lexical("a/.env").is_err());
+
+    // Nor listed, where they would come back as usable paths
+    let names: Vec<_> = jail
+        .read_dir("")
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_owned())
+        .collect();
+    assert_eq!(names, ["notes.txt"]);
+}
+
+#[test]
+fn deny_hidden_applies_to_listings() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join(".git/objects")).unwrap();
+    fs::create_dir(dir.path().join("src")).unwrap();
+    fs::write(dir.path().join("src/.env"), "SECRET=1").unwrap();
+    fs::write(dir.path().join("src/main.rs"), "").unwrap();
+    fs::write(dir.path().join(".gitignore"), "").unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink("src/.env", dir.path().join("env")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .deny_hidden(true)
+        .allow_hidden(".gitignore")
+        .build()
+        .unwrap();
+
+    let mut walked: Vec<_> = jail
+        .walk("")
+        .unwrap()
+        .map(|e| e.unwrap().path().display_relative().to_string())
+        .collect();
+    walked.sort();
+    assert_eq!(walked, [".gitignore", "src", "src/main.rs"]);
+
+    let globbed: Vec<_> = jail
+        .glob("**/*")
+        .unwrap()
+        .map(|p| p.unwrap().display_relative().to_string())
+        .collect();
+    assert!(
+        !globbed
+            .iter()
+            .any(|p| p.contains(".env") || p.contains(".git/")),
+        "{globbed:?}"
+    );
+    assert!(jail.glob("src/.env").unwrap().next().is_none());
+    assert!(jail.glob(".git/*").unwrap().next().is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn deny_hidden_checks_symlink_targets() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join(".git")).unwrap();
+    fs::write(dir.path().join(".git/config"), "x").unwrap();
+    std::os::unix::fs::symlink(".git", dir.path().join("repo")).unwrap();
+    let jail = Jail::builder(dir.path()).deny_hidden(true).build().unwrap();
+
+    assert!(hidden(jail.join("repo/config")));
+}
@@ -0,0 +1,177 @@
+//! Tests for `JailedDir` (openat feature).
+
+#![cfg(all(feature = "openat", unix))]
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::Path;
+use tempfile::tempdir;
+
+#[test]
+fn open_dir_validates_and_pins_the_directory() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("users/alice/photos")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let alice = jail.open_dir("users/alice").unwrap();
+    assert_eq!(alice.path(), jail.root().join("users/alice"));
+    assert_eq!(alice.relative(), Path::new("users/alice"));
+    assert_eq!(jail.open_dir(".").unwrap().relative(), Path::new(""));
+
+    // The handle follows the directory, not the path
+    fs::rename(dir.path().join("users/alice"), dir.path().join("moved")).unwrap();
+    let photos = alice.open_dir("photos").unwrap();
+    assert_eq!(photos.relative(), Path::new("users/alice/photos"));
+    assert!(jail.open_dir("users/alice").is_err());
+}
+
+#[test]
+fn open_dir_rejects_escapes_and_files() {
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("file.txt"), "x").unwrap();
+    symlink(outside.path(), dir.path().join("out")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.open_dir("../").is_err());
+    assert!(jail.open_dir("out").is_err());
+    assert!(jail.open_dir("file.txt").is_err());
+    assert!(jail.open_dir("missing").is_err());
+}
+
+#[test]
+fn relative_paths_stay_below_the_handle() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    fs::create_dir(dir.path().join("c")).unwrap();
+    symlink("../c", dir.path().join("a/to_c")).unwrap();
+    let jail = Jail::builder(dir.path()).deny("secret*").build().unwrap();
+    fs::create_dir(dir.path().join("a/secrets")).unwrap();
+
+    let a = jail.open_dir("a").unwrap();
+    assert!(a.open_dir("b").is_ok());
+    assert!(a.open_dir("./b/.").is_ok());
+    assert!(matches!(a.open_dir("../c"), Err(JailError::InvalidPath(_))));
+    assert!(matches!(a.open_dir("/etc"), Err(JailError::InvalidPath(_))));
+    // Symlinks are not followed, even inside the jail
+    assert!(a.open_dir("to_c").is_err());
+    assert!(jail.open_dir("a/to_c").is_ok());
+    // Builder options still apply
+    assert!(matches!(
+        a.open_dir("secrets"),
+        Err(JailError::InvalidPath(_))
+    ));
+}
+
+#[test]
+fn try_clone_and_raw_fd() {
+    use std::os::fd::AsRawFd;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let handle = jail.open_dir(".").unwrap();
+    let clone = handle.try_clone().unwrap();
+    assert_ne!(handle.as_raw_fd(), clone.as_raw_fd());
+    assert_eq!(clone.path(), handle.path());
+    assert_eq!(jail.read_only().open_dir(".").unwrap().path(), jail.root());
+}
+
+#[test]
+fn file_operations_relative_to_the_handle() {
+    use std::io::{Read, Write};
+
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("inbox/2024")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let inbox = jail.open_dir("inbox").unwrap();
+
+    inbox
+        .create_at("2024/a.txt")
+        .unwrap()
+        .write_all(b"hello")
+        .unwrap();
+    assert!(inbox.create_at("2024/a.txt").is_err());
+    let mut text = String::new();
+    inbox
+        .open_at("2024/a.txt")
+        .unwrap()
+        .read_to_string(&mut text)
+        .unwrap();
+    assert_eq!(text, "hello");
+    assert_eq!(jail.read_to_string("inbox/2024/a.txt").unwrap(), "hello");
+
+    let mut names = inbox.read_dir_at(".").unwrap();
+    names.sort();
+    assert_eq!(names, ["2024"]);
+    assert_eq!(inbox.read_dir_at("2024").unwrap(), ["a.txt"]);
+
+    assert!(inbox.unlink_at("2024").is_err());
+    inbox.unlink_at("2024/a.txt").unwrap();
+    assert!(!dir.path().join("inbox/2024/a.txt").exists());
+    assert!(inbox.open_at("2024/a.txt").is_err());
+    assert!(matches!(inbox.open_at("."), Err(JailError::InvalidPath(_))));
+}
+
+#[test]
+fn file_operations_refuse_symlinks_and_escapes() {
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("inbox")).unwrap();
+    fs::write(dir.path().join("inbox/real.txt"), "real").unwrap();
+    symlink("real.txt", dir.path().join("inbox/link.txt")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .allowed_extensions(["txt"])
+        .build()
+        .unwrap();
+    let inbox = jail.open_dir("inbox").unwrap();
+
+    // Swap the directory after opening: the handle still works on the original
+    fs::rename(dir.path().join("inbox"), dir.path().join("old")).unwrap();
+    symlink(outside.path(), dir.path().join("inbox")).unwrap();
+    assert!(inbox.open_at("real.txt").is_ok());
+    assert!(inbox.open_at("secret.txt").is_err());
+
+    assert!(inbox.open_at("link.txt").is_err());
+    assert!(inbox.open_at("../inbox/secret.txt").is_err());
+    assert!(matches!(
+        inbox.create_at("script.sh"),
+        Err(JailError::InvalidPath(_))
+    ));
+    // unlink removes the symlink itself
+    inbox.unlink_at("link.txt").unwrap();
+    assert!(dir.path().join("old/real.txt").exists());
+}
+
+#[test]
+fn create_at_applies_file_mode_and_on_create() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("inbox")).unwrap();
+    let created: Arc<Mutex<Vec<std::path::PathBuf>>> = Arc::default();
+    let log = Arc::clone(&created);
+    let jail = Jail::builder(dir.path())
+        .file_mode(0o600)
+        .on_create(move |path| {
+            log.lock().unwrap().push(path.to_path_buf());
+            Ok(())
+        })
+        .build()
+        .unwrap();
+
+    let inbox = jail.open_dir("inbox").unwrap();
+    inbox.create_at("message.txt").unwrap();
+    let mode = fs::metadata(dir.path().join("inbox/message.txt"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(
+        *created.lock().unwrap(),
+        [jail.root().join("inbox/message.txt")]
+    );
+}
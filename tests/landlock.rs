@@ -0,0 +1,67 @@
+//! Tests for the landlock feature.
+//!
+//! Landlock restricts the calling thread for good, so every test runs in
+//! its own thread.
+
+#![cfg(all(feature = "landlock", target_os = "linux"))]
+
+use path_jail::{Jail, Landlock, LandlockStatus};
+use std::fs;
+use std::io::ErrorKind;
+use std::thread;
+use tempfile::tempdir;
+
+#[test]
+fn enforce_landlock_denies_access_outside_the_jail() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    fs::write(dir.path().join("inside.txt"), "in").unwrap();
+    fs::write(outside.path().join("outside.txt"), "out").unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let outside_file = outside.path().join("outside.txt");
+
+    thread::spawn(move || {
+        if jail.enforce_landlock().unwrap() == LandlockStatus::Unsupported {
+            return;
+        }
+        assert_eq!(jail.read_to_string("inside.txt").unwrap(), "in");
+        jail.write("new.txt", "new").unwrap();
+        let err = fs::read(&outside_file).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+    })
+    .join()
+    .unwrap();
+
+    // Other threads are unaffected
+    assert_eq!(
+        fs::read_to_string(outside.path().join("outside.txt")).unwrap(),
+        "out"
+    );
+}
+
+#[test]
+fn read_only_jails_are_enforced_read_only() {
+    let data = tempdir().unwrap();
+    let scratch = tempdir().unwrap();
+    fs::write(data.path().join("a.txt"), "a").unwrap();
+    let data_jail = Jail::new(data.path()).unwrap().read_only();
+    let scratch_jail = Jail::new(scratch.path()).unwrap();
+    let data_root = data.path().to_path_buf();
+
+    thread::spawn(move || {
+        let status = Landlock::new()
+            .allow(&data_jail)
+            .allow(&scratch_jail)
+            .enforce()
+            .unwrap();
+        if status == LandlockStatus::Unsupported {
+            return;
+        }
+        assert_eq!(data_jail.read_to_string("a.txt").unwrap(), "a");
+        let err = fs::write(data_root.join("b.txt"), "b").unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::PermissionDenied);
+        scratch_jail.write("b.txt", "b").unwrap();
+    })
+    .join()
+    .unwrap();
+}
@@ -0,0 +1,131 @@
+//! Tests for lexical validation (`LexicalJail`, `Jail::join_lexical`,
+//! `normalize`).
+
+use path_jail::{normalize, Jail, JailError, LexicalJail};
+use std::path::Path;
+use tempfile::tempdir;
+
+#[test]
+fn lexical_jail_root_need_not_exist() {
+    let root = std::env::temp_dir()
+        .join("path_jail_does_not_exist")
+        .join("x");
+    let jail = LexicalJail::new(&root).unwrap();
+    assert_eq!(jail.root(), root);
+    assert_eq!(jail.join("a/b").unwrap(), root.join("a").join("b"));
+}
+
+#[test]
+fn lexical_jail_rejects_bad_roots() {
+    assert!(matches!(
+        LexicalJail::new("relative/root"),
+        Err(JailError::InvalidRoot(_))
+    ));
+    #[cfg(unix)]
+    {
+        assert!(LexicalJail::new("/").is_err());
+        assert!(LexicalJail::new("/srv/..").is_err());
+        assert_eq!(
+            LexicalJail::new("/srv/./data/../app").unwrap().root(),
+            std::path::Path::new("/srv/app")
+        );
+    }
+}
+
+#[test]
+fn lexical_join_normalizes() {
+    let root = std::env::temp_dir().join("lexical");
+    let jail = LexicalJail::new(&root).unwrap();
+    assert_eq!(jail.join("./a/./b/../c").unwrap(), root.join("a").join("c"));
+    assert_eq!(jail.join("a/..").unwrap(), root);
+    assert_eq!(jail.join("").unwrap(), root);
+}
+
+#[test]
+fn lexical_join_rejects_escapes() {
+    let jail = LexicalJail::new(std::env::temp_dir().join("lexical")).unwrap();
+    assert!(matches!(
+        jail.join("../etc/passwd"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(matches!(
+        jail.join("a/../../b"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(matches!(
+        jail.join("/etc/passwd"),
+        Err(JailError::InvalidPath(_))
+    ));
+    assert!(matches!(jail.join("a\0b"), Err(JailError::InvalidPath(_))));
+}
+
+#[test]
+fn join_lexical_does_not_touch_disk() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let path = jail.join_lexical("missing/dir/../file.txt").unwrap();
+    assert_eq!(path, jail.root().join("missing").join("file.txt"));
+    assert!(jail.join_lexical("../x").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn join_lexical_does_not_follow_symlinks() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    // Documented limitation: only join() sees the symlink
+    assert!(jail.join_lexical("link/file").is_ok());
+    assert!(jail.join("link/file").is_err());
+}
+
+#[test]
+fn join_lexical_applies_builder_options() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+    assert!(jail.join_lexical("%2e%2e%2fx").is_err());
+    assert_eq!(jail.join_lexical("a%20b").unwrap(), jail.root().join("a b"));
+}
+
+#[test]
+fn normalize_resolves_dots_and_separators() {
+    for (input, expected) in [
+        ("a/b", "a/b"),
+        ("a/./b", "a/b"),
+        ("a//b/", "a/b"),
+        ("a/c/../b", "a/b"),
+        ("./a/b/..", "a"),
+        ("a/..", ""),
+        ("", ""),
+    ] {
+        assert_eq!(normalize(input).unwrap(), Path::new(expected), "{input}");
+    }
+}
+
+#[test]
+fn normalize_rejects_what_join_rejects() {
+    assert!(matches!(
+        normalize("a/../.."),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(matches!(normalize("/etc"), Err(JailError::InvalidPath(_))));
+    assert!(matches!(normalize("a\0b"), Err(JailError::InvalidPath(_))));
+}
+
+#[test]
+fn normalize_matches_join_lexical() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    for input in ["a/./b", "a/c/../b", "x//y/z/..", "."] {
+        assert_eq!(
+            jail.root().join(normalize(input).unwrap()),
+            jail.join_lexical(input).unwrap(),
+            "{input}"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+//! Tests for JailManager.
+
+use path_jail::{Jail, JailError, JailManager};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tempfile::tempdir;
+
+#[test]
+fn creates_and_caches_tenant_jails() {
+    let dir = tempdir().unwrap();
+    let tenants = JailManager::new(Jail::new(dir.path()).unwrap());
+    assert!(tenants.is_empty());
+
+    let acme = tenants.get("acme").unwrap();
+    assert!(dir.path().join("acme").is_dir());
+    assert_eq!(acme.root(), dir.path().canonicalize().unwrap().join("acme"));
+    assert_eq!(tenants.get("acme").unwrap().root(), acme.root());
+    assert_eq!(tenants.len(), 1);
+
+    acme.write("a.txt", b"hi").unwrap();
+    assert!(acme.join("../globex/a.txt").is_err());
+}
+
+#[test]
+fn rejects_keys_that_are_not_plain_names() {
+    let dir = tempdir().unwrap();
+    let tenants = JailManager::new(Jail::new(dir.path()).unwrap());
+
+    for key in ["..", "../acme", "a/b", ".hidden", "", "a\0b"] {
+        assert!(
+            matches!(tenants.get(key), Err(JailError::InvalidPath(_))),
+            "{key:?}"
+        );
+    }
+    assert!(tenants.is_empty());
+}
+
+#[test]
+fn sanitizes_keys_when_asked() {
+    let dir = tempdir().unwrap();
+    let tenants = JailManager::new(Jail::new(dir.path()).unwrap()).sanitize_keys(true);
+
+    let jail = tenants.get("a/b").unwrap();
+    assert!(jail.root().ends_with("a_b"));
+    assert_eq!(tenants.get("a_b").unwrap().root(), jail.root());
+    assert_eq!(tenants.len(), 1);
+}
+
+#[test]
+fn keys_are_not_percent_decoded() {
+    let dir = tempdir().unwrap();
+    let parent = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+    let tenants = JailManager::new(parent);
+
+    for key in ["a%41", "50%off"] {
+        let jail = tenants.get(key).unwrap();
+        assert!(jail.root().ends_with(key), "{key:?}");
+        assert!(dir.path().join(key).is_dir(), "{key:?}");
+    }
+    assert!(!dir.path().join("aA").exists());
+}
+
+#[test]
+fn without_create_missing_tenants_fail() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("acme")).unwrap();
+    let tenants = JailManager::new(Jail::new(dir.path()).unwrap()).create(false);
+
+    assert!(tenants.get("acme").is_ok());
+    assert!(matches!(
+        tenants.get("globex"),
+        Err(JailError::InvalidRoot(_))
+    ));
+    assert!(!dir.path().join("globex").exists());
+}
+
+#[test]
+fn evicts_least_recently_used() {
+    let dir = tempdir().unwrap();
+    let tenants = JailManager::new(Jail::new(dir.path()).unwrap()).max_jails(2);
+
+    tenants.get("a").unwrap();
+    thread::sleep(Duration::from_millis(5));
+    tenants.get("b").unwrap();
+    thread::sleep(Duration::from_millis(5));
+    tenants.get("a").unwrap();
+    thread::sleep(Duration::from_millis(5));
+    tenants.get("c").unwrap();
+
+    assert_eq!(tenants.len(), 2);
+    assert!(!tenants.evict("b"));
+    assert!(tenants.evict("a"));
+    assert!(dir.path().join("b").is_dir());
+
+    tenants.clear();
+    assert!(tenants.is_empty());
+}
+
+#[test]
+fn drops_idle_jails() {
+    let dir = tempdir().unwrap();
+    let tenants =
+        JailManager::new(Jail::new(dir.path()).unwrap()).idle_timeout(Duration::from_millis(20));
+
+    tenants.get("a").unwrap();
+    thread::sleep(Duration::from_millis(40));
+    tenants.get("b").unwrap();
+
+    assert_eq!(tenants.len(), 1);
+    assert!(!tenants.evict("a"));
+}
+
+#[test]
+fn concurrent_gets_share_one_jail() {
+    let dir = tempdir().unwrap();
+    let tenants = Arc::new(JailManager::new(Jail::new(dir.path()).unwrap()));
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let tenants = Arc::clone(&tenants);
+            thread::spawn(move || tenants.get(&format!("t{}", i % 2)).unwrap())
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(tenants.len(), 2);
+}
+
+#[test]
+#[cfg(unix)]
+fn creates_directories_with_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let tenants = JailManager::new(Jail::new(dir.path()).unwrap()).mode(0o700);
+    tenants.get("acme").unwrap();
+
+    let mode = std::fs::metadata(dir.path().join("acme"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o700);
+}
@@ -0,0 +1,142 @@
+//! Tests for the test-util feature (`MemoryFs`, `MemoryJail`).
+
+#![cfg(feature = "test-util")]
+
+use path_jail::{Jail, JailError, MemoryFs, MemoryJail};
+use std::path::Path;
+
+fn tree() -> MemoryFs {
+    let mut fs = MemoryFs::new();
+    fs.dir("/srv/jail/uploads")
+        .file("/srv/jail/uploads/report.pdf")
+        .file("/etc/passwd")
+        .symlink("/srv/jail/inside", "uploads")
+        .symlink("/srv/jail/outside", "/etc")
+        .symlink("/srv/jail/relative_out", "../../etc")
+        .symlink("/srv/jail/broken", "missing")
+        .symlink("/srv/jail/loop", "loop");
+    fs
+}
+
+#[test]
+fn joins_existing_and_new_paths() {
+    let jail = MemoryJail::new(tree(), "/srv/jail").unwrap();
+    assert_eq!(jail.root(), Path::new("/srv/jail"));
+    assert_eq!(
+        jail.join("uploads/report.pdf").unwrap(),
+        Path::new("/srv/jail/uploads/report.pdf")
+    );
+    assert_eq!(
+        jail.join("uploads/new/deep.txt").unwrap(),
+        Path::new("/srv/jail/uploads/new/deep.txt")
+    );
+}
+
+#[test]
+fn follows_symlinks_inside() {
+    let jail = MemoryJail::new(tree(), "/srv/jail").unwrap();
+    assert_eq!(
+        jail.join("inside/report.pdf").unwrap(),
+        Path::new("/srv/jail/uploads/report.pdf")
+    );
+}
+
+#[test]
+fn rejects_escapes() {
+    let jail = MemoryJail::new(tree(), "/srv/jail").unwrap();
+    for input in [
+        "outside/passwd",
+        "relative_out",
+        "../etc/passwd",
+        "uploads/../../x",
+    ] {
+        assert!(
+            matches!(jail.join(input), Err(JailError::EscapedRoot { .. })),
+            "{input}"
+        );
+    }
+    assert!(matches!(
+        jail.join("/etc/passwd"),
+        Err(JailError::InvalidPath(_))
+    ));
+}
+
+#[test]
+fn rejects_broken_and_looping_symlinks() {
+    let jail = MemoryJail::new(tree(), "/srv/jail").unwrap();
+    assert!(matches!(
+        jail.join("broken"),
+        Err(JailError::BrokenSymlink(_))
+    ));
+    assert!(jail.join("loop").is_err());
+}
+
+#[test]
+fn tree_changes_are_seen() {
+    let mut jail = MemoryJail::new(tree(), "/srv/jail").unwrap();
+    assert!(jail.join("uploads/later").is_ok());
+    jail.fs_mut().symlink("/srv/jail/uploads/later", "/etc");
+    assert!(jail.join("uploads/later").is_err());
+}
+
+#[test]
+fn invalid_roots() {
+    assert!(matches!(
+        MemoryJail::new(tree(), "/srv/missing"),
+        Err(JailError::Io(_))
+    ));
+    assert!(matches!(
+        MemoryJail::new(tree(), "/etc/passwd"),
+        Err(JailError::InvalidRoot(_))
+    ));
+    assert!(matches!(
+        MemoryJail::new(tree(), "/"),
+        Err(JailError::InvalidRoot(_))
+    ));
+    // A symlinked root is canonicalized
+    let jail = MemoryJail::new(tree(), "/srv/jail/inside").unwrap();
+    assert_eq!(jail.root(), Path::new("/srv/jail/uploads"));
+}
+
+#[test]
+fn builder_options_apply() {
+    let jail = Jail::builder("/srv/jail")
+        .percent_decode(true)
+        .build_in_memory(tree())
+        .unwrap();
+    assert!(matches!(
+        jail.join("%2e%2e%2fetc"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert_eq!(
+        jail.join("uploads%2Freport.pdf").unwrap(),
+        Path::new("/srv/jail/uploads/report.pdf")
+    );
+}
+
+#[test]
+fn allowed_extensions_exempt_memory_directories() {
+    let jail = Jail::builder("/srv/jail")
+        .allowed_extensions(["pdf"])
+        .build_in_memory(tree())
+        .unwrap();
+    assert!(jail.join("uploads").is_ok());
+    assert!(jail.join("uploads/report.pdf").is_ok());
+    assert!(jail.join("uploads/report.exe").is_err());
+}
+
+#[test]
+fn allowed_symlink_targets_resolve_in_memory() {
+    let mut fs = tree();
+    fs.file("/usr/share/fonts/a.ttf")
+        .symlink("/srv/jail/fonts", "/usr/share/fonts");
+    let jail = Jail::builder("/srv/jail")
+        .allow_symlink_target("/usr/share/fonts")
+        .build_in_memory(fs)
+        .unwrap();
+    assert_eq!(
+        jail.join("fonts/a.ttf").unwrap(),
+        Path::new("/srv/jail/fonts/a.ttf")
+    );
+    assert!(jail.join("fonts/../../etc/passwd").is_err());
+}
@@ -0,0 +1,55 @@
+//! Tests for the metrics feature.
+
+#![cfg(feature = "metrics")]
+
+use path_jail::{Jail, JailErrorKind, JailStats};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn counts_joins_opens_and_rejections() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "a").unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+    assert_eq!(jail.stats(), JailStats::default());
+
+    jail.join("a.txt").unwrap();
+    jail.join("b.txt").unwrap();
+    assert_eq!(jail.read_to_string("a.txt").unwrap(), "a");
+    assert!(jail.join("../escape").is_err());
+    assert!(jail.join("/etc/passwd").is_err());
+    assert!(jail.join("shell.php").is_err());
+    assert!(jail.read("missing.txt").is_err()); // I/O, not a rejection
+
+    // Clones share the counters
+    let stats = jail.clone().stats();
+    assert_eq!(stats.joins, 7);
+    assert_eq!(stats.opens, 1);
+    assert_eq!(stats.rejections, 3);
+    assert_eq!(stats.rejections_of(JailErrorKind::Escape), 1);
+    assert_eq!(stats.rejections_of(JailErrorKind::InvalidInput), 2);
+    assert_eq!(stats.rejections_of(JailErrorKind::Io), 0);
+
+    // Options are equal, counts aside
+    assert_eq!(
+        jail,
+        Jail::builder(dir.path()).deny("*.php").build().unwrap()
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn counts_symlinks_followed() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    symlink("real", dir.path().join("link")).unwrap();
+    symlink("link", dir.path().join("chain")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    jail.join("link/a.txt").unwrap();
+    jail.join("chain/b.txt").unwrap();
+    jail.join("real/c.txt").unwrap();
+    assert_eq!(jail.stats().symlinks, 2);
+}
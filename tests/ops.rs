@@ -0,0 +1,575 @@
+//! Tests for filesystem operations on the jail.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn create_dir_creates_single_directory() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let created = jail.create_dir("uploads").unwrap();
+    assert!(created.is_dir());
+    assert!(created.starts_with(jail.root()));
+
+    // Parent must exist, and traversal is rejected
+    assert!(jail.create_dir("missing/child").is_err());
+    assert!(jail.create_dir("../outside").is_err());
+}
+
+#[test]
+fn create_dir_all_creates_intermediates() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let created = jail.create_dir_all("a/b/c").unwrap();
+    assert!(dir.path().join("a/b/c").is_dir());
+    assert!(created.ends_with("a/b/c"));
+
+    // Idempotent, like std::fs::create_dir_all
+    jail.create_dir_all("a/b/c").unwrap();
+    assert!(jail.create_dir_all("a/../../escape").is_err());
+}
+
+#[test]
+fn create_dir_all_fails_on_file_intermediate() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("file"), b"x").unwrap();
+    assert!(jail.create_dir_all("file/sub").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn create_dir_all_refuses_escaping_symlink_intermediate() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+    let err = jail.create_dir_all("link/sub").unwrap_err();
+    assert!(matches!(err, JailError::EscapedRoot { .. }));
+    assert!(!outside.path().join("sub").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn create_dir_all_with_mode_sets_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    jail.create_dir_all_with_mode("private/inner", 0o700)
+        .unwrap();
+    for sub in ["private", "private/inner"] {
+        let mode = fs::metadata(dir.path().join(sub))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o700);
+    }
+}
+
+#[test]
+fn remove_file_and_dir_work() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/file.txt"), b"x").unwrap();
+
+    // Non-empty directory can't be removed with remove_dir
+    assert!(jail.remove_dir("sub").is_err());
+
+    jail.remove_file("sub/file.txt").unwrap();
+    jail.remove_dir("sub").unwrap();
+    assert!(!dir.path().join("sub").exists());
+}
+
+#[test]
+fn remove_rejects_traversal_and_root() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.remove_file("../victim").is_err());
+    assert!(jail.remove_file("/etc/passwd").is_err());
+    assert!(jail.remove_dir("").is_err());
+    assert!(jail.remove_dir(".").is_err());
+    assert!(jail.remove_dir("sub/..").is_err());
+    assert!(dir.path().exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn remove_file_unlinks_symlink_not_target() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let victim = outside.path().join("victim.txt");
+    fs::write(&victim, b"keep me").unwrap();
+    std::os::unix::fs::symlink(&victim, dir.path().join("link")).unwrap();
+    std::os::unix::fs::symlink("/nonexistent", dir.path().join("broken")).unwrap();
+
+    jail.remove_file("link").unwrap();
+    jail.remove_file("broken").unwrap();
+    assert!(victim.exists());
+    assert!(fs::symlink_metadata(dir.path().join("link")).is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn remove_refuses_paths_through_escaping_symlink() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(outside.path().join("victim.txt"), b"keep me").unwrap();
+    fs::create_dir(outside.path().join("victim_dir")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+    let err = jail.remove_file("link/victim.txt").unwrap_err();
+    assert!(matches!(err, JailError::EscapedRoot { .. }));
+    assert!(jail.remove_dir("link/victim_dir").is_err());
+    assert!(outside.path().join("victim.txt").exists());
+    assert!(outside.path().join("victim_dir").exists());
+}
+
+#[test]
+fn remove_dir_all_removes_tree() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir_all(dir.path().join("tree/a/b")).unwrap();
+    fs::write(dir.path().join("tree/a/b/file.txt"), b"x").unwrap();
+    fs::write(dir.path().join("tree/top.txt"), b"y").unwrap();
+
+    jail.remove_dir_all("tree").unwrap();
+    assert!(!dir.path().join("tree").exists());
+    assert!(dir.path().exists());
+
+    assert!(jail.remove_dir_all("").is_err());
+    assert!(jail.remove_dir_all("../").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn remove_dir_all_does_not_follow_symlinks() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(outside.path().join("victim.txt"), b"keep me").unwrap();
+    fs::create_dir(dir.path().join("tree")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("tree/link")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("top_link")).unwrap();
+
+    // A symlink inside the tree is unlinked, its target is untouched
+    jail.remove_dir_all("tree").unwrap();
+    assert!(outside.path().join("victim.txt").exists());
+
+    // A path through an escaping symlink is rejected outright
+    let err = jail.remove_dir_all("top_link/sub").unwrap_err();
+    assert!(matches!(err, JailError::EscapedRoot { .. }));
+
+    // The final symlink itself is removed, not followed
+    jail.remove_dir_all("top_link").unwrap();
+    assert!(outside.path().join("victim.txt").exists());
+}
+
+#[test]
+#[cfg(all(feature = "openat", unix))]
+fn remove_dir_all_handles_deep_trees() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    // Deeper than common descriptor limits, and than a test thread's stack
+    // allows for one frame per level; built from the bottom up so no path
+    // gets long
+    fs::create_dir(dir.path().join("tree")).unwrap();
+    for _ in 0..25_000 {
+        fs::create_dir(dir.path().join("next")).unwrap();
+        fs::write(dir.path().join("next/file"), b"x").unwrap();
+        fs::rename(dir.path().join("tree"), dir.path().join("next/d")).unwrap();
+        fs::rename(dir.path().join("next"), dir.path().join("tree")).unwrap();
+    }
+
+    jail.remove_dir_all("tree").unwrap();
+    assert!(!dir.path().join("tree").exists());
+}
+
+#[test]
+fn rename_moves_within_jail() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("incoming")).unwrap();
+    fs::create_dir(dir.path().join("final")).unwrap();
+    fs::write(dir.path().join("incoming/upload.tmp"), b"data").unwrap();
+    fs::write(dir.path().join("final/report.pdf"), b"old").unwrap();
+
+    // Plain rename replaces the destination, like std::fs::rename
+    jail.rename("incoming/upload.tmp", "final/report.pdf")
+        .unwrap();
+    assert_eq!(
+        fs::read(dir.path().join("final/report.pdf")).unwrap(),
+        b"data"
+    );
+    assert!(!dir.path().join("incoming/upload.tmp").exists());
+}
+
+#[test]
+fn rename_rejects_escaping_endpoints() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let other = tempdir().unwrap();
+    let other_jail = Jail::new(other.path()).unwrap();
+
+    fs::write(dir.path().join("file.txt"), b"x").unwrap();
+
+    assert!(jail.rename("file.txt", "../stolen.txt").is_err());
+    assert!(jail.rename("../file.txt", "here.txt").is_err());
+    assert!(jail.rename("file.txt", "").is_err());
+
+    // Paths from another jail are absolute and rejected
+    let foreign = other_jail.join_typed("moved.txt").unwrap();
+    assert!(jail.rename("file.txt", &foreign).is_err());
+    assert!(dir.path().join("file.txt").exists());
+}
+
+#[test]
+fn rename_noreplace_refuses_to_clobber() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("a.txt"), b"a").unwrap();
+    fs::write(dir.path().join("b.txt"), b"b").unwrap();
+
+    let err = jail.rename_noreplace("a.txt", "b.txt").unwrap_err();
+    assert!(matches!(err, JailError::Io(e) if e.kind() == std::io::ErrorKind::AlreadyExists));
+    assert_eq!(fs::read(dir.path().join("b.txt")).unwrap(), b"b");
+
+    jail.rename_noreplace("a.txt", "c.txt").unwrap();
+    assert_eq!(fs::read(dir.path().join("c.txt")).unwrap(), b"a");
+    assert!(!dir.path().join("a.txt").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn rename_moves_symlink_not_target() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("real.txt"), b"x").unwrap();
+    std::os::unix::fs::symlink("real.txt", dir.path().join("link")).unwrap();
+
+    jail.rename("link", "moved_link").unwrap();
+    assert!(fs::symlink_metadata(dir.path().join("moved_link"))
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(dir.path().join("real.txt").exists());
+}
+
+#[test]
+fn hard_link_within_jail() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("original.txt"), b"data").unwrap();
+    jail.hard_link("original.txt", "linked.txt").unwrap();
+    assert_eq!(fs::read(dir.path().join("linked.txt")).unwrap(), b"data");
+
+    // Destination must not exist, and both ends must stay inside
+    assert!(jail.hard_link("original.txt", "linked.txt").is_err());
+    assert!(jail.hard_link("original.txt", "../escape.txt").is_err());
+    assert!(jail.hard_link("../outside.txt", "in.txt").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn hard_link_rejects_escaping_source_symlink() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(outside.path().join("secret"), b"secret").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret"), dir.path().join("link")).unwrap();
+
+    let err = jail.hard_link("link", "copy").unwrap_err();
+    assert!(matches!(err, JailError::EscapedRoot { .. }));
+    assert!(!dir.path().join("copy").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn hard_link_rejects_already_linked_source() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    // Simulate a file planted as a hard link to something outside the jail
+    let secret = outside.path().join("secret");
+    fs::write(&secret, b"secret").unwrap();
+    if fs::hard_link(&secret, dir.path().join("planted")).is_err() {
+        return; // Different filesystems, can't set up the scenario
+    }
+
+    assert!(jail.hard_link("planted", "copy").is_err());
+    assert!(!dir.path().join("copy").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_allows_internal_targets() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir_all(dir.path().join("lib")).unwrap();
+    fs::create_dir_all(dir.path().join("out")).unwrap();
+    fs::write(dir.path().join("lib/libfoo.so.1"), b"elf").unwrap();
+
+    jail.symlink("../lib/libfoo.so.1", "out/libfoo.so").unwrap();
+    assert_eq!(
+        fs::read_link(dir.path().join("out/libfoo.so")).unwrap(),
+        std::path::Path::new("../lib/libfoo.so.1")
+    );
+    assert_eq!(
+        jail.join("out/libfoo.so").unwrap(),
+        jail.join("lib/libfoo.so.1").unwrap()
+    );
+
+    // Absolute targets inside the root are fine unless relative is required
+    let abs = jail.root().join("lib/libfoo.so.1");
+    jail.symlink(&abs, "out/abs").unwrap();
+    assert!(jail.symlink_relative(&abs, "out/abs2").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_rejects_escaping_targets() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("out")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("exit")).unwrap();
+
+    assert!(jail.symlink("../../etc/passwd", "out/passwd").is_err());
+    assert!(jail.symlink("/etc/passwd", "out/passwd").is_err());
+    // Escapes through an existing symlink are caught too
+    assert!(jail.symlink("../exit/file", "out/sneaky").is_err());
+    // The link location itself is validated
+    assert!(jail.symlink("x", "../link").is_err());
+    assert!(fs::read_dir(dir.path().join("out"))
+        .unwrap()
+        .next()
+        .is_none());
+}
+
+#[test]
+fn read_and_write_round_trip() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    jail.write("notes.txt", "hello").unwrap();
+    assert_eq!(jail.read("notes.txt").unwrap(), b"hello");
+    assert_eq!(jail.read_to_string("notes.txt").unwrap(), "hello");
+
+    // write() truncates existing content
+    jail.write("notes.txt", b"hi").unwrap();
+    assert_eq!(jail.read_to_string("notes.txt").unwrap(), "hi");
+
+    jail.write("binary.bin", [0xff, 0xfe]).unwrap();
+    assert!(jail.read_to_string("binary.bin").is_err());
+}
+
+#[test]
+fn read_and_write_reject_traversal() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.read("../secret").is_err());
+    assert!(jail.read_to_string("/etc/passwd").is_err());
+    assert!(jail.write("../escape.txt", "x").is_err());
+    assert!(!dir.path().parent().unwrap().join("escape.txt").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn read_and_write_reject_escaping_symlinks() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let victim = outside.path().join("victim.txt");
+    fs::write(&victim, b"keep me").unwrap();
+    std::os::unix::fs::symlink(&victim, dir.path().join("link")).unwrap();
+
+    assert!(jail.read("link").is_err());
+    assert!(jail.write("link", "overwritten").is_err());
+    assert_eq!(fs::read(&victim).unwrap(), b"keep me");
+}
+
+#[test]
+#[cfg(unix)]
+fn set_mode_changes_permissions_inside_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    jail.write("run.sh", "#!/bin/sh").unwrap();
+    jail.create_dir("bin").unwrap();
+    jail.set_mode("run.sh", 0o750).unwrap();
+    jail.set_mode("bin", 0o700).unwrap();
+    let mode = |p: &str| {
+        fs::metadata(dir.path().join(p))
+            .unwrap()
+            .permissions()
+            .mode()
+            & 0o777
+    };
+    assert_eq!(mode("run.sh"), 0o750);
+    assert_eq!(mode("bin"), 0o700);
+
+    let mut perms = fs::metadata(dir.path().join("run.sh"))
+        .unwrap()
+        .permissions();
+    perms.set_readonly(true);
+    jail.set_permissions("run.sh", perms).unwrap();
+    assert_eq!(mode("run.sh"), 0o550);
+
+    let victim = outside.path().join("victim.txt");
+    fs::write(&victim, b"").unwrap();
+    fs::set_permissions(&victim, fs::Permissions::from_mode(0o600)).unwrap();
+    std::os::unix::fs::symlink(&victim, dir.path().join("link")).unwrap();
+    assert!(jail.set_mode("link", 0o777).is_err());
+    assert!(jail.set_mode("../escape", 0o777).is_err());
+    assert_eq!(
+        fs::metadata(&victim).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+}
+
+#[test]
+fn errors_expose_io_kind_and_os_code() {
+    use std::io::ErrorKind;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let missing = jail.read("missing.txt").unwrap_err();
+    assert!(missing.is_not_found());
+    assert!(!missing.is_permission_denied());
+    assert_eq!(missing.io_kind(), ErrorKind::NotFound);
+    #[cfg(unix)]
+    assert_eq!(missing.os_error(), Some(2)); // ENOENT
+
+    let escape = jail.read("../etc/passwd").unwrap_err();
+    assert_eq!(escape.io_kind(), ErrorKind::PermissionDenied);
+    assert!(!escape.is_not_found());
+    assert!(!escape.is_permission_denied());
+    assert_eq!(escape.os_error(), None);
+
+    let invalid = jail.join("/etc/passwd").unwrap_err();
+    assert_eq!(invalid.io_kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn errors_have_stable_kinds() {
+    use path_jail::JailErrorKind;
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("a")).unwrap();
+    let jail = Jail::builder(dir.path()).max_depth(4).build().unwrap();
+
+    let escape = jail.join("a/../../etc").unwrap_err();
+    assert_eq!(escape.kind(), JailErrorKind::Escape);
+    assert!(escape.is_escape_attempt());
+    assert!(!escape.is_invalid_input());
+
+    for input in ["/etc/passwd", "a/b/c/d/e", "a\0b"] {
+        let err = jail.join(input).unwrap_err();
+        assert_eq!(err.kind(), JailErrorKind::InvalidInput, "{input}");
+        assert!(err.is_invalid_input());
+    }
+
+    let missing = jail.read("a/missing").unwrap_err();
+    assert_eq!(missing.kind(), JailErrorKind::Io);
+    assert!(missing.is_io());
+    assert!(!missing.is_escape_attempt());
+
+    assert_eq!(
+        Jail::new(dir.path().join("nope")).unwrap_err().kind(),
+        JailErrorKind::Io
+    );
+}
+
+#[test]
+fn errors_map_to_http_statuses() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "a").unwrap();
+    let jail = Jail::builder(dir.path()).quota(10).build().unwrap();
+
+    // Refused paths look the same as missing ones
+    assert_eq!(jail.read("missing.txt").unwrap_err().http_status(), 404);
+    assert_eq!(jail.read("../etc/passwd").unwrap_err().http_status(), 404);
+    assert_eq!(jail.join("/etc/passwd").unwrap_err().http_status(), 400);
+    assert_eq!(
+        jail.write("big.bin", [0u8; 20]).unwrap_err().http_status(),
+        413
+    );
+    assert_eq!(jail.join_new("a.txt").unwrap_err().http_status(), 409);
+    assert_eq!(Jail::new("/").unwrap_err().http_status(), 500);
+}
+
+#[test]
+fn exists_validates_before_looking() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs/a.txt"), "a").unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.exists("docs/a.txt"));
+    assert!(jail.exists("docs"));
+    assert!(jail.exists(""));
+    assert!(!jail.exists("docs/b.txt"));
+    assert!(matches!(jail.try_exists("docs/b.txt"), Ok(false)));
+    assert!(matches!(jail.try_exists("docs/a.txt"), Ok(true)));
+
+    assert!(!jail.exists("../"));
+    assert!(matches!(
+        jail.try_exists("docs/../../x"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+}
+
+#[test]
+#[cfg(unix)]
+fn exists_does_not_follow_links_out() {
+    use std::os::unix::fs::symlink;
+
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("secret"), "s").unwrap();
+    let dir = tempdir().unwrap();
+    symlink(outside.path().join("secret"), dir.path().join("escape")).unwrap();
+    symlink(dir.path().join("gone"), dir.path().join("dangling")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(!jail.exists("escape"));
+    assert!(matches!(
+        jail.try_exists("escape"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(!jail.exists("dangling/x"));
+    assert!(matches!(
+        jail.try_exists("dangling/x"),
+        Err(JailError::BrokenSymlink(_))
+    ));
+}
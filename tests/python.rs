@@ -0,0 +1,114 @@
+//! Tests for the pyo3 feature.
+
+#![cfg(feature = "pyo3")]
+
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyModule};
+use std::ffi::CString;
+use tempfile::tempdir;
+
+/// Run `code` with the bindings importable as `path_jail` and the jail
+/// root in `root`.
+fn run(root: &std::path::Path, code: &str) -> PyResult<()> {
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "path_jail")?;
+        path_jail::register_python(&module)?;
+        py.import("sys")?
+            .getattr("modules")?
+            .set_item("path_jail", &module)?;
+        let globals = PyDict::new(py);
+        globals.set_item("root", root)?;
+        let code = CString::new(code).unwrap();
+        py.run(&code, Some(&globals), None)
+    })
+}
+
+#[test]
+fn join_and_contains() {
+    let dir = tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("x")).unwrap();
+    run(
+        dir.path(),
+        r#"
+import pathlib
+from path_jail import Jail, JailError, join
+
+jail = Jail(root)
+assert jail.join("a/b.txt") == jail.root / "a" / "b.txt"
+assert isinstance(jail.join("a"), pathlib.Path)
+assert join(root, "c.txt") == jail.root / "c.txt"
+assert jail.relative(jail.root / "x") == pathlib.Path("x")
+assert jail.root / "x" in jail
+assert "/etc/passwd" not in jail
+
+for bad in ["../etc/passwd", "/etc/passwd"]:
+    try:
+        jail.join(bad)
+    except JailError as err:
+        assert isinstance(err, ValueError)
+    else:
+        raise AssertionError(bad)
+"#,
+    )
+    .unwrap();
+}
+
+#[test]
+#[cfg(unix)]
+fn open_returns_file_objects() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    std::fs::write(outside.path().join("secret"), b"secret").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret"), dir.path().join("link")).unwrap();
+
+    run(
+        dir.path(),
+        r#"
+from path_jail import Jail, JailError
+
+jail = Jail(root)
+with jail.open("new.txt", "x") as f:
+    f.write("hello")
+with jail.open("new.txt") as f:
+    assert f.read() == "hello"
+with jail.open("new.txt", "ab") as f:
+    f.write(b"!")
+with jail.open("new.txt", "rb") as f:
+    assert f.read() == b"hello!"
+
+try:
+    jail.open("new.txt", "x")
+except FileExistsError:
+    pass
+else:
+    raise AssertionError("x overwrote")
+
+for bad in ["link", "../new.txt"]:
+    try:
+        jail.open(bad)
+    except JailError:
+        pass
+    else:
+        raise AssertionError(bad)
+
+try:
+    jail.open("new.txt", "r+")
+except ValueError:
+    pass
+else:
+    raise AssertionError("r+")
+
+for bad in ["wbb", "rbb", "b"]:
+    try:
+        jail.open("new.txt", bad)
+    except ValueError:
+        pass
+    else:
+        raise AssertionError(bad)
+with jail.open("new.txt") as f:
+    assert f.read() == "hello!"
+"#,
+    )
+    .unwrap();
+}
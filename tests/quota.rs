@@ -0,0 +1,277 @@
+//! Tests for the jail-wide storage quota and the per-file size limit.
+
+use path_jail::{Jail, JailError, JailErrorKind};
+use std::fs;
+use tempfile::tempdir;
+
+fn used(jail: &Jail) -> u64 {
+    jail.quota().unwrap().used
+}
+
+#[test]
+fn existing_files_are_counted_at_build() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("a.bin"), [0u8; 100]).unwrap();
+    fs::write(dir.path().join("sub/b.bin"), [0u8; 50]).unwrap();
+
+    let jail = Jail::builder(dir.path()).quota(1000).build().unwrap();
+    let usage = jail.quota().unwrap();
+    assert_eq!(
+        (usage.limit, usage.used, usage.remaining()),
+        (1000, 150, 850)
+    );
+
+    assert_eq!(Jail::new(dir.path()).unwrap().quota(), None);
+}
+
+#[test]
+fn writes_over_the_quota_fail_before_writing() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(100).build().unwrap();
+
+    jail.write("a.bin", [1u8; 60]).unwrap();
+    assert_eq!(used(&jail), 60);
+
+    let err = jail.write("b.bin", [1u8; 60]).unwrap_err();
+    assert!(matches!(
+        err,
+        JailError::QuotaExceeded {
+            limit: 100,
+            used: 60,
+            requested: 60
+        }
+    ));
+    assert_eq!(err.kind(), JailErrorKind::Policy);
+    assert_eq!(used(&jail), 60);
+
+    // Overwriting only counts the difference
+    jail.write("a.bin", [2u8; 100]).unwrap();
+    assert_eq!(used(&jail), 100);
+    assert!(jail.write("a.bin", [3u8; 101]).is_err());
+    assert_eq!(fs::read(dir.path().join("a.bin")).unwrap(), [2u8; 100]);
+    jail.write("a.bin", [4u8; 10]).unwrap();
+    assert_eq!(used(&jail), 10);
+}
+
+#[test]
+fn removals_give_space_back() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(1000).build().unwrap();
+
+    jail.create_dir_all("tree/sub").unwrap();
+    jail.write("tree/a.bin", [0u8; 100]).unwrap();
+    jail.write("tree/sub/b.bin", [0u8; 200]).unwrap();
+    jail.write("c.bin", [0u8; 300]).unwrap();
+    jail.write("d.bin", [0u8; 50]).unwrap();
+    assert_eq!(used(&jail), 650);
+
+    jail.remove_file("c.bin").unwrap();
+    assert_eq!(used(&jail), 350);
+    jail.remove_dir_all("tree").unwrap();
+    assert_eq!(used(&jail), 50);
+
+    jail.write("e.bin", [0u8; 20]).unwrap();
+    jail.rename("e.bin", "d.bin").unwrap();
+    assert_eq!(used(&jail), 20);
+}
+
+#[test]
+fn atomic_writes_are_counted() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(100).build().unwrap();
+
+    jail.write_atomic("config.json", [0u8; 40]).unwrap();
+    jail.write_atomic("config.json", [0u8; 30]).unwrap();
+    assert_eq!(used(&jail), 30);
+
+    let err = jail.write_atomic("config.json", [0u8; 80]).unwrap_err();
+    assert!(matches!(err, JailError::QuotaExceeded { .. }));
+    assert_eq!(used(&jail), 30);
+    assert_eq!(fs::read(dir.path().join("config.json")).unwrap().len(), 30);
+}
+
+#[test]
+fn persisted_temp_files_are_counted() {
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(10).build().unwrap();
+
+    let mut file = jail.tempfile().unwrap();
+    file.write_all(&[0u8; 100]).unwrap();
+    let err = file.persist("big.bin").unwrap_err();
+    assert!(matches!(err, JailError::QuotaExceeded { .. }));
+    assert!(!dir.path().join("big.bin").exists());
+
+    let mut file = jail.tempfile().unwrap();
+    file.write_all(&[0u8; 8]).unwrap();
+    file.persist("small.bin").unwrap();
+    assert_eq!(used(&jail), 8);
+}
+
+#[test]
+#[cfg(all(feature = "secure-open", target_os = "linux"))]
+fn linked_anonymous_files_are_counted() {
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(10).build().unwrap();
+
+    let mut file = jail.create_anonymous(".").unwrap();
+    file.write_all(&[0u8; 100]).unwrap();
+    let err = file.link("big.bin").unwrap_err();
+    assert!(matches!(err, JailError::QuotaExceeded { .. }));
+    assert!(!dir.path().join("big.bin").exists());
+
+    let mut file = jail.create_anonymous(".").unwrap();
+    file.write_all(&[0u8; 8]).unwrap();
+    file.link("small.bin").unwrap();
+    assert_eq!(used(&jail), 8);
+}
+
+#[test]
+fn clones_and_sub_jails_share_the_budget() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+    let jail = Jail::builder(dir.path()).quota(100).build().unwrap();
+    let alice = jail.subjail("alice").unwrap();
+
+    alice.write("notes.txt", [0u8; 70]).unwrap();
+    assert_eq!(used(&jail), 70);
+    assert!(jail.clone().write("other.txt", [0u8; 40]).is_err());
+
+    // Changes made behind the jail's back need a rescan
+    fs::write(dir.path().join("outside.bin"), [0u8; 20]).unwrap();
+    assert_eq!(used(&jail), 70);
+    alice.rescan_quota().unwrap();
+    assert_eq!(used(&jail), 90);
+}
+
+#[test]
+#[cfg(all(feature = "secure-open", unix))]
+fn file_handles_count_growth() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(100).build().unwrap();
+
+    let mut file = jail.create("stream.bin").unwrap();
+    file.write_all(&[0u8; 60]).unwrap();
+    assert_eq!(used(&jail), 60);
+
+    // Rewriting existing bytes takes no more space
+    file.seek(SeekFrom::Start(0)).unwrap();
+    file.write_all(&[1u8; 60]).unwrap();
+    assert_eq!(used(&jail), 60);
+
+    let err = file.write_all(&[0u8; 50]).unwrap_err();
+    assert!(matches!(
+        JailError::from(err),
+        JailError::QuotaExceeded { .. }
+    ));
+    assert!(file.set_len(200).is_err());
+    file.set_len(10).unwrap();
+    assert_eq!(used(&jail), 10);
+    drop(file);
+
+    let mut log = jail.open_append("stream.bin").unwrap();
+    log.write_all(&[0u8; 90]).unwrap();
+    assert_eq!(used(&jail), 100);
+    assert!(log.write_all(b"x").is_err());
+
+    jail.create_or_truncate("stream.bin").unwrap();
+    assert_eq!(used(&jail), 0);
+}
+
+#[test]
+fn files_cannot_outgrow_the_size_limit() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("legacy.bin"), [0u8; 80]).unwrap();
+    let jail = Jail::builder(dir.path()).max_file_size(50).build().unwrap();
+
+    jail.write("small.bin", [0u8; 50]).unwrap();
+    let err = jail.write("big.bin", [0u8; 51]).unwrap_err();
+    assert!(matches!(err, JailError::FileTooLarge { size: 51, max: 50 }));
+    assert_eq!(err.kind(), JailErrorKind::InvalidInput);
+
+    let err = jail.write_atomic("big.bin", [0u8; 51]).unwrap_err();
+    assert!(matches!(err, JailError::FileTooLarge { .. }));
+    assert!(!dir.path().join("big.bin").exists());
+
+    // Existing files over the limit can still be rewritten smaller
+    assert!(jail.read("legacy.bin").is_ok());
+    jail.write("legacy.bin", [0u8; 10]).unwrap();
+}
+
+#[test]
+fn persisted_files_are_held_to_the_size_limit() {
+    use std::io::Write;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).max_file_size(10).build().unwrap();
+
+    let mut file = jail.tempfile().unwrap();
+    file.write_all(&[0u8; 100]).unwrap();
+    let err = file.persist("big.bin").unwrap_err();
+    assert!(matches!(
+        err,
+        JailError::FileTooLarge { size: 100, max: 10 }
+    ));
+    assert!(!dir.path().join("big.bin").exists());
+
+    #[cfg(all(feature = "secure-open", target_os = "linux"))]
+    {
+        let mut file = jail.create_anonymous(".").unwrap();
+        file.write_all(&[0u8; 100]).unwrap();
+        let err = file.link("big.bin").unwrap_err();
+        assert!(matches!(
+            err,
+            JailError::FileTooLarge { size: 100, max: 10 }
+        ));
+        assert!(!dir.path().join("big.bin").exists());
+    }
+}
+
+#[test]
+#[cfg(all(feature = "secure-open", unix))]
+fn file_handles_enforce_the_size_limit() {
+    use std::io::{Seek, SeekFrom, Write};
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .max_file_size(100)
+        .build()
+        .unwrap();
+
+    let mut file = jail.create("upload.bin").unwrap();
+    file.write_all(&[0u8; 100]).unwrap();
+    let err = file.write_all(b"x").unwrap_err();
+    assert!(matches!(
+        JailError::from(err),
+        JailError::FileTooLarge {
+            size: 101,
+            max: 100
+        }
+    ));
+
+    // Seeking past the end does not get around it
+    file.seek(SeekFrom::Start(1000)).unwrap();
+    assert!(file.write_all(b"x").is_err());
+    assert!(file.set_len(101).is_err());
+    file.set_len(10).unwrap();
+    drop(file);
+    assert_eq!(
+        fs::metadata(dir.path().join("upload.bin")).unwrap().len(),
+        10
+    );
+
+    let mut file = jail.create_or_truncate("upload.bin").unwrap();
+    assert!(file.write_all(&[0u8; 101]).is_err());
+    let mut log = jail.open_append("log.txt").unwrap();
+    log.write_all(&[0u8; 100]).unwrap();
+    assert!(log.write_all(b"x").is_err());
+
+    fs::write(dir.path().join("big.bin"), [0u8; 200]).unwrap();
+    assert!(jail.copy("big.bin", "copy.bin").is_err());
+}
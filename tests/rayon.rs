@@ -0,0 +1,93 @@
+//! Tests for the rayon feature.
+
+#![cfg(feature = "rayon")]
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn par_join_many_matches_join_in_order() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("data/a")).unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+
+    let paths: Vec<String> = (0..2_000)
+        .map(|i| match i % 4 {
+            0 => format!("data/a/{i}.json"),
+            1 => format!("data/new/{i}.json"),
+            2 => format!("../{i}"),
+            _ => format!("data/{i}.php"),
+        })
+        .collect();
+    let results = jail.par_join_many(&paths);
+    assert_eq!(results.len(), paths.len());
+    for (path, result) in paths.iter().zip(&results) {
+        match (jail.join(path), result) {
+            (Ok(expected), Ok(actual)) => assert_eq!(&expected, actual, "{path}"),
+            (Err(expected), Err(actual)) => {
+                assert_eq!(expected.to_string(), actual.to_string(), "{path}")
+            }
+            (expected, actual) => panic!("{path}: join gave {expected:?}, par {actual:?}"),
+        }
+    }
+    assert!(matches!(results[2], Err(JailError::EscapedRoot { .. })));
+    assert!(jail.par_join_many(Vec::<&str>::new()).is_empty());
+}
+
+#[test]
+#[cfg(unix)]
+fn par_join_many_with_verification_cache() {
+    use std::os::unix::fs::symlink;
+
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    symlink(dir.path().join("real"), dir.path().join("inside")).unwrap();
+    symlink(outside.path(), dir.path().join("escape")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .verification_cache(std::time::Duration::from_secs(60))
+        .build()
+        .unwrap();
+
+    let paths: Vec<String> = (0..500)
+        .map(|i| {
+            if i % 2 == 0 {
+                format!("inside/{i}")
+            } else {
+                format!("escape/{i}")
+            }
+        })
+        .collect();
+    for (i, result) in jail.par_join_many(&paths).iter().enumerate() {
+        if i % 2 == 0 {
+            assert_eq!(
+                result.as_ref().unwrap(),
+                &jail.root().join(format!("real/{i}"))
+            );
+        } else {
+            assert!(matches!(result, Err(JailError::EscapedRoot { .. })), "{i}");
+        }
+    }
+}
+
+#[test]
+#[cfg(unix)]
+fn par_join_many_joins_lexically_in_a_trusted_tree() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    symlink(dir.path().join("real"), dir.path().join("inside")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .trusted_tree(true)
+        .build()
+        .unwrap();
+
+    let results = jail.par_join_many(["inside/a.txt", "../b.txt"]);
+    assert_eq!(
+        results[0].as_ref().unwrap(),
+        &jail.root().join("inside/a.txt")
+    );
+    assert!(matches!(results[1], Err(JailError::EscapedRoot { .. })));
+}
@@ -0,0 +1,85 @@
+//! Tests for `Jail::read_dir`.
+
+use path_jail::Jail;
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn read_dir_lists_entries() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/a.txt"), b"a").unwrap();
+    fs::create_dir(dir.path().join("sub/nested")).unwrap();
+
+    let mut names: Vec<_> = jail
+        .read_dir("sub")
+        .unwrap()
+        .map(|e| e.unwrap())
+        .map(|e| {
+            assert!(e.path().starts_with(jail.root()));
+            (e.file_name().to_owned(), e.file_type().is_dir())
+        })
+        .collect();
+    names.sort();
+
+    assert_eq!(
+        names,
+        vec![("a.txt".into(), false), ("nested".into(), true)]
+    );
+}
+
+#[test]
+fn read_dir_rejects_traversal() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    // The directory argument goes through join()
+    assert!(jail.read_dir("../").is_err());
+    assert!(jail.read_dir("/etc").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn read_dir_flags_escaping_symlink_entry() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("ok.txt"), b"ok").unwrap();
+    std::os::unix::fs::symlink("/etc", dir.path().join("evil")).unwrap();
+
+    let results: Vec<_> = jail.read_dir("").unwrap().collect();
+    assert_eq!(results.len(), 2);
+
+    // The escaping entry is an error, the regular file still comes through
+    assert!(results
+        .iter()
+        .any(|r| matches!(r, Err(path_jail::JailError::EscapedRoot { .. }))));
+    assert!(results
+        .iter()
+        .any(|r| matches!(r, Ok(e) if e.file_name() == "ok.txt")));
+}
+
+#[test]
+#[cfg(unix)]
+fn read_dir_resolves_internal_symlink_entry() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("real.txt"), b"data").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real.txt"), dir.path().join("link")).unwrap();
+    std::os::unix::fs::symlink("/nonexistent/target", dir.path().join("broken")).unwrap();
+
+    for entry in jail.read_dir("").unwrap() {
+        match entry {
+            Ok(e) if e.file_name() == "link" => {
+                // Symlink is reported as such, path is the resolved target
+                assert!(e.file_type().is_symlink());
+                assert!(e.path().ends_with("real.txt"));
+            }
+            Ok(e) => assert_eq!(e.file_name(), "real.txt"),
+            Err(e) => assert!(matches!(e, path_jail::JailError::BrokenSymlink(_))),
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Tests for the `ReadOnly`/`ReadWrite` capability markers.
+
+use path_jail::{Jail, JailError, JailedPath, ReadOnly};
+use std::fs;
+use tempfile::tempdir;
+
+fn setup() -> (tempfile::TempDir, Jail<ReadOnly>) {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs/a.txt"), "alpha").unwrap();
+    fs::write(dir.path().join("docs/b.md"), "beta").unwrap();
+    let jail = Jail::new(dir.path()).unwrap().read_only();
+    (dir, jail)
+}
+
+#[test]
+fn read_only_jail_validates_like_the_original() {
+    let (dir, jail) = setup();
+    let rw = Jail::new(dir.path()).unwrap();
+
+    assert_eq!(jail.root(), rw.root());
+    assert_eq!(
+        jail.join("docs/a.txt").unwrap(),
+        rw.join("docs/a.txt").unwrap()
+    );
+    assert!(matches!(
+        jail.join("../etc/passwd"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(jail.contains(dir.path().join("docs")).is_ok());
+}
+
+#[test]
+fn read_only_jail_reads() {
+    let (_dir, jail) = setup();
+
+    assert_eq!(jail.read("docs/a.txt").unwrap(), b"alpha");
+    assert_eq!(jail.read_to_string("docs/b.md").unwrap(), "beta");
+
+    let path: JailedPath<ReadOnly> = jail.join_typed("docs/a.txt").unwrap();
+    assert_eq!(path.read_to_string().unwrap(), "alpha");
+    assert_eq!(path.jail(), &jail);
+}
+
+#[test]
+fn read_only_jail_lists_walks_and_globs() {
+    let (_dir, jail) = setup();
+
+    let mut names: Vec<_> = jail
+        .read_dir("docs")
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a.txt", "b.md"]);
+
+    let walked = jail.walk("").unwrap().count();
+    assert_eq!(walked, 3);
+
+    let globbed: Vec<JailedPath<ReadOnly>> = jail
+        .glob("docs/*.txt")
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(globbed.len(), 1);
+    assert_eq!(globbed[0].relative(), std::path::Path::new("docs/a.txt"));
+}
+
+#[test]
+fn read_only_typed_paths() {
+    let (_dir, jail) = setup();
+
+    let dir = jail.join_dir("docs").unwrap();
+    let file = dir.join_file("a.txt").unwrap();
+    assert_eq!(file.read().unwrap(), b"alpha");
+    assert_eq!(file.parent().unwrap(), dir);
+}
+
+#[test]
+fn read_only_subjail_stays_read_only() {
+    let (_dir, jail) = setup();
+
+    let sub: Jail<ReadOnly> = jail.subjail("docs").unwrap();
+    assert_eq!(sub.read_to_string("b.md").unwrap(), "beta");
+}
+
+#[test]
+fn read_only_path_from_read_write_path() {
+    let (dir, _jail) = setup();
+    let rw = Jail::new(dir.path()).unwrap();
+
+    let path = rw.join_typed("docs/a.txt").unwrap();
+    let ro = path.read_only();
+    assert_eq!(ro.as_path(), path.as_path());
+    assert_eq!(ro.relative(), path.relative());
+    assert_eq!(ro.read().unwrap(), b"alpha");
+}
+
+#[test]
+fn read_only_keeps_options() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a b.txt"), "x").unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap()
+        .read_only();
+
+    assert_eq!(jail.read_to_string("a%20b.txt").unwrap(), "x");
+}
@@ -0,0 +1,126 @@
+//! Tests for file name sanitization.
+
+use path_jail::{sanitize_file_name, Jail, JailError};
+use tempfile::tempdir;
+
+#[test]
+fn ordinary_names_are_unchanged() {
+    for name in [
+        "report.pdf",
+        "photo 1.JPG",
+        "données.csv",
+        "a-b_c",
+        "tar.gz",
+    ] {
+        assert_eq!(sanitize_file_name(name).unwrap(), name);
+    }
+}
+
+#[test]
+fn separators_and_forbidden_characters_are_replaced() {
+    assert_eq!(sanitize_file_name("a/b\\c").unwrap(), "a_b_c");
+    assert_eq!(sanitize_file_name("x<>:\"|?*y").unwrap(), "x_______y");
+    assert_eq!(sanitize_file_name("nul\0byte").unwrap(), "nul_byte");
+    assert_eq!(
+        sanitize_file_name("line\nbreak\t\x7f").unwrap(),
+        "line_break__"
+    );
+}
+
+#[test]
+fn leading_dots_and_trailing_dots_and_spaces_are_removed() {
+    assert_eq!(sanitize_file_name(".env").unwrap(), "env");
+    assert_eq!(sanitize_file_name("...hidden").unwrap(), "hidden");
+    assert_eq!(sanitize_file_name("shell.php. . ").unwrap(), "shell.php");
+    assert_eq!(
+        sanitize_file_name(" leading space").unwrap(),
+        " leading space"
+    );
+}
+
+#[test]
+fn device_names_are_prefixed() {
+    for (name, expected) in [
+        ("CON", "_CON"),
+        ("con.txt", "_con.txt"),
+        ("NUL.tar.gz", "_NUL.tar.gz"),
+        ("com1", "_com1"),
+        ("LPT9.log", "_LPT9.log"),
+        ("COM¹", "_COM¹"),
+        ("AUX .txt", "_AUX .txt"),
+    ] {
+        assert_eq!(sanitize_file_name(name).unwrap(), expected, "{name}");
+    }
+    for name in ["CONSOLE", "COM10", "LPT", "icon.png", "null"] {
+        assert_eq!(sanitize_file_name(name).unwrap(), name);
+    }
+}
+
+#[test]
+fn long_names_are_truncated_keeping_the_extension() {
+    let name = format!("{}.pdf", "a".repeat(300));
+    let sanitized = sanitize_file_name(&name).unwrap();
+    assert_eq!(sanitized.len(), 255);
+    assert!(sanitized.ends_with("a.pdf"));
+
+    // Multi-byte characters are never split
+    let name = format!("{}.txt", "é".repeat(200));
+    let sanitized = sanitize_file_name(&name).unwrap();
+    assert!(sanitized.len() <= 255);
+    assert!(sanitized.ends_with("é.txt"));
+
+    // An overly long "extension" is not preserved
+    let name = format!("a.{}", "b".repeat(300));
+    assert_eq!(sanitize_file_name(&name).unwrap().len(), 255);
+}
+
+#[test]
+fn truncated_names_are_never_hidden_or_devices() {
+    let name = format!("{}.txt", " ".repeat(300));
+    assert_eq!(sanitize_file_name(&name).unwrap(), "txt");
+
+    let name = format!("COM1{}x.txt", " ".repeat(300));
+    assert_eq!(sanitize_file_name(&name).unwrap(), "_COM1.txt");
+}
+
+#[test]
+fn empty_results_are_errors() {
+    for name in ["", ".", "..", "...", " . ", ". "] {
+        assert!(
+            matches!(sanitize_file_name(name), Err(JailError::InvalidPath(_))),
+            "{name:?}"
+        );
+    }
+}
+
+#[test]
+fn join_sanitized_stays_in_the_root() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert_eq!(
+        jail.join_sanitized("../../etc/passwd").unwrap(),
+        jail.root().join("_.._etc_passwd")
+    );
+    assert_eq!(
+        jail.join_sanitized("/etc/passwd").unwrap(),
+        jail.root().join("_etc_passwd")
+    );
+    assert!(jail.join_sanitized("..").is_err());
+}
+
+#[test]
+fn join_sanitized_decodes_before_sanitizing() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .allowed_extensions(["txt"])
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        jail.join_sanitized("%2e%2e%2fnotes.txt").unwrap(),
+        jail.root().join("_notes.txt")
+    );
+    assert!(jail.join_sanitized("notes.exe").is_err());
+}
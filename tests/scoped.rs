@@ -0,0 +1,66 @@
+//! Tests for the per-thread default jail.
+
+use path_jail::{current_jail, current_jail_join, Jail, JailError, JailErrorKind, ScopedJail};
+use tempfile::tempdir;
+
+#[test]
+fn joins_against_the_innermost_scope() {
+    let outer_dir = tempdir().unwrap();
+    let inner_dir = tempdir().unwrap();
+    let outer = Jail::new(outer_dir.path()).unwrap();
+    let inner = Jail::new(inner_dir.path()).unwrap();
+
+    let err = current_jail_join("a.txt").unwrap_err();
+    assert!(matches!(err, JailError::NoCurrentJail));
+    assert_eq!(err.kind(), JailErrorKind::Root);
+
+    let _outer = ScopedJail::new(&outer);
+    assert_eq!(
+        current_jail_join("a.txt").unwrap(),
+        outer.join("a.txt").unwrap()
+    );
+    {
+        let _inner = ScopedJail::new(&inner.read_only());
+        assert_eq!(current_jail().unwrap().root(), inner.root());
+        assert!(current_jail_join("../escape")
+            .unwrap_err()
+            .is_escape_attempt());
+    }
+    assert_eq!(current_jail().unwrap().root(), outer.root());
+}
+
+#[test]
+fn scopes_are_per_thread_and_end_on_drop() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let scope = ScopedJail::new(&jail);
+    std::thread::spawn(|| assert!(current_jail().is_none()))
+        .join()
+        .unwrap();
+    assert!(current_jail().is_some());
+
+    // Dropping an outer guard first leaves the inner one installed
+    let inner = ScopedJail::new(&jail);
+    drop(scope);
+    assert!(current_jail().is_some());
+    drop(inner);
+    assert!(current_jail().is_none());
+}
+
+#[test]
+fn out_of_order_drops_remove_only_their_own_jail() {
+    let dirs: Vec<_> = (0..4).map(|_| tempdir().unwrap()).collect();
+    let jails: Vec<_> = dirs
+        .iter()
+        .map(|dir| Jail::new(dir.path()).unwrap())
+        .collect();
+
+    let a = ScopedJail::new(&jails[0]);
+    let b = ScopedJail::new(&jails[1]);
+    drop(a);
+    let _c = ScopedJail::new(&jails[2]);
+    let _d = ScopedJail::new(&jails[3]);
+    drop(b);
+    assert_eq!(current_jail().unwrap().root(), jails[3].root());
+}
@@ -4,7 +4,7 @@
 
 #![cfg(all(feature = "secure-open", unix))]
 
-use path_jail::{Jail, JailedFile};
+use path_jail::{Jail, JailError, JailedFile};
 use std::fs;
 use std::io::{Read, Write};
 use tempfile::tempdir;
@@ -96,6 +96,25 @@ fn create_fails_if_exists() {
     assert!(result.is_err());
 }
 
+#[test]
+fn create_with_parents_makes_missing_directories() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.exe").build().unwrap();
+
+    jail.create_with_parents("a/b/c.txt")
+        .unwrap()
+        .write_all(b"hi")
+        .unwrap();
+    assert_eq!(fs::read(dir.path().join("a/b/c.txt")).unwrap(), b"hi");
+    jail.create_with_parents("top.txt").unwrap();
+
+    // Still O_EXCL, and nothing is created for a rejected path
+    assert!(jail.create_with_parents("a/b/c.txt").is_err());
+    assert!(jail.create_with_parents("x/y/z.exe").is_err());
+    assert!(jail.create_with_parents("../escape/z.txt").is_err());
+    assert!(!dir.path().join("x").exists());
+}
+
 #[test]
 fn create_rejects_symlink_target() {
     let dir = tempdir().unwrap();
@@ -256,3 +275,290 @@ fn open_blocks_symlink_swap_attack() {
     // So the open fails with "file not found"
     assert!(result.is_err());
 }
+
+#[test]
+fn copy_copies_contents_and_returns_size() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("src.txt"), b"hello world").unwrap();
+    fs::write(dir.path().join("dst.txt"), b"previous longer content").unwrap();
+
+    let copied = jail.copy("src.txt", "dst.txt").unwrap();
+    assert_eq!(copied, 11);
+    assert_eq!(
+        fs::read(dir.path().join("dst.txt")).unwrap(),
+        b"hello world"
+    );
+}
+
+#[test]
+fn copy_rejects_symlinks_on_either_end() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let victim = outside.path().join("victim.txt");
+    fs::write(&victim, b"keep me").unwrap();
+    fs::write(dir.path().join("src.txt"), b"data").unwrap();
+    std::os::unix::fs::symlink(&victim, dir.path().join("out_link")).unwrap();
+
+    assert!(jail.copy("out_link", "copy.txt").is_err());
+    assert!(jail.copy("src.txt", "out_link").is_err());
+    assert!(jail.copy("src.txt", "../escape.txt").is_err());
+    assert_eq!(fs::read(&victim).unwrap(), b"keep me");
+}
+
+#[test]
+fn copy_refuses_same_file_and_non_files() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::write(dir.path().join("file.txt"), b"precious").unwrap();
+    fs::create_dir(dir.path().join("sub")).unwrap();
+
+    assert!(jail.copy("file.txt", "file.txt").is_err());
+    assert_eq!(fs::read(dir.path().join("file.txt")).unwrap(), b"precious");
+    assert!(jail.copy("sub", "copy").is_err());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn create_anonymous_links_into_place() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("uploads")).unwrap();
+
+    let mut file = jail.create_anonymous("uploads").unwrap();
+    file.write_all(b"complete").unwrap();
+    // Nothing is visible until the file is linked
+    assert_eq!(fs::read_dir(dir.path().join("uploads")).unwrap().count(), 0);
+
+    let path = file.link("uploads/done.bin").unwrap();
+    assert_eq!(fs::read(&path).unwrap(), b"complete");
+    assert!(file.link("uploads/done.bin").is_err());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn create_anonymous_rejects_escapes() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("out")).unwrap();
+
+    assert!(jail.create_anonymous("../").is_err());
+    assert!(jail.create_anonymous("out").is_err());
+
+    let file = jail.create_anonymous("").unwrap();
+    assert!(file.link("../escape.bin").is_err());
+    assert!(file.link("out/escape.bin").is_err());
+    assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn reject_hard_links_covers_file_handles() {
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("shadow"), "secret").unwrap();
+    let dir = tempdir().unwrap();
+    fs::hard_link(outside.path().join("shadow"), dir.path().join("shadow")).unwrap();
+
+    let jail = Jail::builder(dir.path())
+        .reject_hard_links(true)
+        .build()
+        .unwrap();
+    assert!(matches!(
+        jail.open("shadow"),
+        Err(JailError::HardLinked { .. })
+    ));
+    assert!(matches!(
+        jail.open_append("shadow"),
+        Err(JailError::HardLinked { .. })
+    ));
+    assert!(matches!(
+        jail.create_or_truncate("shadow"),
+        Err(JailError::HardLinked { .. })
+    ));
+    assert_eq!(
+        fs::read_to_string(outside.path().join("shadow")).unwrap(),
+        "secret"
+    );
+    assert!(matches!(
+        jail.join_typed("shadow").unwrap().open(),
+        Err(JailError::HardLinked { .. })
+    ));
+}
+
+#[cfg(feature = "openat")]
+#[test]
+fn openat_walk_rejects_swapped_intermediate_directories() {
+    use std::os::unix::fs::symlink;
+
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("secret.txt"), "secret").unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs/secret.txt"), "mine").unwrap();
+
+    let jail = Jail::new(dir.path()).unwrap();
+    let validated = jail.join_typed("docs/secret.txt").unwrap();
+
+    // Swap the directory for a symlink after validation
+    fs::rename(dir.path().join("docs"), dir.path().join("old")).unwrap();
+    symlink(outside.path(), dir.path().join("docs")).unwrap();
+
+    assert!(validated.open().is_err());
+    let new_file = jail.join_typed("docs/new.txt");
+    // Re-validation follows the new link and rejects it
+    assert!(new_file.is_err());
+
+    // Unswapped paths still open normally
+    fs::remove_file(dir.path().join("docs")).unwrap();
+    fs::rename(dir.path().join("old"), dir.path().join("docs")).unwrap();
+    assert!(validated.open().is_ok());
+    assert!(jail.create("docs/new.txt").is_ok());
+    assert_eq!(
+        fs::read_to_string(outside.path().join("secret.txt")).unwrap(),
+        "secret"
+    );
+}
+
+#[test]
+fn exclusive_lock_waits_for_release() {
+    use std::io::Seek;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    // Created on first lock
+    let mut lock = jail.lock_exclusive("state.lock").unwrap();
+    assert!(dir.path().join("state.lock").exists());
+    lock.write_all(b"held").unwrap();
+
+    let acquired = Arc::new(AtomicBool::new(false));
+    let waiter = {
+        let jail = jail.clone();
+        let acquired = Arc::clone(&acquired);
+        std::thread::spawn(move || {
+            let mut lock = jail.lock_exclusive("state.lock").unwrap();
+            acquired.store(true, Ordering::SeqCst);
+            let mut contents = String::new();
+            lock.rewind().unwrap();
+            lock.read_to_string(&mut contents).unwrap();
+            contents
+        })
+    };
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(!acquired.load(Ordering::SeqCst));
+    lock.unlock().unwrap();
+    assert_eq!(waiter.join().unwrap(), "held");
+    assert!(acquired.load(Ordering::SeqCst));
+}
+
+#[test]
+fn shared_locks_coexist() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("data.txt"), "x").unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let a = jail.lock_shared("data.txt").unwrap();
+    let b = jail.lock_shared("data.txt").unwrap();
+    drop((a, b));
+    drop(jail.lock_exclusive("data.txt").unwrap());
+    assert_eq!(
+        fs::read_to_string(dir.path().join("data.txt")).unwrap(),
+        "x"
+    );
+}
+
+#[test]
+fn lock_validates_the_path() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("target"), "").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("target"), dir.path().join("link")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(matches!(
+        jail.lock_exclusive("../escape.lock"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(jail.lock_shared("link").is_err());
+    assert!(!dir.path().parent().unwrap().join("escape.lock").exists());
+}
+
+#[test]
+fn created_files_get_file_mode() {
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("target"), b"x").unwrap();
+    symlink(outside.path().join("target"), dir.path().join("link")).unwrap();
+    let jail = Jail::builder(dir.path()).file_mode(0o666).build().unwrap();
+
+    jail.create("new.txt").unwrap();
+    jail.create_or_truncate("truncated.txt").unwrap();
+    jail.open_append("log.txt").unwrap();
+    for name in ["new.txt", "truncated.txt", "log.txt"] {
+        let mode = fs::metadata(dir.path().join(name))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o666, "{name}");
+    }
+
+    // The exclusive attempt must not turn a symlink into a follow
+    assert!(jail.open_append("link").is_err());
+    let mode = fs::metadata(outside.path().join("target"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_ne!(mode & 0o777, 0o666);
+}
+
+#[test]
+fn append_only_files_only_grow() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).append_only(true).build().unwrap();
+
+    let mut file = jail.create("log.txt").unwrap();
+    file.write_all(b"one\n").unwrap();
+    drop(file);
+
+    let mut file = jail.open_append("log.txt").unwrap();
+    file.write_all(b"two\n").unwrap();
+    let err = file.set_len(0).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::PermissionDenied);
+    drop(file);
+
+    assert!(matches!(
+        jail.create_or_truncate("log.txt"),
+        Err(JailError::AppendOnly(_))
+    ));
+    assert_eq!(jail.copy("log.txt", "copy.txt").unwrap(), 8);
+    assert!(matches!(
+        jail.copy("copy.txt", "log.txt"),
+        Err(JailError::AppendOnly(_))
+    ));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("log.txt")).unwrap(),
+        "one\ntwo\n"
+    );
+
+    // New files can still be made
+    let mut file = jail.create_or_truncate("new.txt").unwrap();
+    file.write_all(b"new\n").unwrap();
+    drop(file);
+    assert!(matches!(
+        jail.create_or_truncate("new.txt"),
+        Err(JailError::AppendOnly(_))
+    ));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("new.txt")).unwrap(),
+        "new\n"
+    );
+}
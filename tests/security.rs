@@ -7,7 +7,7 @@ fn rejects_filesystem_root() {
     use path_jail::JailError;
 
     // Cannot use filesystem root as jail (defeats the purpose)
-    #[cfg(unix)]
+    #[cfg(any(unix, target_os = "wasi"))]
     {
         let err = Jail::new("/").unwrap_err();
         assert!(matches!(err, JailError::InvalidRoot(_)));
@@ -83,6 +83,46 @@ fn catches_proc_self_root_escape() {
     assert!(result.is_err());
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn rejects_proc_magic_links() {
+    use path_jail::JailError;
+    use std::path::Path;
+
+    if !Path::new("/proc/self/root").exists() {
+        return;
+    }
+
+    let jail = Jail::new("/proc").unwrap();
+    // An open file's fd link reads as a path inside /proc and so passed
+    // the canonical check, but the kernel follows it to the file itself
+    let status = fs::File::open("/proc/self/status").unwrap();
+    let fd = format!("self/fd/{}", std::os::fd::AsRawFd::as_raw_fd(&status));
+    for path in [fd.as_str(), "self/root/etc", "self/cwd", "thread-self/exe"] {
+        assert!(
+            matches!(jail.join(path), Err(JailError::MagicLink(_))),
+            "{path}"
+        );
+    }
+    assert!(matches!(
+        jail.contains("/proc/self/root"),
+        Err(JailError::MagicLink(_))
+    ));
+    // Ordinary procfs files and the fd directory itself are fine
+    assert!(jail.join("self/status").is_ok());
+    assert!(jail.join("self/fd").is_ok());
+}
+
+#[test]
+fn magic_link_names_are_fine_as_directories() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("proc/1/root")).unwrap();
+    fs::create_dir_all(dir.path().join("proc/self/fd/3")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    assert!(jail.join("proc/1/root/file").is_ok());
+    assert!(jail.join("proc/self/fd/3").is_ok());
+}
+
 #[test]
 fn rejects_file_as_root() {
     use path_jail::JailError;
@@ -362,6 +402,29 @@ fn contains_verifies_paths() {
     assert!(jail.contains("relative/path").is_err());
 }
 
+#[test]
+fn contains_future_vets_paths_before_creation() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+
+    let path = jail
+        .contains_future(dir.path().join("new/dir/a.txt"))
+        .unwrap();
+    assert_eq!(path, jail.join("new/dir/a.txt").unwrap());
+    assert!(jail.contains(dir.path().join("new/dir/a.txt")).is_err());
+
+    // The part that does not exist is held to the jail's rules
+    assert!(jail
+        .contains_future(dir.path().join("new/../../escape"))
+        .unwrap_err()
+        .is_escape_attempt());
+    assert!(jail
+        .contains_future(dir.path().join("new/shell.php"))
+        .is_err());
+    assert!(jail.contains_future("/etc/new.conf").is_err());
+    assert!(jail.contains_future("relative/path").is_err());
+}
+
 #[test]
 fn rejects_triple_dots() {
     let dir = tempdir().unwrap();
@@ -386,6 +449,54 @@ fn relative_rejects_nonexistent_absolute() {
     assert!(jail.relative(&abs).is_err());
 }
 
+#[test]
+fn relative_future_accepts_nonexistent_absolute() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("2025")).unwrap();
+
+    let abs = jail.join("2025/q3/report.pdf").unwrap();
+    assert_eq!(
+        jail.relative_future(&abs).unwrap(),
+        std::path::Path::new("2025/q3/report.pdf")
+    );
+    // The existing root spelled the way the caller has it
+    let abs = dir.path().join("new/../b.txt");
+    assert_eq!(
+        jail.relative_future(abs).unwrap(),
+        std::path::Path::new("b.txt")
+    );
+
+    assert!(jail
+        .relative_future(dir.path().join("new/../../escape"))
+        .is_err());
+    assert!(jail
+        .relative_future(outside.path().join("new.txt"))
+        .is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn relative_future_follows_existing_symlinks() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("link")).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("escape")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert_eq!(
+        jail.relative_future(dir.path().join("link/new.txt"))
+            .unwrap(),
+        std::path::Path::new("real/new.txt")
+    );
+    assert!(jail
+        .relative_future(dir.path().join("escape/new.txt"))
+        .unwrap_err()
+        .is_escape_attempt());
+}
+
 // ============================================================================
 // Path input edge cases
 // ============================================================================
@@ -403,6 +514,31 @@ fn handles_null_bytes() {
     assert!(result.is_err());
 }
 
+#[test]
+#[cfg(unix)]
+fn validates_non_utf8_names_without_lossy_conversion() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    // Latin-1 "café.txt" is kept byte for byte, not replaced with U+FFFD
+    let path = jail.join_bytes(b"caf\xe9.txt").unwrap();
+    assert_eq!(path.file_name().unwrap().as_bytes(), b"caf\xe9.txt");
+    assert_eq!(
+        jail.join_os(OsStr::from_bytes(b"caf\xe9.txt")).unwrap(),
+        path
+    );
+
+    for bad in [&b"\xff/../../etc"[..], b"\xff\0.txt", b"/etc/\xff"] {
+        assert!(matches!(
+            jail.join_bytes(bad),
+            Err(path_jail::JailError::EscapedRoot { .. } | path_jail::JailError::InvalidPath(_))
+        ));
+    }
+}
+
 #[test]
 #[cfg(unix)]
 fn backslash_is_valid_filename_on_unix() {
@@ -425,7 +561,7 @@ fn handles_control_characters() {
 
     // Control characters are technically valid in filenames on Unix
     // (except null and slash). This is a logging/display issue, not security.
-    #[cfg(unix)]
+    #[cfg(any(unix, target_os = "wasi"))]
     {
         // These should work (though they're ugly)
         let _ = jail.join("file\n.txt"); // Newline
@@ -569,6 +705,43 @@ fn join_typed_returns_jailed_path() {
     assert!(pathbuf.ends_with("file.txt"));
 }
 
+#[test]
+fn contains_typed_promotes_absolute_paths() {
+    use path_jail::{JailError, JailedPath};
+
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+    fs::write(dir.path().join("alice/report.pdf"), "").unwrap();
+    fs::write(dir.path().join("alice/shell.php"), "").unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.php").build().unwrap();
+
+    let path: JailedPath = jail
+        .contains_typed(dir.path().join("alice/../alice/report.pdf"))
+        .unwrap();
+    assert_eq!(path.relative(), std::path::Path::new("alice/report.pdf"));
+    assert_eq!(path.jail(), &jail);
+    assert_eq!(
+        jail.contains_typed(jail.root()).unwrap().relative(),
+        std::path::Path::new("")
+    );
+
+    // contains() only checks the location; the typed version applies the rules
+    assert!(jail.contains(dir.path().join("alice/shell.php")).is_ok());
+    assert!(jail
+        .contains_typed(dir.path().join("alice/shell.php"))
+        .is_err());
+
+    assert!(matches!(
+        jail.contains_typed(outside.path()),
+        Err(JailError::EscapedRoot { .. })
+    ));
+    assert!(jail.contains_typed("alice/report.pdf").is_err());
+    assert!(jail
+        .contains_typed(dir.path().join("alice/missing"))
+        .is_err());
+}
+
 #[test]
 fn jailed_path_can_be_used_with_std_fs() {
     use path_jail::JailedPath;
@@ -594,10 +767,132 @@ fn jailed_path_blocks_traversal() {
     assert!(jail.join_typed("foo/../../secret").is_err());
 }
 
+#[test]
+fn jailed_path_navigation_revalidates() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+
+    let alice = jail.join_typed("alice").unwrap();
+    let report = alice.join("report.pdf").unwrap();
+    assert!(report.ends_with("alice/report.pdf"));
+    assert_eq!(report.parent().unwrap(), alice);
+    assert!(report
+        .with_file_name("notes.txt")
+        .unwrap()
+        .ends_with("alice/notes.txt"));
+    assert!(report
+        .with_extension("txt")
+        .unwrap()
+        .ends_with("alice/report.txt"));
+
+    assert!(alice.join("../../secret").is_err());
+    assert!(alice.join("/etc/passwd").is_err());
+    assert!(report.with_file_name("../../secret").is_err());
+
+    // The root has no parent inside the jail
+    let root = alice.parent().unwrap();
+    assert_eq!(root.as_path(), jail.root());
+    assert!(matches!(
+        root.parent(),
+        Err(path_jail::JailError::EscapedRoot { .. })
+    ));
+    assert!(root.with_file_name("sibling").is_err());
+}
+
+#[test]
+fn jailed_path_knows_its_jail() {
+    let dir = tempdir().unwrap();
+    let other = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let other_jail = Jail::new(other.path()).unwrap();
+
+    let path = jail.join_typed("notes/today.txt").unwrap();
+    assert_eq!(path.jail(), &jail);
+    assert_ne!(path.jail(), &other_jail);
+    assert_eq!(path.relative(), std::path::Path::new("notes/today.txt"));
+    assert_eq!(
+        jail.join_typed("").unwrap().relative(),
+        std::path::Path::new("")
+    );
+
+    fs::create_dir(dir.path().join("notes")).unwrap();
+    path.write(b"hello").unwrap();
+    assert_eq!(path.read_to_string().unwrap(), "hello");
+    let dir_path = path.parent().unwrap();
+    assert_eq!(dir_path.read_dir().unwrap().count(), 1);
+    path.remove_file().unwrap();
+    dir_path.remove_dir().unwrap();
+    assert!(!dir.path().join("notes").exists());
+}
+
+#[test]
+fn display_relative_hides_the_root() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let root = jail.root().to_string_lossy().into_owned();
+
+    let path = jail.join_typed("invoices/2024.pdf").unwrap();
+    assert!(path.to_string().contains(&root));
+    let shown = path.display_relative().to_string();
+    assert!(!shown.contains(&root));
+    assert_eq!(
+        std::path::Path::new(&shown),
+        std::path::Path::new("invoices/2024.pdf")
+    );
+    assert_eq!(
+        jail.join_typed("").unwrap().display_relative().to_string(),
+        "."
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn jailed_path_join_rejects_escaping_symlink() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+    let root = jail.join_typed("").unwrap();
+    assert!(root.join("link/file.txt").is_err());
+}
+
 // ============================================================================
 // join_segments tests
 // ============================================================================
 
+#[test]
+fn join_existing_and_join_new_check_presence() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs/a.txt"), b"a").unwrap();
+
+    assert_eq!(
+        jail.join_existing("docs/a.txt").unwrap(),
+        jail.join("docs/a.txt").unwrap()
+    );
+    assert!(jail.join_existing("docs/b.txt").unwrap_err().is_not_found());
+    assert!(jail
+        .join_existing("../escape")
+        .unwrap_err()
+        .is_escape_attempt());
+
+    assert_eq!(
+        jail.join_new("docs/b.txt").unwrap(),
+        jail.join("docs/b.txt").unwrap()
+    );
+    let err = jail.join_new("docs/a.txt").unwrap_err();
+    assert_eq!(err.io_kind(), std::io::ErrorKind::AlreadyExists);
+    assert!(jail.join_new("missing/b.txt").unwrap_err().is_not_found());
+    assert!(matches!(
+        jail.join_new("docs/a.txt/b.txt").unwrap_err(),
+        path_jail::JailError::InvalidPath(_)
+    ));
+    assert!(jail.join_new("").is_err());
+}
+
 #[test]
 fn join_segments_basic() {
     let dir = tempdir().unwrap();
@@ -678,3 +973,262 @@ fn segments_returns_jailed_path() {
     let path: JailedPath = jail.segments(["users", "bob", "photo.jpg"]).unwrap();
     assert!(path.ends_with("users/bob/photo.jpg"));
 }
+
+#[test]
+#[cfg(windows)]
+fn short_names_resolve_or_are_rejected() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("long directory name")).unwrap();
+
+    // An existing alias is expanded by canonicalization (if 8.3 names are
+    // enabled on the volume); a missing one is refused
+    if let Ok(path) = jail.join("LONGDI~1") {
+        assert!(path.ends_with("long directory name"));
+    }
+    assert!(matches!(
+        jail.join("MISSIN~1.TXT"),
+        Err(path_jail::JailError::InvalidPath(_))
+    ));
+}
+
+#[test]
+#[cfg(unix)]
+fn tildes_are_ordinary_characters_on_unix() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    assert_eq!(jail.join("PROGRA~1").unwrap(), jail.root().join("PROGRA~1"));
+}
+
+#[test]
+#[cfg(windows)]
+fn junction_escapes_have_dedicated_errors() {
+    use path_jail::JailError;
+    use std::process::Command;
+
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let junction = dir.path().join("junction");
+
+    // Junctions need no privilege, unlike symlinks
+    let status = Command::new("cmd")
+        .args(["/C", "mklink", "/J"])
+        .arg(&junction)
+        .arg(outside.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert!(matches!(
+        jail.join("junction/secret.txt"),
+        Err(JailError::JunctionEscape { .. })
+    ));
+
+    // Dangling once the target is gone
+    let target = outside.path().to_path_buf();
+    drop(outside);
+    assert!(!target.exists());
+    assert!(matches!(
+        jail.join("junction/secret.txt"),
+        Err(JailError::BrokenJunction(_))
+    ));
+}
+
+#[test]
+fn junction_errors_display() {
+    use path_jail::JailError;
+    use std::path::PathBuf;
+
+    let err = JailError::JunctionEscape {
+        path: PathBuf::from("uploads/j"),
+        root: PathBuf::from("uploads"),
+    };
+    assert!(err.to_string().contains("junction"));
+    let err = JailError::BrokenJunction(PathBuf::from("uploads/j"));
+    assert!(err.to_string().contains("broken junction"));
+}
+
+#[test]
+#[cfg(unix)]
+fn errors_display_relative_to_the_root() {
+    use path_jail::JailError;
+    use std::os::unix::fs::symlink;
+
+    let outside = tempdir().unwrap();
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("data")).unwrap();
+    symlink(
+        outside.path().join("gone"),
+        dir.path().join("data/dangling"),
+    )
+    .unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let root = jail.root().to_string_lossy().into_owned();
+
+    let err = jail.join("../secrets").unwrap_err();
+    assert!(err.to_string().contains(&root));
+    assert_eq!(
+        err.display_relative(jail.root()).to_string(),
+        "path '../secrets' escapes jail root '.'"
+    );
+
+    let err = jail.join("data/dangling/x").unwrap_err();
+    assert_eq!(
+        err.display_relative(jail.root()).to_string(),
+        "broken symlink at 'data/dangling' (cannot verify target)"
+    );
+
+    let err = JailError::CrossDevice(outside.path().to_path_buf());
+    let shown = err.display_relative(jail.root()).to_string();
+    assert!(
+        !shown.contains(&*outside.path().to_string_lossy()),
+        "{shown}"
+    );
+    assert!(shown.contains("<outside jail>"), "{shown}");
+}
+
+#[test]
+#[cfg(windows)]
+fn long_paths_use_verbatim_prefix() {
+    use std::path::{Path, PathBuf};
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    assert!(jail.root().to_string_lossy().starts_with(r"\\?\"));
+
+    // Well past MAX_PATH (260)
+    let deep: PathBuf = std::iter::repeat("nested-directory-name")
+        .take(20)
+        .collect();
+    jail.create_dir_all(&deep).unwrap();
+    let file = jail.join(deep.join("upload.txt")).unwrap();
+    assert!(file.as_os_str().len() > 260);
+    assert!(file.to_string_lossy().starts_with(r"\\?\"));
+    fs::write(&file, b"data").unwrap();
+
+    // Verbatim and plain spellings are treated alike
+    let plain = PathBuf::from(file.to_string_lossy().trim_start_matches(r"\\?\"));
+    assert_eq!(jail.contains(&file).unwrap(), file);
+    assert_eq!(jail.contains(&plain).unwrap(), file);
+    assert_eq!(
+        jail.relative(&plain).unwrap(),
+        jail.relative(&file).unwrap()
+    );
+    assert!(jail
+        .relative(&file)
+        .unwrap()
+        .ends_with(Path::new("upload.txt")));
+}
+
+#[test]
+#[cfg(windows)]
+fn inputs_cannot_name_another_volume() {
+    use path_jail::JailError;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    for input in [
+        r"\\evil\share\x",
+        "//evil/share/x",
+        r"\\?\UNC\evil\share\x",
+        r"\\.\UNC\evil\share\x",
+        r"\\?\C:\Windows",
+        r"C:Windows",
+    ] {
+        assert!(
+            matches!(jail.join(input), Err(JailError::InvalidPath(_))),
+            "{input}"
+        );
+    }
+}
+
+#[test]
+#[cfg(windows)]
+fn unc_share_roots() {
+    use std::path::PathBuf;
+
+    // Reach the temp directory through the drive's administrative share;
+    // skip where that is not available
+    let dir = tempdir().unwrap();
+    let local = dir.path().canonicalize().unwrap();
+    let local = local.to_string_lossy();
+    let local = local.trim_start_matches(r"\\?\");
+    let (drive, rest) = local.split_at(1);
+    let unc = PathBuf::from(format!(r"\\localhost\{drive}${}", &rest[1..]));
+    if !unc.is_dir() {
+        return;
+    }
+
+    let jail = Jail::new(&unc).unwrap();
+    assert!(jail.root().to_string_lossy().starts_with(r"\\?\UNC\"));
+    assert!(Jail::new(r"\\localhost\C$").is_err());
+
+    fs::write(unc.join("a.txt"), b"data").unwrap();
+    let file = jail.join("a.txt").unwrap();
+    assert_eq!(jail.contains(unc.join("a.txt")).unwrap(), file);
+    assert_eq!(
+        jail.contains_future(unc.join("b/new.txt")).unwrap(),
+        jail.root().join(r"b\new.txt")
+    );
+
+    // Server and share names are not case-sensitive
+    let upper = PathBuf::from(unc.to_string_lossy().replacen("localhost", "LOCALHOST", 1));
+    assert_eq!(jail.contains(upper.join("a.txt")).unwrap(), file);
+
+    // The same directory by drive letter, or another share, is another
+    // volume and so outside
+    assert!(jail.contains(local).is_err());
+    assert!(jail.contains(r"\\localhost\IPC$").is_err());
+    assert!(jail.join(r"..\..\..\..\..\..\..\..\ADMIN$").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn resolves_symlinks_component_by_component() {
+    use path_jail::JailError;
+    use std::os::unix::fs::symlink;
+
+    let dir = tempdir().unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    fs::create_dir_all(root.join("a/b/c")).unwrap();
+    fs::write(root.join("a/b/c/file.txt"), "").unwrap();
+    // Relative with `..`, absolute, chained, and to a file
+    symlink("../b/c", root.join("a/b/up")).unwrap();
+    symlink(root.join("a/b"), root.join("abs")).unwrap();
+    symlink("abs/up", root.join("chain")).unwrap();
+    symlink("a/b/c/file.txt", root.join("to_file")).unwrap();
+    symlink("loop2", root.join("loop1")).unwrap();
+    symlink("loop1", root.join("loop2")).unwrap();
+    symlink("a/../../outside", root.join("sneaky")).unwrap();
+    let jail = Jail::new(&root).unwrap();
+
+    assert_eq!(
+        jail.join("a/b/up/file.txt").unwrap(),
+        root.join("a/b/c/file.txt")
+    );
+    assert_eq!(
+        jail.join("abs/c/new.txt").unwrap(),
+        root.join("a/b/c/new.txt")
+    );
+    assert_eq!(
+        jail.join("chain/file.txt").unwrap(),
+        root.join("a/b/c/file.txt")
+    );
+    assert_eq!(jail.join("chain/../c").unwrap(), root.join("a/b/c"));
+    assert_eq!(jail.join("to_file").unwrap(), root.join("a/b/c/file.txt"));
+    assert!(matches!(
+        jail.join("loop1"),
+        Err(JailError::BrokenSymlink(_))
+    ));
+    assert!(matches!(
+        jail.join("sneaky"),
+        Err(JailError::BrokenSymlink(_) | JailError::EscapedRoot { .. })
+    ));
+    let deep: std::path::PathBuf = std::iter::repeat("d").take(64).collect();
+    fs::create_dir_all(root.join(&deep)).unwrap();
+    assert_eq!(
+        jail.join(deep.join("f")).unwrap(),
+        root.join(&deep).join("f")
+    );
+}
@@ -0,0 +1,144 @@
+//! Tests for the serde feature.
+
+#![cfg(feature = "serde")]
+
+use path_jail::{Jail, JailedPath, ReadOnly};
+use std::collections::HashMap;
+use tempfile::tempdir;
+
+type Config = HashMap<String, Jail>;
+
+#[test]
+fn jail_deserializes_from_root_path() {
+    let dir = tempdir().unwrap();
+    let json = serde_json::json!({ "uploads": dir.path() });
+
+    let config: Config = serde_json::from_value(json).unwrap();
+    assert_eq!(config["uploads"].root(), dir.path().canonicalize().unwrap());
+}
+
+#[test]
+fn invalid_root_fails_at_load_time() {
+    let dir = tempdir().unwrap();
+    let missing = dir.path().join("missing");
+    let file = dir.path().join("file.txt");
+    std::fs::write(&file, b"").unwrap();
+
+    for root in [missing.as_path(), file.as_path(), std::path::Path::new("/")] {
+        let json = serde_json::json!({ "uploads": root });
+        let err = serde_json::from_value::<Config>(json).unwrap_err();
+        assert!(err.to_string().contains("invalid jail"), "{err}");
+    }
+}
+
+#[test]
+fn jail_round_trips() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let json = serde_json::to_string(&jail).unwrap();
+    let back: Jail = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, jail);
+}
+
+#[test]
+fn read_only_jail_deserializes() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let json = serde_json::to_string(&jail).unwrap();
+    let back: Jail<ReadOnly> = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, jail.read_only());
+}
+
+#[test]
+fn security_events_serialize_to_flat_records() {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    let dir = tempdir().unwrap();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let log = Arc::clone(&events);
+    let jail = Jail::builder(dir.path())
+        .id("uploads")
+        .on_reject(move |event| log.lock().unwrap().push(event.to_security_event()))
+        .build()
+        .unwrap();
+    let err = jail.join("../../etc/passwd").unwrap_err();
+
+    let mut event = events.lock().unwrap().pop().unwrap();
+    event.timestamp = UNIX_EPOCH + Duration::from_millis(1_714_564_800_123);
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        serde_json::json!({
+            "timestamp": "2024-05-01T12:00:00.123Z",
+            "operation": "validate",
+            "input": "../../etc/passwd",
+            "path": null,
+            "kind": "escape",
+            "reason": err.to_string(),
+            "jail_id": "uploads",
+        })
+    );
+}
+
+#[derive(Debug, serde_derive::Deserialize)]
+struct Move {
+    from: JailedPath,
+    to: JailedPath,
+}
+
+#[test]
+fn jailed_paths_validate_while_deserializing() {
+    use serde::de::DeserializeSeed;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let path = jail
+        .deserializer()
+        .deserialize(serde_json::json!("a/b.txt"))
+        .unwrap();
+    assert_eq!(path.relative(), std::path::Path::new("a/b.txt"));
+    assert_eq!(serde_json::to_value(&path).unwrap(), "a/b.txt");
+
+    let body = r#"{ "from": "in.txt", "to": "out/in.txt" }"#;
+    let request: Move = jail
+        .deserializer()
+        .scope(|| serde_json::from_str(body))
+        .unwrap();
+    assert_eq!(request.from.relative(), std::path::Path::new("in.txt"));
+    assert_eq!(request.to, jail.join_typed("out/in.txt").unwrap());
+
+    let body = r#"{ "from": "in.txt", "to": "../../etc/passwd" }"#;
+    let err = jail
+        .deserializer()
+        .scope(|| serde_json::from_str::<Move>(body))
+        .unwrap_err();
+    assert!(err.to_string().contains("../../etc/passwd"), "{err}");
+}
+
+#[test]
+fn jailed_paths_need_a_scope() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let body = r#"{ "from": "in.txt", "to": "out.txt" }"#;
+
+    assert!(serde_json::from_str::<Move>(body).is_err());
+    // The scope ends with the closure
+    jail.deserializer()
+        .scope(|| serde_json::from_str::<Move>(body))
+        .unwrap();
+    assert!(serde_json::from_str::<Move>(body).is_err());
+
+    // No upgrading a read-only jail
+    let read_only = jail.read_only();
+    let path = read_only
+        .deserializer()
+        .scope(|| serde_json::from_str::<JailedPath<ReadOnly>>(r#""in.txt""#));
+    assert!(path.is_ok());
+    assert!(read_only
+        .deserializer()
+        .scope(|| serde_json::from_str::<Move>(body))
+        .is_err());
+}
@@ -0,0 +1,143 @@
+//! Tests for the tower feature.
+
+#![cfg(feature = "tower")]
+
+use http::{Method, Request, Response, StatusCode};
+use http_body::Body;
+use path_jail::{Jail, ServeBody, ServeJail};
+use std::fs;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use tempfile::tempdir;
+use tower_service::Service;
+
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+fn poll_now<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    match pin!(future).poll(&mut Context::from_waker(&waker)) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("future was not ready"),
+    }
+}
+
+fn get(service: &mut ServeJail, method: Method, uri: &str) -> Response<ServeBody> {
+    let request = Request::builder().method(method).uri(uri).body(()).unwrap();
+    poll_now(service.call(request)).unwrap()
+}
+
+fn body_bytes(mut body: ServeBody) -> Vec<u8> {
+    let waker = Waker::from(Arc::new(NoopWaker));
+    let mut cx = Context::from_waker(&waker);
+    let mut out = Vec::new();
+    while let Poll::Ready(Some(frame)) = std::pin::Pin::new(&mut body).poll_frame(&mut cx) {
+        out.extend_from_slice(frame.unwrap().data_ref().unwrap());
+    }
+    out
+}
+
+fn setup() -> (tempfile::TempDir, ServeJail) {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("docs")).unwrap();
+    fs::write(dir.path().join("docs/index.html"), b"<h1>docs</h1>").unwrap();
+    fs::write(dir.path().join("hello world.txt"), b"hello").unwrap();
+    let service = ServeJail::new(Jail::new(dir.path()).unwrap());
+    (dir, service)
+}
+
+#[test]
+fn serves_files_with_headers() {
+    let (_dir, mut service) = setup();
+
+    let response = get(&mut service, Method::GET, "/hello%20world.txt");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-length"], "5");
+    assert_eq!(
+        response.headers()["content-type"],
+        "text/plain; charset=utf-8"
+    );
+    assert_eq!(body_bytes(response.into_body()), b"hello");
+
+    let head = get(&mut service, Method::HEAD, "/hello%20world.txt");
+    assert_eq!(head.headers()["content-length"], "5");
+    assert!(body_bytes(head.into_body()).is_empty());
+}
+
+#[test]
+fn directories_redirect_and_serve_index() {
+    let (_dir, mut service) = setup();
+
+    let redirect = get(&mut service, Method::GET, "/docs");
+    assert_eq!(redirect.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(redirect.headers()["location"], "/docs/");
+
+    let index = get(&mut service, Method::GET, "/docs/");
+    assert_eq!(index.status(), StatusCode::OK);
+    assert_eq!(body_bytes(index.into_body()), b"<h1>docs</h1>");
+
+    let mut no_index = service.clone().index_file(None::<String>);
+    assert_eq!(
+        get(&mut no_index, Method::GET, "/docs/").status(),
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[test]
+fn traversal_attempts_are_not_found() {
+    let (_dir, mut service) = setup();
+
+    for uri in [
+        "/../etc/passwd",
+        "/%2e%2e/%2e%2e/etc/passwd",
+        "/docs/%2E%2E/%2E%2E/secret",
+        "/%00",
+    ] {
+        let status = get(&mut service, Method::GET, uri).status();
+        assert_eq!(status, StatusCode::NOT_FOUND, "{uri}");
+    }
+    for uri in ["/bad%zz", "/..%5c..%5csecret"] {
+        let status = get(&mut service, Method::GET, uri).status();
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{uri}");
+    }
+}
+
+#[test]
+fn only_get_and_head_are_allowed() {
+    let (_dir, mut service) = setup();
+    let response = get(&mut service, Method::POST, "/hello%20world.txt");
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    assert_eq!(response.headers()["allow"], "GET, HEAD");
+}
+
+#[test]
+#[cfg(unix)]
+fn symlinks_are_not_served() {
+    let (dir, mut service) = setup();
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("secret"), b"secret").unwrap();
+    std::os::unix::fs::symlink(outside.path().join("secret"), dir.path().join("leak")).unwrap();
+    std::os::unix::fs::symlink("hello world.txt", dir.path().join("inner")).unwrap();
+
+    assert_eq!(
+        get(&mut service, Method::GET, "/leak").status(),
+        StatusCode::NOT_FOUND
+    );
+    // Symlinks that stay inside the jail resolve like join() does
+    assert_eq!(
+        get(&mut service, Method::GET, "/inner").status(),
+        StatusCode::OK
+    );
+}
+
+#[test]
+fn protocol_relative_redirects_are_not_produced() {
+    let (_dir, mut service) = setup();
+    let redirect = get(&mut service, Method::GET, "//docs");
+    assert_eq!(redirect.headers()["location"], "/docs/");
+}
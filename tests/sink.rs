@@ -0,0 +1,127 @@
+//! Tests for the `EntrySink` implementation on `Jail`.
+
+use path_jail::{EntrySink, Jail, JailError};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tempfile::tempdir;
+
+/// Feed entries through the trait only, as an archive library would.
+fn unpack<S: EntrySink>(sink: &mut S, name: &str, data: &[u8]) -> Result<(), JailError> {
+    let mut file = sink.begin_file(Path::new(name), 0o4755)?;
+    file.write_all(data)?;
+    Ok(())
+}
+
+#[test]
+fn files_and_directories_are_created() {
+    let dir = tempdir().unwrap();
+    let mut jail = Jail::new(dir.path()).unwrap();
+
+    jail.mkdir(Path::new("a/b")).unwrap();
+    unpack(&mut jail, "a/b/file.txt", b"hello").unwrap();
+    unpack(&mut jail, "new/parent/file.txt", b"x").unwrap();
+
+    assert_eq!(fs::read(dir.path().join("a/b/file.txt")).unwrap(), b"hello");
+    assert!(dir.path().join("new/parent/file.txt").is_file());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(dir.path().join("a/b/file.txt"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7000, 0);
+    }
+}
+
+#[test]
+fn escaping_entries_are_rejected() {
+    let dir = tempdir().unwrap();
+    let mut jail = Jail::new(dir.path()).unwrap();
+
+    assert!(unpack(&mut jail, "../evil.txt", b"evil").is_err());
+    assert!(unpack(&mut jail, "/tmp/evil.txt", b"evil").is_err());
+    assert!(jail.mkdir(Path::new("../evil")).is_err());
+}
+
+#[test]
+fn existing_entries_are_not_written_through() {
+    let dir = tempdir().unwrap();
+    let mut jail = Jail::new(dir.path()).unwrap();
+    fs::write(dir.path().join("file.txt"), b"old").unwrap();
+
+    assert!(unpack(&mut jail, "file.txt", b"new").is_err());
+    assert_eq!(fs::read(dir.path().join("file.txt")).unwrap(), b"old");
+}
+
+#[test]
+#[cfg(unix)]
+fn symlinks_must_stay_inside() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let mut jail = Jail::new(dir.path()).unwrap();
+
+    EntrySink::symlink(&mut jail, Path::new("lib/alias"), Path::new("real")).unwrap();
+    assert!(fs::symlink_metadata(dir.path().join("lib/alias")).is_ok());
+
+    let err = EntrySink::symlink(&mut jail, Path::new("leak"), outside.path()).unwrap_err();
+    assert!(matches!(err, JailError::EscapedRoot { .. }));
+
+    // Writing through a symlink that escapes is refused too
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("out")).unwrap();
+    assert!(unpack(&mut jail, "out/pwned.txt", b"x").is_err());
+    assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn file_data_counts_against_the_quota() {
+    let dir = tempdir().unwrap();
+    let mut jail = Jail::builder(dir.path()).quota(8).build().unwrap();
+
+    unpack(&mut jail, "a.txt", b"hello").unwrap();
+    let err = unpack(&mut jail, "b.txt", b"world").unwrap_err();
+    assert!(matches!(err, JailError::QuotaExceeded { limit: 8, .. }));
+    assert_eq!(jail.quota().unwrap().used, 5);
+}
+
+#[test]
+fn files_are_held_to_max_file_size() {
+    let dir = tempdir().unwrap();
+    let mut jail = Jail::builder(dir.path()).max_file_size(4).build().unwrap();
+
+    let mut file = jail.begin_file(Path::new("a.txt"), 0o644).unwrap();
+    file.write_all(b"abcd").unwrap();
+    let err = JailError::from(file.write_all(b"e").unwrap_err());
+    assert!(matches!(err, JailError::FileTooLarge { size: 5, max: 4 }));
+    file.discard();
+    assert!(!dir.path().join("a.txt").exists());
+}
+
+#[test]
+#[cfg(unix)]
+fn files_get_the_file_mode_and_on_create() {
+    use std::os::unix::fs::PermissionsExt;
+    use std::sync::{Arc, Mutex};
+
+    let dir = tempdir().unwrap();
+    let created: Arc<Mutex<Vec<std::path::PathBuf>>> = Arc::default();
+    let log = Arc::clone(&created);
+    let mut jail = Jail::builder(dir.path())
+        .file_mode(0o600)
+        .on_create(move |path| {
+            log.lock().unwrap().push(path.to_path_buf());
+            Ok(())
+        })
+        .build()
+        .unwrap();
+
+    unpack(&mut jail, "file.txt", b"hello").unwrap();
+    let mode = fs::metadata(dir.path().join("file.txt"))
+        .unwrap()
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(*created.lock().unwrap(), [jail.root().join("file.txt")]);
+}
@@ -0,0 +1,127 @@
+//! Tests for staging areas.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn publish_moves_files_into_place() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let staging = jail.stage().unwrap();
+
+    staging.jail().write("report.pdf", b"pdf").unwrap();
+    assert!(!dir.path().join("report.pdf").exists());
+
+    let path = staging.publish("report.pdf").unwrap();
+    assert_eq!(path.relative(), std::path::Path::new("report.pdf"));
+    assert_eq!(fs::read(dir.path().join("report.pdf")).unwrap(), b"pdf");
+    assert!(!staging.jail().join("report.pdf").unwrap().exists());
+}
+
+#[test]
+fn publish_creates_parents_and_moves_directories() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let staging = jail.stage().unwrap();
+
+    staging.jail().create_dir_all("alice/batch").unwrap();
+    staging.jail().write("alice/batch/a.txt", b"a").unwrap();
+    staging.jail().write("alice/batch/b.txt", b"b").unwrap();
+    staging.publish("alice/batch").unwrap();
+
+    assert_eq!(
+        fs::read(dir.path().join("alice/batch/a.txt")).unwrap(),
+        b"a"
+    );
+    assert_eq!(
+        fs::read(dir.path().join("alice/batch/b.txt")).unwrap(),
+        b"b"
+    );
+}
+
+#[test]
+fn publish_replaces_and_noreplace_refuses() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), b"old").unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let staging = jail.stage().unwrap();
+
+    staging.jail().write("a.txt", b"new").unwrap();
+    assert!(matches!(
+        staging.publish_noreplace("a.txt"),
+        Err(JailError::Io(err)) if err.kind() == std::io::ErrorKind::AlreadyExists
+    ));
+    assert_eq!(fs::read(dir.path().join("a.txt")).unwrap(), b"old");
+
+    staging.publish("a.txt").unwrap();
+    assert_eq!(fs::read(dir.path().join("a.txt")).unwrap(), b"new");
+}
+
+#[test]
+fn publish_validates_paths() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let staging = jail.stage().unwrap();
+
+    assert!(staging.publish("../escape").is_err());
+    assert!(staging.publish("missing.txt").is_err());
+    assert!(staging.jail().write("../outside.txt", b"x").is_err());
+}
+
+#[test]
+fn leftovers_are_removed_on_drop() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let staging = jail.stage().unwrap();
+    staging.jail().write("draft.txt", b"x").unwrap();
+    let area = staging.jail().root().to_path_buf();
+    assert!(area.starts_with(jail.root()));
+    drop(staging);
+
+    assert!(!area.exists());
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn staging_shares_the_quota() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(10).build().unwrap();
+    let staging = jail.stage().unwrap();
+
+    staging.jail().write("a.txt", b"12345678").unwrap();
+    assert!(matches!(
+        jail.write("b.txt", b"123"),
+        Err(JailError::QuotaExceeded { .. })
+    ));
+    staging.publish("a.txt").unwrap();
+    assert_eq!(jail.quota().unwrap().used, 8);
+}
+
+#[test]
+fn staging_works_with_deny_hidden() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny_hidden(true).build().unwrap();
+
+    let staging = jail.stage().unwrap();
+    staging.jail().write("a.txt", b"a").unwrap();
+    staging.publish("a.txt").unwrap();
+    assert_eq!(fs::read(dir.path().join("a.txt")).unwrap(), b"a");
+
+    // The published path is still held to the rules
+    assert!(staging.jail().write(".b.txt", b"b").is_err());
+    assert!(staging.publish(".b.txt").is_err());
+}
+
+#[test]
+fn staging_works_with_deny_rules() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).deny("*.tmp").build().unwrap();
+
+    let staging = jail.stage().unwrap();
+    staging.jail().write("a.txt", b"a").unwrap();
+    staging.publish_noreplace("a.txt").unwrap();
+    assert_eq!(fs::read(dir.path().join("a.txt")).unwrap(), b"a");
+    assert!(staging.publish("b.tmp").is_err());
+}
@@ -0,0 +1,99 @@
+//! Tests for `Jail::subjail`, `Jail::create_subjail` and containment between jails.
+
+use path_jail::{Jail, JailError};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn subjail_narrows_the_root() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("users/alice")).unwrap();
+    fs::create_dir_all(dir.path().join("users/bob")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let alice = jail.subjail("users/alice").unwrap();
+    assert_eq!(alice.root(), jail.root().join("users").join("alice"));
+    assert!(alice.join("photo.jpg").is_ok());
+    assert!(matches!(
+        alice.join("../bob/secret.txt"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+}
+
+#[test]
+fn subjail_requires_existing_directory_inside() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("file.txt"), b"x").unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(matches!(
+        jail.subjail("missing"),
+        Err(JailError::InvalidRoot(_))
+    ));
+    assert!(matches!(
+        jail.subjail("file.txt"),
+        Err(JailError::InvalidRoot(_))
+    ));
+    assert!(matches!(
+        jail.subjail("../"),
+        Err(JailError::EscapedRoot { .. })
+    ));
+}
+
+#[test]
+fn create_subjail_creates_missing_directories() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let tenant = jail.create_subjail("tenants/acme/data").unwrap();
+    assert!(dir.path().join("tenants/acme/data").is_dir());
+    assert_eq!(tenant, jail.subjail("tenants/acme/data").unwrap());
+    assert!(jail.create_subjail("../escape").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn subjail_through_symlink_uses_canonical_root() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("real")).unwrap();
+    std::os::unix::fs::symlink("real", dir.path().join("alias")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let sub = jail.subjail("alias").unwrap();
+    assert_eq!(sub.root(), jail.root().join("real"));
+}
+
+#[test]
+fn subjail_keeps_options() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("a b")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+
+    let sub = jail.subjail("a%20b").unwrap();
+    assert_eq!(sub.join("x%20y").unwrap(), sub.root().join("x y"));
+}
+
+#[test]
+fn containment_relations_follow_the_roots() {
+    let dir = tempdir().unwrap();
+    fs::create_dir_all(dir.path().join("users/alice")).unwrap();
+    fs::create_dir_all(dir.path().join("users/bob")).unwrap();
+    let uploads = Jail::new(dir.path()).unwrap();
+    let alice = uploads.subjail("users/alice").unwrap();
+    let bob = uploads.subjail("users/bob").unwrap();
+
+    assert!(uploads.contains_jail(&alice));
+    assert!(alice.is_subjail_of(&uploads));
+    assert!(!alice.contains_jail(&uploads));
+    assert!(alice.contains_jail(&alice.read_only()));
+    assert!(alice.overlaps(&uploads) && uploads.overlaps(&alice));
+    assert!(!alice.overlaps(&bob));
+
+    // Sibling names sharing a prefix are not nested
+    fs::create_dir(dir.path().join("users/alice2")).unwrap();
+    let alice2 = uploads.subjail("users/alice2").unwrap();
+    assert!(!alice.contains_jail(&alice2));
+}
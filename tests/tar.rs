@@ -0,0 +1,253 @@
+//! Tests for the tar feature.
+
+#![cfg(feature = "tar")]
+
+use path_jail::{EscapePolicy, ExtractOptions, Jail, JailError, LinkPolicy};
+use std::fs;
+use tempfile::tempdir;
+
+enum Entry<'a> {
+    File(&'a str, &'a [u8]),
+    Dir(&'a str),
+    #[cfg_attr(not(unix), allow(dead_code))]
+    Symlink(&'a str, &'a str),
+    HardLink(&'a str, &'a str),
+}
+
+/// Build an archive with raw names, bypassing the tar crate's own checks.
+fn archive(entries: &[Entry<'_>]) -> Vec<u8> {
+    let mut builder = tar::Builder::new(Vec::new());
+    for entry in entries {
+        let mut header = tar::Header::new_gnu();
+        let (name, data, link): (&str, &[u8], Option<&str>) = match *entry {
+            Entry::File(name, data) => {
+                header.set_entry_type(tar::EntryType::Regular);
+                (name, data, None)
+            }
+            Entry::Dir(name) => {
+                header.set_entry_type(tar::EntryType::Directory);
+                (name, b"", None)
+            }
+            Entry::Symlink(name, target) => {
+                header.set_entry_type(tar::EntryType::Symlink);
+                (name, b"", Some(target))
+            }
+            Entry::HardLink(name, target) => {
+                header.set_entry_type(tar::EntryType::Link);
+                (name, b"", Some(target))
+            }
+        };
+        let gnu = header.as_gnu_mut().unwrap();
+        gnu.name[..name.len()].copy_from_slice(name.as_bytes());
+        if let Some(link) = link {
+            gnu.linkname[..link.len()].copy_from_slice(link.as_bytes());
+        }
+        header.set_mode(0o4755);
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        builder.append(&header, data).unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+#[test]
+fn extracts_files_and_directories() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let data = archive(&[
+        Entry::Dir("docs/"),
+        Entry::File("docs/readme.txt", b"hello"),
+        Entry::File("./nested/deep/file.bin", b"x"),
+    ]);
+
+    let report = jail
+        .extract_tar(data.as_slice(), &ExtractOptions::new())
+        .unwrap();
+    assert_eq!(report.extracted.len(), 3);
+    assert!(report.skipped.is_empty());
+    assert_eq!(
+        fs::read(dir.path().join("docs/readme.txt")).unwrap(),
+        b"hello"
+    );
+    assert!(dir.path().join("nested/deep/file.bin").is_file());
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(dir.path().join("docs/readme.txt"))
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o7000, 0, "setuid bit must be dropped");
+    }
+}
+
+#[test]
+fn escaping_entries_follow_policy() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let data = archive(&[
+        Entry::File("ok.txt", b"ok"),
+        Entry::File("../evil.txt", b"evil"),
+        Entry::File("/abs/evil.txt", b"evil"),
+    ]);
+
+    let err = jail
+        .extract_tar(data.as_slice(), &ExtractOptions::new())
+        .unwrap_err();
+    assert!(matches!(err, JailError::EscapedRoot { .. }));
+    assert!(!dir.path().parent().unwrap().join("evil.txt").exists());
+
+    let skip = ExtractOptions::new()
+        .escapes(EscapePolicy::Skip)
+        .overwrite(true);
+    let report = jail.extract_tar(data.as_slice(), &skip).unwrap();
+    assert_eq!(report.skipped, ["../evil.txt", "/abs/evil.txt"]);
+
+    let strip = ExtractOptions::new()
+        .escapes(EscapePolicy::Strip)
+        .overwrite(true);
+    jail.extract_tar(data.as_slice(), &strip).unwrap();
+    assert!(dir.path().join("evil.txt").is_file());
+    assert!(dir.path().join("abs/evil.txt").is_file());
+}
+
+#[test]
+fn existing_files_are_kept_unless_overwrite() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::write(dir.path().join("file.txt"), b"old").unwrap();
+    let data = archive(&[Entry::File("file.txt", b"new")]);
+
+    assert!(jail
+        .extract_tar(data.as_slice(), &ExtractOptions::new())
+        .is_err());
+    assert_eq!(fs::read(dir.path().join("file.txt")).unwrap(), b"old");
+
+    jail.extract_tar(data.as_slice(), &ExtractOptions::new().overwrite(true))
+        .unwrap();
+    assert_eq!(fs::read(dir.path().join("file.txt")).unwrap(), b"new");
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_then_file_cannot_escape() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let target = outside.path().to_str().unwrap();
+    let data = archive(&[
+        Entry::Symlink("link", target),
+        Entry::File("link/pwned.txt", b"pwned"),
+    ]);
+
+    // Default: links skipped, so the file lands in a real directory
+    let report = jail
+        .extract_tar(data.as_slice(), &ExtractOptions::new())
+        .unwrap();
+    assert_eq!(report.skipped, ["link"]);
+    assert!(dir.path().join("link/pwned.txt").is_file());
+    assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+
+    // In-jail links: the escaping symlink itself is rejected
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let options = ExtractOptions::new().links(LinkPolicy::InJail);
+    assert!(jail.extract_tar(data.as_slice(), &options).is_err());
+    assert_eq!(fs::read_dir(outside.path()).unwrap().count(), 0);
+}
+
+#[test]
+#[cfg(unix)]
+fn in_jail_links_are_created() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let data = archive(&[
+        Entry::File("lib/libfoo.so.1", b"elf"),
+        Entry::Symlink("lib/libfoo.so", "libfoo.so.1"),
+        Entry::HardLink("lib/copy.so", "lib/libfoo.so.1"),
+    ]);
+
+    let options = ExtractOptions::new().links(LinkPolicy::InJail);
+    let report = jail.extract_tar(data.as_slice(), &options).unwrap();
+    assert_eq!(report.extracted.len(), 3);
+    assert_eq!(fs::read(dir.path().join("lib/libfoo.so")).unwrap(), b"elf");
+    assert_eq!(fs::read(dir.path().join("lib/copy.so")).unwrap(), b"elf");
+
+    let reject = ExtractOptions::new()
+        .links(LinkPolicy::Reject)
+        .overwrite(true);
+    assert!(jail.extract_tar(data.as_slice(), &reject).is_err());
+}
+
+#[test]
+fn hard_links_must_target_extracted_files() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::write(dir.path().join("preexisting"), b"secret").unwrap();
+    let data = archive(&[Entry::HardLink("grab", "preexisting")]);
+
+    let options = ExtractOptions::new().links(LinkPolicy::InJail);
+    assert!(jail.extract_tar(data.as_slice(), &options).is_err());
+    assert!(!dir.path().join("grab").exists());
+}
+
+#[test]
+fn extraction_counts_against_the_quota() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(10).build().unwrap();
+    let data = archive(&[
+        Entry::File("a.bin", b"12345"),
+        Entry::HardLink("b.bin", "a.bin"),
+        Entry::File("bomb.bin", &[0u8; 64]),
+    ]);
+    let options = ExtractOptions::new().links(LinkPolicy::InJail);
+
+    let err = jail.extract_tar(data.as_slice(), &options).unwrap_err();
+    assert!(matches!(err, JailError::QuotaExceeded { limit: 10, .. }));
+    assert!(dir.path().join("b.bin").is_file());
+    // The member that went over is removed, and its bytes given back
+    assert!(!dir.path().join("bomb.bin").exists());
+    assert_eq!(jail.quota().unwrap().used, 10);
+}
+
+#[test]
+fn oversized_members_fail_extraction() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).max_file_size(8).build().unwrap();
+    let data = archive(&[
+        Entry::File("small.txt", b"hello"),
+        Entry::File("big.bin", &[0u8; 9]),
+        Entry::File("later.txt", b"x"),
+    ]);
+
+    let err = jail
+        .extract_tar(data.as_slice(), &ExtractOptions::new())
+        .unwrap_err();
+    assert!(matches!(err, JailError::FileTooLarge { size: 9, max: 8 }));
+    assert!(dir.path().join("small.txt").is_file());
+    assert!(!dir.path().join("big.bin").exists());
+    assert!(!dir.path().join("later.txt").exists());
+}
+
+#[test]
+fn entry_names_are_not_percent_decoded() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+    let data = archive(&[
+        Entry::Dir("50%off/"),
+        Entry::File("50%off/100%25.txt", b"x"),
+        Entry::File("%2e%2e/x", b"y"),
+    ]);
+
+    let report = jail
+        .extract_tar(data.as_slice(), &ExtractOptions::new())
+        .unwrap();
+    assert_eq!(report.extracted.len(), 3);
+    assert!(dir.path().join("50%off/100%25.txt").is_file());
+    assert!(dir.path().join("%2e%2e/x").is_file());
+    assert!(!dir.path().join("50%off/100%.txt").exists());
+}
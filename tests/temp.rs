@@ -0,0 +1,102 @@
+//! Tests for jail-scoped temporary files and directories.
+
+use path_jail::Jail;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use tempfile::tempdir;
+
+#[test]
+fn tempfile_is_removed_on_drop() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let mut tmp = jail.tempfile().unwrap();
+    let path = tmp.path().clone();
+    assert!(path.starts_with(jail.root()));
+
+    tmp.write_all(b"scratch").unwrap();
+    tmp.seek(SeekFrom::Start(0)).unwrap();
+    let mut contents = String::new();
+    tmp.read_to_string(&mut contents).unwrap();
+    assert_eq!(contents, "scratch");
+
+    drop(tmp);
+    assert!(!path.exists());
+}
+
+#[test]
+fn tempfile_persist_moves_into_place() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("final")).unwrap();
+
+    let mut tmp = jail.tempfile().unwrap();
+    tmp.write_all(b"done").unwrap();
+    let path = tmp.persist("final/upload.bin").unwrap();
+
+    assert_eq!(fs::read(&path).unwrap(), b"done");
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn tempfile_persist_rejects_escape() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let tmp = jail.tempfile().unwrap();
+    assert!(tmp.persist("../escape.bin").is_err());
+    // Dropped (and removed) on the failed persist
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn tempdir_is_removed_recursively_on_drop() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("scratch")).unwrap();
+
+    let tmp = jail.tempdir_in("scratch").unwrap();
+    let path = tmp.path().clone();
+    assert!(path.starts_with(dir.path().join("scratch").canonicalize().unwrap()));
+
+    let inner = tmp.jail().unwrap();
+    inner.create_dir_all("a/b").unwrap();
+    inner.write("a/b/file.txt", "x").unwrap();
+
+    drop(tmp);
+    assert!(!path.exists());
+    assert!(dir.path().join("scratch").exists());
+}
+
+#[test]
+fn tempdir_keep_leaves_directory() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let path = jail.tempdir().unwrap().keep();
+    assert!(path.is_dir());
+}
+
+#[test]
+fn temp_in_rejects_bad_parents() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.tempfile_in("../").is_err());
+    assert!(jail.tempdir_in("missing").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn temp_entries_are_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    let file = jail.tempfile().unwrap();
+    let tdir = jail.tempdir().unwrap();
+    let mode = |p: &std::path::Path| fs::metadata(p).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(file.path()), 0o600);
+    assert_eq!(mode(tdir.path()), 0o700);
+}
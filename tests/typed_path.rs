@@ -0,0 +1,59 @@
+//! Tests for `JailedDirPath` and `JailedFilePath`.
+
+use path_jail::{Jail, JailedDirPath, JailedFilePath, JailedPath};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn join_dir_requires_existing_directory() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+    fs::write(dir.path().join("file.txt"), b"x").unwrap();
+
+    let alice: JailedDirPath = jail.join_dir("alice").unwrap();
+    assert!(alice.ends_with("alice"));
+    assert!(jail.join_dir("file.txt").is_err());
+    assert!(jail.join_dir("missing").is_err());
+    assert!(jail.join_dir("../").is_err());
+}
+
+#[test]
+fn join_file_rejects_directories() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir(dir.path().join("alice")).unwrap();
+
+    // A file that doesn't exist yet is fine
+    let file: JailedFilePath = jail.join_file("alice/new.txt").unwrap();
+    file.write(b"hello").unwrap();
+    assert_eq!(file.read_to_string().unwrap(), "hello");
+
+    assert!(jail.join_file("alice").is_err());
+    assert!(jail.join_file("../escape.txt").is_err());
+}
+
+#[test]
+fn typed_paths_navigate_and_convert() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    fs::create_dir_all(dir.path().join("a/b")).unwrap();
+
+    let a = jail.join_dir("a").unwrap();
+    let b = a.join_dir("b").unwrap();
+    let file = b.join_file("data.bin").unwrap();
+    assert_eq!(file.parent().unwrap(), b);
+    assert!(a.join_file("b").is_err());
+    assert!(a.join_dir("../../x").is_err());
+
+    file.write(b"1").unwrap();
+    assert_eq!(b.read_dir().unwrap().count(), 1);
+
+    let untyped: JailedPath = file.clone().into();
+    assert!(untyped.clone().into_file().is_ok());
+    assert!(untyped.into_dir().is_err());
+
+    file.remove_file().unwrap();
+    b.remove_dir().unwrap();
+    assert!(!dir.path().join("a/b").exists());
+}
@@ -0,0 +1,75 @@
+//! Tests for the unicode feature.
+
+#![cfg(feature = "unicode")]
+
+use path_jail::{Jail, UnicodeForm};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const NFC: &str = "caf\u{e9}";
+const NFD: &str = "cafe\u{301}";
+
+#[test]
+fn nfc_jail_maps_both_spellings_to_one_path() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .normalize_unicode(Some(UnicodeForm::Nfc))
+        .build()
+        .unwrap();
+
+    let composed = jail.join(NFC).unwrap();
+    let decomposed = jail.join(NFD).unwrap();
+    assert_eq!(composed, decomposed);
+    assert_eq!(composed, jail.root().join(NFC));
+}
+
+#[test]
+fn nfd_jail_decomposes() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .normalize_unicode(Some(UnicodeForm::Nfd))
+        .build()
+        .unwrap();
+
+    assert_eq!(jail.join(NFC).unwrap(), jail.root().join(NFD));
+}
+
+#[test]
+fn default_jail_does_not_normalize() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .normalize_unicode(None)
+        .build()
+        .unwrap();
+
+    assert_ne!(jail.join(NFC).unwrap(), jail.join(NFD).unwrap());
+    assert_eq!(jail, Jail::new(dir.path()).unwrap());
+}
+
+#[test]
+fn relative_is_normalized() {
+    let dir = tempdir().unwrap();
+    // Stored decomposed, as macOS would
+    fs::write(dir.path().join(NFD), b"x").unwrap();
+    let jail = Jail::builder(dir.path())
+        .normalize_unicode(Some(UnicodeForm::Nfc))
+        .build()
+        .unwrap();
+
+    let stored = jail.root().join(NFD);
+    assert_eq!(jail.relative(&stored).unwrap(), Path::new(NFC));
+}
+
+#[test]
+fn normalization_runs_after_percent_decoding() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .normalize_unicode(Some(UnicodeForm::Nfc))
+        .build()
+        .unwrap();
+
+    // "cafe" + U+0301 percent-encoded as UTF-8
+    assert_eq!(jail.join("cafe%CC%81").unwrap(), jail.root().join(NFC));
+}
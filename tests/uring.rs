@@ -0,0 +1,110 @@
+//! Tests for the io-uring feature.
+//!
+//! io_uring can be disabled by the kernel or a seccomp profile; the tests
+//! return early when the ring cannot be set up.
+
+#![cfg(all(feature = "io-uring", target_os = "linux"))]
+
+use path_jail::{Jail, JailError, JailRing};
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::symlink;
+use tempfile::tempdir;
+
+fn ring(jail: &Jail, entries: u32) -> Option<JailRing> {
+    match jail.io_uring(entries) {
+        Ok(ring) => Some(ring),
+        Err(err) => {
+            eprintln!("io_uring unavailable: {err}");
+            None
+        }
+    }
+}
+
+#[test]
+fn open_many_keeps_order_and_per_path_errors() {
+    let dir = tempdir().unwrap();
+    for name in ["a", "b", "c", "d"] {
+        fs::write(dir.path().join(name), name).unwrap();
+    }
+    let jail = Jail::new(dir.path()).unwrap();
+    // Smaller than the batch, so it is submitted in several rounds
+    let Some(mut ring) = ring(&jail, 2) else {
+        return;
+    };
+
+    let files = ring.open_many(["a", "../escape", "b", "missing", "c", "d"]);
+    assert_eq!(files.len(), 6);
+    assert!(matches!(files[1], Err(JailError::EscapedRoot { .. })));
+    assert!(matches!(&files[3], Err(JailError::Io(err)) if err.kind() == ErrorKind::NotFound));
+    for (index, name) in [(0, "a"), (2, "b"), (4, "c"), (5, "d")] {
+        let file = files[index].as_ref().unwrap();
+        let mut buf = [0; 8];
+        let n = ring.read_at(file, &mut buf, 0).unwrap();
+        assert_eq!(&buf[..n], name.as_bytes());
+    }
+    assert!(ring.open("").is_err());
+}
+
+#[test]
+fn create_write_and_read_back() {
+    let dir = tempdir().unwrap();
+    fs::create_dir(dir.path().join("out")).unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let Some(mut ring) = ring(&jail, 8) else {
+        return;
+    };
+
+    let file = ring.create("out/data.bin").unwrap();
+    assert_eq!(ring.write_at(&file, b"hello world", 0).unwrap(), 11);
+    assert_eq!(ring.write_at(&file, b"HELLO", 0).unwrap(), 5);
+    drop(file);
+    assert_eq!(jail.read_to_string("out/data.bin").unwrap(), "HELLO world");
+    assert!(ring.create("out/data.bin").is_err());
+
+    let file = ring.open("out/data.bin").unwrap();
+    let mut buf = [0; 5];
+    assert_eq!(ring.read_at(&file, &mut buf, 6).unwrap(), 5);
+    assert_eq!(&buf, b"world");
+    assert_eq!(ring.read_at(&file, &mut buf, 11).unwrap(), 0);
+    assert_eq!(ring.jail().root(), jail.root());
+}
+
+#[test]
+fn kernel_refuses_opens_outside_the_root() {
+    let outside = tempdir().unwrap();
+    fs::write(outside.path().join("shared.txt"), "shared").unwrap();
+    let dir = tempdir().unwrap();
+    symlink(outside.path(), dir.path().join("shared")).unwrap();
+    // Validation lets this link through; RESOLVE_BENEATH does not
+    let jail = Jail::builder(dir.path())
+        .allow_symlink_target(outside.path())
+        .build()
+        .unwrap();
+    let Some(mut ring) = ring(&jail, 4) else {
+        return;
+    };
+    assert_eq!(jail.read_to_string("shared/shared.txt").unwrap(), "shared");
+    assert!(matches!(
+        ring.open("shared/shared.txt"),
+        Err(JailError::Io(err)) if err.raw_os_error() == Some(18) // EXDEV
+    ));
+}
+
+#[test]
+fn hard_link_check_applies() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("a.txt"), "data").unwrap();
+    fs::hard_link(dir.path().join("a.txt"), dir.path().join("b.txt")).unwrap();
+    let jail = Jail::builder(dir.path())
+        .reject_hard_links(true)
+        .build()
+        .unwrap();
+    let Some(mut ring) = ring(&jail, 4) else {
+        return;
+    };
+    assert!(matches!(
+        ring.open("a.txt"),
+        Err(JailError::HardLinked { links: 2, .. })
+    ));
+}
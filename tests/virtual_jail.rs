@@ -0,0 +1,101 @@
+//! Tests for `VirtualJail`, the `no_std` lexical core.
+
+use path_jail::{LexicalError, VirtualJail};
+
+#[test]
+fn roots_are_normalized() {
+    assert_eq!(VirtualJail::new("/a//b/./c/..").unwrap().root(), "/a/b");
+    assert_eq!(VirtualJail::new("relative"), Err(LexicalError::InvalidRoot));
+    assert_eq!(VirtualJail::new("/"), Err(LexicalError::InvalidRoot));
+    assert_eq!(VirtualJail::new("/a/.."), Err(LexicalError::InvalidRoot));
+    assert_eq!(VirtualJail::new("/.."), Err(LexicalError::InvalidRoot));
+    assert_eq!(VirtualJail::new("/a\0"), Err(LexicalError::InvalidRoot));
+}
+
+#[test]
+fn join_resolves_dots() {
+    let jail = VirtualJail::new("/bucket/acme").unwrap();
+    assert_eq!(jail.join("a/./b/../c").unwrap(), "/bucket/acme/a/c");
+    assert_eq!(jail.join("a//b/").unwrap(), "/bucket/acme/a/b");
+    assert_eq!(jail.join("a/..").unwrap(), "/bucket/acme");
+    assert_eq!(jail.join("").unwrap(), "/bucket/acme");
+    // Only '/' separates
+    assert_eq!(jail.join(r"..\etc").unwrap(), r"/bucket/acme/..\etc");
+}
+
+#[test]
+fn join_rejects_escapes() {
+    let jail = VirtualJail::new("/bucket/acme").unwrap();
+    assert_eq!(jail.join("../other"), Err(LexicalError::EscapedRoot));
+    assert_eq!(jail.join("a/../../other"), Err(LexicalError::EscapedRoot));
+    assert_eq!(jail.join("/etc/passwd"), Err(LexicalError::Absolute));
+    assert_eq!(jail.join("a\0b"), Err(LexicalError::NullByte));
+}
+
+#[test]
+fn contains_checks_whole_components() {
+    let jail = VirtualJail::new("/bucket/acme").unwrap();
+    assert!(jail.contains("/bucket/acme"));
+    assert!(jail.contains("/bucket/acme/"));
+    assert!(jail.contains("/bucket/acme/x/../y"));
+    assert!(jail.contains("/bucket/other/../acme/z"));
+    assert!(!jail.contains("/bucket/acme-evil/x"));
+    assert!(!jail.contains("/bucket/acme/../other"));
+    assert!(!jail.contains("/bucket"));
+    assert!(!jail.contains("bucket/acme/x"));
+    assert!(!jail.contains("/.."));
+}
+
+#[test]
+#[cfg(unix)]
+fn agrees_with_lexical_jail() {
+    use path_jail::{normalize, LexicalJail};
+    use std::path::Path;
+
+    for root in [
+        "/a//b/./c/..",
+        "/a/..",
+        "/..",
+        "//a",
+        "/./a",
+        "/a/../..",
+        "/a\0",
+    ] {
+        let lexical = LexicalJail::new(root).map(|jail| jail.root().to_path_buf());
+        match (VirtualJail::new(root), lexical) {
+            (Ok(jail), Ok(lexical)) => assert_eq!(Path::new(jail.root()), lexical, "{root:?}"),
+            (Err(_), Err(_)) => {}
+            (jail, lexical) => panic!("{root:?}: {jail:?} vs {lexical:?}"),
+        }
+    }
+
+    let jail = VirtualJail::new("/bucket/acme").unwrap();
+    let lexical = LexicalJail::new("/bucket/acme").unwrap();
+    for path in [
+        "",
+        ".",
+        "..",
+        "./..",
+        "a/..",
+        "a/../..",
+        "a//b/./../c",
+        "/",
+        "/etc",
+        "a/\0",
+        "../\0",
+        "a/b/../../..",
+        "a/./././..",
+        ".../b",
+    ] {
+        match (jail.join(path), lexical.join(path), normalize(path)) {
+            (Ok(joined), Ok(lexical), Ok(normalized)) => {
+                assert_eq!(Path::new(&joined), lexical, "{path:?}");
+                assert_eq!(lexical, Path::new(jail.root()).join(normalized), "{path:?}");
+            }
+            (Err(_), Err(_), Err(_)) => {}
+            (joined, lexical, normalized) => {
+                panic!("{path:?}: {joined:?} vs {lexical:?} vs {normalized:?}")
+            }
+        }
+    }
+}
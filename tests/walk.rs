@@ -0,0 +1,124 @@
+//! Tests for `Jail::walk`.
+
+use path_jail::Jail;
+use std::fs;
+use tempfile::tempdir;
+
+fn names(jail: &Jail, walk: path_jail::Walk<'_>) -> Vec<String> {
+    let mut names: Vec<_> = walk
+        .filter_map(Result::ok)
+        .map(|e| {
+            jail.relative(e.path())
+                .unwrap()
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn walk_visits_whole_tree() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir_all(dir.path().join("a/b")).unwrap();
+    fs::write(dir.path().join("a/one.txt"), b"1").unwrap();
+    fs::write(dir.path().join("a/b/two.txt"), b"2").unwrap();
+
+    let walk = jail.walk("").unwrap();
+    assert_eq!(names(&jail, walk), ["a", "a/b", "a/b/two.txt", "a/one.txt"]);
+}
+
+#[test]
+fn walk_respects_max_depth() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir_all(dir.path().join("a/b/c")).unwrap();
+
+    let walk = jail.walk("a").unwrap().max_depth(1);
+    assert_eq!(names(&jail, walk), ["a/b"]);
+}
+
+#[test]
+fn walk_rejects_escaping_start() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    assert!(jail.walk("../").is_err());
+    assert!(jail.walk("missing").is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_does_not_follow_symlinks_by_default() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("real")).unwrap();
+    fs::write(dir.path().join("real/file.txt"), b"x").unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("link")).unwrap();
+
+    // The link is reported (resolved to "real") but not descended into
+    let walk = jail.walk("").unwrap();
+    assert_eq!(names(&jail, walk), ["real", "real", "real/file.txt"]);
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_follows_internal_symlinks_when_enabled() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("real")).unwrap();
+    fs::write(dir.path().join("real/file.txt"), b"x").unwrap();
+    fs::create_dir(dir.path().join("other")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("real"), dir.path().join("other/link")).unwrap();
+
+    let files = jail
+        .walk("")
+        .unwrap()
+        .follow_symlinks(true)
+        .filter_map(Result::ok)
+        .filter(|e| e.file_type().is_file())
+        .count();
+
+    // real/file.txt is reached twice: directly and through other/link
+    assert_eq!(files, 2);
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_reports_symlink_loops() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("a")).unwrap();
+    std::os::unix::fs::symlink(dir.path().join("a"), dir.path().join("a/loop")).unwrap();
+
+    let results: Vec<_> = jail.walk("").unwrap().follow_symlinks(true).collect();
+    assert!(results
+        .iter()
+        .any(|r| matches!(r, Err(path_jail::JailError::InvalidPath(msg)) if msg.contains("loop"))));
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_reports_escaping_symlinks_and_continues() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+
+    fs::create_dir(dir.path().join("sub")).unwrap();
+    fs::write(dir.path().join("sub/ok.txt"), b"ok").unwrap();
+    std::os::unix::fs::symlink("/etc", dir.path().join("sub/evil")).unwrap();
+
+    let results: Vec<_> = jail.walk("").unwrap().follow_symlinks(true).collect();
+    assert!(results
+        .iter()
+        .any(|r| matches!(r, Err(path_jail::JailError::EscapedRoot { .. }))));
+    assert!(results
+        .iter()
+        .any(|r| matches!(r, Ok(e) if e.file_name() == "ok.txt")));
+}
@@ -0,0 +1,57 @@
+//! Tests for change notifications limited to the jail.
+
+#![cfg(feature = "watch")]
+
+use path_jail::{Jail, JailWatcher, WatchEvent, WatchKind};
+use std::path::Path;
+use std::time::Duration;
+use tempfile::tempdir;
+
+fn wait_for(watcher: &mut JailWatcher, mut found: impl FnMut(&WatchEvent) -> bool) -> bool {
+    while let Some(event) = watcher.next_timeout(Duration::from_secs(5)) {
+        if found(&event.unwrap()) {
+            return true;
+        }
+    }
+    false
+}
+
+#[test]
+fn new_files_are_reported_as_jailed_paths() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let mut watcher = jail.watch().unwrap();
+
+    jail.write("report.txt", b"hi").unwrap();
+    assert!(wait_for(&mut watcher, |event| {
+        event.kind() == WatchKind::Create
+            && event
+                .paths()
+                .iter()
+                .any(|path| path.relative() == Path::new("report.txt"))
+    }));
+}
+
+#[test]
+#[cfg(unix)]
+fn symlinks_out_of_the_jail_are_left_out() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let mut watcher = jail.watch().unwrap();
+
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("escape")).unwrap();
+    std::fs::write(dir.path().join("marker"), b"").unwrap();
+
+    // Everything up to the marker is seen, none of it the symlink
+    assert!(wait_for(&mut watcher, |event| {
+        for path in event.paths() {
+            assert!(path.as_path().starts_with(jail.root()));
+            assert_ne!(path.relative(), Path::new("escape"));
+        }
+        event
+            .paths()
+            .iter()
+            .any(|path| path.relative() == Path::new("marker"))
+    }));
+}
@@ -0,0 +1,148 @@
+//! Tests for the zip feature.
+
+#![cfg(feature = "zip")]
+
+use path_jail::{EscapePolicy, ExtractOptions, Jail, JailError};
+use std::fs;
+use std::io::{Cursor, Write};
+use tempfile::tempdir;
+use zip::write::SimpleFileOptions;
+
+fn archive(files: &[(&str, &[u8])], symlinks: &[(&str, &str)]) -> Cursor<Vec<u8>> {
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (name, data) in files {
+        writer.start_file(*name, options).unwrap();
+        writer.write_all(data).unwrap();
+    }
+    for (name, target) in symlinks {
+        writer.add_symlink(*name, *target, options).unwrap();
+    }
+    let mut cursor = writer.finish().unwrap();
+    cursor.set_position(0);
+    cursor
+}
+
+#[test]
+fn extracts_entries_and_normalizes_backslashes() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let data = archive(
+        &[
+            ("docs/readme.txt", b"hello"),
+            ("win\\style\\file.txt", b"x"),
+        ],
+        &[],
+    );
+
+    let report = jail.extract_zip(data, &ExtractOptions::new()).unwrap();
+    assert_eq!(report.extracted.len(), 2);
+    assert_eq!(
+        fs::read(dir.path().join("docs/readme.txt")).unwrap(),
+        b"hello"
+    );
+    assert!(dir.path().join("win/style/file.txt").is_file());
+}
+
+#[test]
+fn zip_slip_is_rejected() {
+    let dir = tempdir().unwrap();
+    let parent = dir.path().join("jail");
+    fs::create_dir(&parent).unwrap();
+    let jail = Jail::new(&parent).unwrap();
+
+    for name in [
+        "../evil.txt",
+        "..\\evil.txt",
+        "/evil.txt",
+        "a/../../evil.txt",
+    ] {
+        let data = archive(&[(name, b"evil")], &[]);
+        let err = jail.extract_zip(data, &ExtractOptions::new()).unwrap_err();
+        assert!(matches!(err, JailError::EscapedRoot { .. }), "{name}");
+    }
+    assert!(!dir.path().join("evil.txt").exists());
+
+    let data = archive(&[("..\\evil.txt", b"evil"), ("ok.txt", b"ok")], &[]);
+    let options = ExtractOptions::new().escapes(EscapePolicy::Skip);
+    let report = jail.extract_zip(data, &options).unwrap();
+    assert_eq!(report.skipped, ["../evil.txt"]);
+    assert!(parent.join("ok.txt").is_file());
+}
+
+#[test]
+#[cfg(unix)]
+fn symlink_entries_follow_policy() {
+    let dir = tempdir().unwrap();
+    let outside = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let escape = outside.path().to_str().unwrap();
+
+    let data = archive(&[("real.txt", b"data")], &[("alias", "real.txt")]);
+    let options = ExtractOptions::new().links(path_jail::LinkPolicy::InJail);
+    jail.extract_zip(data, &options).unwrap();
+    assert_eq!(fs::read(dir.path().join("alias")).unwrap(), b"data");
+
+    let data = archive(&[], &[("leak", escape)]);
+    assert!(jail.extract_zip(data.clone(), &options).is_err());
+    let report = jail.extract_zip(data, &ExtractOptions::new()).unwrap();
+    assert_eq!(report.skipped, ["leak"]);
+    assert!(fs::symlink_metadata(dir.path().join("leak")).is_err());
+}
+
+#[test]
+fn extraction_counts_against_the_quota() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path()).quota(1024).build().unwrap();
+    let bomb = vec![0u8; 1 << 20];
+    let data = archive(&[("small.txt", b"hello"), ("bomb.bin", &bomb)], &[]);
+
+    let err = jail.extract_zip(data, &ExtractOptions::new()).unwrap_err();
+    assert!(matches!(err, JailError::QuotaExceeded { limit: 1024, .. }));
+    assert!(dir.path().join("small.txt").is_file());
+    assert!(!dir.path().join("bomb.bin").exists());
+    assert_eq!(jail.quota().unwrap().used, 5);
+}
+
+#[test]
+fn oversized_members_fail_extraction() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .max_file_size(1024)
+        .build()
+        .unwrap();
+    let big = vec![0u8; 4096];
+    let data = archive(&[("small.txt", b"hello"), ("big.bin", &big)], &[]);
+
+    let err = jail.extract_zip(data, &ExtractOptions::new()).unwrap_err();
+    assert!(matches!(err, JailError::FileTooLarge { max: 1024, .. }));
+    assert!(dir.path().join("small.txt").is_file());
+    assert!(!dir.path().join("big.bin").exists());
+}
+
+#[test]
+fn entry_names_are_not_percent_decoded() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::builder(dir.path())
+        .percent_decode(true)
+        .build()
+        .unwrap();
+    let data = archive(&[("50%off/100%25.txt", b"x"), ("%2e%2e\\x", b"y")], &[]);
+
+    let report = jail.extract_zip(data, &ExtractOptions::new()).unwrap();
+    assert_eq!(report.extracted.len(), 2);
+    assert!(dir.path().join("50%off/100%25.txt").is_file());
+    assert!(dir.path().join("%2e%2e/x").is_file());
+}
+
+#[test]
+fn oversized_symlink_targets_are_rejected() {
+    let dir = tempdir().unwrap();
+    let jail = Jail::new(dir.path()).unwrap();
+    let target = "a/".repeat(5000);
+    let zip = archive(&[], &[("link", &target)]);
+
+    let err = jail.extract_zip(zip, &ExtractOptions::new()).unwrap_err();
+    assert!(matches!(err, JailError::InvalidPath(_)), "{err:?}");
+    assert!(fs::symlink_metadata(dir.path().join("link")).is_err());
+}